@@ -0,0 +1,214 @@
+//! 客户列表的性能测试：准备 5 万个客户和约 10 万条跟进记录，
+//! 比较一条 SQL 完成的列表查询和原来逐个客户查询跟进、在内存中分页的做法。
+//!
+//! 运行：`cargo bench --bench customer_list`，可以用 `BENCH_CUSTOMERS` 调整客户数量
+
+use std::time::Instant;
+
+use customer_tracker::{
+    entities::{
+        customer::{self, Entity as Customer},
+        customer_track::{self, Entity as CustomerTrack},
+        next_action::NextAction,
+        user_role::UserRole,
+    },
+    middleware::auth::CurrentUser,
+    migration::DatabaseMigrator,
+    services::{
+        customer_list_service::{
+            CustomerListFilter, CustomerListService, CustomerSort, CustomerSortField,
+        },
+        permission_service::{AccessScope, PermissionService},
+    },
+};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, Database, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder,
+};
+
+const DEFAULT_CUSTOMERS: u64 = 50_000;
+/// 客户平均分给这么多个销售
+const SALES_USERS: u64 = 10;
+const PAGE_SIZE: u64 = 20;
+/// 每个场景重复的次数，取平均值
+const ROUNDS: u32 = 10;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let customers = std::env::var("BENCH_CUSTOMERS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_CUSTOMERS);
+
+    let path = std::env::temp_dir().join(format!("customer_list_bench_{}.db", std::process::id()));
+    let url = format!("sqlite://{}", path.display());
+    DatabaseMigrator::new(url.clone()).run_migrations().await?;
+    let db = Database::connect(&url).await?;
+
+    let started = Instant::now();
+    seed(&db, customers).await?;
+    println!("准备 {} 个客户，用时 {:?}", customers, started.elapsed());
+
+    let admin = AccessScope::organization(1);
+    let sales = PermissionService::scope_for(&db, &sales_user()).await?;
+
+    let scenarios: [(&str, &AccessScope, CustomerListFilter, u64); 5] = [
+        ("管理员 第1页", &admin, CustomerListFilter::default(), 1),
+        ("管理员 第100页", &admin, CustomerListFilter::default(), 100),
+        (
+            "管理员 结束跟进",
+            &admin,
+            CustomerListFilter { status: Some(NextAction::End), ..Default::default() },
+            1,
+        ),
+        (
+            "管理员 搜索",
+            &admin,
+            CustomerListFilter { search: Some("客户1234".to_string()), ..Default::default() },
+            1,
+        ),
+        ("销售 第1页", &sales, CustomerListFilter::default(), 1),
+    ];
+
+    println!("\n{:<16} {:>12} {:>10}", "场景", "单条查询", "总数");
+    for (label, scope, filter, page) in &scenarios {
+        let mut total = 0;
+        let started = Instant::now();
+        for _ in 0..ROUNDS {
+            let sort = CustomerSort::default();
+            (_, total) = CustomerListService::list(&db, scope, filter, sort, *page, PAGE_SIZE).await?;
+        }
+        println!("{:<16} {:>12?} {:>10}", label, started.elapsed() / ROUNDS, total);
+    }
+
+    // 其他排序方式，每种都从大到小和从小到大各查一页
+    let sorts = [
+        ("名称", CustomerSortField::Name),
+        ("评分", CustomerSortField::Rate),
+        ("创建时间", CustomerSortField::CreatedAt),
+        ("最新跟进时间", CustomerSortField::LatestTrackTime),
+        ("下次跟进时间", CustomerSortField::NextTrackTime),
+    ];
+    println!("\n{:<16} {:>12} {:>12}", "排序", "从小到大", "从大到小");
+    for (label, field) in sorts {
+        let mut elapsed = Vec::new();
+        for ascending in [true, false] {
+            let sort = CustomerSort { field, ascending };
+            let started = Instant::now();
+            for _ in 0..ROUNDS {
+                let filter = CustomerListFilter::default();
+                CustomerListService::list(&db, &admin, &filter, sort, 1, PAGE_SIZE).await?;
+            }
+            elapsed.push(started.elapsed() / ROUNDS);
+        }
+        println!("{:<16} {:>12?} {:>12?}", label, elapsed[0], elapsed[1]);
+    }
+
+    // 原来的做法太慢，只运行一次
+    println!("\n{:<16} {:>12} {:>10}", "场景", "原来的做法", "总数");
+    let unsearched = scenarios.iter().filter(|(_, _, filter, _)| filter.search.is_none());
+    for (label, scope, filter, page) in unsearched {
+        let started = Instant::now();
+        let total = legacy_list(&db, scope, filter.status.as_ref(), *page).await?;
+        println!("{:<16} {:>12?} {:>10}", label, started.elapsed(), total);
+    }
+
+    drop(db);
+    let _ = std::fs::remove_file(&path);
+    Ok(())
+}
+
+/// 一个组织、一个管理员和若干销售，客户轮流分给销售；
+/// 每 5 个客户中有 1 个没有跟进，其余有 1 到 4 条跟进，每 7 个客户中有 1 个最新跟进为结束跟进
+async fn seed(db: &DatabaseConnection, customers: u64) -> Result<(), DbErr> {
+    db.execute_unprepared(
+        "INSERT OR IGNORE INTO organizations (id, name) VALUES (1, 'bench')",
+    )
+    .await?;
+    db.execute_unprepared(&format!(
+        "WITH RECURSIVE seq(n) AS (SELECT 0 UNION ALL SELECT n + 1 FROM seq WHERE n < {SALES_USERS})
+         INSERT INTO users (id, username, password_hash, name, role, organization_id)
+         SELECT n + 1, 'user' || n, 'x', '用户' || n, CASE n WHEN 0 THEN 'admin' ELSE 'sales' END, 1 FROM seq"
+    ))
+    .await?;
+    db.execute_unprepared(&format!(
+        "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < {customers})
+         INSERT INTO customers (id, name, phone, address, notes, rate, customer_group, user_id,
+                                created_at, updated_at, is_deleted, organization_id)
+         SELECT n, '客户' || n, printf('138%08d', n), '地址' || (n % 100), NULL, n % 6, '团课',
+                n % {SALES_USERS} + 2,
+                datetime('2025-01-01', '+' || (n % 500) || ' days'),
+                datetime('2025-01-01', '+' || (n % 600) || ' days', '+' || n || ' seconds'),
+                0, 1
+         FROM seq"
+    ))
+    .await?;
+    db.execute_unprepared(&format!(
+        "WITH RECURSIVE seq(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM seq WHERE n < {customers}),
+              k(i) AS (VALUES (1), (2), (3), (4))
+         INSERT INTO customer_tracks (customer_id, content, next_action, track_time, created_at, updated_at, user_id)
+         SELECT n, '第' || i || '次跟进',
+                CASE WHEN i = n % 4 + 1 AND n % 7 = 0 THEN '结束跟进' ELSE '继续跟进' END,
+                datetime('2025-06-01', '+' || i || ' days', '+' || n || ' seconds'),
+                datetime('now'), datetime('now'), n % {SALES_USERS} + 2
+         FROM seq JOIN k ON i <= n % 4 + 1
+         WHERE n % 5 != 0"
+    ))
+    .await?;
+    db.execute_unprepared("ANALYZE").await?;
+    Ok(())
+}
+
+fn sales_user() -> CurrentUser {
+    CurrentUser {
+        id: 2,
+        username: "user1".to_string(),
+        name: "用户1".to_string(),
+        role: UserRole::Sales,
+        organization_id: 1,
+        session_id: None,
+        scopes: None,
+    }
+}
+
+/// 原来的列表做法：取出全部客户，逐个查询最新跟进和跟进次数，筛选后在内存中分页
+async fn legacy_list(
+    db: &DatabaseConnection,
+    scope: &AccessScope,
+    status: Option<&NextAction>,
+    page: u64,
+) -> Result<u64, DbErr> {
+    let all_customers = Customer::find()
+        .filter(scope.visible_condition())
+        .filter(customer::Column::IsDeleted.eq(false))
+        .order_by_desc(customer::Column::UpdatedAt)
+        .all(db)
+        .await?;
+
+    let mut rows = Vec::new();
+    for customer in all_customers {
+        let latest_track = CustomerTrack::find()
+            .filter(customer_track::Column::CustomerId.eq(customer.id))
+            .order_by_desc(customer_track::Column::TrackTime)
+            .one(db)
+            .await?;
+        if let Some(status) = status
+            && latest_track.as_ref().is_none_or(|track| track.next_action != *status)
+        {
+            continue;
+        }
+        let track_count = CustomerTrack::find()
+            .filter(customer_track::Column::CustomerId.eq(customer.id))
+            .count(db)
+            .await?;
+        rows.push((customer, latest_track, track_count));
+    }
+
+    let total = rows.len() as u64;
+    let _page: Vec<_> = rows
+        .into_iter()
+        .skip(((page - 1) * PAGE_SIZE) as usize)
+        .take(PAGE_SIZE as usize)
+        .collect();
+    Ok(total)
+}
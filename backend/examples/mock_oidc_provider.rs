@@ -0,0 +1,48 @@
+//! 本地模拟的 OpenID Connect 身份提供方，用于开发和测试单点登录。
+//!
+//! 授权请求会直接通过，以环境变量中配置的身份签发 HS256 身份令牌（用客户端密钥签名）。
+//!
+//! ```bash
+//! OIDC_CLIENT_ID=customer-tracker OIDC_CLIENT_SECRET=mock-secret \
+//! MOCK_OIDC_EMAIL=alice@example.com cargo run --example mock_oidc_provider
+//! ```
+//!
+//! 然后将后端的 `OIDC_ISSUER_URL` 设为 `http://127.0.0.1:9000`，`OIDC_ID_TOKEN_SIGNING_ALGS` 设为 `HS256`。
+
+#[path = "../tests/support/mock_oidc.rs"]
+mod mock_oidc;
+
+use std::{env, net::SocketAddr};
+
+use mock_oidc::{Identity, MockProvider};
+
+fn env_or(key: &str, default: &str) -> String {
+    env::var(key).unwrap_or_else(|_| default.to_string())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let port: u16 = env_or("MOCK_OIDC_PORT", "9000").parse()?;
+    let preferred_username = env_or("MOCK_OIDC_USERNAME", "alice");
+
+    let provider = MockProvider::new(
+        format!("http://127.0.0.1:{}", port),
+        env_or("OIDC_CLIENT_ID", "customer-tracker"),
+        env_or("OIDC_CLIENT_SECRET", "mock-secret"),
+        Identity {
+            sub: env_or("MOCK_OIDC_SUB", &format!("mock|{}", preferred_username)),
+            email: env_or("MOCK_OIDC_EMAIL", &format!("{}@example.com", preferred_username)),
+            email_verified: env_or("MOCK_OIDC_EMAIL_VERIFIED", "true") == "true",
+            name: env_or("MOCK_OIDC_NAME", &preferred_username),
+            preferred_username,
+        },
+    );
+    let issuer = provider.issuer.clone();
+
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    println!("Mock OIDC provider listening on {}", issuer);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, provider.router()).await?;
+
+    Ok(())
+}
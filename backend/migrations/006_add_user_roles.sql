@@ -0,0 +1,15 @@
+-- 006_add_user_roles.sql
+-- 添加用户角色和所属主管（团队）字段
+
+-- 现有用户默认为销售，保持原有的仅本人可见行为
+ALTER TABLE users
+ADD COLUMN role VARCHAR(20) NOT NULL DEFAULT 'sales'
+CHECK (role IN ('admin', 'manager', 'sales'));
+
+-- 销售所属的主管，主管可以查看和编辑其团队成员的客户
+ALTER TABLE users
+ADD COLUMN manager_id INTEGER NULL REFERENCES users(id) ON DELETE SET NULL;
+
+-- 创建索引
+CREATE INDEX idx_users_role ON users(role);
+CREATE INDEX idx_users_manager_id ON users(manager_id);
@@ -0,0 +1,20 @@
+-- 007_create_organizations.sql
+-- 多租户：创建组织表，并为用户和客户添加所属组织
+
+CREATE TABLE organizations (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(100) UNIQUE NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 现有数据全部归入默认组织
+INSERT INTO organizations (id, name) VALUES (1, '默认组织');
+
+ALTER TABLE users ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+ALTER TABLE customers ADD COLUMN organization_id INTEGER NOT NULL DEFAULT 1;
+
+-- 创建索引
+CREATE INDEX idx_users_organization_id ON users(organization_id);
+CREATE INDEX idx_customers_organization_id ON customers(organization_id);
+CREATE INDEX idx_customers_org_user ON customers(organization_id, user_id);
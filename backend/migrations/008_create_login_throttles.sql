@@ -0,0 +1,16 @@
+-- 008_create_login_throttles.sql
+-- 登录失败计数与锁定记录（按用户名和客户端IP分别统计）
+
+CREATE TABLE login_throttles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind VARCHAR(20) NOT NULL CHECK (kind IN ('username', 'ip')),
+    key VARCHAR(255) NOT NULL,
+    failed_count INTEGER NOT NULL DEFAULT 0,
+    lockout_count INTEGER NOT NULL DEFAULT 0,
+    locked_until TIMESTAMP NULL,
+    last_failed_at TIMESTAMP NULL,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+-- 创建索引
+CREATE UNIQUE INDEX idx_login_throttles_kind_key ON login_throttles(kind, key);
@@ -0,0 +1,23 @@
+-- 011_create_audit_log.sql
+-- 审计日志：记录客户、跟进记录和用户的每次增删改
+
+CREATE TABLE audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    organization_id INTEGER NOT NULL,
+    -- 操作人，命令行操作时为空
+    actor_id INTEGER NULL,
+    actor_name VARCHAR(100) NOT NULL,
+    action VARCHAR(20) NOT NULL CHECK (action IN ('create', 'update', 'delete')),
+    entity VARCHAR(30) NOT NULL CHECK (entity IN ('customer', 'customer_track', 'user')),
+    entity_id INTEGER NOT NULL,
+    -- {"before": {...}, "after": {...}}，更新时只包含变化的字段
+    changes TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (actor_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+-- 创建索引
+CREATE INDEX idx_audit_log_org_created_at ON audit_log(organization_id, created_at);
+CREATE INDEX idx_audit_log_entity ON audit_log(entity, entity_id);
+CREATE INDEX idx_audit_log_actor_id ON audit_log(actor_id);
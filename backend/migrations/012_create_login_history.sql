@@ -0,0 +1,28 @@
+-- 012_create_login_history.sql
+-- 登录历史，以及会话的来源信息
+
+-- 会话记录登录时的IP和客户端，便于用户辨认并吊销
+ALTER TABLE sessions ADD COLUMN ip_address VARCHAR(45) NULL;
+ALTER TABLE sessions ADD COLUMN user_agent VARCHAR(255) NULL;
+
+-- 每次登录尝试（成功或失败）一条记录
+CREATE TABLE login_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 用户名不存在或被锁定时可能为空
+    user_id INTEGER NULL,
+    username VARCHAR(50) NOT NULL,
+    ip_address VARCHAR(45) NOT NULL,
+    user_agent VARCHAR(255) NULL,
+    success BOOLEAN NOT NULL,
+    failure_reason VARCHAR(30) NULL CHECK (
+        failure_reason IN ('invalid_credentials', 'locked', 'invalid_two_factor_code')
+    ),
+    session_id INTEGER NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE SET NULL
+);
+
+-- 创建索引
+CREATE INDEX idx_login_history_user_id ON login_history(user_id, created_at);
+CREATE INDEX idx_login_history_username ON login_history(username);
@@ -0,0 +1,19 @@
+-- 013_add_oidc_login.sql
+-- OpenID Connect 单点登录
+
+-- 身份提供方的 subject 绑定到用户；邮箱用于首次登录时匹配已有用户
+ALTER TABLE users ADD COLUMN email VARCHAR(255) NULL;
+ALTER TABLE users ADD COLUMN oidc_subject VARCHAR(255) NULL;
+
+CREATE UNIQUE INDEX idx_users_email ON users(email);
+CREATE UNIQUE INDEX idx_users_oidc_subject ON users(oidc_subject);
+
+-- 发起授权时生成的 state、nonce 和 PKCE code_verifier，回调时一次性取出
+CREATE TABLE oidc_login_states (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    state_hash VARCHAR(64) NOT NULL UNIQUE,
+    nonce VARCHAR(128) NOT NULL,
+    code_verifier VARCHAR(128) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL
+);
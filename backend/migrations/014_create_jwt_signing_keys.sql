@@ -0,0 +1,18 @@
+-- 014_create_jwt_signing_keys.sql
+-- 访问令牌的非对称签名密钥，支持轮换
+
+CREATE TABLE jwt_signing_keys (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    -- 令牌头部的 kid，校验时据此选择公钥
+    kid VARCHAR(64) NOT NULL UNIQUE,
+    algorithm VARCHAR(20) NOT NULL,
+    -- PKCS#8 私钥（base64）
+    private_key TEXT NOT NULL,
+    -- 公钥（base64url），即 JWK 中的 x
+    public_key TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    -- 轮换后不再用于签名
+    retired_at TIMESTAMP NULL,
+    -- 超过该时间后不再接受此密钥签发的令牌
+    expires_at TIMESTAMP NULL
+);
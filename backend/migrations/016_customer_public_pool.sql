@@ -0,0 +1,71 @@
+-- 016_customer_public_pool.sql
+-- 公海：客户负责人可以为空，负责人为空的客户在公海中，组织内任何人都可以领取
+-- SQLite 不能修改列约束，需要重建 customers 表；重建期间关闭外键，避免删除旧表时级联删除跟进记录。
+-- PRAGMA foreign_keys 在事务中不生效，必须在 BEGIN 之前执行
+
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE customers_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(100) NOT NULL,
+    phone VARCHAR(20),
+    address TEXT,
+    notes TEXT,
+    rate REAL DEFAULT 0.0 CHECK(rate >= 0.0 AND rate <= 5.0),
+    user_id INTEGER,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    is_deleted BOOLEAN DEFAULT FALSE,
+    customer_group VARCHAR(20) NOT NULL DEFAULT '团课' CHECK (customer_group IN ('团课', '小班', '私教', '教培')),
+    organization_id INTEGER NOT NULL DEFAULT 1,
+    -- 删除用户时其客户退回公海
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+INSERT INTO customers_new (id, name, phone, address, notes, rate, user_id, created_at, updated_at, is_deleted, customer_group, organization_id)
+SELECT id, name, phone, address, notes, rate, user_id, created_at, updated_at, is_deleted, customer_group, organization_id FROM customers;
+
+DROP TABLE customers;
+ALTER TABLE customers_new RENAME TO customers;
+
+CREATE INDEX idx_customers_user_id ON customers(user_id);
+CREATE INDEX idx_customers_is_deleted ON customers(is_deleted);
+CREATE INDEX idx_customers_name ON customers(name);
+CREATE INDEX idx_customers_group ON customers(customer_group);
+CREATE INDEX idx_customers_user_group ON customers(user_id, customer_group);
+CREATE INDEX idx_customers_organization_id ON customers(organization_id);
+CREATE INDEX idx_customers_org_user ON customers(organization_id, user_id);
+
+-- 领取和退回公海也记入负责人变更历史：领取时原负责人为空，退回时新负责人为空
+CREATE TABLE customer_ownership_history_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_id INTEGER NOT NULL,
+    organization_id INTEGER NOT NULL,
+    from_user_id INTEGER NULL,
+    to_user_id INTEGER NULL,
+    -- 操作人，命令行或自动退回时为空
+    transferred_by INTEGER NULL,
+    transferred_by_name VARCHAR(100) NOT NULL,
+    reason TEXT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id),
+    FOREIGN KEY (from_user_id) REFERENCES users(id),
+    FOREIGN KEY (to_user_id) REFERENCES users(id),
+    FOREIGN KEY (transferred_by) REFERENCES users(id) ON DELETE SET NULL
+);
+
+INSERT INTO customer_ownership_history_new SELECT * FROM customer_ownership_history;
+
+DROP TABLE customer_ownership_history;
+ALTER TABLE customer_ownership_history_new RENAME TO customer_ownership_history;
+
+CREATE INDEX idx_customer_ownership_history_customer_id ON customer_ownership_history(customer_id);
+-- 统计用户24小时内的领取数量
+CREATE INDEX idx_customer_ownership_history_to_user ON customer_ownership_history(to_user_id, created_at);
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
@@ -0,0 +1,29 @@
+-- 017_create_customer_collaborators.sql
+-- 客户协作者：负责人以外的用户可以查看（read）或编辑并添加跟进记录（write）
+
+CREATE TABLE customer_collaborators (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_id INTEGER NOT NULL,
+    user_id INTEGER NOT NULL,
+    permission VARCHAR(10) NOT NULL DEFAULT 'read' CHECK (permission IN ('read', 'write')),
+    -- 添加人，命令行操作时为空
+    added_by INTEGER NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    FOREIGN KEY (added_by) REFERENCES users(id) ON DELETE SET NULL,
+    UNIQUE (customer_id, user_id)
+);
+
+CREATE INDEX idx_customer_collaborators_user_id ON customer_collaborators(user_id);
+
+-- 跟进记录的填写人，协作者添加的记录记在自己名下
+ALTER TABLE customer_tracks ADD COLUMN user_id INTEGER NULL DEFAULT NULL REFERENCES users(id) ON DELETE SET NULL;
+
+-- 已有记录视为当前负责人填写
+UPDATE customer_tracks SET user_id = (
+    SELECT customers.user_id FROM customers WHERE customers.id = customer_tracks.customer_id
+);
+
+CREATE INDEX idx_customer_tracks_user_id ON customer_tracks(user_id);
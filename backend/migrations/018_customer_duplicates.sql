@@ -0,0 +1,20 @@
+-- 018_customer_duplicates.sql
+-- 客户查重与合并：保存规范化后的电话用于比对，被合并的客户记录合并到哪个客户
+
+-- 只保留数字、去掉 86 / 0086 国家码后的电话
+ALTER TABLE customers ADD COLUMN phone_normalized VARCHAR(20) NULL DEFAULT NULL;
+
+-- 被合并的客户会被软删除，并指向保留下来的客户
+ALTER TABLE customers ADD COLUMN merged_into_id INTEGER NULL DEFAULT NULL REFERENCES customers(id) ON DELETE SET NULL;
+
+-- 已有客户按电话中允许出现的分隔符回填
+UPDATE customers SET phone_normalized = REPLACE(REPLACE(REPLACE(REPLACE(REPLACE(phone, ' ', ''), '-', ''), '+', ''), '(', ''), ')', '') WHERE phone IS NOT NULL;
+
+UPDATE customers SET phone_normalized = SUBSTR(phone_normalized, 5) WHERE LENGTH(phone_normalized) = 15 AND phone_normalized LIKE '0086%';
+
+UPDATE customers SET phone_normalized = SUBSTR(phone_normalized, 3) WHERE LENGTH(phone_normalized) = 13 AND phone_normalized LIKE '86%';
+
+UPDATE customers SET phone_normalized = NULL WHERE phone_normalized = '';
+
+CREATE INDEX idx_customers_org_phone_normalized ON customers(organization_id, phone_normalized);
+CREATE INDEX idx_customers_merged_into_id ON customers(merged_into_id);
@@ -0,0 +1,11 @@
+-- 019_customer_trash.sql
+-- 回收站：记录客户的删除时间和删除人，超过保留期限后彻底清除
+
+ALTER TABLE customers ADD COLUMN deleted_at TIMESTAMP NULL DEFAULT NULL;
+
+ALTER TABLE customers ADD COLUMN deleted_by INTEGER NULL DEFAULT NULL REFERENCES users(id) ON DELETE SET NULL;
+
+-- 已删除的客户无法确定删除时间，从升级时开始计算保留期限，避免升级后立即被清除
+UPDATE customers SET deleted_at = CURRENT_TIMESTAMP WHERE is_deleted = 1;
+
+CREATE INDEX idx_customers_deleted_at ON customers(is_deleted, deleted_at);
@@ -0,0 +1,37 @@
+-- 020_create_custom_fields.sql
+-- 自定义字段：管理员为客户定义额外的字段，可限定只用于某些分组
+
+CREATE TABLE custom_fields (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    organization_id INTEGER NOT NULL,
+    -- 接口和筛选参数中使用的标识，创建后不能修改
+    field_key VARCHAR(50) NOT NULL,
+    label VARCHAR(100) NOT NULL,
+    field_type VARCHAR(20) NOT NULL CHECK (field_type IN ('text', 'number', 'date', 'select', 'multi_select')),
+    -- 单选、多选的可选项，JSON 数组
+    options TEXT NOT NULL DEFAULT '[]',
+    -- 适用的客户分组，JSON 数组，为空表示所有分组
+    customer_groups TEXT NOT NULL DEFAULT '[]',
+    required BOOLEAN NOT NULL DEFAULT 0,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT 1,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    UNIQUE (organization_id, field_key)
+);
+
+-- 字段值统一按文本保存：数字为十进制字符串，日期为 YYYY-MM-DD，多选为 JSON 数组
+CREATE TABLE customer_field_values (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    customer_id INTEGER NOT NULL,
+    field_id INTEGER NOT NULL,
+    value TEXT NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    FOREIGN KEY (field_id) REFERENCES custom_fields(id) ON DELETE CASCADE,
+    UNIQUE (customer_id, field_id)
+);
+
+CREATE INDEX idx_customer_field_values_field_value ON customer_field_values(field_id, value);
@@ -0,0 +1,28 @@
+-- 021_create_tags.sql
+-- 客户标签：跨分组的自由标签，如 VIP、价格敏感、转介绍
+
+CREATE TABLE tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    organization_id INTEGER NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    -- 前端显示的颜色，如 #f5222d
+    color VARCHAR(20) NULL,
+    -- 创建人，命令行操作时为空
+    created_by INTEGER NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE,
+    FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL,
+    UNIQUE (organization_id, name)
+);
+
+CREATE TABLE customer_tags (
+    customer_id INTEGER NOT NULL,
+    tag_id INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (customer_id, tag_id),
+    FOREIGN KEY (customer_id) REFERENCES customers(id) ON DELETE CASCADE,
+    FOREIGN KEY (tag_id) REFERENCES tags(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_customer_tags_tag_id ON customer_tags(tag_id);
@@ -0,0 +1,81 @@
+-- 022_create_customer_groups.sql
+-- 客户分组改为由管理员在表中维护；customers.customer_group 仍保存分组名称
+
+-- 下面要重建 customers 表，重建期间关闭外键。PRAGMA foreign_keys 在事务中不生效，必须在 BEGIN 之前执行；
+-- 整个迁移放在一个事务中，中途失败时不会留下只重建了一半的表
+PRAGMA foreign_keys = OFF;
+
+BEGIN;
+
+CREATE TABLE customer_groups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    organization_id INTEGER NOT NULL,
+    -- 保存在 customers.customer_group 中的名称，创建后不能修改
+    name VARCHAR(20) NOT NULL,
+    display_name VARCHAR(50) NOT NULL,
+    color VARCHAR(20) NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    -- 归档的分组不能再分配给客户，已有客户保持不变
+    is_archived BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (organization_id, name),
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_customer_groups_org_sort ON customer_groups(organization_id, sort_order);
+
+-- 每个组织都带上原来固定的四个分组
+INSERT INTO customer_groups (organization_id, name, display_name, sort_order) SELECT id, '团课', '团课', 1 FROM organizations;
+
+INSERT INTO customer_groups (organization_id, name, display_name, sort_order) SELECT id, '小班', '小班', 2 FROM organizations;
+
+INSERT INTO customer_groups (organization_id, name, display_name, sort_order) SELECT id, '私教', '私教', 3 FROM organizations;
+
+INSERT INTO customer_groups (organization_id, name, display_name, sort_order) SELECT id, '教培', '教培', 4 FROM organizations;
+
+-- 客户上已有但不在上面的分组也补上
+INSERT OR IGNORE INTO customer_groups (organization_id, name, display_name, sort_order) SELECT DISTINCT organization_id, customer_group, customer_group, 100 FROM customers;
+
+-- 去掉 customer_group 的 CHECK 约束；SQLite 需要重建 customers 表
+CREATE TABLE customers_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name VARCHAR(100) NOT NULL,
+    phone VARCHAR(20),
+    address TEXT,
+    notes TEXT,
+    rate REAL DEFAULT 0.0 CHECK(rate >= 0.0 AND rate <= 5.0),
+    user_id INTEGER,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    is_deleted BOOLEAN DEFAULT FALSE,
+    customer_group VARCHAR(20) NOT NULL DEFAULT '团课',
+    organization_id INTEGER NOT NULL DEFAULT 1,
+    phone_normalized VARCHAR(20) NULL DEFAULT NULL,
+    merged_into_id INTEGER NULL DEFAULT NULL REFERENCES customers(id) ON DELETE SET NULL,
+    deleted_at TIMESTAMP NULL DEFAULT NULL,
+    deleted_by INTEGER NULL DEFAULT NULL REFERENCES users(id) ON DELETE SET NULL,
+    -- 删除用户时其客户退回公海
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE SET NULL
+);
+
+INSERT INTO customers_new (id, name, phone, address, notes, rate, user_id, created_at, updated_at, is_deleted, customer_group, organization_id, phone_normalized, merged_into_id, deleted_at, deleted_by)
+SELECT id, name, phone, address, notes, rate, user_id, created_at, updated_at, is_deleted, customer_group, organization_id, phone_normalized, merged_into_id, deleted_at, deleted_by FROM customers;
+
+DROP TABLE customers;
+ALTER TABLE customers_new RENAME TO customers;
+
+CREATE INDEX idx_customers_user_id ON customers(user_id);
+CREATE INDEX idx_customers_is_deleted ON customers(is_deleted);
+CREATE INDEX idx_customers_name ON customers(name);
+CREATE INDEX idx_customers_group ON customers(customer_group);
+CREATE INDEX idx_customers_user_group ON customers(user_id, customer_group);
+CREATE INDEX idx_customers_organization_id ON customers(organization_id);
+CREATE INDEX idx_customers_org_user ON customers(organization_id, user_id);
+CREATE INDEX idx_customers_org_phone_normalized ON customers(organization_id, phone_normalized);
+CREATE INDEX idx_customers_merged_into_id ON customers(merged_into_id);
+CREATE INDEX idx_customers_deleted_at ON customers(is_deleted, deleted_at);
+
+COMMIT;
+
+PRAGMA foreign_keys = ON;
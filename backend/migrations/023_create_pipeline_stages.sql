@@ -0,0 +1,50 @@
+-- 023_create_pipeline_stages.sql
+-- 销售阶段：管理员维护有顺序的阶段，跟进记录可以把客户推进到某个阶段，客户上保存当前阶段
+
+CREATE TABLE pipeline_stages (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    organization_id INTEGER NOT NULL,
+    -- 稳定的标识，创建后不能修改
+    stage_key VARCHAR(30) NOT NULL,
+    name VARCHAR(50) NOT NULL,
+    -- open 进行中，won 赢单，lost 输单；赢单和输单的阶段表示结束跟进
+    outcome VARCHAR(10) NOT NULL DEFAULT 'open' CHECK (outcome IN ('open', 'won', 'lost')),
+    color VARCHAR(20) NULL,
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (organization_id, stage_key),
+    FOREIGN KEY (organization_id) REFERENCES organizations(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_pipeline_stages_org_sort ON pipeline_stages(organization_id, sort_order);
+
+-- 每个组织的默认阶段
+INSERT INTO pipeline_stages (organization_id, stage_key, name, outcome, sort_order) SELECT id, 'new', '新线索', 'open', 1 FROM organizations;
+
+INSERT INTO pipeline_stages (organization_id, stage_key, name, outcome, sort_order) SELECT id, 'contacted', '已联系', 'open', 2 FROM organizations;
+
+INSERT INTO pipeline_stages (organization_id, stage_key, name, outcome, sort_order) SELECT id, 'trial_booked', '已约体验', 'open', 3 FROM organizations;
+
+INSERT INTO pipeline_stages (organization_id, stage_key, name, outcome, sort_order) SELECT id, 'trial_done', '已体验', 'open', 4 FROM organizations;
+
+INSERT INTO pipeline_stages (organization_id, stage_key, name, outcome, sort_order) SELECT id, 'signed', '已签约', 'won', 5 FROM organizations;
+
+INSERT INTO pipeline_stages (organization_id, stage_key, name, outcome, sort_order) SELECT id, 'lost', '已流失', 'lost', 6 FROM organizations;
+
+-- 客户当前所处的阶段
+ALTER TABLE customers ADD COLUMN stage_id INTEGER NULL DEFAULT NULL REFERENCES pipeline_stages(id) ON DELETE SET NULL;
+
+-- 跟进记录把客户推进到的阶段，为空表示没有改变阶段
+ALTER TABLE customer_tracks ADD COLUMN stage_id INTEGER NULL DEFAULT NULL REFERENCES pipeline_stages(id) ON DELETE SET NULL;
+
+-- 已有客户：最新跟进为「结束跟进」的视为已流失，有跟进的视为已联系，其余为新线索
+UPDATE customers SET stage_id = (SELECT s.id FROM pipeline_stages s WHERE s.organization_id = customers.organization_id AND s.stage_key = 'new');
+
+UPDATE customers SET stage_id = (SELECT s.id FROM pipeline_stages s WHERE s.organization_id = customers.organization_id AND s.stage_key = 'contacted') WHERE EXISTS (SELECT 1 FROM customer_tracks t WHERE t.customer_id = customers.id);
+
+UPDATE customers SET stage_id = (SELECT s.id FROM pipeline_stages s WHERE s.organization_id = customers.organization_id AND s.stage_key = 'lost') WHERE (SELECT t.next_action FROM customer_tracks t WHERE t.customer_id = customers.id ORDER BY t.track_time DESC, t.id DESC LIMIT 1) = '结束跟进';
+
+CREATE INDEX idx_customers_stage_id ON customers(stage_id);
+CREATE INDEX idx_customer_tracks_stage_id ON customer_tracks(stage_id);
@@ -0,0 +1,52 @@
+-- 024_create_search_index.sql
+-- 全文搜索：客户的名称、电话、地址、备注和跟进记录内容
+-- 使用 trigram 分词，中文不需要分词也能按任意三个字以上的片段匹配；更短的词由应用改用 LIKE 查询
+-- 索引表只保存索引，内容来自原表，由触发器保持同步
+
+CREATE VIRTUAL TABLE customers_fts USING fts5(
+    name,
+    phone,
+    address,
+    notes,
+    content = 'customers',
+    content_rowid = 'id',
+    tokenize = 'trigram'
+);
+
+CREATE VIRTUAL TABLE customer_tracks_fts USING fts5(
+    content,
+    content = 'customer_tracks',
+    content_rowid = 'id',
+    tokenize = 'trigram'
+);
+
+CREATE TRIGGER customers_fts_insert AFTER INSERT ON customers BEGIN
+    INSERT INTO customers_fts (rowid, name, phone, address, notes) VALUES (new.id, new.name, new.phone, new.address, new.notes);
+END;
+
+CREATE TRIGGER customers_fts_delete AFTER DELETE ON customers BEGIN
+    INSERT INTO customers_fts (customers_fts, rowid, name, phone, address, notes) VALUES ('delete', old.id, old.name, old.phone, old.address, old.notes);
+END;
+
+CREATE TRIGGER customers_fts_update AFTER UPDATE OF name, phone, address, notes ON customers BEGIN
+    INSERT INTO customers_fts (customers_fts, rowid, name, phone, address, notes) VALUES ('delete', old.id, old.name, old.phone, old.address, old.notes);
+    INSERT INTO customers_fts (rowid, name, phone, address, notes) VALUES (new.id, new.name, new.phone, new.address, new.notes);
+END;
+
+CREATE TRIGGER customer_tracks_fts_insert AFTER INSERT ON customer_tracks BEGIN
+    INSERT INTO customer_tracks_fts (rowid, content) VALUES (new.id, new.content);
+END;
+
+CREATE TRIGGER customer_tracks_fts_delete AFTER DELETE ON customer_tracks BEGIN
+    INSERT INTO customer_tracks_fts (customer_tracks_fts, rowid, content) VALUES ('delete', old.id, old.content);
+END;
+
+CREATE TRIGGER customer_tracks_fts_update AFTER UPDATE OF content ON customer_tracks BEGIN
+    INSERT INTO customer_tracks_fts (customer_tracks_fts, rowid, content) VALUES ('delete', old.id, old.content);
+    INSERT INTO customer_tracks_fts (rowid, content) VALUES (new.id, new.content);
+END;
+
+-- 为已有数据建立索引
+INSERT INTO customers_fts (customers_fts) VALUES ('rebuild');
+
+INSERT INTO customer_tracks_fts (customer_tracks_fts) VALUES ('rebuild');
@@ -0,0 +1,9 @@
+-- 025_add_customer_name_pinyin.sql
+-- 客户名称的拼音，用于按全拼和首字母搜索，例如用 zhangsan、zs、zhs 找到「张三」
+-- 拼音由应用在创建和修改客户时计算；已有客户由迁移程序在执行本迁移后补齐
+
+-- 全拼，例如 zhangsan
+ALTER TABLE customers ADD COLUMN name_pinyin VARCHAR(255) NOT NULL DEFAULT '';
+
+-- 首字母和声母简拼，不同时用空格分隔，例如 zs zhs
+ALTER TABLE customers ADD COLUMN name_initials VARCHAR(100) NOT NULL DEFAULT '';
@@ -0,0 +1,13 @@
+-- 026_customer_list_indexes.sql
+-- 客户列表在一条查询中完成筛选、排序和分页，补充相应的索引
+
+-- 按客户取最新一条跟进和统计跟进次数，替代只有 customer_id 的索引
+CREATE INDEX idx_customer_tracks_customer_time ON customer_tracks(customer_id, track_time DESC, id DESC);
+
+DROP INDEX IF EXISTS idx_customer_tracks_customer_id;
+
+-- 组织内未删除的客户按更新时间倒序分页
+CREATE INDEX idx_customers_org_updated ON customers(organization_id, is_deleted, updated_at DESC);
+
+-- 负责人的客户按更新时间倒序分页
+CREATE INDEX idx_customers_user_updated ON customers(user_id, is_deleted, updated_at DESC);
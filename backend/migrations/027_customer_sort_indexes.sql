@@ -0,0 +1,9 @@
+-- 027_customer_sort_indexes.sql
+-- 客户列表可以按名称、评分、创建时间排序，为组织内未删除的客户补充相应的索引
+
+-- 名称按全拼排序
+CREATE INDEX idx_customers_org_name_pinyin ON customers(organization_id, is_deleted, name_pinyin);
+
+CREATE INDEX idx_customers_org_rate ON customers(organization_id, is_deleted, rate);
+
+CREATE INDEX idx_customers_org_created ON customers(organization_id, is_deleted, created_at);
@@ -0,0 +1,5 @@
+-- 028_session_previous_token.sql
+-- 保存会话轮换前的刷新令牌哈希，只有提交的令牌与它相同才视为旧令牌被重复使用；
+-- 其他不匹配的令牌（例如猜测的令牌）只拒绝，不影响会话
+
+ALTER TABLE sessions ADD COLUMN previous_token_hash VARCHAR(64) NULL;
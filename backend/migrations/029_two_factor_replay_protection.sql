@@ -0,0 +1,17 @@
+-- 029_two_factor_replay_protection.sql
+-- 双因素认证的防重放：验证码和登录中间令牌都只能使用一次
+
+-- 最近一次通过校验的 TOTP 时间步，之后只接受更大的时间步（RFC 6238 第 5.2 节）
+ALTER TABLE users ADD COLUMN totp_last_step INTEGER NULL;
+
+-- 密码校验通过后签发的中间令牌，提交验证码成功时删除（仅存储哈希）
+CREATE TABLE two_factor_challenges (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    user_id INTEGER NOT NULL,
+    token_hash VARCHAR(64) NOT NULL UNIQUE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP NOT NULL,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_two_factor_challenges_user_id ON two_factor_challenges(user_id);
@@ -0,0 +1,8 @@
+-- 030_customer_duplicate_keys.sql
+-- 查重用的规范化名称，与规范化电话一样由应用在创建和修改客户时计算，SQL 中只做比较
+-- 已有客户的名称和电话由迁移程序在执行本迁移后按应用的规则重新计算（018 用 SQL 回填的电话只去掉了部分分隔符）
+
+-- 去掉所有空白并转小写的名称
+ALTER TABLE customers ADD COLUMN name_normalized VARCHAR(100) NOT NULL DEFAULT '';
+
+CREATE INDEX idx_customers_org_name_normalized ON customers(organization_id, name_normalized);
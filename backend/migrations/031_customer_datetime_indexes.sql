@@ -0,0 +1,14 @@
+-- 031_customer_datetime_indexes.sql
+-- 客户列表改为经 datetime() 比较和排序，按时间排序的索引相应改为表达式索引
+
+DROP INDEX IF EXISTS idx_customers_org_updated;
+
+CREATE INDEX idx_customers_org_updated ON customers(organization_id, is_deleted, datetime(updated_at) DESC);
+
+DROP INDEX IF EXISTS idx_customers_user_updated;
+
+CREATE INDEX idx_customers_user_updated ON customers(user_id, is_deleted, datetime(updated_at) DESC);
+
+DROP INDEX IF EXISTS idx_customers_org_created;
+
+CREATE INDEX idx_customers_org_created ON customers(organization_id, is_deleted, datetime(created_at));
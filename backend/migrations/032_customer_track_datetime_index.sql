@@ -0,0 +1,5 @@
+-- 032_customer_track_datetime_index.sql
+-- 客户列表经 datetime() 取每个客户最新的一条跟进，补充对应的表达式索引；
+-- 原有的 idx_customer_tracks_customer_time 仍用于跟进记录列表
+
+CREATE INDEX idx_customer_tracks_customer_latest ON customer_tracks(customer_id, datetime(track_time) DESC, id DESC);
@@ -1,14 +1,35 @@
 use clap::{Args, Parser, Subcommand};
-use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, QuerySelect, Set};
-use chrono::Utc;
-use uuid::Uuid;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, PaginatorTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use chrono::{Duration, Utc};
 
 use crate::{
     config::Config,
     database::create_database_connection,
-    entities::{user, user::Entity as User},
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        customer_group::CustomerGroup,
+        login_throttle::ThrottleKind, organization, organization::Entity as Organization, user,
+        user::Entity as User, user_role::UserRole,
+    },
     migration::{run_database_migrations, check_database_status},
-    utils::password::hash_password,
+    services::{
+        audit_service::{Actor, AuditService},
+        customer_group_service::CustomerGroupService,
+        customer_pool_service::{CustomerPoolService, PoolSettings},
+        pipeline_service::PipelineService,
+        customer_transfer_service::{CustomerTransferService, TransferRequest, TransferSelection},
+        jwt_key_service::JwtKeyService,
+        login_history_service::LoginHistoryService,
+        login_throttle_service::LoginThrottleService,
+        permission_service::AccessScope,
+        session_service::SessionService,
+        trash_service::TrashService,
+        two_factor_service::TwoFactorService,
+        user_service::{NewUser, UserListFilter, UserService, UserUpdate},
+    },
 };
 
 #[derive(Parser)]
@@ -23,6 +44,10 @@ pub struct Cli {
 pub enum Commands {
     /// 用户管理
     User(UserArgs),
+    /// 组织（租户）管理
+    Organization(OrganizationArgs),
+    /// 客户管理
+    Customer(CustomerArgs),
     /// 数据库管理
     Database(DatabaseArgs),
     /// 服务器管理
@@ -45,6 +70,18 @@ pub enum UserAction {
         password: String,
         #[arg(short, long)]
         name: String,
+        /// 邮箱，单点登录时用于匹配用户
+        #[arg(short, long)]
+        email: Option<String>,
+        /// 角色: admin, manager, sales
+        #[arg(short, long, default_value = "sales")]
+        role: UserRole,
+        /// 所属主管的用户名
+        #[arg(short, long)]
+        manager: Option<String>,
+        /// 所属组织ID
+        #[arg(short, long, default_value = "1")]
+        organization: i32,
     },
     /// 列出所有用户
     List {
@@ -63,6 +100,94 @@ pub enum UserAction {
         #[arg(short, long)]
         username: String,
     },
+    /// 设置用户角色和所属主管
+    SetRole {
+        #[arg(short, long)]
+        username: String,
+        /// 角色: admin, manager, sales
+        #[arg(short, long)]
+        role: UserRole,
+        /// 所属主管的用户名，不指定则清除
+        #[arg(short, long)]
+        manager: Option<String>,
+    },
+    /// 重置用户的双因素认证
+    #[command(name = "reset-2fa")]
+    Reset2fa {
+        #[arg(short, long)]
+        username: String,
+    },
+    /// 查看用户的有效会话和最近的登录记录
+    Sessions {
+        #[arg(short, long)]
+        username: String,
+        /// 显示的登录记录条数
+        #[arg(short, long, default_value = "20")]
+        limit: u64,
+    },
+    /// 解除登录失败锁定
+    Unlock {
+        #[arg(short, long)]
+        username: String,
+        /// 同时解除该IP的锁定
+        #[arg(long)]
+        ip: Option<String>,
+    },
+}
+
+#[derive(Args)]
+pub struct OrganizationArgs {
+    #[command(subcommand)]
+    pub action: OrganizationAction,
+}
+
+#[derive(Subcommand)]
+pub enum OrganizationAction {
+    /// 创建新组织
+    Create {
+        #[arg(short, long)]
+        name: String,
+    },
+    /// 列出所有组织
+    List,
+}
+
+#[derive(Args)]
+pub struct CustomerArgs {
+    #[command(subcommand)]
+    pub action: CustomerAction,
+}
+
+#[derive(Subcommand)]
+pub enum CustomerAction {
+    /// 转移客户负责人，跟进记录随客户保留
+    ///
+    /// 指定 --customer 时转移这些客户；否则转移 --from 用户名下的客户，可用 --group 和 --search 筛选
+    Transfer {
+        /// 原负责人的用户名
+        #[arg(short, long)]
+        from: Option<String>,
+        /// 接收人的用户名
+        #[arg(short, long)]
+        to: String,
+        /// 客户ID，可重复指定
+        #[arg(short, long = "customer")]
+        customers: Vec<i32>,
+        /// 只转移该分组的客户: 团课, 小班, 私教, 教培
+        #[arg(short, long)]
+        group: Option<CustomerGroup>,
+        /// 只转移名称或电话包含该内容的客户
+        #[arg(short, long)]
+        search: Option<String>,
+        /// 转移原因，记录在负责人变更历史中
+        #[arg(short, long)]
+        reason: Option<String>,
+        /// 给原负责人发送站内通知
+        #[arg(long)]
+        notify: bool,
+    },
+    /// 立即将长时间没有跟进的客户退回公海（服务器也会按 POOL_RELEASE_INTERVAL_MINUTES 定期执行）
+    ReleaseStale,
 }
 
 #[derive(Args)]
@@ -77,6 +202,12 @@ pub enum DatabaseAction {
     Migrate,
     /// 数据库状态
     Status,
+    /// 彻底清除回收站中超过保留期限的客户及其跟进记录（服务器也会按 TRASH_PURGE_INTERVAL_MINUTES 定期执行）
+    Purge {
+        /// 保留天数，默认使用 TRASH_RETENTION_DAYS
+        #[arg(short, long)]
+        days: Option<i64>,
+    },
 }
 
 #[derive(Args)]
@@ -94,20 +225,28 @@ pub enum ServerAction {
         #[arg(long, default_value = "0.0.0.0")]
         host: String,
     },
-    /// 生成JWT密钥
-    GenerateJwtSecret,
+    /// 轮换JWT签名密钥，旧密钥在访问令牌有效期内仍可用于校验
+    RotateJwtKey {
+        /// 旧密钥立即失效（密钥泄露时使用），已登录用户会用刷新令牌自动换取新令牌
+        #[arg(long)]
+        immediate: bool,
+    },
+    /// 列出JWT签名密钥
+    ListJwtKeys,
 }
 
 pub async fn handle_cli_command(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     match cli.command {
         Commands::User(user_args) => handle_user_command(user_args).await,
+        Commands::Organization(org_args) => handle_organization_command(org_args).await,
+        Commands::Customer(customer_args) => handle_customer_command(customer_args).await,
         Commands::Database(db_args) => handle_database_command(db_args).await,
         Commands::Server(server_args) => handle_server_command(server_args).await,
     }
 }
 
-async fn handle_user_command(args: UserArgs) -> Result<(), Box<dyn std::error::Error>> {
-    // 如果数据库不存在，先运行迁移
+/// 连接数据库，如果数据库不存在，先运行迁移
+async fn prepare_database() -> Result<DatabaseConnection, Box<dyn std::error::Error>> {
     let config = Config::from_env()?;
     let db_url = &config.database_url;
     
@@ -117,11 +256,28 @@ async fn handle_user_command(args: UserArgs) -> Result<(), Box<dyn std::error::E
     migrator.ensure_database_exists().await?;
     migrator.run_migrations().await?;
     
-    let db = create_database_connection(db_url).await?;
+    Ok(create_database_connection(db_url).await?)
+}
+
+async fn handle_user_command(args: UserArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let db = prepare_database().await?;
 
     match args.action {
-        UserAction::Create { username, password, name } => {
-            create_user(&db, &username, &password, &name).await?;
+        UserAction::Create { username, password, name, email, role, manager, organization } => {
+            let manager_id = match manager {
+                Some(manager) => Some(find_manager_id(&db, &manager).await?),
+                None => None,
+            };
+            let new_user = NewUser {
+                username,
+                password: Some(password),
+                name,
+                email,
+                role,
+                manager_id,
+                organization_id: organization,
+            };
+            create_user(&db, new_user).await?;
         }
         UserAction::List { limit } => {
             list_users(&db, limit).await?;
@@ -132,6 +288,18 @@ async fn handle_user_command(args: UserArgs) -> Result<(), Box<dyn std::error::E
         UserAction::Toggle { username } => {
             toggle_user_status(&db, &username).await?;
         }
+        UserAction::SetRole { username, role, manager } => {
+            set_user_role(&db, &username, role, manager.as_deref()).await?;
+        }
+        UserAction::Reset2fa { username } => {
+            reset_user_two_factor(&db, &username).await?;
+        }
+        UserAction::Sessions { username, limit } => {
+            show_user_sessions(&db, &username, limit).await?;
+        }
+        UserAction::Unlock { username, ip } => {
+            unlock_user(&db, &username, ip.as_deref()).await?;
+        }
     }
 
     Ok(())
@@ -139,37 +307,12 @@ async fn handle_user_command(args: UserArgs) -> Result<(), Box<dyn std::error::E
 
 async fn create_user(
     db: &DatabaseConnection,
-    username: &str,
-    password: &str,
-    name: &str,
+    new_user: NewUser,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    // Check if user already exists
-    let existing_user = User::find()
-        .filter(user::Column::Username.eq(username))
-        .one(db)
-        .await?;
-
-    if existing_user.is_some() {
-        println!("错误: 用户名 '{}' 已存在", username);
-        return Ok(());
-    }
-
-    let password_hash = hash_password(password)?;
-    let now = Utc::now();
-
-    let new_user = user::ActiveModel {
-        username: Set(username.to_string()),
-        password_hash: Set(password_hash),
-        name: Set(name.to_string()),
-        created_at: Set(now),
-        updated_at: Set(now),
-        is_active: Set(true),
-        last_login_at: Set(None),
-        ..Default::default()
-    };
-
-    let user = new_user.insert(db).await?;
-    println!("用户创建成功: {} ({})", user.name, user.username);
+    let policy = Config::from_env()?.password_policy();
+    let actor = Actor::cli(new_user.organization_id);
+    let user = UserService::create(db, &actor, &policy, new_user).await.map_err(|e| e.to_string())?;
+    println!("用户创建成功: {} ({}) - {}", user.name, user.username, user.role.display_name());
 
     Ok(())
 }
@@ -178,10 +321,13 @@ async fn list_users(
     db: &DatabaseConnection,
     limit: u64,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let users = User::find()
-        .limit(Some(limit))
-        .all(db)
-        .await?;
+    let filter = UserListFilter {
+        organization_id: None,
+        search: None,
+        page: 1,
+        limit,
+    };
+    let (users, _) = UserService::list(db, &filter).await.map_err(|e| e.to_string())?;
 
     if users.is_empty() {
         println!("没有找到用户");
@@ -189,8 +335,8 @@ async fn list_users(
     }
 
     println!("用户列表:");
-    println!("{:<5} {:<20} {:<30} {:<10} {:<20}", "ID", "用户名", "姓名", "状态", "最后登录");
-    println!("{:-<85}", "");
+    println!("{:<5} {:<20} {:<30} {:<8} {:<10} {:<10} {:<20}", "ID", "用户名", "姓名", "组织", "角色", "状态", "最后登录");
+    println!("{:-<103}", "");
 
     for user in users {
         let status = if user.is_active { "启用" } else { "禁用" };
@@ -199,8 +345,8 @@ async fn list_users(
             .unwrap_or_else(|| "从未".to_string());
 
         println!(
-            "{:<5} {:<20} {:<30} {:<10} {:<20}",
-            user.id, user.username, user.name, status, last_login
+            "{:<5} {:<20} {:<30} {:<8} {:<10} {:<10} {:<20}",
+            user.id, user.username, user.name, user.organization_id, user.role.display_name(), status, last_login
         );
     }
 
@@ -211,6 +357,60 @@ async fn reset_user_password(
     db: &DatabaseConnection,
     username: &str,
     new_password: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let user = UserService::find_by_username(db, username).await.map_err(|e| e.to_string())?;
+    let policy = Config::from_env()?.password_policy();
+    let actor = Actor::cli(user.organization_id);
+
+    UserService::reset_password(db, &actor, &policy, user, new_password).await.map_err(|e| e.to_string())?;
+    println!("用户 '{}' 的密码已重置", username);
+
+    Ok(())
+}
+
+async fn toggle_user_status(
+    db: &DatabaseConnection,
+    username: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let user = UserService::find_by_username(db, username).await.map_err(|e| e.to_string())?;
+    let actor = Actor::cli(user.organization_id);
+    let active = !user.is_active;
+
+    UserService::set_active(db, &actor, user, active).await.map_err(|e| e.to_string())?;
+
+    let status = if active { "启用" } else { "禁用" };
+    println!("用户 '{}' 已{}", username, status);
+
+    Ok(())
+}
+
+async fn set_user_role(
+    db: &DatabaseConnection,
+    username: &str,
+    role: UserRole,
+    manager: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let user = UserService::find_by_username(db, username).await.map_err(|e| e.to_string())?;
+    let manager_id = match manager {
+        Some(manager) => Some(find_manager_id(db, manager).await?),
+        None => None,
+    };
+
+    let actor = Actor::cli(user.organization_id);
+    let update = UserUpdate {
+        role: Some(role),
+        manager_id: Some(manager_id),
+        ..Default::default()
+    };
+    UserService::update(db, &actor, user, update).await.map_err(|e| e.to_string())?;
+    println!("用户 '{}' 的角色已设置为{}", username, role.display_name());
+
+    Ok(())
+}
+
+async fn reset_user_two_factor(
+    db: &DatabaseConnection,
+    username: &str,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let user = User::find()
         .filter(user::Column::Username.eq(username))
@@ -218,21 +418,27 @@ async fn reset_user_password(
         .await?
         .ok_or("用户不存在")?;
 
-    let password_hash = hash_password(new_password)?;
-    let mut user_active: user::ActiveModel = user.into();
-    
-    user_active.password_hash = Set(password_hash);
-    user_active.updated_at = Set(Utc::now());
-
-    user_active.update(db).await?;
-    println!("用户 '{}' 的密码已重置", username);
+    TwoFactorService::reset(db, user.clone()).await?;
+    let updated_user = User::find_by_id(user.id).one(db).await?;
+    AuditService::record(
+        db,
+        &Actor::cli(user.organization_id),
+        AuditAction::Update,
+        AuditEntity::User,
+        user.id,
+        Some(&user),
+        updated_user.as_ref(),
+    )
+    .await?;
+    println!("用户 '{}' 的双因素认证已重置", username);
 
     Ok(())
 }
 
-async fn toggle_user_status(
+async fn show_user_sessions(
     db: &DatabaseConnection,
     username: &str,
+    limit: u64,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let user = User::find()
         .filter(user::Column::Username.eq(username))
@@ -240,14 +446,207 @@ async fn toggle_user_status(
         .await?
         .ok_or("用户不存在")?;
 
-    let mut user_active: user::ActiveModel = user.clone().into();
-    user_active.is_active = Set(!user.is_active);
-    user_active.updated_at = Set(Utc::now());
+    let sessions = SessionService::list_active(db, user.id).await?;
+    println!("有效会话 ({}):", sessions.len());
+    println!("{:<6} {:<18} {:<18} {:<18} {:<40}", "ID", "IP", "登录时间", "最后活跃", "客户端");
+    println!("{:-<100}", "");
+    for session in sessions {
+        println!(
+            "{:<6} {:<18} {:<18} {:<18} {:<40}",
+            session.id,
+            session.ip_address.as_deref().unwrap_or("-"),
+            session.created_at.format("%Y-%m-%d %H:%M"),
+            session.updated_at.format("%Y-%m-%d %H:%M"),
+            session.user_agent.as_deref().unwrap_or("-"),
+        );
+    }
 
-    user_active.update(db).await?;
-    
-    let status = if !user.is_active { "启用" } else { "禁用" };
-    println!("用户 '{}' 已{}", username, status);
+    let history = LoginHistoryService::recent_for_user(db, &user, limit).await?;
+    println!();
+    println!("最近登录记录 ({}):", history.len());
+    println!("{:<20} {:<18} {:<20} {:<40}", "时间", "IP", "结果", "客户端");
+    println!("{:-<100}", "");
+    for entry in history {
+        let result = match entry.failure_reason {
+            None => "成功".to_string(),
+            Some(reason) => format!("失败: {}", reason.display_name()),
+        };
+        println!(
+            "{:<20} {:<18} {:<20} {:<40}",
+            entry.created_at.format("%Y-%m-%d %H:%M:%S"),
+            entry.ip_address,
+            result,
+            entry.user_agent.as_deref().unwrap_or("-"),
+        );
+    }
+
+    Ok(())
+}
+
+async fn unlock_user(
+    db: &DatabaseConnection,
+    username: &str,
+    ip: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if LoginThrottleService::clear(db, ThrottleKind::Username, username).await? {
+        println!("用户 '{}' 的登录锁定已解除", username);
+    } else {
+        println!("用户 '{}' 没有登录失败记录", username);
+    }
+
+    if let Some(ip) = ip {
+        if LoginThrottleService::clear(db, ThrottleKind::Ip, ip).await? {
+            println!("IP '{}' 的登录锁定已解除", ip);
+        } else {
+            println!("IP '{}' 没有登录失败记录", ip);
+        }
+    }
+
+    Ok(())
+}
+
+/// 主管是否属于同一组织、角色是否为主管由 `UserService` 校验
+async fn find_manager_id(
+    db: &DatabaseConnection,
+    username: &str,
+) -> Result<i32, Box<dyn std::error::Error>> {
+    let manager = UserService::find_by_username(db, username)
+        .await
+        .map_err(|_| format!("主管 '{}' 不存在", username))?;
+
+    Ok(manager.id)
+}
+
+async fn handle_organization_command(args: OrganizationArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let db = prepare_database().await?;
+
+    match args.action {
+        OrganizationAction::Create { name } => {
+            create_organization(&db, &name).await?;
+        }
+        OrganizationAction::List => {
+            list_organizations(&db).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn create_organization(
+    db: &DatabaseConnection,
+    name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let existing = Organization::find()
+        .filter(organization::Column::Name.eq(name))
+        .one(db)
+        .await?;
+
+    if existing.is_some() {
+        println!("错误: 组织 '{}' 已存在", name);
+        return Ok(());
+    }
+
+    let now = Utc::now();
+    let txn = db.begin().await?;
+    let org = organization::ActiveModel {
+        name: Set(name.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(&txn)
+    .await?;
+    // 新组织带上默认的客户分组和销售阶段，之后由管理员调整
+    CustomerGroupService::create_defaults(&txn, org.id).await?;
+    PipelineService::create_defaults(&txn, org.id).await?;
+    txn.commit().await?;
+
+    println!("组织创建成功: {} (ID: {})", org.name, org.id);
+
+    Ok(())
+}
+
+async fn list_organizations(db: &DatabaseConnection) -> Result<(), Box<dyn std::error::Error>> {
+    let orgs = Organization::find().all(db).await?;
+
+    if orgs.is_empty() {
+        println!("没有找到组织");
+        return Ok(());
+    }
+
+    println!("组织列表:");
+    println!("{:<5} {:<30} {:<10} {:<20}", "ID", "名称", "用户数", "创建时间");
+    println!("{:-<65}", "");
+
+    for org in orgs {
+        let user_count = User::find()
+            .filter(user::Column::OrganizationId.eq(org.id))
+            .count(db)
+            .await?;
+
+        println!(
+            "{:<5} {:<30} {:<10} {:<20}",
+            org.id,
+            org.name,
+            user_count,
+            org.created_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+
+    Ok(())
+}
+
+async fn handle_customer_command(args: CustomerArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let db = prepare_database().await?;
+
+    match args.action {
+        CustomerAction::Transfer { from, to, customers, group, search, reason, notify } => {
+            let target = UserService::find_by_username(&db, &to)
+                .await
+                .map_err(|_| format!("用户 '{}' 不存在", to))?;
+
+            let selection = match (customers.is_empty(), from) {
+                (false, None) => TransferSelection::Customers(customers),
+                (true, Some(from)) => {
+                    let source = UserService::find_by_username(&db, &from)
+                        .await
+                        .map_err(|_| format!("用户 '{}' 不存在", from))?;
+                    TransferSelection::OwnedBy {
+                        user_id: source.id,
+                        customer_group: group,
+                        search,
+                    }
+                }
+                _ => return Err("需要且只能指定 --customer 或 --from 之一".into()),
+            };
+
+            let request = TransferRequest {
+                to_user_id: target.id,
+                selection,
+                reason,
+                notify_previous_owner: notify,
+            };
+            // 命令行不受角色限制，但只能在接收人所在组织内转移
+            let outcome = CustomerTransferService::transfer(
+                &db,
+                &Actor::cli(target.organization_id),
+                &AccessScope::organization(target.organization_id),
+                request,
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+            println!("已将 {} 位客户转给 {} ({})", outcome.transferred.len(), target.name, target.username);
+            if outcome.skipped > 0 {
+                println!("{} 位客户已属于 {}，已跳过", outcome.skipped, target.username);
+            }
+        }
+        CustomerAction::ReleaseStale => {
+            let settings = PoolSettings::from_config(&Config::from_env()?);
+            let released = CustomerPoolService::release_stale(&db, &settings, Utc::now()).await?;
+            println!("已将 {} 位客户退回公海", released);
+        }
+    }
 
     Ok(())
 }
@@ -274,6 +673,19 @@ async fn handle_database_command(args: DatabaseArgs) -> Result<(), Box<dyn std::
                 Err(e) => println!("数据库连接失败: {}", e),
             }
         }
+        DatabaseAction::Purge { days } => {
+            let days = match days {
+                Some(days) => days,
+                None => Config::from_env()?.trash_retention_days,
+            };
+            if days <= 0 {
+                return Err("未设置保留天数，请通过 --days 或 TRASH_RETENTION_DAYS 指定".into());
+            }
+
+            let db = prepare_database().await?;
+            let purged = TrashService::purge_expired(&db, days, Utc::now()).await?;
+            println!("已从回收站清除 {} 位删除超过 {} 天的客户", purged, days);
+        }
     }
 
     Ok(())
@@ -285,12 +697,58 @@ async fn handle_server_command(args: ServerArgs) -> Result<(), Box<dyn std::erro
             println!("服务器启动功能需要在main.rs中实现");
             println!("计划在 {}:{} 启动服务器", host, port);
         }
-        ServerAction::GenerateJwtSecret => {
-            let secret = Uuid::new_v4().to_string();
-            println!("生成的JWT密钥: {}", secret);
-            println!("请将此密钥设置为环境变量 JWT_SECRET");
+        ServerAction::RotateJwtKey { immediate } => {
+            let config = Config::from_env()?;
+            let db = prepare_database().await?;
+            let grace = if immediate {
+                Duration::zero()
+            } else {
+                Duration::minutes(config.access_token_expire_minutes)
+            };
+
+            let key = JwtKeyService::rotate(&db, grace).await?;
+            println!("已生成新的签名密钥: {}", key.kid);
+            if immediate {
+                println!("旧密钥已立即失效");
+            } else {
+                println!("旧密钥在 {} 分钟内仍可校验已签发的令牌", config.access_token_expire_minutes);
+            }
+            println!("运行中的服务器会在一分钟内切换到新密钥");
+        }
+        ServerAction::ListJwtKeys => {
+            let db = prepare_database().await?;
+            list_jwt_keys(&db).await?;
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+async fn list_jwt_keys(db: &DatabaseConnection) -> Result<(), Box<dyn std::error::Error>> {
+    let keys = JwtKeyService::list(db).await?;
+
+    if keys.is_empty() {
+        println!("还没有签名密钥，服务器首次启动时会自动生成");
+        return Ok(());
+    }
+
+    println!("{:<34} {:<8} {:<18} {:<20}", "KID", "算法", "创建时间", "状态");
+    println!("{:-<85}", "");
+    for key in keys {
+        let status = match (key.retired_at, key.expires_at) {
+            (None, _) => "签名中".to_string(),
+            _ if !key.is_valid() => "已失效".to_string(),
+            (Some(_), Some(expires_at)) => format!("仅校验，至 {}", expires_at.format("%Y-%m-%d %H:%M")),
+            (Some(_), None) => "仅校验".to_string(),
+        };
+        println!(
+            "{:<34} {:<8} {:<18} {:<20}",
+            key.kid,
+            key.algorithm,
+            key.created_at.format("%Y-%m-%d %H:%M"),
+            status,
+        );
+    }
+
+    Ok(())
+}
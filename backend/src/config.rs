@@ -1,15 +1,44 @@
 use serde::{Deserialize, Serialize};
 use std::env;
 
+use crate::utils::validation::PasswordPolicy;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub database_url: String,
-    pub jwt_secret: String,
-    pub jwt_expire_hours: i64,
+    pub access_token_expire_minutes: i64,
+    pub refresh_token_expire_days: i64,
     pub server_host: String,
     pub server_port: u16,
     pub cors_origin: String,
     pub log_level: String,
+    pub login_max_failed_attempts: i32,
+    pub login_max_failed_attempts_per_ip: i32,
+    pub login_lockout_base_seconds: i64,
+    pub login_lockout_max_seconds: i64,
+    pub trust_proxy_headers: bool,
+    pub totp_issuer: String,
+    pub password_min_length: usize,
+    pub password_require_uppercase: bool,
+    pub password_require_lowercase: bool,
+    pub password_require_digit: bool,
+    pub password_require_symbol: bool,
+    pub oidc_issuer_url: Option<String>,
+    pub oidc_client_id: Option<String>,
+    pub oidc_client_secret: Option<String>,
+    pub oidc_redirect_uri: String,
+    pub oidc_scopes: String,
+    pub oidc_jit_provisioning: bool,
+    pub oidc_default_role: String,
+    pub oidc_default_organization_id: i32,
+    pub oidc_require_verified_email: bool,
+    pub oidc_id_token_signing_algs: String,
+    pub pool_release_days: i64,
+    pub pool_release_days_by_group: String,
+    pub pool_claim_limit: u64,
+    pub pool_release_interval_minutes: u64,
+    pub trash_retention_days: i64,
+    pub trash_purge_interval_minutes: u64,
 }
 
 impl Config {
@@ -19,12 +48,14 @@ impl Config {
         Ok(Config {
             database_url: env::var("DATABASE_URL")
                 .unwrap_or_else(|_| "sqlite://./data/customer_tracker.db".to_string()),
-            jwt_secret: env::var("JWT_SECRET")
-                .map_err(|_| "JWT_SECRET environment variable is required")?,
-            jwt_expire_hours: env::var("JWT_EXPIRE_HOURS")
-                .unwrap_or_else(|_| "24".to_string())
+            access_token_expire_minutes: env::var("ACCESS_TOKEN_EXPIRE_MINUTES")
+                .unwrap_or_else(|_| "15".to_string())
+                .parse()
+                .unwrap_or(15),
+            refresh_token_expire_days: env::var("REFRESH_TOKEN_EXPIRE_DAYS")
+                .unwrap_or_else(|_| "30".to_string())
                 .parse()
-                .unwrap_or(24),
+                .unwrap_or(30),
             server_host: env::var("SERVER_HOST")
                 .unwrap_or_else(|_| "0.0.0.0".to_string()),
             server_port: env::var("SERVER_PORT")
@@ -35,6 +66,95 @@ impl Config {
                 .unwrap_or_else(|_| "*".to_string()),
             log_level: env::var("LOG_LEVEL")
                 .unwrap_or_else(|_| "info".to_string()),
+            login_max_failed_attempts: env::var("LOGIN_MAX_FAILED_ATTEMPTS")
+                .unwrap_or_else(|_| "5".to_string())
+                .parse()
+                .unwrap_or(5),
+            login_max_failed_attempts_per_ip: env::var("LOGIN_MAX_FAILED_ATTEMPTS_PER_IP")
+                .unwrap_or_else(|_| "20".to_string())
+                .parse()
+                .unwrap_or(20),
+            login_lockout_base_seconds: env::var("LOGIN_LOCKOUT_BASE_SECONDS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            login_lockout_max_seconds: env::var("LOGIN_LOCKOUT_MAX_SECONDS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse()
+                .unwrap_or(3600),
+            trust_proxy_headers: env_flag("TRUST_PROXY_HEADERS"),
+            totp_issuer: env::var("TOTP_ISSUER")
+                .unwrap_or_else(|_| "CustomerTracker".to_string()),
+            password_min_length: env::var("PASSWORD_MIN_LENGTH")
+                .unwrap_or_else(|_| "6".to_string())
+                .parse()
+                .unwrap_or(6),
+            password_require_uppercase: env_flag("PASSWORD_REQUIRE_UPPERCASE"),
+            password_require_lowercase: env_flag("PASSWORD_REQUIRE_LOWERCASE"),
+            password_require_digit: env_flag("PASSWORD_REQUIRE_DIGIT"),
+            password_require_symbol: env_flag("PASSWORD_REQUIRE_SYMBOL"),
+            oidc_issuer_url: env_optional("OIDC_ISSUER_URL"),
+            oidc_client_id: env_optional("OIDC_CLIENT_ID"),
+            oidc_client_secret: env_optional("OIDC_CLIENT_SECRET"),
+            oidc_redirect_uri: env::var("OIDC_REDIRECT_URI")
+                .unwrap_or_else(|_| "http://localhost:3000/sso/callback".to_string()),
+            oidc_scopes: env::var("OIDC_SCOPES")
+                .unwrap_or_else(|_| "openid email profile".to_string()),
+            oidc_jit_provisioning: env_flag("OIDC_JIT_PROVISIONING"),
+            oidc_default_role: env::var("OIDC_DEFAULT_ROLE")
+                .unwrap_or_else(|_| "sales".to_string()),
+            oidc_default_organization_id: env::var("OIDC_DEFAULT_ORGANIZATION_ID")
+                .unwrap_or_else(|_| "1".to_string())
+                .parse()
+                .unwrap_or(1),
+            oidc_require_verified_email: env::var("OIDC_REQUIRE_VERIFIED_EMAIL")
+                .unwrap_or_else(|_| "true".to_string())
+                .parse()
+                .unwrap_or(true),
+            oidc_id_token_signing_algs: env::var("OIDC_ID_TOKEN_SIGNING_ALGS").unwrap_or_default(),
+            pool_release_days: env::var("POOL_RELEASE_DAYS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            pool_release_days_by_group: env::var("POOL_RELEASE_DAYS_BY_GROUP")
+                .unwrap_or_default(),
+            pool_claim_limit: env::var("POOL_CLAIM_LIMIT")
+                .unwrap_or_else(|_| "10".to_string())
+                .parse()
+                .unwrap_or(10),
+            pool_release_interval_minutes: env::var("POOL_RELEASE_INTERVAL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
+            trash_retention_days: env::var("TRASH_RETENTION_DAYS")
+                .unwrap_or_else(|_| "0".to_string())
+                .parse()
+                .unwrap_or(0),
+            trash_purge_interval_minutes: env::var("TRASH_PURGE_INTERVAL_MINUTES")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
         })
     }
-}
\ No newline at end of file
+
+    pub fn password_policy(&self) -> PasswordPolicy {
+        PasswordPolicy {
+            min_length: self.password_min_length,
+            require_uppercase: self.password_require_uppercase,
+            require_lowercase: self.password_require_lowercase,
+            require_digit: self.password_require_digit,
+            require_symbol: self.password_require_symbol,
+        }
+    }
+}
+
+fn env_flag(key: &str) -> bool {
+    env::var(key)
+        .map(|value| value.parse().unwrap_or(false))
+        .unwrap_or(false)
+}
+
+/// 未设置或为空时返回 `None`
+fn env_optional(key: &str) -> Option<String> {
+    env::var(key).ok().filter(|value| !value.trim().is_empty())
+}
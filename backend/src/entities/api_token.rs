@@ -0,0 +1,103 @@
+use std::{fmt, str::FromStr};
+
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// API 令牌的权限范围，按资源和读写区分
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ApiScope {
+    #[serde(rename = "customers:read")]
+    CustomersRead,
+    #[serde(rename = "customers:write")]
+    CustomersWrite,
+    #[serde(rename = "tracks:read")]
+    TracksRead,
+    #[serde(rename = "tracks:write")]
+    TracksWrite,
+}
+
+impl ApiScope {
+    pub const ALL: [ApiScope; 4] = [
+        ApiScope::CustomersRead,
+        ApiScope::CustomersWrite,
+        ApiScope::TracksRead,
+        ApiScope::TracksWrite,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiScope::CustomersRead => "customers:read",
+            ApiScope::CustomersWrite => "customers:write",
+            ApiScope::TracksRead => "tracks:read",
+            ApiScope::TracksWrite => "tracks:write",
+        }
+    }
+}
+
+impl FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|scope| scope.as_str() == s)
+            .ok_or_else(|| format!("未知的权限范围: {}", s))
+    }
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// 个人 API 令牌，只保存哈希，明文仅在创建时返回一次
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "api_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub token_prefix: String,
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+    /// 逗号分隔的权限范围，使用 [`Model::scope_list`] 解析
+    pub scopes: String,
+    pub expires_at: Option<ChronoDateTimeUtc>,
+    pub last_used_at: Option<ChronoDateTimeUtc>,
+    pub revoked_at: Option<ChronoDateTimeUtc>,
+    pub created_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn is_active(&self) -> bool {
+        self.revoked_at.is_none() && self.expires_at.is_none_or(|t| t > chrono::Utc::now())
+    }
+
+    /// 解析权限范围，忽略无法识别的值
+    pub fn scope_list(&self) -> Vec<ApiScope> {
+        self.scopes
+            .split(',')
+            .filter_map(|s| s.trim().parse().ok())
+            .collect()
+    }
+}
@@ -0,0 +1,63 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(20))")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    #[sea_orm(string_value = "create")]
+    Create,
+    #[sea_orm(string_value = "update")]
+    Update,
+    #[sea_orm(string_value = "delete")]
+    Delete,
+}
+
+/// 被审计的数据类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(30))")]
+#[serde(rename_all = "snake_case")]
+pub enum AuditEntity {
+    #[sea_orm(string_value = "customer")]
+    Customer,
+    #[sea_orm(string_value = "customer_track")]
+    CustomerTrack,
+    #[sea_orm(string_value = "user")]
+    User,
+}
+
+/// 一条审计记录
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "audit_log")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub organization_id: i32,
+    /// 操作人，命令行操作时为空
+    pub actor_id: Option<i32>,
+    pub actor_name: String,
+    pub action: AuditAction,
+    pub entity: AuditEntity,
+    pub entity_id: i32,
+    /// `{"before": ..., "after": ...}`，更新时只包含变化的字段
+    pub changes: Json,
+    pub created_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::ActorId",
+        to = "super::user::Column::Id"
+    )]
+    Actor,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Actor.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
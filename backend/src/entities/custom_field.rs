@@ -0,0 +1,79 @@
+use sea_orm::{entity::prelude::*, FromJsonQueryResult};
+use serde::{Deserialize, Serialize};
+
+use super::customer_group::CustomerGroup;
+
+/// 自定义字段类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(20))")]
+#[serde(rename_all = "snake_case")]
+pub enum CustomFieldType {
+    #[sea_orm(string_value = "text")]
+    Text,
+    #[sea_orm(string_value = "number")]
+    Number,
+    /// `YYYY-MM-DD`
+    #[sea_orm(string_value = "date")]
+    Date,
+    /// 从 `options` 中选一项
+    #[sea_orm(string_value = "select")]
+    Select,
+    /// 从 `options` 中选任意多项
+    #[sea_orm(string_value = "multi_select")]
+    MultiSelect,
+}
+
+impl CustomFieldType {
+    pub fn has_options(self) -> bool {
+        matches!(self, CustomFieldType::Select | CustomFieldType::MultiSelect)
+    }
+}
+
+/// 单选、多选的可选项
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct FieldOptions(pub Vec<String>);
+
+/// 字段适用的客户分组，为空表示所有分组
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize, FromJsonQueryResult)]
+pub struct FieldGroups(pub Vec<CustomerGroup>);
+
+/// 管理员定义的客户字段
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "custom_fields")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub organization_id: i32,
+    #[serde(rename = "key")]
+    pub field_key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    pub options: FieldOptions,
+    pub customer_groups: FieldGroups,
+    pub required: bool,
+    pub sort_order: i32,
+    pub is_active: bool,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+impl Model {
+    /// 字段是否用于该分组的客户
+    pub fn applies_to(&self, customer_group: &CustomerGroup) -> bool {
+        self.customer_groups.0.is_empty() || self.customer_groups.0.contains(customer_group)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::customer_field_value::Entity")]
+    CustomerFieldValue,
+}
+
+impl Related<super::customer_field_value::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomerFieldValue.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
 use super::next_action::NextAction;
@@ -14,10 +16,32 @@ pub struct Model {
     pub notes: Option<String>,
     pub rate: f32,
     pub customer_group: CustomerGroup,
-    pub user_id: i32,
+    /// 负责人，为空表示客户在公海中
+    pub user_id: Option<i32>,
     pub created_at: ChronoDateTimeUtc,
     pub updated_at: ChronoDateTimeUtc,
     pub is_deleted: bool,
+    pub organization_id: i32,
+    /// 规范化后的电话，仅用于查重
+    #[serde(skip_serializing)]
+    pub phone_normalized: Option<String>,
+    /// 被合并到的客户，合并后本客户即被软删除
+    pub merged_into_id: Option<i32>,
+    /// 移入回收站的时间
+    pub deleted_at: Option<ChronoDateTimeUtc>,
+    /// 删除人，命令行或系统操作时为空
+    pub deleted_by: Option<i32>,
+    /// 当前销售阶段，由跟进记录推进
+    pub stage_id: Option<i32>,
+    /// 名称的全拼，仅用于搜索
+    #[serde(default, skip_serializing)]
+    pub name_pinyin: String,
+    /// 名称的首字母和声母简拼，仅用于搜索
+    #[serde(default, skip_serializing)]
+    pub name_initials: String,
+    /// 规范化后的名称，仅用于查重
+    #[serde(default, skip_serializing)]
+    pub name_normalized: String,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -30,6 +54,12 @@ pub enum Relation {
     User,
     #[sea_orm(has_many = "super::customer_track::Entity")]
     CustomerTrack,
+    #[sea_orm(
+        belongs_to = "super::organization::Entity",
+        from = "Column::OrganizationId",
+        to = "super::organization::Column::Id"
+    )]
+    Organization,
 }
 
 impl Related<super::user::Entity> for Entity {
@@ -44,6 +74,12 @@ impl Related<super::customer_track::Entity> for Entity {
     }
 }
 
+impl Related<super::organization::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Organization.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -67,6 +103,11 @@ pub struct CreateCustomerRequest {
     pub notes: Option<String>,
     pub rate: Option<f32>,
     pub customer_group: Option<CustomerGroup>,
+    /// 自定义字段标识 -> 值
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub tag_ids: Vec<i32>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -77,4 +118,9 @@ pub struct UpdateCustomerRequest {
     pub notes: Option<String>,
     pub rate: Option<f32>,
     pub customer_group: Option<CustomerGroup>,
-}
\ No newline at end of file
+    /// 只修改出现的自定义字段，值为 `null` 表示清除
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, serde_json::Value>,
+    /// 给出时替换客户的全部标签
+    pub tag_ids: Option<Vec<i32>>,
+}
@@ -0,0 +1,46 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 客户在某个自定义字段上的值
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "customer_field_values")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub customer_id: i32,
+    pub field_id: i32,
+    /// 数字为十进制字符串，日期为 `YYYY-MM-DD`，多选为 JSON 数组
+    pub value: String,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::customer::Entity",
+        from = "Column::CustomerId",
+        to = "super::customer::Column::Id"
+    )]
+    Customer,
+    #[sea_orm(
+        belongs_to = "super::custom_field::Entity",
+        from = "Column::FieldId",
+        to = "super::custom_field::Column::Id"
+    )]
+    CustomField,
+}
+
+impl Related<super::customer::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Customer.def()
+    }
+}
+
+impl Related<super::custom_field::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomField.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
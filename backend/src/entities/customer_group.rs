@@ -1,31 +1,17 @@
-use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Deserializer, Serialize};
 use sea_orm::entity::prelude::*;
 
-/// 客户分组枚举
-#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
-#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(20))")]
-pub enum CustomerGroup {
-    #[sea_orm(string_value = "团课")]
-    GroupClass,
-    #[sea_orm(string_value = "小班")]
-    SmallClass,
-    #[sea_orm(string_value = "私教")]
-    Personal,
-    #[sea_orm(string_value = "教培")]
-    Training,
-}
+/// 客户分组名称，可选的分组由 customer_groups 表维护
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, DeriveValueType)]
+#[serde(transparent)]
+pub struct CustomerGroup(pub String);
 
-impl Serialize for CustomerGroup {
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        match self {
-            CustomerGroup::GroupClass => serializer.serialize_str("团课"),
-            CustomerGroup::SmallClass => serializer.serialize_str("小班"),
-            CustomerGroup::Personal => serializer.serialize_str("私教"),
-            CustomerGroup::Training => serializer.serialize_str("教培"),
-        }
+impl CustomerGroup {
+    /// 分组名称的最大长度，与 customers.customer_group 列一致
+    pub const MAX_LENGTH: usize = 20;
+
+    pub fn as_str(&self) -> &str {
+        &self.0
     }
 }
 
@@ -35,34 +21,31 @@ impl<'de> Deserialize<'de> for CustomerGroup {
         D: Deserializer<'de>,
     {
         let s = String::deserialize(deserializer)?;
-        match s.as_str() {
-            "团课" => Ok(CustomerGroup::GroupClass),
-            "小班" => Ok(CustomerGroup::SmallClass),
-            "私教" => Ok(CustomerGroup::Personal),
-            "教培" => Ok(CustomerGroup::Training),
-            // 为了兼容性，也支持英文变量名
-            "GroupClass" => Ok(CustomerGroup::GroupClass),
-            "SmallClass" => Ok(CustomerGroup::SmallClass),
-            "Personal" => Ok(CustomerGroup::Personal),
-            "Training" => Ok(CustomerGroup::Training),
-            _ => Err(serde::de::Error::unknown_variant(&s, &["团课", "小班", "私教", "教培"])),
-        }
+        s.parse().map_err(serde::de::Error::custom)
     }
 }
 
-impl Default for CustomerGroup {
-    fn default() -> Self {
-        CustomerGroup::GroupClass
+impl std::fmt::Display for CustomerGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
     }
 }
 
-impl std::fmt::Display for CustomerGroup {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            CustomerGroup::GroupClass => write!(f, "团课"),
-            CustomerGroup::SmallClass => write!(f, "小班"),
-            CustomerGroup::Personal => write!(f, "私教"),
-            CustomerGroup::Training => write!(f, "教培"),
+impl std::str::FromStr for CustomerGroup {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // 为了兼容性，也支持原来固定分组的英文变量名
+        let name = match s.trim() {
+            "GroupClass" => "团课",
+            "SmallClass" => "小班",
+            "Personal" => "私教",
+            "Training" => "教培",
+            name => name,
+        };
+        if name.is_empty() || name.chars().count() > Self::MAX_LENGTH {
+            return Err(format!("分组名称不能为空，且不能超过{}个字符", Self::MAX_LENGTH));
         }
+        Ok(CustomerGroup(name.to_string()))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,27 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::customer_group::CustomerGroup;
+
+/// 管理员维护的客户分组，在组织内按名称唯一
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "customer_groups")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub organization_id: i32,
+    /// 保存在客户上的名称，创建后不能修改
+    pub name: CustomerGroup,
+    pub display_name: String,
+    pub color: Option<String>,
+    pub sort_order: i32,
+    /// 归档的分组不能再分配给客户
+    pub is_archived: bool,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -14,6 +14,10 @@ pub struct Model {
     pub next_track_time: Option<ChronoDateTimeUtc>,
     pub created_at: ChronoDateTimeUtc,
     pub updated_at: ChronoDateTimeUtc,
+    /// 填写人，负责人或协作者
+    pub user_id: Option<i32>,
+    /// 这次跟进把客户推进到的阶段，为空表示没有改变阶段
+    pub stage_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -24,6 +28,12 @@ pub enum Relation {
         to = "super::customer::Column::Id"
     )]
     Customer,
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
 }
 
 impl Related<super::customer::Entity> for Entity {
@@ -32,6 +42,12 @@ impl Related<super::customer::Entity> for Entity {
     }
 }
 
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
 
 #[derive(Debug, Serialize)]
@@ -44,6 +60,10 @@ pub struct CustomerTrackInfo {
     pub next_track_time: Option<ChronoDateTimeUtc>,
     pub created_at: ChronoDateTimeUtc,
     pub updated_at: ChronoDateTimeUtc,
+    pub user_id: Option<i32>,
+    /// 填写人姓名
+    pub user_name: Option<String>,
+    pub stage_id: Option<i32>,
 }
 
 impl From<Model> for CustomerTrackInfo {
@@ -57,6 +77,9 @@ impl From<Model> for CustomerTrackInfo {
             next_track_time: track.next_track_time,
             created_at: track.created_at,
             updated_at: track.updated_at,
+            user_id: track.user_id,
+            user_name: None,
+            stage_id: track.stage_id,
         }
     }
 }
@@ -65,7 +88,10 @@ impl From<Model> for CustomerTrackInfo {
 pub struct CreateTrackRequest {
     pub customer_id: i32,
     pub content: String,
+    /// 未给出时按阶段推断，没有阶段时为继续跟进
     pub next_action: Option<NextAction>,
+    /// 把客户推进到该阶段
+    pub stage_id: Option<i32>,
     pub track_time: Option<ChronoDateTimeUtc>,
     pub next_track_time: Option<ChronoDateTimeUtc>,
 }
@@ -74,6 +100,8 @@ pub struct CreateTrackRequest {
 pub struct UpdateTrackRequest {
     pub content: Option<String>,
     pub next_action: Option<NextAction>,
+    /// 只有客户最新的跟进记录修改阶段时才会改变客户的阶段
+    pub stage_id: Option<i32>,
     pub track_time: Option<ChronoDateTimeUtc>,
     pub next_track_time: Option<ChronoDateTimeUtc>,
 }
\ No newline at end of file
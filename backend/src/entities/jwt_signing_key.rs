@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 访问令牌的签名密钥。
+///
+/// 最新的未退役密钥用于签名；退役的密钥在 `expires_at` 之前仍可用于校验，
+/// 已签发的令牌不会因为轮换而立即失效。
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "jwt_signing_keys")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(unique)]
+    pub kid: String,
+    pub algorithm: String,
+    #[serde(skip_serializing)]
+    pub private_key: String,
+    pub public_key: String,
+    pub created_at: ChronoDateTimeUtc,
+    pub retired_at: Option<ChronoDateTimeUtc>,
+    pub expires_at: Option<ChronoDateTimeUtc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// 当前是否仍可用于校验令牌
+    pub fn is_valid(&self) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > chrono::Utc::now())
+    }
+}
@@ -0,0 +1,63 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 登录失败的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(30))")]
+#[serde(rename_all = "snake_case")]
+pub enum LoginFailureReason {
+    /// 用户名或密码错误（含用户已停用）
+    #[sea_orm(string_value = "invalid_credentials")]
+    InvalidCredentials,
+    /// 用户名或IP处于锁定期
+    #[sea_orm(string_value = "locked")]
+    Locked,
+    /// 双因素验证码错误
+    #[sea_orm(string_value = "invalid_two_factor_code")]
+    InvalidTwoFactorCode,
+}
+
+impl LoginFailureReason {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            LoginFailureReason::InvalidCredentials => "用户名或密码错误",
+            LoginFailureReason::Locked => "已锁定",
+            LoginFailureReason::InvalidTwoFactorCode => "验证码错误",
+        }
+    }
+}
+
+/// 一次登录尝试
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "login_history")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub user_id: Option<i32>,
+    pub username: String,
+    pub ip_address: String,
+    pub user_agent: Option<String>,
+    pub success: bool,
+    pub failure_reason: Option<LoginFailureReason>,
+    /// 登录成功时创建的会话
+    pub session_id: Option<i32>,
+    pub created_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::user::Entity",
+        from = "Column::UserId",
+        to = "super::user::Column::Id"
+    )]
+    User,
+}
+
+impl Related<super::user::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::User.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
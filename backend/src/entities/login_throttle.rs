@@ -0,0 +1,34 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 登录限流的统计维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(20))")]
+#[serde(rename_all = "lowercase")]
+pub enum ThrottleKind {
+    #[sea_orm(string_value = "username")]
+    Username,
+    #[sea_orm(string_value = "ip")]
+    Ip,
+}
+
+/// 某个用户名或IP的登录失败计数和锁定状态
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "login_throttles")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub kind: ThrottleKind,
+    pub key: String,
+    pub failed_count: i32,
+    /// 连续锁定次数，用于计算指数退避的锁定时长
+    pub lockout_count: i32,
+    pub locked_until: Option<ChronoDateTimeUtc>,
+    pub last_failed_at: Option<ChronoDateTimeUtc>,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,11 +1,51 @@
 pub mod user;
+pub mod api_token;
+pub mod audit_log;
+pub mod custom_field;
 pub mod customer;
+pub mod customer_collaborator;
+pub mod customer_field_value;
 pub mod customer_group;
+pub mod customer_group_config;
+pub mod customer_ownership_history;
+pub mod customer_tag;
 pub mod customer_track;
+pub mod jwt_signing_key;
+pub mod login_history;
+pub mod login_throttle;
 pub mod next_action;
+pub mod notification;
+pub mod oidc_login_state;
+pub mod organization;
+pub mod pipeline_stage;
+pub mod recovery_code;
+pub mod session;
+pub mod tag;
+pub mod two_factor_challenge;
+pub mod user_role;
 
 pub use user::Entity as User;
+pub use api_token::Entity as ApiToken;
+pub use audit_log::Entity as AuditLog;
+pub use custom_field::Entity as CustomField;
 pub use customer::Entity as Customer;
+pub use customer_collaborator::Entity as CustomerCollaborator;
+pub use customer_field_value::Entity as CustomerFieldValue;
 pub use customer_group::CustomerGroup;
+pub use customer_group_config::Entity as CustomerGroupConfig;
+pub use customer_ownership_history::Entity as CustomerOwnershipHistory;
+pub use customer_tag::Entity as CustomerTag;
 pub use customer_track::Entity as CustomerTrack;
-pub use next_action::NextAction;
\ No newline at end of file
+pub use jwt_signing_key::Entity as JwtSigningKey;
+pub use login_history::Entity as LoginHistory;
+pub use login_throttle::Entity as LoginThrottle;
+pub use next_action::NextAction;
+pub use notification::Entity as Notification;
+pub use oidc_login_state::Entity as OidcLoginState;
+pub use organization::Entity as Organization;
+pub use pipeline_stage::Entity as PipelineStage;
+pub use recovery_code::Entity as RecoveryCode;
+pub use session::Entity as Session;
+pub use tag::Entity as Tag;
+pub use two_factor_challenge::Entity as TwoFactorChallenge;
+pub use user_role::UserRole;
\ No newline at end of file
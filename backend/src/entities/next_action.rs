@@ -2,9 +2,10 @@ use sea_orm::entity::prelude::*;
 use sea_orm::sea_query::StringLen;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum)]
 #[sea_orm(rs_type = "String", db_type = "String(StringLen::N(64))")]
 pub enum NextAction {
+    #[default]
     #[sea_orm(string_value = "继续跟进")]
     Continue,
     #[sea_orm(string_value = "结束跟进")]
@@ -32,12 +33,6 @@ impl<'de> Deserialize<'de> for NextAction {
     }
 }
 
-impl Default for NextAction {
-    fn default() -> Self {
-        NextAction::Continue
-    }
-}
-
 impl NextAction {
     pub fn as_str(&self) -> &str {
         match self {
@@ -46,6 +41,7 @@ impl NextAction {
         }
     }
     
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
         match s {
             "继续跟进" => Some(NextAction::Continue),
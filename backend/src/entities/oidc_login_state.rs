@@ -0,0 +1,23 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 一次未完成的单点登录授权请求，回调时取出并删除
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "oidc_login_states")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[serde(skip_serializing)]
+    pub state_hash: String,
+    #[serde(skip_serializing)]
+    pub nonce: String,
+    #[serde(skip_serializing)]
+    pub code_verifier: String,
+    pub created_at: ChronoDateTimeUtc,
+    pub expires_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
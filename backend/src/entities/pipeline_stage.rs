@@ -0,0 +1,52 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::next_action::NextAction;
+
+/// 阶段的结果：进行中、赢单、输单
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(10))")]
+#[serde(rename_all = "snake_case")]
+pub enum StageOutcome {
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "won")]
+    Won,
+    #[sea_orm(string_value = "lost")]
+    Lost,
+}
+
+impl StageOutcome {
+    /// 赢单和输单都表示结束跟进
+    pub fn next_action(self) -> NextAction {
+        match self {
+            StageOutcome::Open => NextAction::Continue,
+            StageOutcome::Won | StageOutcome::Lost => NextAction::End,
+        }
+    }
+}
+
+/// 管理员维护的销售阶段，在组织内按标识唯一
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "pipeline_stages")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub organization_id: i32,
+    /// 稳定的标识，创建后不能修改
+    #[serde(rename = "key")]
+    pub stage_key: String,
+    pub name: String,
+    pub outcome: StageOutcome,
+    pub color: Option<String>,
+    pub sort_order: i32,
+    /// 停用的阶段不能再选择，已在该阶段的客户保持不变
+    pub is_active: bool,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
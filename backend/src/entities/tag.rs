@@ -0,0 +1,31 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 客户标签，在组织内按名称唯一
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "tags")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    pub organization_id: i32,
+    pub name: String,
+    pub color: Option<String>,
+    /// 创建人，命令行操作时为空
+    pub created_by: Option<i32>,
+    pub created_at: ChronoDateTimeUtc,
+    pub updated_at: ChronoDateTimeUtc,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::customer_tag::Entity")]
+    CustomerTag,
+}
+
+impl Related<super::customer_tag::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CustomerTag.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
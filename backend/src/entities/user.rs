@@ -1,5 +1,6 @@
 use sea_orm::entity::prelude::*;
 use serde::{Deserialize, Serialize};
+use super::user_role::UserRole;
 
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
 #[sea_orm(table_name = "users")]
@@ -15,12 +16,31 @@ pub struct Model {
     pub updated_at: ChronoDateTimeUtc,
     pub is_active: bool,
     pub last_login_at: Option<ChronoDateTimeUtc>,
+    pub role: UserRole,
+    pub manager_id: Option<i32>,
+    pub organization_id: i32,
+    #[serde(skip_serializing)]
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    pub email: Option<String>,
+    /// 单点登录时身份提供方返回的 subject
+    #[serde(skip_serializing)]
+    pub oidc_subject: Option<String>,
+    /// 最近一次通过校验的 TOTP 时间步，防止验证码被重放
+    #[serde(skip_serializing)]
+    pub totp_last_step: Option<i64>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::customer::Entity")]
     Customer,
+    #[sea_orm(
+        belongs_to = "super::organization::Entity",
+        from = "Column::OrganizationId",
+        to = "super::organization::Column::Id"
+    )]
+    Organization,
 }
 
 impl Related<super::customer::Entity> for Entity {
@@ -29,6 +49,12 @@ impl Related<super::customer::Entity> for Entity {
     }
 }
 
+impl Related<super::organization::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Organization.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -36,6 +62,7 @@ pub struct UserInfo {
     pub id: i32,
     pub username: String,
     pub name: String,
+    pub role: UserRole,
     pub last_login_at: Option<ChronoDateTimeUtc>,
 }
 
@@ -45,6 +72,7 @@ impl From<Model> for UserInfo {
             id: user.id,
             username: user.username,
             name: user.name,
+            role: user.role,
             last_login_at: user.last_login_at,
         }
     }
@@ -0,0 +1,57 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// 用户角色
+///
+/// - 管理员：可以查看和编辑所有客户
+/// - 主管：可以查看和编辑自己及团队成员的客户
+/// - 销售：只能查看和编辑自己的客户
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::N(20))")]
+#[serde(rename_all = "lowercase")]
+pub enum UserRole {
+    #[sea_orm(string_value = "admin")]
+    Admin,
+    #[sea_orm(string_value = "manager")]
+    Manager,
+    #[default]
+    #[sea_orm(string_value = "sales")]
+    Sales,
+}
+
+impl UserRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UserRole::Admin => "admin",
+            UserRole::Manager => "manager",
+            UserRole::Sales => "sales",
+        }
+    }
+
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            UserRole::Admin => "管理员",
+            UserRole::Manager => "主管",
+            UserRole::Sales => "销售",
+        }
+    }
+}
+
+impl std::str::FromStr for UserRole {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "admin" => Ok(UserRole::Admin),
+            "manager" => Ok(UserRole::Manager),
+            "sales" => Ok(UserRole::Sales),
+            _ => Err(format!("未知角色 '{}'，可选值: admin, manager, sales", s)),
+        }
+    }
+}
+
+impl std::fmt::Display for UserRole {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
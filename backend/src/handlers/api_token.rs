@@ -0,0 +1,128 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::api_token::{self, ApiScope},
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::api_token_service::ApiTokenService,
+};
+
+/// Upper bound for `expires_in_days`; omit the field for a token that never expires
+const MAX_EXPIRE_DAYS: i64 = 3650;
+
+#[derive(Debug, Deserialize)]
+pub struct CreateApiTokenRequest {
+    pub name: String,
+    pub scopes: Vec<ApiScope>,
+    pub expires_in_days: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ApiTokenInfo {
+    pub id: i32,
+    pub name: String,
+    pub token_prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<api_token::Model> for ApiTokenInfo {
+    fn from(token: api_token::Model) -> Self {
+        Self {
+            id: token.id,
+            scopes: token.scope_list(),
+            name: token.name,
+            token_prefix: token.token_prefix,
+            expires_at: token.expires_at,
+            last_used_at: token.last_used_at,
+            created_at: token.created_at,
+        }
+    }
+}
+
+/// The plaintext `token` is only ever returned here
+#[derive(Debug, Serialize)]
+pub struct CreateApiTokenResponse {
+    pub token: String,
+    #[serde(flatten)]
+    pub info: ApiTokenInfo,
+}
+
+pub async fn list_api_tokens(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<ApiTokenInfo>>, StatusCode> {
+    let tokens = ApiTokenService::list_for_user(&app_state.db, current_user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(tokens.into_iter().map(ApiTokenInfo::from).collect()))
+}
+
+pub async fn create_api_token(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<CreateApiTokenRequest>,
+) -> Result<(StatusCode, Json<CreateApiTokenResponse>), ApiError> {
+    let name = req.name.trim();
+    if name.is_empty() || name.chars().count() > 100 {
+        return Err(ApiError::unprocessable("令牌名称不能为空且不超过100个字符"));
+    }
+    if req.scopes.is_empty() {
+        return Err(ApiError::unprocessable("至少需要选择一个权限范围"));
+    }
+
+    let expires_at = match req.expires_in_days {
+        Some(days) if !(1..=MAX_EXPIRE_DAYS).contains(&days) => {
+            return Err(ApiError::unprocessable(format!(
+                "有效期需在1到{}天之间",
+                MAX_EXPIRE_DAYS
+            )));
+        }
+        Some(days) => Some(Utc::now() + Duration::days(days)),
+        None => None,
+    };
+
+    let issued = ApiTokenService::create(
+        &app_state.db,
+        current_user.id,
+        name,
+        &req.scopes,
+        expires_at,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiTokenResponse {
+            token: issued.plaintext,
+            info: ApiTokenInfo::from(issued.token),
+        }),
+    ))
+}
+
+pub async fn revoke_api_token(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<i32>,
+) -> Result<StatusCode, StatusCode> {
+    let revoked = ApiTokenService::revoke(&app_state.db, current_user.id, id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if !revoked {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
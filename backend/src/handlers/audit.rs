@@ -0,0 +1,241 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{
+        audit_log::{self, AuditEntity, Entity as AuditLog},
+        user_role::UserRole,
+    },
+    handlers::auth::AppState,
+    middleware::auth::CurrentUser,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct AuditListQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+    pub entity: Option<AuditEntity>,
+    pub entity_id: Option<i32>,
+    /// Actor user id
+    pub user_id: Option<i32>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+fn default_page() -> u64 { 1 }
+fn default_limit() -> u64 { 20 }
+
+#[derive(Debug, Serialize)]
+pub struct AuditListResponse {
+    pub entries: Vec<audit_log::Model>,
+    pub total: u64,
+    pub page: u64,
+    pub limit: u64,
+}
+
+// 审计日志只对管理员开放，范围限定在本组织
+pub async fn list_audit_log(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<AuditListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<AuditListResponse>, StatusCode> {
+    if current_user.role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN);
+    }
+    if params.page == 0 || params.limit == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut query = AuditLog::find()
+        .filter(audit_log::Column::OrganizationId.eq(current_user.organization_id));
+
+    if let Some(entity) = params.entity {
+        query = query.filter(audit_log::Column::Entity.eq(entity));
+    }
+    if let Some(entity_id) = params.entity_id {
+        query = query.filter(audit_log::Column::EntityId.eq(entity_id));
+    }
+    if let Some(user_id) = params.user_id {
+        query = query.filter(audit_log::Column::ActorId.eq(user_id));
+    }
+    if let Some(from) = params.from {
+        query = query.filter(audit_log::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = params.to {
+        query = query.filter(audit_log::Column::CreatedAt.lte(to));
+    }
+
+    let paginator = query
+        .order_by_desc(audit_log::Column::CreatedAt)
+        .order_by_desc(audit_log::Column::Id)
+        .paginate(&app_state.db, params.limit);
+
+    let entries = paginator
+        .fetch_page(params.page - 1)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let total = paginator
+        .num_items()
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(AuditListResponse {
+        entries,
+        total,
+        page: params.page,
+        limit: params.limit,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_support::{self, TestApp, PASSWORD};
+
+    fn entries(json: &Value) -> &Vec<Value> {
+        json["entries"].as_array().unwrap()
+    }
+
+    #[tokio::test]
+    async fn only_admins_read_the_audit_log() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        test_support::create_user(app.db(), "boss", UserRole::Manager).await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+
+        for username in ["boss", "alice"] {
+            let token = app.login(username).await;
+            let (status, _) = app
+                .request(Method::GET, "/api/audit", Some(&token), None)
+                .await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+        }
+        let token = app.login("admin").await;
+        let (status, _) = app
+            .request(Method::GET, "/api/audit", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app
+            .request(Method::GET, "/api/audit?page=0", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn entries_are_scoped_to_the_organization() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let org = test_support::create_organization(app.db(), "其他组织").await;
+        test_support::create_user_in(app.db(), "admin_b", UserRole::Admin, org.id, None).await;
+
+        for username in ["admin", "admin_b"] {
+            let token = app.login(username).await;
+            let body = json!({ "name": format!("{} 的客户", username) });
+            let (status, _) = app
+                .request(Method::POST, "/api/customers", Some(&token), Some(body))
+                .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        for (username, organization_id) in [("admin", 1), ("admin_b", org.id)] {
+            let token = app.login(username).await;
+            let (_, json) = app
+                .request(Method::GET, "/api/audit", Some(&token), None)
+                .await;
+            assert_eq!(json["total"], 1);
+            let entry = &entries(&json)[0];
+            assert_eq!(entry["organization_id"], organization_id);
+            assert_eq!(entry["actor_name"], username);
+            assert_eq!(
+                entry["changes"]["after"]["name"],
+                format!("{} 的客户", username)
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn updates_record_only_the_changed_fields() {
+        let app = TestApp::new().await;
+        let admin = test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let admin_token = app.login("admin").await;
+
+        let body = json!({ "name": "Alice", "role": "manager" });
+        let uri = format!("/api/users/{}", alice.id);
+        let (status, _) = app
+            .request(Method::PUT, &uri, Some(&admin_token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        // 本人修改姓名和密码同样留下记录，密码只记录发生了修改
+        let token = app.login("alice").await;
+        let body = json!({ "name": "Alice Wang" });
+        let (status, _) = app
+            .request(Method::PUT, "/api/auth/me", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let body = json!({ "current_password": PASSWORD, "new_password": "changed456" });
+        let (status, _) = app
+            .request(Method::POST, "/api/auth/password", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let uri = format!("/api/audit?entity=user&entity_id={}", alice.id);
+        let (_, json) = app
+            .request(Method::GET, &uri, Some(&admin_token), None)
+            .await;
+        assert_eq!(json["total"], 3);
+        // 最新的在前
+        let [password, profile, by_admin] = entries(&json).as_slice() else {
+            panic!("审计记录数量不对: {}", json);
+        };
+        assert_eq!(by_admin["actor_id"], admin.id);
+        assert_eq!(by_admin["action"], "update");
+        assert_eq!(
+            by_admin["changes"],
+            json!({
+                "before": { "name": "alice", "role": "sales" },
+                "after": { "name": "Alice", "role": "manager" },
+            })
+        );
+        assert_eq!(profile["actor_id"], alice.id);
+        assert_eq!(
+            profile["changes"],
+            json!({ "before": { "name": "Alice" }, "after": { "name": "Alice Wang" } })
+        );
+        assert_eq!(password["actor_id"], alice.id);
+        assert_eq!(
+            password["changes"],
+            json!({ "before": { "password": "******" }, "after": { "password": "(已修改)" } })
+        );
+        assert!(!json.to_string().contains("password_hash"));
+
+        let uri = format!("/api/audit?user_id={}", alice.id);
+        let (_, json) = app
+            .request(Method::GET, &uri, Some(&admin_token), None)
+            .await;
+        assert_eq!(json["total"], 2);
+        let (_, json) = app
+            .request(
+                Method::GET,
+                "/api/audit?entity=customer",
+                Some(&admin_token),
+                None,
+            )
+            .await;
+        assert_eq!(json["total"], 0);
+    }
+}
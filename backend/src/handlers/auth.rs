@@ -1,19 +1,45 @@
 use axum::{
-    extract::State,
-    http::StatusCode,
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
     response::Json,
     Extension,
 };
-use chrono::Utc;
+use std::{net::SocketAddr, sync::Arc};
+use chrono::{Duration, Utc};
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter, Set,
+    TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    entities::{user, user::Entity as User},
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        login_history::LoginFailureReason,
+        user,
+        user::Entity as User,
+        user_role::UserRole,
+    },
+    handlers::error::ApiError,
     middleware::auth::CurrentUser,
-    utils::{jwt::generate_jwt_token, password::verify_password},
+    services::{
+        api_token_service::ApiTokenService,
+        audit_service::{Actor, AuditService},
+        customer_pool_service::PoolSettings,
+        jwt_key_service::JwtKeyStore,
+        login_history_service::LoginHistoryService,
+        login_throttle_service::{LoginThrottlePolicy, LoginThrottleService},
+        oidc_service::OidcProvider,
+        session_service::{SessionError, SessionService},
+        trash_service::TrashSettings,
+        two_factor_service::{TwoFactorError, TwoFactorService},
+    },
+    utils::{
+        client_ip::ClientInfo,
+        jwt::{generate_jwt_token, generate_two_factor_token, verify_two_factor_token},
+        password::{hash_password, verify_password},
+        validation::{validate_name, validate_password, PasswordPolicy},
+    },
 };
 
 #[derive(Debug, Deserialize)]
@@ -26,14 +52,40 @@ pub struct LoginRequest {
 pub struct LoginResponse {
     pub token: String,
     pub expires_in: i64,
+    pub refresh_token: String,
+    pub refresh_expires_in: i64,
     pub user: UserInfo,
 }
 
+/// Returned instead of [`LoginResponse`] when the account has two-factor
+/// authentication enabled; the client then calls `/api/auth/login/2fa`.
+#[derive(Debug, Serialize)]
+pub struct TwoFactorChallenge {
+    pub two_factor_required: bool,
+    pub two_factor_token: String,
+    pub expires_in: i64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum LoginResult {
+    Authenticated(LoginResponse),
+    TwoFactorRequired(TwoFactorChallenge),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorLoginRequest {
+    pub two_factor_token: String,
+    pub code: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct UserInfo {
     pub id: i32,
     pub username: String,
     pub name: String,
+    pub role: UserRole,
+    pub two_factor_enabled: bool,
     pub last_login_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
@@ -43,15 +95,24 @@ impl From<user::Model> for UserInfo {
             id: user.id,
             username: user.username,
             name: user.name,
+            role: user.role,
+            two_factor_enabled: user.totp_enabled,
             last_login_at: user.last_login_at,
         }
     }
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RefreshTokenRequest {
+    pub refresh_token: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct RefreshTokenResponse {
     pub token: String,
     pub expires_in: i64,
+    pub refresh_token: String,
+    pub refresh_expires_in: i64,
 }
 
 #[derive(Debug, Serialize)]
@@ -59,91 +120,352 @@ pub struct LogoutResponse {
     pub message: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct UpdateProfileRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+/// Every earlier session is revoked on a password change, so the caller
+/// receives a fresh token pair to stay signed in.
+#[derive(Debug, Serialize)]
+pub struct ChangePasswordResponse {
+    pub message: String,
+    pub token: String,
+    pub expires_in: i64,
+    pub refresh_token: String,
+    pub refresh_expires_in: i64,
+}
+
+/// Lifetime of the intermediate token between the password and code steps
+const TWO_FACTOR_TOKEN_EXPIRE_MINUTES: i64 = 5;
+
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
-    pub jwt_secret: String,
-    pub jwt_expire_hours: i64,
+    pub jwt_keys: Arc<JwtKeyStore>,
+    pub access_token_expire_minutes: i64,
+    pub refresh_token_expire_days: i64,
+    pub login_throttle: LoginThrottlePolicy,
+    pub trust_proxy_headers: bool,
+    pub totp_issuer: String,
+    pub password_policy: PasswordPolicy,
+    /// Present only when single sign-on is configured
+    pub oidc: Option<Arc<OidcProvider>>,
+    pub pool: PoolSettings,
+    pub trash: TrashSettings,
+}
+
+impl AsRef<JwtKeyStore> for AppState {
+    fn as_ref(&self) -> &JwtKeyStore {
+        &self.jwt_keys
+    }
 }
 
-impl AsRef<String> for AppState {
-    fn as_ref(&self) -> &String {
-        &self.jwt_secret
+impl AsRef<DatabaseConnection> for AppState {
+    fn as_ref(&self) -> &DatabaseConnection {
+        &self.db
     }
 }
 
 pub async fn login(
     State(app_state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
     Json(req): Json<LoginRequest>,
-) -> Result<Json<LoginResponse>, StatusCode> {
+) -> Result<Json<LoginResult>, StatusCode> {
+    let client = ClientInfo::from_request(&headers, peer, app_state.trust_proxy_headers);
+
+    // Reject early while the username or client IP is locked out
+    let locked_until =
+        LoginThrottleService::locked_until(&app_state.db, &req.username, Some(&client.ip))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if locked_until.is_some() {
+        LoginHistoryService::record_failure(
+            &app_state.db,
+            None,
+            &req.username,
+            &client,
+            LoginFailureReason::Locked,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
     // Find user by username
     let user = User::find()
         .filter(user::Column::Username.eq(&req.username))
         .filter(user::Column::IsActive.eq(true))
         .one(&app_state.db)
         .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     // Verify password
-    if !verify_password(&req.password, &user.password_hash)
+    let user = match user {
+        Some(user)
+            if verify_password(&req.password, &user.password_hash)
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)? =>
+        {
+            user
+        }
+        other => {
+            // Unknown usernames count too, so probing for accounts is throttled as well
+            LoginThrottleService::record_failure(
+                &app_state.db,
+                &app_state.login_throttle,
+                &req.username,
+                Some(&client.ip),
+            )
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            LoginHistoryService::record_failure(
+                &app_state.db,
+                other.map(|user| user.id),
+                &req.username,
+                &client,
+                LoginFailureReason::InvalidCredentials,
+            )
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+    };
+
+    // Password is only the first step when two-factor authentication is on;
+    // the failure counter is reset once the second step succeeds
+    if user.totp_enabled {
+        let expires_at = Utc::now() + Duration::minutes(TWO_FACTOR_TOKEN_EXPIRE_MINUTES);
+        let challenge_id = TwoFactorService::create_challenge(&app_state.db, user.id, expires_at)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let challenge = generate_two_factor_token(
+            user.id,
+            &challenge_id,
+            &app_state.jwt_keys,
+            TWO_FACTOR_TOKEN_EXPIRE_MINUTES,
+        )
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        return Ok(Json(LoginResult::TwoFactorRequired(TwoFactorChallenge {
+            two_factor_required: true,
+            two_factor_token: challenge.access_token,
+            expires_in: challenge.expires_in,
+        })));
+    }
+
+    LoginThrottleService::record_success(&app_state.db, &user.username)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(LoginResult::Authenticated(
+        complete_login(&app_state, user, &client).await?,
+    )))
+}
+
+pub async fn login_two_factor(
+    State(app_state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(req): Json<TwoFactorLoginRequest>,
+) -> Result<Json<LoginResponse>, StatusCode> {
+    let client = ClientInfo::from_request(&headers, peer, app_state.trust_proxy_headers);
+
+    let claims = verify_two_factor_token(&req.two_factor_token, &app_state.jwt_keys)
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let user = User::find_by_id(claims.user_id)
+        .filter(user::Column::IsActive.eq(true))
+        .one(&app_state.db)
+        .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
-    {
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Code guesses share the password lockout counters
+    let locked_until =
+        LoginThrottleService::locked_until(&app_state.db, &user.username, Some(&client.ip))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if locked_until.is_some() {
+        LoginHistoryService::record_failure(
+            &app_state.db,
+            Some(user.id),
+            &user.username,
+            &client,
+            LoginFailureReason::Locked,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Err(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    let verified = TwoFactorService::verify(&app_state.db, &user, &app_state.totp_issuer, &req.code)
+        .await
+        .map_err(|e| match e {
+            TwoFactorError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            _ => StatusCode::UNAUTHORIZED,
+        })?;
+
+    if !verified {
+        LoginThrottleService::record_failure(
+            &app_state.db,
+            &app_state.login_throttle,
+            &user.username,
+            Some(&client.ip),
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        LoginHistoryService::record_failure(
+            &app_state.db,
+            Some(user.id),
+            &user.username,
+            &client,
+            LoginFailureReason::InvalidTwoFactorCode,
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    // Generate JWT token
-    let token_pair = generate_jwt_token(
-        user.id,
-        &user.username,
-        &user.name,
-        &app_state.jwt_secret,
-        app_state.jwt_expire_hours,
-    )
-    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // The intermediate token is single-use: a replayed one finds no pending login
+    let consumed = TwoFactorService::consume_challenge(&app_state.db, user.id, &claims.jti)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if !consumed {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    LoginThrottleService::record_success(&app_state.db, &user.username)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(complete_login(&app_state, user, &client).await?))
+}
+
+/// Open a session for a fully authenticated user and issue the token pair
+pub(crate) async fn complete_login(
+    app_state: &AppState,
+    user: user::Model,
+    client: &ClientInfo,
+) -> Result<LoginResponse, StatusCode> {
+    let (session_id, tokens) = issue_tokens(app_state, &user, client).await?;
+    LoginHistoryService::record_success(&app_state.db, &user, client, session_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     // Update last_login_at
-    let mut user_active: user::ActiveModel = user.clone().into();
+    let mut user_active: user::ActiveModel = user.into();
     user_active.last_login_at = Set(Some(Utc::now()));
     let updated_user = user_active
         .update(&app_state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(LoginResponse {
-        token: token_pair.access_token,
-        expires_in: token_pair.expires_in,
+    Ok(LoginResponse {
+        token: tokens.token,
+        expires_in: tokens.expires_in,
+        refresh_token: tokens.refresh_token,
+        refresh_expires_in: tokens.refresh_expires_in,
         user: UserInfo::from(updated_user),
-    }))
+    })
+}
+
+/// Create a new session and the access token bound to it; returns the
+/// session id along with the token pair
+async fn issue_tokens(
+    app_state: &AppState,
+    user: &user::Model,
+    client: &ClientInfo,
+) -> Result<(i32, RefreshTokenResponse), StatusCode> {
+    // Create a session holding the refresh token
+    let issued = SessionService::create_session(
+        &app_state.db,
+        user.id,
+        app_state.refresh_token_expire_days,
+        client,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Generate JWT token bound to the session
+    let token_pair = generate_jwt_token(
+        user,
+        issued.session.id,
+        &app_state.jwt_keys,
+        app_state.access_token_expire_minutes,
+    )
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok((
+        issued.session.id,
+        RefreshTokenResponse {
+            token: token_pair.access_token,
+            expires_in: token_pair.expires_in,
+            refresh_token: issued.refresh_token,
+            refresh_expires_in: app_state.refresh_token_expire_days * 24 * 3600,
+        },
+    ))
 }
 
 pub async fn logout(
-    Extension(_current_user): Extension<CurrentUser>,
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
 ) -> Result<Json<LogoutResponse>, StatusCode> {
-    // In a JWT-based system, logout is typically handled client-side
-    // by removing the token from storage. Here we just return a success message.
+    // Revoke the session so both the refresh token and any access token
+    // issued for it stop working immediately
+    if let Some(session_id) = current_user.session_id {
+        SessionService::revoke(&app_state.db, session_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+
     Ok(Json(LogoutResponse {
         message: "Successfully logged out".to_string(),
     }))
 }
 
 pub async fn refresh_token(
-    Extension(current_user): Extension<CurrentUser>,
     State(app_state): State<AppState>,
+    Json(req): Json<RefreshTokenRequest>,
 ) -> Result<Json<RefreshTokenResponse>, StatusCode> {
-    // Generate new JWT token with current user info
+    // Rotate the refresh token; a reused token revokes the whole session
+    let issued = SessionService::rotate(
+        &app_state.db,
+        &req.refresh_token,
+        app_state.refresh_token_expire_days,
+    )
+    .await
+    .map_err(|e| match e {
+        SessionError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::UNAUTHORIZED,
+    })?;
+
+    let user = User::find_by_id(issued.session.user_id)
+        .filter(user::Column::IsActive.eq(true))
+        .one(&app_state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
     let token_pair = generate_jwt_token(
-        current_user.id,
-        &current_user.username,
-        &current_user.name,
-        &app_state.jwt_secret,
-        app_state.jwt_expire_hours,
+        &user,
+        issued.session.id,
+        &app_state.jwt_keys,
+        app_state.access_token_expire_minutes,
     )
     .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(RefreshTokenResponse {
         token: token_pair.access_token,
         expires_in: token_pair.expires_in,
+        refresh_token: issued.refresh_token,
+        refresh_expires_in: app_state.refresh_token_expire_days * 24 * 3600,
     }))
 }
 
@@ -153,6 +475,7 @@ pub async fn get_current_user(
 ) -> Result<Json<UserInfo>, StatusCode> {
     // Get fresh user data from database
     let user = User::find_by_id(current_user.id)
+        .filter(user::Column::OrganizationId.eq(current_user.organization_id))
         .filter(user::Column::IsActive.eq(true))
         .one(&app_state.db)
         .await
@@ -160,4 +483,201 @@ pub async fn get_current_user(
         .ok_or(StatusCode::UNAUTHORIZED)?;
 
     Ok(Json(UserInfo::from(user)))
-}
\ No newline at end of file
+}
+
+pub async fn update_current_user(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<UpdateProfileRequest>,
+) -> Result<Json<UserInfo>, ApiError> {
+    let name = req.name.trim();
+    if !validate_name(name) {
+        return Err(ApiError::unprocessable("姓名不能为空且不超过100个字符"));
+    }
+
+    let user = User::find_by_id(current_user.id)
+        .filter(user::Column::OrganizationId.eq(current_user.organization_id))
+        .filter(user::Column::IsActive.eq(true))
+        .one(&app_state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let mut user_active: user::ActiveModel = user.clone().into();
+    user_active.name = Set(name.to_string());
+    user_active.updated_at = Set(Utc::now());
+
+    // Audited like the admin user endpoints
+    let txn = app_state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let updated_user = user_active
+        .update(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    AuditService::record(
+        &txn,
+        &Actor::from(&current_user),
+        AuditAction::Update,
+        AuditEntity::User,
+        updated_user.id,
+        Some(&user),
+        Some(&updated_user),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(UserInfo::from(updated_user)))
+}
+
+pub async fn change_password(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(req): Json<ChangePasswordRequest>,
+) -> Result<Json<ChangePasswordResponse>, ApiError> {
+    let user = User::find_by_id(current_user.id)
+        .filter(user::Column::OrganizationId.eq(current_user.organization_id))
+        .filter(user::Column::IsActive.eq(true))
+        .one(&app_state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Guesses at the current password share the login lockout counters, so a
+    // stolen access token cannot be used to brute-force the password
+    let client = ClientInfo::from_request(&headers, peer, app_state.trust_proxy_headers);
+    let locked_until =
+        LoginThrottleService::locked_until(&app_state.db, &user.username, Some(&client.ip))
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if locked_until.is_some() {
+        return Err(StatusCode::TOO_MANY_REQUESTS.into());
+    }
+
+    if !verify_password(&req.current_password, &user.password_hash)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        LoginThrottleService::record_failure(
+            &app_state.db,
+            &app_state.login_throttle,
+            &user.username,
+            Some(&client.ip),
+        )
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Err(ApiError::unprocessable("当前密码错误"));
+    }
+
+    validate_password(&req.new_password, &app_state.password_policy)
+        .map_err(ApiError::unprocessable)?;
+
+    let password_hash =
+        hash_password(&req.new_password).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.password_hash = Set(password_hash);
+    user_active.updated_at = Set(Utc::now());
+
+    let txn = app_state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let user = user_active
+        .update(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // The hash is never serialized, so only the fact of the change is recorded
+    AuditService::record(
+        &txn,
+        &Actor::from(&current_user),
+        AuditAction::Update,
+        AuditEntity::User,
+        user.id,
+        Some(&serde_json::json!({ "password": "******" })),
+        Some(&serde_json::json!({ "password": "(已修改)" })),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Invalidate every session and API token issued before the change, then sign
+    // the caller in again
+    SessionService::revoke_all_for_user(&txn, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    ApiTokenService::revoke_all_for_user(&txn, user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    LoginThrottleService::record_success(&app_state.db, &user.username)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let (_, tokens) = issue_tokens(&app_state, &user, &client).await?;
+
+    Ok(Json(ChangePasswordResponse {
+        message: "密码已修改".to_string(),
+        token: tokens.token,
+        expires_in: tokens.expires_in,
+        refresh_token: tokens.refresh_token,
+        refresh_expires_in: tokens.refresh_expires_in,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::{self, TestApp, PASSWORD};
+
+    #[tokio::test]
+    async fn change_password_revokes_sessions_and_api_tokens() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let token = app.login("alice").await;
+        let (status, created) = app
+            .request(
+                Method::POST,
+                "/api/auth/tokens",
+                Some(&token),
+                Some(json!({ "name": "script", "scopes": ["customers:read"] })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", created);
+        let api_token = created["token"].as_str().unwrap().to_string();
+        let (status, _) = app.request(Method::GET, "/api/customers", Some(&api_token), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let body = json!({ "current_password": PASSWORD, "new_password": "changed456" });
+        let (status, changed) =
+            app.request(Method::POST, "/api/auth/password", Some(&token), Some(body)).await;
+        assert_eq!(status, StatusCode::OK, "{}", changed);
+
+        let (status, _) = app.request(Method::GET, "/api/auth/me", Some(&token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.request(Method::GET, "/api/customers", Some(&api_token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let new_token = changed["token"].as_str().unwrap();
+        let (status, _) = app.request(Method::GET, "/api/auth/me", Some(new_token), None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn wrong_current_passwords_are_throttled() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let token = app.login("alice").await;
+        let wrong = json!({ "current_password": "wrong", "new_password": "changed456" });
+
+        // 测试策略中同一用户名最多连续失败 3 次
+        for _ in 0..3 {
+            let (status, _) = app
+                .request(Method::POST, "/api/auth/password", Some(&token), Some(wrong.clone()))
+                .await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        }
+
+        let right = json!({ "current_password": PASSWORD, "new_password": "changed456" });
+        let (status, _) =
+            app.request(Method::POST, "/api/auth/password", Some(&token), Some(right)).await;
+        assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    }
+}
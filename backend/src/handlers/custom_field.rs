@@ -0,0 +1,224 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{
+        custom_field::{self, CustomFieldType},
+        customer_group::CustomerGroup,
+        user_role::UserRole,
+    },
+    handlers::{auth::AppState, customer_group::customer_group_error, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        custom_field_service::{
+            CustomFieldError, CustomFieldService, CustomFieldUpdate, NewCustomField,
+        },
+        customer_group_service::CustomerGroupService,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CustomFieldListQuery {
+    /// Only fields that apply to this group
+    pub customer_group: Option<CustomerGroup>,
+    /// Admins may also list disabled fields
+    #[serde(default)]
+    pub include_inactive: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomFieldRequest {
+    pub key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    #[serde(default)]
+    pub options: Vec<String>,
+    /// Empty means every group
+    #[serde(default)]
+    pub customer_groups: Vec<CustomerGroup>,
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCustomFieldRequest {
+    pub label: Option<String>,
+    pub options: Option<Vec<String>>,
+    pub customer_groups: Option<Vec<CustomerGroup>>,
+    pub required: Option<bool>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// A custom field and the customer's value, `null` when not filled in
+#[derive(Debug, Serialize)]
+pub struct CustomFieldValue {
+    pub field_id: i32,
+    pub key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    pub options: Vec<String>,
+    pub required: bool,
+    pub value: serde_json::Value,
+}
+
+impl CustomFieldValue {
+    pub fn new(field: custom_field::Model, value: serde_json::Value) -> Self {
+        Self {
+            field_id: field.id,
+            key: field.field_key,
+            label: field.label,
+            field_type: field.field_type,
+            options: field.options.0,
+            required: field.required,
+            value,
+        }
+    }
+}
+
+pub fn custom_field_error(error: CustomFieldError) -> ApiError {
+    match error {
+        CustomFieldError::KeyExists(_) => ApiError::new(StatusCode::CONFLICT, error.to_string()),
+        CustomFieldError::UnknownField(_) | CustomFieldError::RangeNotSupported(_) => {
+            ApiError::new(StatusCode::BAD_REQUEST, error.to_string())
+        }
+        CustomFieldError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+        _ => ApiError::unprocessable(error.to_string()),
+    }
+}
+
+fn require_admin(current_user: &CurrentUser) -> Result<(), ApiError> {
+    if current_user.role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+    Ok(())
+}
+
+// Everyone needs the definitions to render the customer form
+pub async fn list_custom_fields(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<CustomFieldListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<custom_field::Model>>, ApiError> {
+    if params.include_inactive {
+        require_admin(&current_user)?;
+    }
+
+    let fields = CustomFieldService::list(
+        &app_state.db,
+        current_user.organization_id,
+        params.include_inactive,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        fields
+            .into_iter()
+            .filter(|field| {
+                params
+                    .customer_group
+                    .as_ref()
+                    .is_none_or(|group| field.applies_to(group))
+            })
+            .collect(),
+    ))
+}
+
+pub async fn create_custom_field(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<CreateCustomFieldRequest>,
+) -> Result<Json<custom_field::Model>, ApiError> {
+    require_admin(&current_user)?;
+    CustomerGroupService::ensure_known(
+        &app_state.db,
+        current_user.organization_id,
+        &req.customer_groups,
+    )
+    .await
+    .map_err(customer_group_error)?;
+
+    let field = CustomFieldService::create(
+        &app_state.db,
+        current_user.organization_id,
+        NewCustomField {
+            field_key: req.key,
+            label: req.label,
+            field_type: req.field_type,
+            options: req.options,
+            customer_groups: req.customer_groups,
+            required: req.required,
+            sort_order: req.sort_order,
+        },
+    )
+    .await
+    .map_err(custom_field_error)?;
+
+    Ok(Json(field))
+}
+
+pub async fn update_custom_field(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(field_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<UpdateCustomFieldRequest>,
+) -> Result<Json<custom_field::Model>, ApiError> {
+    require_admin(&current_user)?;
+    let field = CustomFieldService::find(&app_state.db, current_user.organization_id, field_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if let Some(customer_groups) = &req.customer_groups {
+        CustomerGroupService::ensure_known(
+            &app_state.db,
+            current_user.organization_id,
+            customer_groups,
+        )
+        .await
+        .map_err(customer_group_error)?;
+    }
+
+    let field = CustomFieldService::update(
+        &app_state.db,
+        field,
+        CustomFieldUpdate {
+            label: req.label,
+            options: req.options,
+            customer_groups: req.customer_groups,
+            required: req.required,
+            sort_order: req.sort_order,
+            is_active: req.is_active,
+        },
+    )
+    .await
+    .map_err(custom_field_error)?;
+
+    Ok(Json(field))
+}
+
+// Removes the values stored on every customer as well
+pub async fn delete_custom_field(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(field_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&current_user)?;
+    let field = CustomFieldService::find(&app_state.db, current_user.organization_id, field_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    CustomFieldService::delete(&app_state.db, &field)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
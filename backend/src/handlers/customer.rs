@@ -1,25 +1,50 @@
+use std::collections::{BTreeMap, HashMap};
+
 use axum::{
     extract::{Path, Query, State},
     http::StatusCode,
     response::Json,
     Extension,
 };
-use chrono::Utc;
+use chrono::{NaiveDate, Utc};
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait,
-    QueryFilter, QueryOrder, Set,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
 
 use crate::{
     entities::{
-        customer::{self, Entity as Customer, CreateCustomerRequest, UpdateCustomerRequest},
+        audit_log::{AuditAction, AuditEntity},
+        customer::{self, CreateCustomerRequest, UpdateCustomerRequest},
         customer_group::CustomerGroup,
         customer_track::{self, Entity as CustomerTrack},
         next_action::NextAction,
+        pipeline_stage,
+        tag,
     },
     middleware::auth::CurrentUser,
-    handlers::auth::AppState,
+    handlers::{
+        auth::AppState,
+        custom_field::{custom_field_error, CustomFieldValue},
+        customer_group::customer_group_error,
+        customer_duplicate::{describe_matches, DuplicateCandidate},
+        error::ApiError,
+        tag::tag_error,
+    },
+    services::{
+        audit_service::{Actor, AuditService},
+        custom_field_service::{CustomFieldService, FieldFilter},
+        customer_list_service::{
+            self, CustomerListError, CustomerListFilter, CustomerListService, CustomerSort,
+        },
+        customer_group_service::CustomerGroupService,
+        duplicate_service::{normalize_name, normalize_phone, DuplicateService},
+        permission_service::{CustomerAccess, PermissionService},
+        pipeline_service::PipelineService,
+        tag_service::TagService,
+    },
+    utils::pinyin,
 };
 
 #[derive(Debug, Deserialize)]
@@ -31,8 +56,37 @@ pub struct CustomerListQuery {
     pub search: Option<String>,
     pub status: Option<NextAction>,
     pub customer_group: Option<CustomerGroup>,
+    pub stage_id: Option<i32>,
+    /// Comma separated tag ids; customers with at least one of them
+    pub any_tags: Option<String>,
+    /// Comma separated tag ids; customers with every one of them
+    pub all_tags: Option<String>,
+    /// name, rate, created_at, updated_at (default), latest_track_time or next_track_time
+    pub sort: Option<String>,
+    /// asc or desc; name and next_track_time default to asc, the rest to desc
+    pub order: Option<String>,
+    pub rate_min: Option<f32>,
+    pub rate_max: Option<f32>,
+    /// Inclusive YYYY-MM-DD dates
+    pub created_from: Option<String>,
+    pub created_to: Option<String>,
+    /// Only customers whose next follow-up time has passed
+    #[serde(default)]
+    pub overdue: bool,
+    pub has_tracks: Option<bool>,
+    pub address: Option<String>,
 }
 
+/// Upper bound for `limit`, larger values are clamped
+const MAX_LIMIT: u64 = 100;
+
+/// Query parameters understood by `list_customers`, besides `field.<key>` custom field filters
+const LIST_PARAMS: [&str; 17] = [
+    "page", "limit", "search", "status", "customer_group", "stage_id", "any_tags", "all_tags",
+    "sort", "order", "rate_min", "rate_max", "created_from", "created_to", "overdue",
+    "has_tracks", "address",
+];
+
 fn default_page() -> u64 { 1 }
 fn default_limit() -> u64 { 20 }
 
@@ -58,10 +112,15 @@ pub struct CustomerWithLatestTrack {
     pub latest_next_action: Option<NextAction>,
     pub latest_content: Option<String>,
     pub track_count: i64,
-    pub user_id: i32,
+    /// `None` while the customer is in the public pool
+    pub user_id: Option<i32>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub is_deleted: bool,
+    pub tags: Vec<tag::Model>,
+    pub stage_id: Option<i32>,
+    /// Current pipeline stage
+    pub stage: Option<pipeline_stage::Model>,
 }
 
 #[derive(Debug, Serialize)]
@@ -73,117 +132,213 @@ pub struct CustomerDetailResponse {
     pub notes: Option<String>,
     pub rate: f32,
     pub customer_group: CustomerGroup,
-    pub user_id: i32,
+    pub user_id: Option<i32>,
     pub next_action: NextAction,
     pub track_count: i64,
     pub last_track_at: Option<chrono::DateTime<chrono::Utc>>,
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub updated_at: chrono::DateTime<chrono::Utc>,
     pub is_deleted: bool,
+    /// What the current user may do with the customer
+    pub access: CustomerAccess,
+    /// Active custom fields that apply to the customer's group
+    pub custom_fields: Vec<CustomFieldValue>,
+    pub tags: Vec<tag::Model>,
+    pub stage_id: Option<i32>,
+    /// Current pipeline stage
+    pub stage: Option<pipeline_stage::Model>,
+}
+
+/// Audit snapshot of a customer together with its custom field values and tag names
+#[derive(Serialize)]
+struct CustomerSnapshot<'a> {
+    #[serde(flatten)]
+    customer: &'a customer::Model,
+    custom_fields: BTreeMap<String, serde_json::Value>,
+    tags: Vec<String>,
+}
+
+impl<'a> CustomerSnapshot<'a> {
+    async fn load<C: sea_orm::ConnectionTrait>(
+        db: &C,
+        customer: &'a customer::Model,
+    ) -> Result<Self, sea_orm::DbErr> {
+        Ok(Self {
+            customer,
+            custom_fields: CustomFieldService::snapshot(db, customer.id).await?,
+            tags: TagService::tags_for_customer(db, customer.id)
+                .await?
+                .into_iter()
+                .map(|tag| tag.name)
+                .collect(),
+        })
+    }
+}
+
+pub fn customer_list_error(error: CustomerListError) -> ApiError {
+    match error {
+        CustomerListError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+        _ => ApiError::new(StatusCode::BAD_REQUEST, error.to_string()),
+    }
+}
+
+/// Misspelled filters would otherwise be ignored silently and return the unfiltered list
+fn reject_unknown_params(raw_params: &HashMap<String, String>) -> Result<(), ApiError> {
+    let mut unknown: Vec<&str> = raw_params
+        .keys()
+        .map(String::as_str)
+        .filter(|name| !LIST_PARAMS.contains(name) && !name.starts_with("field."))
+        .collect();
+    if unknown.is_empty() {
+        return Ok(());
+    }
+    unknown.sort_unstable();
+    Err(ApiError::new(
+        StatusCode::BAD_REQUEST,
+        format!("未知的查询参数: {}", unknown.join(", ")),
+    ))
 }
 
+/// Empty values are treated as absent, like the other list filters
+fn parse_date(param: &'static str, value: Option<&str>) -> Result<Option<NaiveDate>, ApiError> {
+    value
+        .filter(|value| !value.trim().is_empty())
+        .map(|value| customer_list_service::parse_date(param, value))
+        .transpose()
+        .map_err(customer_list_error)
+}
+
+fn parse_tag_ids(value: Option<&str>) -> Result<Vec<i32>, ApiError> {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse().map_err(|_| {
+                ApiError::new(StatusCode::BAD_REQUEST, format!("无效的标签ID: {}", id))
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreateCustomerResponse {
+    #[serde(flatten)]
+    pub customer: customer::Model,
+    /// Existing customers that look like the same person; creation is not blocked
+    pub duplicates: Vec<DuplicateCandidate>,
+}
+
+// Custom fields are filtered with `field.<key>=value`, plus `field.<key>.from` /
+// `field.<key>.to` for number and date fields. Tags are filtered with `any_tags` /
+// `all_tags`, both comma separated ids. Unknown parameters and sort fields are rejected
 pub async fn list_customers(
     Extension(current_user): Extension<CurrentUser>,
     Query(params): Query<CustomerListQuery>,
+    Query(raw_params): Query<HashMap<String, String>>,
     State(app_state): State<AppState>,
-) -> Result<Json<CustomerListResponse>, StatusCode> {
-    // 第一步：获取所有符合基本条件的客户
-    let mut base_query = Customer::find()
-        .filter(customer::Column::UserId.eq(current_user.id))
-        .filter(customer::Column::IsDeleted.eq(false));
-
-    // 添加基本搜索过滤
-    if let Some(search_term) = &params.search {
-        base_query = base_query.filter(
-            customer::Column::Name.contains(search_term)
-                .or(customer::Column::Phone.contains(search_term))
-        );
+) -> Result<Json<CustomerListResponse>, ApiError> {
+    if params.page == 0 || params.limit == 0 {
+        return Err(StatusCode::BAD_REQUEST.into());
     }
+    let limit = params.limit.min(MAX_LIMIT);
+    reject_unknown_params(&raw_params)?;
+    let sort = CustomerSort::parse(params.sort.as_deref(), params.order.as_deref())
+        .map_err(customer_list_error)?;
 
-    // 获取所有符合基本条件的客户
-    let all_customers = base_query
-        .order_by_desc(customer::Column::UpdatedAt)
-        .all(&app_state.db)
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-    // 第二步：获取每个客户的跟进信息并进行状态筛选
-    let mut customer_with_tracks = Vec::new();
-    for customer in all_customers {
-        // 查询该客户的最新跟进记录
-        let latest_track = CustomerTrack::find()
-            .filter(customer_track::Column::CustomerId.eq(customer.id))
-            .order_by_desc(customer_track::Column::TrackTime)
-            .one(&app_state.db)
-            .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        // 如果有状态筛选条件，检查是否匹配
-        if let Some(filter_status) = &params.status {
-            // 状态筛选时，只包含有跟进记录的客户
-            if let Some(track) = &latest_track {
-                if track.next_action != *filter_status {
-                    continue; // 跳过状态不匹配的客户
-                }
-            } else {
-                // 没有跟进记录的客户在状态筛选时被过滤掉
-                continue;
-            }
-        }
-
-        // 如果有分组筛选条件，检查是否匹配
-        if let Some(filter_group) = &params.customer_group {
-            if customer.customer_group != *filter_group {
-                continue; // 跳过不匹配的客户
-            }
-        }
-        
-        // 确定客户的当前状态（用于返回数据）
-        let current_status = latest_track.as_ref().map(|t| t.next_action.clone()).unwrap_or(NextAction::Continue);
-
-        // 查询该客户的跟进记录总数
-        let track_count = CustomerTrack::find()
-            .filter(customer_track::Column::CustomerId.eq(customer.id))
-            .count(&app_state.db)
+    let field_condition = CustomFieldService::filter_condition(
+        &app_state.db,
+        current_user.organization_id,
+        &FieldFilter::from_query(&raw_params),
+    )
+    .await
+    .map_err(custom_field_error)?;
+    let any_tags = parse_tag_ids(params.any_tags.as_deref())?;
+    let all_tags = parse_tag_ids(params.all_tags.as_deref())?;
+
+    let filter = CustomerListFilter {
+        search: params.search,
+        status: params.status,
+        customer_group: params.customer_group,
+        stage_id: params.stage_id,
+        any_tags,
+        all_tags,
+        field_condition,
+        rate_min: params.rate_min,
+        rate_max: params.rate_max,
+        created_from: parse_date("created_from", params.created_from.as_deref())?,
+        created_to: parse_date("created_to", params.created_to.as_deref())?,
+        overdue: params.overdue,
+        has_tracks: params.has_tracks,
+        address: params.address.filter(|address| !address.trim().is_empty()),
+        in_pool: false,
+    };
+    filter.validate().map_err(customer_list_error)?;
+
+    let (rows, total) = CustomerListService::list(
+        &app_state.db,
+        &scope,
+        &filter,
+        sort,
+        params.page,
+        limit,
+    )
+    .await
+    .map_err(customer_list_error)?;
+
+    // 停用的阶段也要能显示
+    let stages: HashMap<i32, pipeline_stage::Model> =
+        PipelineService::list(&app_state.db, current_user.organization_id, true)
             .await
-            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
-
-        customer_with_tracks.push(CustomerWithLatestTrack {
-            id: customer.id,
-            name: customer.name,
-            phone: customer.phone,
-            address: customer.address,
-            rate: customer.rate,
-            notes: customer.notes,
-            customer_group: customer.customer_group,
-            next_action: current_status,
-            latest_track_time: latest_track.as_ref().map(|t| t.track_time),
-            latest_next_action: latest_track.as_ref().map(|t| t.next_action.clone()),
-            latest_content: latest_track.as_ref().map(|t| t.content.clone()),
-            track_count: track_count as i64,
-            user_id: customer.user_id,
-            created_at: customer.created_at,
-            updated_at: customer.updated_at,
-            is_deleted: customer.is_deleted,
-        });
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .into_iter()
+            .map(|stage| (stage.id, stage))
+            .collect();
+
+    let mut customers: Vec<CustomerWithLatestTrack> = rows
+        .into_iter()
+        .map(|row| CustomerWithLatestTrack {
+            id: row.id,
+            name: row.name,
+            phone: row.phone,
+            address: row.address,
+            rate: row.rate,
+            notes: row.notes,
+            customer_group: row.customer_group,
+            // 没有跟进记录的客户视为继续跟进
+            next_action: row.latest_next_action.clone().unwrap_or(NextAction::Continue),
+            latest_track_time: row.latest_track_time,
+            latest_next_action: row.latest_next_action,
+            latest_content: row.latest_content,
+            track_count: row.track_count,
+            user_id: row.user_id,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+            is_deleted: row.is_deleted,
+            tags: Vec::new(),
+            stage_id: row.stage_id,
+            stage: row.stage_id.and_then(|stage_id| stages.get(&stage_id).cloned()),
+        })
+        .collect();
+
+    // 只为当前页的客户加载标签
+    let customer_ids: Vec<i32> = customers.iter().map(|c| c.id).collect();
+    let mut tags = TagService::tags_for_customers(&app_state.db, &customer_ids)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    for customer in &mut customers {
+        customer.tags = tags.remove(&customer.id).unwrap_or_default();
     }
 
-    // 第三步：手动分页
-    let total = customer_with_tracks.len() as u64;
-    let start_index = ((params.page - 1) * params.limit) as usize;
-    let end_index = std::cmp::min(start_index + params.limit as usize, customer_with_tracks.len());
-    
-    let paginated_customers = if start_index < customer_with_tracks.len() {
-        customer_with_tracks[start_index..end_index].to_vec()
-    } else {
-        Vec::new()
-    };
-
     Ok(Json(CustomerListResponse {
-        customers: paginated_customers,
+        customers,
         total,
         page: params.page,
-        limit: params.limit,
+        limit,
     }))
 }
 
@@ -192,10 +347,10 @@ pub async fn get_customer(
     Path(customer_id): Path<i32>,
     State(app_state): State<AppState>,
 ) -> Result<Json<CustomerDetailResponse>, StatusCode> {
-    let customer = Customer::find_by_id(customer_id)
-        .filter(customer::Column::UserId.eq(current_user.id))
-        .filter(customer::Column::IsDeleted.eq(false))
-        .one(&app_state.db)
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (customer, access) = PermissionService::find_customer(&app_state.db, &scope, customer_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
@@ -211,6 +366,7 @@ pub async fn get_customer(
     let latest_track = CustomerTrack::find()
         .filter(customer_track::Column::CustomerId.eq(customer_id))
         .order_by_desc(customer_track::Column::TrackTime)
+        .order_by_desc(customer_track::Column::Id)
         .one(&app_state.db)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
@@ -221,6 +377,22 @@ pub async fn get_customer(
         (NextAction::Continue, None) // Default action for customers without tracks
     };
 
+    let custom_fields = CustomFieldService::values_for(&app_state.db, &customer)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|(field, value)| CustomFieldValue::new(field, value))
+        .collect();
+    let tags = TagService::tags_for_customer(&app_state.db, customer.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let stage = match customer.stage_id {
+        Some(stage_id) => PipelineService::find(&app_state.db, customer.organization_id, stage_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?,
+        None => None,
+    };
+
     let response = CustomerDetailResponse {
         id: customer.id,
         name: customer.name,
@@ -236,6 +408,11 @@ pub async fn get_customer(
         created_at: customer.created_at,
         updated_at: customer.updated_at,
         is_deleted: customer.is_deleted,
+        access,
+        custom_fields,
+        tags,
+        stage_id: customer.stage_id,
+        stage,
     };
 
     Ok(Json(response))
@@ -245,29 +422,102 @@ pub async fn create_customer(
     Extension(current_user): Extension<CurrentUser>,
     State(app_state): State<AppState>,
     Json(req): Json<CreateCustomerRequest>,
-) -> Result<Json<customer::Model>, StatusCode> {
+) -> Result<Json<CreateCustomerResponse>, ApiError> {
     let now = Utc::now();
+    let customer_group = match req.customer_group {
+        Some(customer_group) => customer_group,
+        None => CustomerGroupService::default_group(&app_state.db, current_user.organization_id)
+            .await
+            .map_err(customer_group_error)?,
+    };
+    CustomerGroupService::ensure_assignable(
+        &app_state.db,
+        current_user.organization_id,
+        &customer_group,
+    )
+    .await
+    .map_err(customer_group_error)?;
+    let field_values = CustomFieldService::prepare_values(
+        &app_state.db,
+        current_user.organization_id,
+        &customer_group,
+        &req.custom_fields,
+        true,
+    )
+    .await
+    .map_err(custom_field_error)?;
+    let tag_ids = TagService::checked_ids(&app_state.db, current_user.organization_id, &req.tag_ids)
+        .await
+        .map_err(tag_error)?;
+
+    // New customers start in the first active pipeline stage
+    let stage = PipelineService::initial_stage(&app_state.db, current_user.organization_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Look for duplicates across the whole organization before inserting
+    let matches = DuplicateService::find_matches(
+        &app_state.db,
+        current_user.organization_id,
+        &req.name,
+        req.phone.as_deref(),
+        None,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     
     let customer = customer::ActiveModel {
+        name_pinyin: Set(pinyin::full_pinyin(&req.name)),
+        name_initials: Set(pinyin::name_initials(&req.name)),
+        name_normalized: Set(normalize_name(&req.name)),
         name: Set(req.name),
+        phone_normalized: Set(req.phone.as_deref().and_then(normalize_phone)),
         phone: Set(req.phone),
         address: Set(req.address),
         notes: Set(req.notes),
         rate: Set(req.rate.unwrap_or(0.0)),
-        customer_group: Set(req.customer_group.unwrap_or(CustomerGroup::GroupClass)),
-        user_id: Set(current_user.id), // Automatically associate with current user
+        customer_group: Set(customer_group),
+        user_id: Set(Some(current_user.id)), // Automatically associate with current user
+        organization_id: Set(current_user.organization_id),
         created_at: Set(now),
         updated_at: Set(now),
         is_deleted: Set(false),
+        stage_id: Set(stage.map(|stage| stage.id)),
         ..Default::default()
     };
 
+    let txn = app_state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let customer = customer
-        .insert(&app_state.db)
+        .insert(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    CustomFieldService::save_values(&txn, customer.id, field_values)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    TagService::add_customer_tags(&txn, customer.id, &tag_ids)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let snapshot = CustomerSnapshot::load(&txn, &customer)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    AuditService::record(
+        &txn,
+        &Actor::from(&current_user),
+        AuditAction::Create,
+        AuditEntity::Customer,
+        customer.id,
+        None,
+        Some(&snapshot),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let duplicates = describe_matches(&app_state.db, &current_user, matches)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(customer))
+    Ok(Json(CreateCustomerResponse { customer, duplicates }))
 }
 
 pub async fn update_customer(
@@ -275,23 +525,63 @@ pub async fn update_customer(
     Path(customer_id): Path<i32>,
     State(app_state): State<AppState>,
     Json(req): Json<UpdateCustomerRequest>,
-) -> Result<Json<customer::Model>, StatusCode> {
-    // Check if customer belongs to current user
-    let customer = Customer::find_by_id(customer_id)
-        .filter(customer::Column::UserId.eq(current_user.id))
-        .filter(customer::Column::IsDeleted.eq(false))
-        .one(&app_state.db)
+) -> Result<Json<customer::Model>, ApiError> {
+    // Check if current user may access the customer
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (customer, access) = PermissionService::find_customer(&app_state.db, &scope, customer_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    if access < CustomerAccess::Write {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+
+    // Customers may stay in an archived group but cannot be moved into one
+    if let Some(customer_group) = &req.customer_group
+        && *customer_group != customer.customer_group
+    {
+        CustomerGroupService::ensure_assignable(
+            &app_state.db,
+            customer.organization_id,
+            customer_group,
+        )
+        .await
+        .map_err(customer_group_error)?;
+    }
+
+    // Custom fields are checked against the group the customer ends up in
+    let customer_group = req.customer_group.clone().unwrap_or(customer.customer_group.clone());
+    let field_values = CustomFieldService::prepare_values(
+        &app_state.db,
+        customer.organization_id,
+        &customer_group,
+        &req.custom_fields,
+        false,
+    )
+    .await
+    .map_err(custom_field_error)?;
+    let tag_ids = match &req.tag_ids {
+        Some(tag_ids) => Some(
+            TagService::checked_ids(&app_state.db, customer.organization_id, tag_ids)
+                .await
+                .map_err(tag_error)?,
+        ),
+        None => None,
+    };
 
     // Update customer
-    let mut customer_active: customer::ActiveModel = customer.into();
+    let mut customer_active: customer::ActiveModel = customer.clone().into();
     
     if let Some(name) = req.name {
+        customer_active.name_pinyin = Set(pinyin::full_pinyin(&name));
+        customer_active.name_initials = Set(pinyin::name_initials(&name));
+        customer_active.name_normalized = Set(normalize_name(&name));
         customer_active.name = Set(name);
     }
     if let Some(phone) = req.phone {
+        customer_active.phone_normalized = Set(normalize_phone(&phone));
         customer_active.phone = Set(if phone.is_empty() { None } else { Some(phone) });
     }
     if let Some(address) = req.address {
@@ -309,10 +599,37 @@ pub async fn update_customer(
     
     customer_active.updated_at = Set(Utc::now());
 
+    let txn = app_state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let before = CustomerSnapshot::load(&txn, &customer)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let updated_customer = customer_active
-        .update(&app_state.db)
+        .update(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    CustomFieldService::save_values(&txn, customer_id, field_values)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    if let Some(tag_ids) = &tag_ids {
+        TagService::set_customer_tags(&txn, customer_id, tag_ids)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    let after = CustomerSnapshot::load(&txn, &updated_customer)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    AuditService::record(
+        &txn,
+        &Actor::from(&current_user),
+        AuditAction::Update,
+        AuditEntity::Customer,
+        customer_id,
+        Some(&before),
+        Some(&after),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(Json(updated_customer))
 }
@@ -322,24 +639,179 @@ pub async fn delete_customer(
     Path(customer_id): Path<i32>,
     State(app_state): State<AppState>,
 ) -> Result<StatusCode, StatusCode> {
-    // Check if customer belongs to current user
-    let customer = Customer::find_by_id(customer_id)
-        .filter(customer::Column::UserId.eq(current_user.id))
-        .filter(customer::Column::IsDeleted.eq(false))
-        .one(&app_state.db)
+    // Check if current user may access the customer
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (customer, access) = PermissionService::find_customer(&app_state.db, &scope, customer_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    // Collaborators cannot delete the customer
+    if access < CustomerAccess::Owner {
+        return Err(StatusCode::FORBIDDEN);
+    }
 
-    // Soft delete the customer
-    let mut customer_active: customer::ActiveModel = customer.into();
+    // Soft delete the customer; it stays in the trash until restored or purged
+    let now = Utc::now();
+    let mut customer_active: customer::ActiveModel = customer.clone().into();
     customer_active.is_deleted = Set(true);
-    customer_active.updated_at = Set(Utc::now());
+    customer_active.deleted_at = Set(Some(now));
+    customer_active.deleted_by = Set(Some(current_user.id));
+    customer_active.updated_at = Set(now);
 
+    let txn = app_state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     customer_active
-        .update(&app_state.db)
+        .update(&txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    AuditService::record(
+        &txn,
+        &Actor::from(&current_user),
+        AuditAction::Delete,
+        AuditEntity::Customer,
+        customer_id,
+        Some(&customer),
+        None,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        entities::user_role::UserRole,
+        test_support::{self, TestApp},
+    };
+
+    fn names(json: &Value) -> Vec<&str> {
+        json["customers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|customer| customer["name"].as_str().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn list_search_follows_the_pinyin_of_renamed_customers() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let token = app.login("alice").await;
+
+        let body = json!({ "name": "张三" });
+        let (status, customer) = app
+            .request(Method::POST, "/api/customers", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, json) = app
+            .request(Method::GET, "/api/customers?search=zhs", Some(&token), None)
+            .await;
+        assert_eq!(names(&json), vec!["张三"]);
+
+        let uri = format!("/api/customers/{}", customer["id"]);
+        let body = json!({ "name": "李四" });
+        let (status, _) = app
+            .request(Method::PUT, &uri, Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, json) = app
+            .request(Method::GET, "/api/customers?search=zhs", Some(&token), None)
+            .await;
+        assert!(names(&json).is_empty());
+        let (_, json) = app
+            .request(
+                Method::GET,
+                "/api/customers?search=lisi",
+                Some(&token),
+                None,
+            )
+            .await;
+        assert_eq!(names(&json), vec!["李四"]);
+        // 「李s」编码后的查询
+        let (_, json) = app
+            .request(
+                Method::GET,
+                "/api/customers?search=%E6%9D%8Es",
+                Some(&token),
+                None,
+            )
+            .await;
+        assert_eq!(names(&json), vec!["李四"]);
+    }
+
+    #[tokio::test]
+    async fn pages_are_bounded_and_the_detail_agrees_with_the_list() {
+        let app = TestApp::new().await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let customer = test_support::create_customer(app.db(), "张三", None, Some(alice.id)).await;
+        // 两条跟进时间相同，ID 较大的一条为最新
+        let time = chrono::Utc::now();
+        test_support::create_track(app.db(), customer.id, Some(alice.id), time).await;
+        let latest = test_support::create_track(app.db(), customer.id, Some(alice.id), time).await;
+        let mut latest: customer_track::ActiveModel = latest.into();
+        latest.next_action = Set(NextAction::End);
+        latest.update(app.db()).await.unwrap();
+        let token = app.login("alice").await;
+
+        let (status, json) = app
+            .request(Method::GET, "/api/customers?limit=1000", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["limit"], MAX_LIMIT);
+        assert_eq!(json["customers"][0]["next_action"], "结束跟进");
+        let uri = format!("/api/customers/{}", customer.id);
+        let (_, json) = app.request(Method::GET, &uri, Some(&token), None).await;
+        assert_eq!(json["next_action"], "结束跟进");
+
+        let uri = format!("/api/customers?page={}&limit=100", u64::MAX);
+        let (status, _) = app.request(Method::GET, &uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let uri = format!("/api/customers?page={}&limit=100", u64::MAX / 100);
+        let (status, _) = app.request(Method::GET, &uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, json) = app
+            .request(Method::GET, "/api/customers?page=99999", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["customers"].as_array().unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn invalid_sorts_and_filters_are_rejected() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let token = app.login("alice").await;
+
+        for query in [
+            "sort=phone",
+            "sort=name&order=up",
+            "rate_min=4&rate_max=3",
+            "created_from=2025-03-02&created_to=2025-03-01",
+            "created_from=2025-02-30",
+            "overdue_only=true",
+        ] {
+            let uri = format!("/api/customers?{}", query);
+            let (status, json) = app.request(Method::GET, &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}: {}", query, json);
+        }
+        // 最大的日期没有下一天
+        let uri = "/api/customers?created_to=%2B262142-12-31";
+        let (status, json) = app.request(Method::GET, uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(json["message"], "created_to 超出日期范围");
+
+        let uri = "/api/customers?sort=latest_track_time&order=asc&created_to=2025-03-01&has_tracks=false";
+        let (status, _) = app.request(Method::GET, uri, Some(&token), None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
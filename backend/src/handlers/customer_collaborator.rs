@@ -0,0 +1,254 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::customer_collaborator::CollaboratorPermission,
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        audit_service::Actor,
+        collaborator_service::{CollaboratorError, CollaboratorService},
+        permission_service::{CustomerAccess, PermissionService},
+    },
+};
+
+#[derive(Debug, Serialize)]
+pub struct CollaboratorInfo {
+    pub user_id: i32,
+    pub username: String,
+    pub name: String,
+    pub permission: CollaboratorPermission,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetCollaboratorRequest {
+    pub permission: CollaboratorPermission,
+}
+
+fn collaborator_error(error: CollaboratorError) -> ApiError {
+    match error {
+        CollaboratorError::UserNotFound | CollaboratorError::IsOwner => {
+            ApiError::unprocessable(error.to_string())
+        }
+        CollaboratorError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+    }
+}
+
+pub async fn list_collaborators(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(customer_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<CollaboratorInfo>>, StatusCode> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    PermissionService::find_customer(&app_state.db, &scope, customer_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let collaborators = CollaboratorService::list(&app_state.db, customer_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        collaborators
+            .into_iter()
+            .map(|(collaborator, user)| CollaboratorInfo {
+                user_id: user.id,
+                username: user.username,
+                name: user.name,
+                permission: collaborator.permission,
+                created_at: collaborator.created_at,
+            })
+            .collect(),
+    ))
+}
+
+// Only the owner, their manager or an admin can share a customer
+pub async fn set_collaborator(
+    Extension(current_user): Extension<CurrentUser>,
+    Path((customer_id, user_id)): Path<(i32, i32)>,
+    State(app_state): State<AppState>,
+    Json(req): Json<SetCollaboratorRequest>,
+) -> Result<StatusCode, ApiError> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (customer, access) = PermissionService::find_customer(&app_state.db, &scope, customer_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if access < CustomerAccess::Owner {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+
+    CollaboratorService::set(
+        &app_state.db,
+        &Actor::from(&current_user),
+        &customer,
+        user_id,
+        req.permission,
+    )
+    .await
+    .map_err(collaborator_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// Collaborators may also remove themselves
+pub async fn remove_collaborator(
+    Extension(current_user): Extension<CurrentUser>,
+    Path((customer_id, user_id)): Path<(i32, i32)>,
+    State(app_state): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (_customer, access) = PermissionService::find_customer(&app_state.db, &scope, customer_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+    if access < CustomerAccess::Owner && user_id != current_user.id {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    let removed = CollaboratorService::remove(
+        &app_state.db,
+        &Actor::from(&current_user),
+        customer_id,
+        user_id,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if removed {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        entities::user_role::UserRole,
+        test_support::{self, TestApp},
+    };
+
+    /// alice 负责一位客户，bob 和 carol 是同组织的其他销售；返回客户和三人的ID
+    async fn shared_customer(app: &TestApp) -> (i32, i32, i32, i32) {
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(app.db(), "bob", UserRole::Sales).await;
+        let carol = test_support::create_user(app.db(), "carol", UserRole::Sales).await;
+        let customer = test_support::create_customer(app.db(), "张三", None, Some(alice.id)).await;
+        (customer.id, alice.id, bob.id, carol.id)
+    }
+
+    async fn share(
+        app: &TestApp,
+        token: &str,
+        customer_id: i32,
+        user_id: i32,
+        permission: &str,
+    ) -> StatusCode {
+        let uri = format!("/api/customers/{}/collaborators/{}", customer_id, user_id);
+        let body = json!({ "permission": permission });
+        app.request(Method::PUT, &uri, Some(token), Some(body)).await.0
+    }
+
+    #[tokio::test]
+    async fn read_collaborators_can_view_but_not_edit() {
+        let app = TestApp::new().await;
+        let (customer_id, _, bob_id, _) = shared_customer(&app).await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bob").await;
+        let customer_uri = format!("/api/customers/{}", customer_id);
+        let tracks_uri = format!("/api/customers/{}/tracks", customer_id);
+
+        let (status, _) = app.request(Method::GET, &customer_uri, Some(&bob), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        assert_eq!(share(&app, &alice, customer_id, bob_id, "read").await, StatusCode::NO_CONTENT);
+
+        let (status, json) = app.request(Method::GET, &customer_uri, Some(&bob), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["access"], "read");
+        let (_, json) = app.request(Method::GET, "/api/customers", Some(&bob), None).await;
+        assert_eq!(json["total"], 1);
+
+        let body = json!({ "name": "李四" });
+        let (status, _) = app.request(Method::PUT, &customer_uri, Some(&bob), Some(body)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let body = json!({ "customer_id": customer_id, "content": "电话沟通" });
+        let (status, _) = app.request(Method::POST, &tracks_uri, Some(&bob), Some(body)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn write_collaborators_only_modify_their_own_tracks() {
+        let app = TestApp::new().await;
+        let (customer_id, _, bob_id, _) = shared_customer(&app).await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bob").await;
+        let tracks_uri = format!("/api/customers/{}/tracks", customer_id);
+        assert_eq!(share(&app, &alice, customer_id, bob_id, "write").await, StatusCode::NO_CONTENT);
+
+        let body = json!({ "customer_id": customer_id, "content": "负责人的跟进" });
+        let (status, owner_track) =
+            app.request(Method::POST, &tracks_uri, Some(&alice), Some(body)).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = json!({ "customer_id": customer_id, "content": "协作者的跟进" });
+        let (status, own_track) =
+            app.request(Method::POST, &tracks_uri, Some(&bob), Some(body)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let update = json!({ "content": "修改" });
+        let uri = format!("/api/tracks/{}", own_track["id"]);
+        let (status, _) = app.request(Method::PUT, &uri, Some(&bob), Some(update.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+        let uri = format!("/api/tracks/{}", owner_track["id"]);
+        let (status, _) = app.request(Method::PUT, &uri, Some(&bob), Some(update)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn only_owners_manage_collaborators_but_anyone_can_leave() {
+        let app = TestApp::new().await;
+        let (customer_id, alice_id, bob_id, carol_id) = shared_customer(&app).await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bob").await;
+        let customer_uri = format!("/api/customers/{}", customer_id);
+        assert_eq!(share(&app, &alice, customer_id, bob_id, "write").await, StatusCode::NO_CONTENT);
+
+        assert_eq!(share(&app, &bob, customer_id, carol_id, "read").await, StatusCode::FORBIDDEN);
+        assert_eq!(
+            share(&app, &alice, customer_id, alice_id, "read").await,
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let (_, json) = app
+            .request(Method::GET, &format!("{}/collaborators", customer_uri), Some(&bob), None)
+            .await;
+        assert_eq!(json.as_array().unwrap().len(), 1);
+        assert_eq!(json[0]["username"], "bob");
+        assert_eq!(json[0]["permission"], "write");
+
+        let uri = format!("{}/collaborators/{}", customer_uri, bob_id);
+        let (status, _) = app.request(Method::DELETE, &uri, Some(&bob), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let (status, _) = app.request(Method::GET, &customer_uri, Some(&bob), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{
+        customer,
+        user::{self, Entity as User},
+    },
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        audit_service::Actor,
+        duplicate_service::{DuplicateMatch, DuplicateReason, DuplicateService, MergeError},
+        permission_service::{CustomerAccess, PermissionService},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicateCheckQuery {
+    pub name: String,
+    pub phone: Option<String>,
+    /// The customer being edited, so it does not match itself
+    pub exclude_id: Option<i32>,
+}
+
+/// An existing customer that looks like the one being entered. Matches may
+/// belong to other users, so the name, phone and owner are only shown when the
+/// current user can see the customer.
+#[derive(Debug, Clone, Serialize)]
+pub struct DuplicateCandidate {
+    pub id: i32,
+    pub name: Option<String>,
+    pub phone: Option<String>,
+    pub user_id: Option<i32>,
+    pub owner_name: Option<String>,
+    pub reasons: Vec<DuplicateReason>,
+    /// `None` when the current user cannot open the customer
+    pub access: Option<CustomerAccess>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroupInfo {
+    pub reason: DuplicateReason,
+    pub key: String,
+    pub customers: Vec<customer::Model>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateReportResponse {
+    pub groups: Vec<DuplicateGroupInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeCustomerRequest {
+    /// The customer merged into the one in the path and then deleted
+    pub duplicate_id: i32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MergeCustomerResponse {
+    pub customer: customer::Model,
+    pub moved_tracks: u64,
+}
+
+fn merge_error(error: MergeError) -> ApiError {
+    match error {
+        MergeError::SameCustomer => ApiError::unprocessable(error.to_string()),
+        MergeError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+    }
+}
+
+/// Attach owner names and the current user's access to duplicate matches
+pub async fn describe_matches(
+    db: &DatabaseConnection,
+    current_user: &CurrentUser,
+    matches: Vec<DuplicateMatch>,
+) -> Result<Vec<DuplicateCandidate>, DbErr> {
+    if matches.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let scope = PermissionService::scope_for(db, current_user).await?;
+    let mut visible = Vec::with_capacity(matches.len());
+    for duplicate in matches {
+        let access = PermissionService::customer_access(db, &scope, &duplicate.customer).await?;
+        visible.push((duplicate, access));
+    }
+
+    let owner_ids: Vec<i32> = visible
+        .iter()
+        .filter(|(_, access)| access.is_some())
+        .filter_map(|(duplicate, _)| duplicate.customer.user_id)
+        .collect();
+    let owners: HashMap<i32, String> = User::find()
+        .filter(user::Column::Id.is_in(owner_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user| (user.id, user.name))
+        .collect();
+
+    // Matches the user cannot open only reveal that a duplicate exists
+    Ok(visible
+        .into_iter()
+        .map(|(DuplicateMatch { customer, reasons }, access)| {
+            let visible = access.is_some();
+            DuplicateCandidate {
+                id: customer.id,
+                name: Some(customer.name).filter(|_| visible),
+                phone: customer.phone.filter(|_| visible),
+                user_id: customer.user_id.filter(|_| visible),
+                owner_name: customer
+                    .user_id
+                    .filter(|_| visible)
+                    .and_then(|user_id| owners.get(&user_id).cloned()),
+                reasons,
+                access,
+            }
+        })
+        .collect())
+}
+
+// Lets the customer form warn while the user is still typing
+pub async fn check_duplicates(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<DuplicateCheckQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<DuplicateCandidate>>, StatusCode> {
+    let matches = DuplicateService::find_matches(
+        &app_state.db,
+        current_user.organization_id,
+        &params.name,
+        params.phone.as_deref(),
+        params.exclude_id,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let candidates = describe_matches(&app_state.db, &current_user, matches)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(candidates))
+}
+
+// Groups of customers in the caller's scope sharing a phone number or name
+pub async fn duplicate_report(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<DuplicateReportResponse>, StatusCode> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let groups = DuplicateService::report(&app_state.db, &scope)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(DuplicateReportResponse {
+        groups: groups
+            .into_iter()
+            .map(|group| DuplicateGroupInfo {
+                reason: group.reason,
+                key: group.key,
+                customers: group.customers,
+            })
+            .collect(),
+    }))
+}
+
+// Both customers must be owned by (or managed by) the caller
+pub async fn merge_customers(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(customer_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<MergeCustomerRequest>,
+) -> Result<Json<MergeCustomerResponse>, ApiError> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let mut customers = Vec::with_capacity(2);
+    for id in [customer_id, req.duplicate_id] {
+        let (customer, access) = PermissionService::find_customer(&app_state.db, &scope, id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+            .ok_or(StatusCode::NOT_FOUND)?;
+        if access < CustomerAccess::Owner {
+            return Err(StatusCode::FORBIDDEN.into());
+        }
+        customers.push(customer);
+    }
+    let duplicate = customers.pop().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let survivor = customers.pop().ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let outcome = DuplicateService::merge(
+        &app_state.db,
+        &Actor::from(&current_user),
+        survivor,
+        duplicate,
+    )
+    .await
+    .map_err(merge_error)?;
+
+    Ok(Json(MergeCustomerResponse {
+        customer: outcome.customer,
+        moved_tracks: outcome.moved_tracks,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+
+    use crate::{
+        entities::user_role::UserRole,
+        test_support::{self, TestApp},
+    };
+
+    #[tokio::test]
+    async fn hides_details_of_customers_the_user_cannot_open() {
+        let app = TestApp::new().await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(app.db(), "bob", UserRole::Sales).await;
+        let own =
+            test_support::create_customer(app.db(), "张三", Some("13800000001"), Some(alice.id))
+                .await;
+        let foreign =
+            test_support::create_customer(app.db(), "张三", Some("13800000002"), Some(bob.id))
+                .await;
+        let token = app.login("alice").await;
+
+        let uri = "/api/customers/duplicates/check?name=%E5%BC%A0%E4%B8%89";
+        let (status, json) = app.request(Method::GET, uri, Some(&token), None).await;
+        assert_eq!(status, axum::http::StatusCode::OK);
+
+        let candidates = json.as_array().unwrap();
+        assert_eq!(candidates.len(), 2);
+        assert_eq!(candidates[0]["id"], own.id);
+        assert_eq!(candidates[0]["name"], "张三");
+        assert_eq!(candidates[0]["phone"], "13800000001");
+        assert_eq!(candidates[0]["owner_name"], "alice");
+        assert_eq!(candidates[0]["access"], "owner");
+
+        assert_eq!(candidates[1]["id"], foreign.id);
+        for field in ["name", "phone", "user_id", "owner_name", "access"] {
+            assert!(candidates[1][field].is_null(), "{} 不应返回", field);
+        }
+        assert_eq!(candidates[1]["reasons"], serde_json::json!(["name"]));
+    }
+}
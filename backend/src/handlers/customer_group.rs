@@ -0,0 +1,164 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{customer_group::CustomerGroup, customer_group_config, user_role::UserRole},
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::customer_group_service::{
+        CustomerGroupError, CustomerGroupService, CustomerGroupUpdate, NewCustomerGroup,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CustomerGroupListQuery {
+    /// Archived groups are still needed to display existing customers
+    #[serde(default)]
+    pub include_archived: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCustomerGroupRequest {
+    pub name: CustomerGroup,
+    pub display_name: Option<String>,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateCustomerGroupRequest {
+    pub display_name: Option<String>,
+    /// An empty string clears the colour
+    pub color: Option<String>,
+    pub sort_order: Option<i32>,
+    pub is_archived: Option<bool>,
+}
+
+/// A group and how many customers of the organization are in it
+#[derive(Debug, Serialize)]
+pub struct CustomerGroupWithCount {
+    #[serde(flatten)]
+    pub group: customer_group_config::Model,
+    pub customer_count: i64,
+}
+
+pub fn customer_group_error(error: CustomerGroupError) -> ApiError {
+    match error {
+        CustomerGroupError::NameExists(_) | CustomerGroupError::InUse(_) => {
+            ApiError::new(StatusCode::CONFLICT, error.to_string())
+        }
+        CustomerGroupError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+        _ => ApiError::unprocessable(error.to_string()),
+    }
+}
+
+fn require_admin(current_user: &CurrentUser) -> Result<(), ApiError> {
+    if current_user.role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+    Ok(())
+}
+
+// Everyone needs the groups to render forms and filters
+pub async fn list_customer_groups(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<CustomerGroupListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<CustomerGroupWithCount>>, StatusCode> {
+    let groups = CustomerGroupService::list(
+        &app_state.db,
+        current_user.organization_id,
+        params.include_archived,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let counts = CustomerGroupService::customer_counts(&app_state.db, current_user.organization_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        groups
+            .into_iter()
+            .map(|group| {
+                let customer_count = counts.get(&group.name).copied().unwrap_or(0);
+                CustomerGroupWithCount { group, customer_count }
+            })
+            .collect(),
+    ))
+}
+
+pub async fn create_customer_group(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<CreateCustomerGroupRequest>,
+) -> Result<Json<customer_group_config::Model>, ApiError> {
+    require_admin(&current_user)?;
+
+    let group = CustomerGroupService::create(
+        &app_state.db,
+        current_user.organization_id,
+        NewCustomerGroup {
+            name: req.name,
+            display_name: req.display_name,
+            color: req.color,
+            sort_order: req.sort_order,
+        },
+    )
+    .await
+    .map_err(customer_group_error)?;
+
+    Ok(Json(group))
+}
+
+pub async fn update_customer_group(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(group_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<UpdateCustomerGroupRequest>,
+) -> Result<Json<customer_group_config::Model>, ApiError> {
+    require_admin(&current_user)?;
+    let group = CustomerGroupService::find(&app_state.db, current_user.organization_id, group_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let group = CustomerGroupService::update(
+        &app_state.db,
+        group,
+        CustomerGroupUpdate {
+            display_name: req.display_name,
+            color: req.color,
+            sort_order: req.sort_order,
+            is_archived: req.is_archived,
+        },
+    )
+    .await
+    .map_err(customer_group_error)?;
+
+    Ok(Json(group))
+}
+
+// Groups that still have customers must be archived instead
+pub async fn delete_customer_group(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(group_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&current_user)?;
+    let group = CustomerGroupService::find(&app_state.db, current_user.organization_id, group_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    CustomerGroupService::delete(&app_state.db, &group)
+        .await
+        .map_err(customer_group_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,149 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{customer, customer_group::CustomerGroup},
+    handlers::{auth::AppState, customer::customer_list_error, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        customer_pool_service::{CustomerPoolService, PoolError, PoolListFilter},
+        permission_service::PermissionService,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct PoolListQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+    pub search: Option<String>,
+    pub customer_group: Option<CustomerGroup>,
+}
+
+fn default_page() -> u64 { 1 }
+fn default_limit() -> u64 { 20 }
+
+#[derive(Debug, Serialize)]
+pub struct PoolCustomer {
+    pub id: i32,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub rate: f32,
+    pub customer_group: CustomerGroup,
+    pub latest_track_time: Option<chrono::DateTime<chrono::Utc>>,
+    pub track_count: i64,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// When the customer was last returned to the pool
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PoolListResponse {
+    pub customers: Vec<PoolCustomer>,
+    pub total: u64,
+    pub page: u64,
+    pub limit: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReleaseCustomerRequest {
+    pub reason: Option<String>,
+}
+
+fn pool_error(error: PoolError) -> ApiError {
+    match error {
+        PoolError::CustomerNotFound => StatusCode::NOT_FOUND.into(),
+        PoolError::AlreadyClaimed | PoolError::AlreadyInPool => {
+            ApiError::new(StatusCode::CONFLICT, error.to_string())
+        }
+        PoolError::NotOwner => ApiError::new(StatusCode::FORBIDDEN, error.to_string()),
+        PoolError::ClaimLimitReached(_) => {
+            ApiError::new(StatusCode::TOO_MANY_REQUESTS, error.to_string())
+        }
+        PoolError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+    }
+}
+
+// Everyone in the organization can browse and claim pool customers
+pub async fn list_pool_customers(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<PoolListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<PoolListResponse>, ApiError> {
+    if params.page == 0 || params.limit == 0 {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    let (rows, total) = CustomerPoolService::list(
+        &app_state.db,
+        current_user.organization_id,
+        PoolListFilter {
+            search: params.search,
+            customer_group: params.customer_group,
+        },
+        params.page,
+        params.limit,
+    )
+    .await
+    .map_err(customer_list_error)?;
+
+    let customers = rows
+        .into_iter()
+        .map(|row| PoolCustomer {
+            id: row.id,
+            name: row.name,
+            phone: row.phone,
+            address: row.address,
+            rate: row.rate,
+            customer_group: row.customer_group,
+            latest_track_time: row.latest_track_time,
+            track_count: row.track_count,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        })
+        .collect();
+
+    Ok(Json(PoolListResponse {
+        customers,
+        total,
+        page: params.page,
+        limit: params.limit,
+    }))
+}
+
+pub async fn claim_customer(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(customer_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<Json<customer::Model>, ApiError> {
+    let customer =
+        CustomerPoolService::claim(&app_state.db, &app_state.pool, &current_user, customer_id)
+            .await
+            .map_err(pool_error)?;
+
+    Ok(Json(customer))
+}
+
+pub async fn release_customer(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(customer_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<ReleaseCustomerRequest>,
+) -> Result<Json<customer::Model>, ApiError> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let customer =
+        CustomerPoolService::release(&app_state.db, &current_user, &scope, customer_id, req.reason)
+            .await
+            .map_err(pool_error)?;
+
+    Ok(Json(customer))
+}
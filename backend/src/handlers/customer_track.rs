@@ -6,21 +6,31 @@ use axum::{
 };
 use chrono::Utc;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 use crate::{
     entities::{
-        customer::{self, Entity as Customer},
+        audit_log::{AuditAction, AuditEntity},
+        customer,
         customer_track::{
             self, Entity as CustomerTrack, CreateTrackRequest, UpdateTrackRequest,
             CustomerTrackInfo,
         },
         next_action::NextAction,
+        pipeline_stage,
+        user::{self, Entity as User},
     },
     middleware::auth::CurrentUser,
-    handlers::auth::AppState,
+    handlers::{auth::AppState, error::ApiError, pipeline_stage::pipeline_error},
+    services::{
+        audit_service::{Actor, AuditService},
+        permission_service::{CustomerAccess, PermissionService},
+        pipeline_service::PipelineService,
+    },
 };
 
 #[derive(Debug, Deserialize)]
@@ -70,7 +80,130 @@ impl From<customer::Model> for CustomerInfo {
 
 #[derive(Debug, Serialize)]
 pub struct NextActionsResponse {
+    /// Legacy continue/stop values, derived from the stage outcome when a track sets a stage
     pub actions: Vec<String>,
+    /// Active pipeline stages in order
+    pub stages: Vec<pipeline_stage::Model>,
+}
+
+// Shared by both create endpoints once access to the customer has been checked
+async fn insert_track(
+    app_state: &AppState,
+    current_user: CurrentUser,
+    customer: customer::Model,
+    req: CreateTrackRequest,
+) -> Result<CustomerTrackInfo, ApiError> {
+    let stage = match req.stage_id {
+        Some(stage_id) => Some(
+            PipelineService::find_active(&app_state.db, customer.organization_id, stage_id)
+                .await
+                .map_err(pipeline_error)?,
+        ),
+        None => None,
+    };
+    let next_action = req
+        .next_action
+        .or_else(|| stage.as_ref().map(|stage| stage.outcome.next_action()))
+        .unwrap_or(NextAction::Continue);
+
+    let now = Utc::now();
+    let track = customer_track::ActiveModel {
+        customer_id: Set(customer.id),
+        content: Set(req.content),
+        next_action: Set(next_action),
+        track_time: Set(req.track_time.unwrap_or(now)),
+        next_track_time: Set(req.next_track_time),
+        created_at: Set(now),
+        updated_at: Set(now),
+        user_id: Set(Some(current_user.id)),
+        stage_id: Set(stage.as_ref().map(|stage| stage.id)),
+        ..Default::default()
+    };
+
+    let actor = Actor::from(&current_user);
+    let txn = app_state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let track = track
+        .insert(&txn)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    AuditService::record(
+        &txn,
+        &actor,
+        AuditAction::Create,
+        AuditEntity::CustomerTrack,
+        track.id,
+        None,
+        Some(&track),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // A backdated track does not move the customer back to an earlier stage
+    if let Some(stage) = &stage
+        && is_latest_track(&txn, &track)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        PipelineService::move_customer(&txn, &actor, &customer, stage.id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(CustomerTrackInfo {
+        user_name: Some(current_user.name),
+        ..CustomerTrackInfo::from(track)
+    })
+}
+
+// Whether no other track of the customer is more recent
+async fn is_latest_track<C: ConnectionTrait>(
+    db: &C,
+    track: &customer_track::Model,
+) -> Result<bool, DbErr> {
+    let newer = CustomerTrack::find()
+        .filter(customer_track::Column::CustomerId.eq(track.customer_id))
+        .filter(customer_track::Column::Id.ne(track.id))
+        .filter(
+            Condition::any()
+                .add(customer_track::Column::TrackTime.gt(track.track_time))
+                .add(
+                    Condition::all()
+                        .add(customer_track::Column::TrackTime.eq(track.track_time))
+                        .add(customer_track::Column::Id.gt(track.id)),
+                ),
+        )
+        .count(db)
+        .await?;
+    Ok(newer == 0)
+}
+
+// Attaches the author's name, since collaborators add tracks under their own name
+async fn with_authors(
+    db: &DatabaseConnection,
+    tracks: Vec<customer_track::Model>,
+) -> Result<Vec<CustomerTrackInfo>, DbErr> {
+    let mut user_ids: Vec<i32> = tracks.iter().filter_map(|track| track.user_id).collect();
+    user_ids.sort_unstable();
+    user_ids.dedup();
+
+    let names: HashMap<i32, String> = User::find()
+        .filter(user::Column::Id.is_in(user_ids))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|user| (user.id, user.name))
+        .collect();
+
+    Ok(tracks
+        .into_iter()
+        .map(|track| {
+            let user_name = track.user_id.and_then(|user_id| names.get(&user_id).cloned());
+            CustomerTrackInfo {
+                user_name,
+                ..CustomerTrackInfo::from(track)
+            }
+        })
+        .collect())
 }
 
 pub async fn list_customer_tracks(
@@ -78,11 +211,11 @@ pub async fn list_customer_tracks(
     Path(customer_id): Path<i32>,
     State(app_state): State<AppState>,
 ) -> Result<Json<CustomerTrackListResponse>, StatusCode> {
-    // First verify customer belongs to current user
-    let customer = Customer::find_by_id(customer_id)
-        .filter(customer::Column::UserId.eq(current_user.id))
-        .filter(customer::Column::IsDeleted.eq(false))
-        .one(&app_state.db)
+    // First verify current user may access the customer
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (customer, _access) = PermissionService::find_customer(&app_state.db, &scope, customer_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
@@ -95,8 +228,12 @@ pub async fn list_customer_tracks(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let tracks = with_authors(&app_state.db, tracks)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     Ok(Json(CustomerTrackListResponse {
-        tracks: tracks.into_iter().map(CustomerTrackInfo::from).collect(),
+        tracks,
         customer: CustomerInfo::from(customer),
     }))
 }
@@ -106,35 +243,22 @@ pub async fn create_customer_track(
     Path(customer_id): Path<i32>,
     State(app_state): State<AppState>,
     Json(req): Json<CreateTrackRequest>,
-) -> Result<Json<CustomerTrackInfo>, StatusCode> {
-    // Verify customer belongs to current user
-    let _customer = Customer::find_by_id(customer_id)
-        .filter(customer::Column::UserId.eq(current_user.id))
-        .filter(customer::Column::IsDeleted.eq(false))
-        .one(&app_state.db)
+) -> Result<Json<CustomerTrackInfo>, ApiError> {
+    // Verify current user may access the customer
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (customer, access) = PermissionService::find_customer(&app_state.db, &scope, customer_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    if access < CustomerAccess::Write {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
 
-    let now = Utc::now();
-    
-    let track = customer_track::ActiveModel {
-        customer_id: Set(customer_id),
-        content: Set(req.content),
-        next_action: Set(req.next_action.unwrap_or(NextAction::Continue)),
-        track_time: Set(req.track_time.unwrap_or(now)),
-        next_track_time: Set(req.next_track_time),
-        created_at: Set(now),
-        updated_at: Set(now),
-        ..Default::default()
-    };
-
-    let track = track
-        .insert(&app_state.db)
-        .await
-        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let track = insert_track(&app_state, current_user, customer, req).await?;
 
-    Ok(Json(CustomerTrackInfo::from(track)))
+    Ok(Json(track))
 }
 
 pub async fn update_customer_track(
@@ -142,32 +266,42 @@ pub async fn update_customer_track(
     Path(track_id): Path<i32>,
     State(app_state): State<AppState>,
     Json(req): Json<UpdateTrackRequest>,
-) -> Result<Json<CustomerTrackInfo>, StatusCode> {
-    // Find the track and verify ownership through customer relationship
-    let track = CustomerTrack::find_by_id(track_id)
-        .find_also_related(Customer)
-        .one(&app_state.db)
+) -> Result<Json<CustomerTrackInfo>, ApiError> {
+    // Find the track and verify access through customer relationship
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (track, customer, access) = PermissionService::find_track(&app_state.db, &scope, track_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
-
-    let (track, customer) = track;
-    let customer = customer.ok_or(StatusCode::NOT_FOUND)?;
-
-    // Verify the customer belongs to current user
-    if customer.user_id != current_user.id || customer.is_deleted {
-        return Err(StatusCode::NOT_FOUND);
+    if !access.can_modify_track(&track, current_user.id) {
+        return Err(StatusCode::FORBIDDEN.into());
     }
+    let stage = match req.stage_id {
+        Some(stage_id) => Some(
+            PipelineService::find_active(&app_state.db, customer.organization_id, stage_id)
+                .await
+                .map_err(pipeline_error)?,
+        ),
+        None => None,
+    };
 
     // Update track
-    let mut track_active: customer_track::ActiveModel = track.into();
+    let mut track_active: customer_track::ActiveModel = track.clone().into();
     
     if let Some(content) = req.content {
         track_active.content = Set(content);
     }
-    if let Some(next_action) = req.next_action {
+    if let Some(next_action) = req
+        .next_action
+        .or_else(|| stage.as_ref().map(|stage| stage.outcome.next_action()))
+    {
         track_active.next_action = Set(next_action);
     }
+    if let Some(stage) = &stage {
+        track_active.stage_id = Set(Some(stage.id));
+    }
     if let Some(track_time) = req.track_time {
         track_active.track_time = Set(track_time);
     }
@@ -177,12 +311,40 @@ pub async fn update_customer_track(
     
     track_active.updated_at = Set(Utc::now());
 
+    let txn = app_state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     let updated_track = track_active
-        .update(&app_state.db)
+        .update(&txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    AuditService::record(
+        &txn,
+        &Actor::from(&current_user),
+        AuditAction::Update,
+        AuditEntity::CustomerTrack,
+        track_id,
+        Some(&track),
+        Some(&updated_track),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // Only the latest track decides the customer's current stage
+    if let Some(stage) = &stage
+        && is_latest_track(&txn, &updated_track)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        PipelineService::move_customer(&txn, &Actor::from(&current_user), &customer, stage.id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let updated_track = with_authors(&app_state.db, vec![updated_track])
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .remove(0);
 
-    Ok(Json(CustomerTrackInfo::from(updated_track)))
+    Ok(Json(updated_track))
 }
 
 pub async fn delete_customer_track(
@@ -190,27 +352,46 @@ pub async fn delete_customer_track(
     Path(track_id): Path<i32>,
     State(app_state): State<AppState>,
 ) -> Result<StatusCode, StatusCode> {
-    // Find the track and verify ownership through customer relationship
-    let track = CustomerTrack::find_by_id(track_id)
-        .find_also_related(Customer)
-        .one(&app_state.db)
+    // Find the track and verify access through customer relationship
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (track, customer, access) = PermissionService::find_track(&app_state.db, &scope, track_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
-
-    let (track, customer) = track;
-    let customer = customer.ok_or(StatusCode::NOT_FOUND)?;
-
-    // Verify the customer belongs to current user
-    if customer.user_id != current_user.id || customer.is_deleted {
-        return Err(StatusCode::NOT_FOUND);
+    if !access.can_modify_track(&track, current_user.id) {
+        return Err(StatusCode::FORBIDDEN);
     }
 
     // Delete the track
+    let txn = app_state.db.begin().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let sets_current_stage = track.stage_id.is_some()
+        && is_latest_track(&txn, &track)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
     CustomerTrack::delete_by_id(track.id)
-        .exec(&app_state.db)
+        .exec(&txn)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    AuditService::record(
+        &txn,
+        &Actor::from(&current_user),
+        AuditAction::Delete,
+        AuditEntity::CustomerTrack,
+        track_id,
+        Some(&track),
+        None,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    // The customer's stage came from this track, fall back to the remaining ones
+    if sets_current_stage {
+        PipelineService::restore_from_tracks(&txn, &Actor::from(&current_user), &customer)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    }
+    txn.commit().await.map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
     Ok(StatusCode::NO_CONTENT)
 }
@@ -221,11 +402,11 @@ pub async fn list_tracks(
     Query(params): Query<TrackListQuery>,
     State(app_state): State<AppState>,
 ) -> Result<Json<TrackListResponse>, StatusCode> {
-    // 验证客户是否属于当前用户
-    let _customer = Customer::find_by_id(params.customer_id)
-        .filter(customer::Column::UserId.eq(current_user.id))
-        .filter(customer::Column::IsDeleted.eq(false))
-        .one(&app_state.db)
+    // 验证当前用户是否有权访问该客户
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let _customer = PermissionService::find_customer(&app_state.db, &scope, params.customer_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
@@ -246,8 +427,12 @@ pub async fn list_tracks(
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
+    let tracks = with_authors(&app_state.db, tracks_page)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
     Ok(Json(TrackListResponse {
-        tracks: tracks_page.into_iter().map(CustomerTrackInfo::from).collect(),
+        tracks,
         total,
         page: params.page,
         limit: params.limit,
@@ -259,39 +444,196 @@ pub async fn create_track(
     Extension(current_user): Extension<CurrentUser>,
     State(app_state): State<AppState>,
     Json(req): Json<CreateTrackRequest>,
-) -> Result<Json<CustomerTrackInfo>, StatusCode> {
-    // 验证客户是否属于当前用户
-    let _customer = Customer::find_by_id(req.customer_id)
-        .filter(customer::Column::UserId.eq(current_user.id))
-        .filter(customer::Column::IsDeleted.eq(false))
-        .one(&app_state.db)
+) -> Result<Json<CustomerTrackInfo>, ApiError> {
+    // 验证当前用户是否有权访问该客户
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (customer, access) = PermissionService::find_customer(&app_state.db, &scope, req.customer_id)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
         .ok_or(StatusCode::NOT_FOUND)?;
+    if access < CustomerAccess::Write {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
 
-    let now = Utc::now();
-    
-    let track = customer_track::ActiveModel {
-        customer_id: Set(req.customer_id),
-        content: Set(req.content),
-        next_action: Set(req.next_action.unwrap_or(NextAction::Continue)),
-        track_time: Set(req.track_time.unwrap_or(now)),
-        next_track_time: Set(req.next_track_time),
-        created_at: Set(now),
-        updated_at: Set(now),
-        ..Default::default()
-    };
+    let track = insert_track(&app_state, current_user, customer, req).await?;
 
-    let track = track
-        .insert(&app_state.db)
+    Ok(Json(track))
+}
+
+pub async fn get_next_actions(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<NextActionsResponse>, StatusCode> {
+    let stages = PipelineService::list(&app_state.db, current_user.organization_id, false)
         .await
         .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
 
-    Ok(Json(CustomerTrackInfo::from(track)))
+    Ok(Json(NextActionsResponse {
+        actions: NextAction::variants().into_iter().map(|s| s.to_string()).collect(),
+        stages,
+    }))
 }
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        entities::user_role::UserRole,
+        test_support::{self, TestApp},
+    };
 
-pub async fn get_next_actions() -> Json<NextActionsResponse> {
-    Json(NextActionsResponse {
-        actions: NextAction::variants().into_iter().map(|s| s.to_string()).collect(),
-    })
-}
\ No newline at end of file
+    fn stage_id(stages: &Value, key: &str) -> i64 {
+        stages
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|stage| stage["key"] == key)
+            .and_then(|stage| stage["id"].as_i64())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn tracks_move_the_customer_through_the_pipeline() {
+        let app = TestApp::new().await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let customer = test_support::create_customer(app.db(), "张三", None, Some(alice.id)).await;
+        let token = app.login("alice").await;
+        let admin = app.login("admin").await;
+        let customer_uri = format!("/api/customers/{}", customer.id);
+        let tracks_uri = format!("/api/customers/{}/tracks", customer.id);
+
+        let (status, json) = app
+            .request(Method::GET, "/api/tracks/actions", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["actions"], json!(["继续跟进", "结束跟进"]));
+        let stages = json["stages"].clone();
+        assert_eq!(stages.as_array().unwrap().len(), 6);
+        let (trial_booked, signed) = (
+            stage_id(&stages, "trial_booked"),
+            stage_id(&stages, "signed"),
+        );
+
+        let now = Utc::now();
+        let body = json!({
+            "customer_id": customer.id,
+            "content": "约了体验课",
+            "stage_id": trial_booked,
+            "track_time": now - chrono::Duration::days(1),
+        });
+        let (status, older) = app
+            .request(Method::POST, &tracks_uri, Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(older["next_action"], "继续跟进");
+        let (_, json) = app
+            .request(Method::GET, &customer_uri, Some(&token), None)
+            .await;
+        assert_eq!(json["stage"]["key"], "trial_booked");
+
+        // 赢单阶段推断为结束跟进
+        let body = json!({ "customer_id": customer.id, "content": "签约", "stage_id": signed, "track_time": now });
+        let (status, latest) = app
+            .request(Method::POST, &tracks_uri, Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(latest["next_action"], "结束跟进");
+        let (_, json) = app
+            .request(Method::GET, &customer_uri, Some(&token), None)
+            .await;
+        assert_eq!(json["stage_id"], signed);
+
+        // 修改较早的跟进记录不会改变客户当前的阶段
+        let older_uri = format!("/api/tracks/{}", older["id"]);
+        let body = json!({ "stage_id": stage_id(&stages, "contacted") });
+        let (status, _) = app
+            .request(Method::PUT, &older_uri, Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, json) = app
+            .request(Method::GET, &customer_uri, Some(&token), None)
+            .await;
+        assert_eq!(json["stage_id"], signed);
+        let uri = format!("/api/customers?stage_id={}", signed);
+        let (_, json) = app.request(Method::GET, &uri, Some(&token), None).await;
+        assert_eq!(json["total"], 1);
+
+        // 停用的阶段不能再选择，其他组织或不存在的阶段同样拒绝
+        let stage_uri = format!("/api/pipeline-stages/{}", trial_booked);
+        let body = json!({ "is_active": false });
+        let (status, _) = app
+            .request(Method::PUT, &stage_uri, Some(&token), Some(body.clone()))
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = app
+            .request(Method::PUT, &stage_uri, Some(&admin), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        for stage in [trial_booked, 9999] {
+            let body = json!({ "customer_id": customer.id, "content": "回访", "stage_id": stage });
+            let (status, _) = app
+                .request(Method::POST, &tracks_uri, Some(&token), Some(body))
+                .await;
+            assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        let (_, json) = app
+            .request(Method::GET, "/api/tracks/actions", Some(&token), None)
+            .await;
+        assert_eq!(json["stages"].as_array().unwrap().len(), 5);
+    }
+
+    #[tokio::test]
+    async fn only_the_latest_track_decides_the_stage() {
+        let app = TestApp::new().await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let customer = test_support::create_customer(app.db(), "张三", None, Some(alice.id)).await;
+        let token = app.login("alice").await;
+        let customer_uri = format!("/api/customers/{}", customer.id);
+        let tracks_uri = format!("/api/customers/{}/tracks", customer.id);
+        let (_, json) = app
+            .request(Method::GET, "/api/tracks/actions", Some(&token), None)
+            .await;
+        let stages = json["stages"].clone();
+        let now = Utc::now();
+
+        let mut track_ids = Vec::new();
+        for (key, days_ago) in [("contacted", 3), ("trial_done", 1), ("trial_booked", 2)] {
+            let body = json!({
+                "customer_id": customer.id,
+                "content": key,
+                "stage_id": stage_id(&stages, key),
+                "track_time": now - chrono::Duration::days(days_ago),
+            });
+            let (status, track) = app
+                .request(Method::POST, &tracks_uri, Some(&token), Some(body))
+                .await;
+            assert_eq!(status, StatusCode::OK);
+            track_ids.push(track["id"].clone());
+        }
+        // 补记的较早跟进不会把客户退回之前的阶段
+        let (_, json) = app
+            .request(Method::GET, &customer_uri, Some(&token), None)
+            .await;
+        assert_eq!(json["stage"]["key"], "trial_done");
+
+        // 删除较早的跟进不影响阶段；删除最新一条时回到剩下的跟进中最新的阶段
+        for (track_id, expected) in [
+            (&track_ids[0], "trial_done"),
+            (&track_ids[1], "trial_booked"),
+            (&track_ids[2], "new"),
+        ] {
+            let uri = format!("/api/tracks/{}", track_id);
+            let (status, _) = app.request(Method::DELETE, &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::NO_CONTENT);
+            let (_, json) = app
+                .request(Method::GET, &customer_uri, Some(&token), None)
+                .await;
+            assert_eq!(json["stage"]["key"], expected);
+        }
+    }
+}
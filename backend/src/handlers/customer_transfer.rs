@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{
+        customer_group::CustomerGroup, customer_ownership_history, user_role::UserRole,
+    },
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        audit_service::Actor,
+        customer_transfer_service::{
+            CustomerTransferService, TransferError, TransferRequest, TransferSelection,
+        },
+        permission_service::PermissionService,
+    },
+};
+
+/// Either `customer_ids`, or `from_user_id` optionally narrowed by
+/// `customer_group` / `search`; `from_user_id` alone moves all of that
+/// user's customers.
+#[derive(Debug, Deserialize)]
+pub struct TransferCustomersRequest {
+    pub to_user_id: i32,
+    pub customer_ids: Option<Vec<i32>>,
+    pub from_user_id: Option<i32>,
+    pub customer_group: Option<CustomerGroup>,
+    pub search: Option<String>,
+    pub reason: Option<String>,
+    #[serde(default)]
+    pub notify_previous_owner: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransferCustomersResponse {
+    pub transferred: usize,
+    pub skipped: usize,
+    pub customer_ids: Vec<i32>,
+}
+
+fn transfer_error(error: TransferError) -> ApiError {
+    match error {
+        TransferError::TargetNotFound | TransferError::EmptySelection => {
+            ApiError::unprocessable(error.to_string())
+        }
+        TransferError::TargetOutOfScope | TransferError::SourceOutOfScope => {
+            ApiError::new(StatusCode::FORBIDDEN, error.to_string())
+        }
+        TransferError::CustomerNotFound(_) => ApiError::new(StatusCode::NOT_FOUND, error.to_string()),
+        TransferError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+    }
+}
+
+// 转移客户负责人只对管理员和主管开放，主管只能在本人及团队成员之间转移
+pub async fn transfer_customers(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<TransferCustomersRequest>,
+) -> Result<Json<TransferCustomersResponse>, ApiError> {
+    if current_user.role == UserRole::Sales {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+
+    let selection = match (req.customer_ids, req.from_user_id) {
+        (Some(customer_ids), None) => TransferSelection::Customers(customer_ids),
+        (None, Some(user_id)) => TransferSelection::OwnedBy {
+            user_id,
+            customer_group: req.customer_group,
+            search: req.search,
+        },
+        _ => {
+            return Err(ApiError::unprocessable(
+                "customer_ids 和 from_user_id 需要且只能指定一个",
+            ))
+        }
+    };
+
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let outcome = CustomerTransferService::transfer(
+        &app_state.db,
+        &Actor::from(&current_user),
+        &scope,
+        TransferRequest {
+            to_user_id: req.to_user_id,
+            selection,
+            reason: req.reason,
+            notify_previous_owner: req.notify_previous_owner,
+        },
+    )
+    .await
+    .map_err(transfer_error)?;
+
+    Ok(Json(TransferCustomersResponse {
+        transferred: outcome.transferred.len(),
+        skipped: outcome.skipped,
+        customer_ids: outcome.transferred,
+    }))
+}
+
+pub async fn get_ownership_history(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(customer_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<customer_ownership_history::Model>>, StatusCode> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    PermissionService::find_customer(&app_state.db, &scope, customer_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let history = CustomerTransferService::history_for_customer(&app_state.db, customer_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(history))
+}
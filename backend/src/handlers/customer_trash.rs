@@ -0,0 +1,234 @@
+use std::collections::HashMap;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{
+        customer,
+        customer_group::CustomerGroup,
+        customer_track::{self, Entity as CustomerTrack},
+        user::{self, Entity as User},
+    },
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        audit_service::Actor,
+        permission_service::PermissionService,
+        trash_service::{TrashError, TrashService},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct TrashListQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+    pub search: Option<String>,
+}
+
+fn default_page() -> u64 { 1 }
+fn default_limit() -> u64 { 20 }
+
+#[derive(Debug, Serialize)]
+pub struct TrashedCustomer {
+    pub id: i32,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub rate: f32,
+    pub customer_group: CustomerGroup,
+    pub user_id: Option<i32>,
+    pub track_count: i64,
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub deleted_by: Option<i32>,
+    pub deleted_by_name: Option<String>,
+    /// Set when the customer was removed by merging it into another one
+    pub merged_into_id: Option<i32>,
+    /// When the customer will be purged automatically, if a retention period is configured
+    pub purge_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TrashListResponse {
+    pub customers: Vec<TrashedCustomer>,
+    pub total: u64,
+    pub page: u64,
+    pub limit: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PurgeCustomerResponse {
+    pub deleted_tracks: u64,
+}
+
+fn trash_error(error: TrashError) -> ApiError {
+    match error {
+        TrashError::Merged(_) => ApiError::new(StatusCode::CONFLICT, error.to_string()),
+        TrashError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+    }
+}
+
+// Deleted customers the caller owns or manages; collaborators do not see them
+pub async fn list_trash(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<TrashListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<TrashListResponse>, StatusCode> {
+    if params.page == 0 || params.limit == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let (customers, total) = TrashService::list(
+        &app_state.db,
+        &scope,
+        params.search.as_deref(),
+        params.page,
+        params.limit,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    let deleted_by_ids: Vec<i32> = customers
+        .iter()
+        .filter_map(|customer| customer.deleted_by)
+        .collect();
+    let names: HashMap<i32, String> = User::find()
+        .filter(user::Column::Id.is_in(deleted_by_ids))
+        .all(&app_state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .into_iter()
+        .map(|user| (user.id, user.name))
+        .collect();
+
+    let mut trashed = Vec::with_capacity(customers.len());
+    for customer in customers {
+        let track_count = CustomerTrack::find()
+            .filter(customer_track::Column::CustomerId.eq(customer.id))
+            .count(&app_state.db)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+        trashed.push(TrashedCustomer {
+            id: customer.id,
+            name: customer.name,
+            phone: customer.phone,
+            address: customer.address,
+            rate: customer.rate,
+            customer_group: customer.customer_group,
+            user_id: customer.user_id,
+            track_count: track_count as i64,
+            deleted_at: customer.deleted_at,
+            deleted_by: customer.deleted_by,
+            deleted_by_name: customer
+                .deleted_by
+                .and_then(|user_id| names.get(&user_id).cloned()),
+            merged_into_id: customer.merged_into_id,
+            purge_at: customer
+                .deleted_at
+                .and_then(|deleted_at| app_state.trash.purge_at(deleted_at)),
+        });
+    }
+
+    Ok(Json(TrashListResponse {
+        customers: trashed,
+        total,
+        page: params.page,
+        limit: params.limit,
+    }))
+}
+
+pub async fn restore_customer(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(customer_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<Json<customer::Model>, ApiError> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let customer = TrashService::find(&app_state.db, &scope, customer_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let restored = TrashService::restore(&app_state.db, &Actor::from(&current_user), customer)
+        .await
+        .map_err(trash_error)?;
+
+    Ok(Json(restored))
+}
+
+// Only customers already in the trash can be purged
+pub async fn purge_customer(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(customer_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<Json<PurgeCustomerResponse>, StatusCode> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let customer = TrashService::find(&app_state.db, &scope, customer_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let deleted_tracks = TrashService::purge(&app_state.db, &Actor::from(&current_user), &customer)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(PurgeCustomerResponse { deleted_tracks }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+
+    use super::*;
+    use crate::{
+        entities::user_role::UserRole,
+        test_support::{self, TestApp},
+    };
+
+    #[tokio::test]
+    async fn deleted_customers_go_to_the_owners_trash_and_can_be_restored() {
+        let app = TestApp::new().await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        test_support::create_user(app.db(), "bob", UserRole::Sales).await;
+        let customer = test_support::create_customer(app.db(), "张三", None, Some(alice.id)).await;
+        let alice = app.login("alice").await;
+        let bob = app.login("bob").await;
+        let customer_uri = format!("/api/customers/{}", customer.id);
+
+        let (status, _) = app.request(Method::DELETE, &customer_uri, Some(&alice), None).await;
+        assert!(status.is_success());
+        let (status, _) = app.request(Method::GET, &customer_uri, Some(&alice), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (_, json) = app.request(Method::GET, "/api/customers/trash", Some(&alice), None).await;
+        assert_eq!(json["total"], 1);
+        assert_eq!(json["customers"][0]["deleted_by_name"], "alice");
+        // 默认不自动清除
+        assert!(json["customers"][0]["purge_at"].is_null());
+        let (_, json) = app.request(Method::GET, "/api/customers/trash", Some(&bob), None).await;
+        assert_eq!(json["total"], 0);
+
+        let restore_uri = format!("/api/customers/{}/restore", customer.id);
+        let (status, _) = app.request(Method::POST, &restore_uri, Some(&bob), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app.request(Method::POST, &restore_uri, Some(&alice), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = app.request(Method::GET, &customer_uri, Some(&alice), None).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+}
@@ -0,0 +1,51 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+
+/// Error returned by handlers that need to tell the client why a request was
+/// rejected. A bare `StatusCode` converts into it, so `?` keeps working with
+/// the usual `map_err(|_| StatusCode::...)` pattern.
+#[derive(Debug)]
+pub struct ApiError {
+    status: StatusCode,
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ErrorBody {
+    message: String,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: Some(message.into()),
+        }
+    }
+
+    /// 422 with a message, shown as-is by the frontend
+    pub fn unprocessable(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::UNPROCESSABLE_ENTITY, message)
+    }
+}
+
+impl From<StatusCode> for ApiError {
+    fn from(status: StatusCode) -> Self {
+        Self {
+            status,
+            message: None,
+        }
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        match self.message {
+            Some(message) => (self.status, Json(ErrorBody { message })).into_response(),
+            None => self.status.into_response(),
+        }
+    }
+}
@@ -0,0 +1,10 @@
+use axum::{extract::State, response::Json};
+use jsonwebtoken::jwk::JwkSet;
+
+use crate::handlers::auth::AppState;
+
+// Public keys of every signing key still accepted, so other services can
+// verify our access tokens without sharing a secret
+pub async fn get_jwks(State(app_state): State<AppState>) -> Json<JwkSet> {
+    Json(app_state.jwt_keys.jwks())
+}
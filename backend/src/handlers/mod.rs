@@ -1,3 +1,22 @@
+pub mod api_token;
+pub mod audit;
 pub mod auth;
+pub mod custom_field;
 pub mod customer;
-pub mod customer_track;
\ No newline at end of file
+pub mod customer_collaborator;
+pub mod customer_duplicate;
+pub mod customer_group;
+pub mod customer_pool;
+pub mod customer_track;
+pub mod customer_transfer;
+pub mod customer_trash;
+pub mod error;
+pub mod jwks;
+pub mod notification;
+pub mod oidc;
+pub mod pipeline_stage;
+pub mod search;
+pub mod session;
+pub mod tag;
+pub mod two_factor;
+pub mod user;
\ No newline at end of file
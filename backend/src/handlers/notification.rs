@@ -0,0 +1,100 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::notification,
+    handlers::auth::AppState,
+    middleware::auth::CurrentUser,
+    services::notification_service::NotificationService,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct NotificationListQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+    #[serde(default)]
+    pub unread_only: bool,
+}
+
+fn default_page() -> u64 { 1 }
+fn default_limit() -> u64 { 20 }
+
+#[derive(Debug, Serialize)]
+pub struct NotificationListResponse {
+    pub notifications: Vec<notification::Model>,
+    pub total: u64,
+    /// Unread notifications regardless of the `unread_only` filter
+    pub unread: u64,
+    pub page: u64,
+    pub limit: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MarkAllReadResponse {
+    pub marked: u64,
+}
+
+pub async fn list_notifications(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<NotificationListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<NotificationListResponse>, StatusCode> {
+    if params.page == 0 || params.limit == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let (notifications, total) = NotificationService::list_for_user(
+        &app_state.db,
+        current_user.id,
+        params.unread_only,
+        params.page,
+        params.limit,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let unread = NotificationService::unread_count(&app_state.db, current_user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(NotificationListResponse {
+        notifications,
+        total,
+        unread,
+        page: params.page,
+        limit: params.limit,
+    }))
+}
+
+pub async fn mark_notification_read(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(notification_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    let found = NotificationService::mark_read(&app_state.db, current_user.id, notification_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if found {
+        Ok(StatusCode::NO_CONTENT)
+    } else {
+        Err(StatusCode::NOT_FOUND)
+    }
+}
+
+pub async fn mark_all_notifications_read(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<MarkAllReadResponse>, StatusCode> {
+    let marked = NotificationService::mark_all_read(&app_state.db, current_user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(MarkAllReadResponse { marked }))
+}
@@ -0,0 +1,93 @@
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{HeaderMap, StatusCode},
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+use std::{net::SocketAddr, sync::Arc};
+
+use crate::{
+    handlers::{
+        auth::{complete_login, AppState, LoginResponse},
+        error::ApiError,
+    },
+    services::oidc_service::{OidcError, OidcProvider},
+    utils::client_ip::ClientInfo,
+};
+
+#[derive(Debug, Serialize)]
+pub struct OidcConfigResponse {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OidcAuthorizeResponse {
+    pub authorization_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OidcCallbackRequest {
+    pub code: String,
+    pub state: String,
+}
+
+fn oidc_error(error: OidcError) -> ApiError {
+    match error {
+        OidcError::InvalidState => ApiError::new(StatusCode::BAD_REQUEST, error.to_string()),
+        OidcError::Provider(ref message) => {
+            tracing::warn!("OIDC provider request failed: {}", message);
+            ApiError::new(StatusCode::BAD_GATEWAY, "身份提供方暂时不可用")
+        }
+        OidcError::InvalidIdToken(ref message) => {
+            tracing::warn!("Rejected OIDC id_token: {}", message);
+            ApiError::new(StatusCode::UNAUTHORIZED, "身份令牌无效")
+        }
+        OidcError::EmailNotVerified | OidcError::UserNotFound | OidcError::UserDisabled => {
+            ApiError::new(StatusCode::FORBIDDEN, error.to_string())
+        }
+        OidcError::User(ref e) => {
+            tracing::error!("OIDC user provisioning failed: {}", e);
+            ApiError::new(StatusCode::FORBIDDEN, "无法自动创建用户，请联系管理员")
+        }
+        OidcError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+    }
+}
+
+fn provider(app_state: &AppState) -> Result<&Arc<OidcProvider>, ApiError> {
+    app_state.oidc.as_ref().ok_or_else(|| StatusCode::NOT_FOUND.into())
+}
+
+// Lets the login page decide whether to show the single sign-on button
+pub async fn get_config(State(app_state): State<AppState>) -> Json<OidcConfigResponse> {
+    Json(OidcConfigResponse {
+        enabled: app_state.oidc.is_some(),
+    })
+}
+
+pub async fn authorize(
+    State(app_state): State<AppState>,
+) -> Result<Json<OidcAuthorizeResponse>, ApiError> {
+    let authorization_url = provider(&app_state)?
+        .begin(&app_state.db)
+        .await
+        .map_err(oidc_error)?;
+
+    Ok(Json(OidcAuthorizeResponse { authorization_url }))
+}
+
+// The frontend callback page posts back the code and state it received
+pub async fn callback(
+    State(app_state): State<AppState>,
+    ConnectInfo(peer): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(req): Json<OidcCallbackRequest>,
+) -> Result<Json<LoginResponse>, ApiError> {
+    let client = ClientInfo::from_request(&headers, peer, app_state.trust_proxy_headers);
+
+    let user = provider(&app_state)?
+        .complete(&app_state.db, &req.code, &req.state)
+        .await
+        .map_err(oidc_error)?;
+
+    Ok(Json(complete_login(&app_state, user, &client).await?))
+}
@@ -0,0 +1,173 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{
+        pipeline_stage::{self, StageOutcome},
+        user_role::UserRole,
+    },
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::pipeline_service::{
+        NewPipelineStage, PipelineError, PipelineService, PipelineStageUpdate,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct PipelineStageListQuery {
+    /// Inactive stages are still needed to display existing customers
+    #[serde(default)]
+    pub include_inactive: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreatePipelineStageRequest {
+    pub key: String,
+    pub name: String,
+    #[serde(default = "default_outcome")]
+    pub outcome: StageOutcome,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub sort_order: i32,
+}
+
+fn default_outcome() -> StageOutcome { StageOutcome::Open }
+
+#[derive(Debug, Deserialize)]
+pub struct UpdatePipelineStageRequest {
+    pub name: Option<String>,
+    pub outcome: Option<StageOutcome>,
+    /// An empty string clears the colour
+    pub color: Option<String>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// A stage and how many customers of the organization are in it
+#[derive(Debug, Serialize)]
+pub struct PipelineStageWithCount {
+    #[serde(flatten)]
+    pub stage: pipeline_stage::Model,
+    pub customer_count: i64,
+}
+
+pub fn pipeline_error(error: PipelineError) -> ApiError {
+    match error {
+        PipelineError::KeyExists(_) | PipelineError::InUse(_) => {
+            ApiError::new(StatusCode::CONFLICT, error.to_string())
+        }
+        PipelineError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+        _ => ApiError::unprocessable(error.to_string()),
+    }
+}
+
+fn require_admin(current_user: &CurrentUser) -> Result<(), ApiError> {
+    if current_user.role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+    Ok(())
+}
+
+pub async fn list_pipeline_stages(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<PipelineStageListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<PipelineStageWithCount>>, StatusCode> {
+    let stages = PipelineService::list(
+        &app_state.db,
+        current_user.organization_id,
+        params.include_inactive,
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let counts = PipelineService::customer_counts(&app_state.db, current_user.organization_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        stages
+            .into_iter()
+            .map(|stage| {
+                let customer_count = counts.get(&stage.id).copied().unwrap_or(0);
+                PipelineStageWithCount { stage, customer_count }
+            })
+            .collect(),
+    ))
+}
+
+pub async fn create_pipeline_stage(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<CreatePipelineStageRequest>,
+) -> Result<Json<pipeline_stage::Model>, ApiError> {
+    require_admin(&current_user)?;
+
+    let stage = PipelineService::create(
+        &app_state.db,
+        current_user.organization_id,
+        NewPipelineStage {
+            stage_key: req.key,
+            name: req.name,
+            outcome: req.outcome,
+            color: req.color,
+            sort_order: req.sort_order,
+        },
+    )
+    .await
+    .map_err(pipeline_error)?;
+
+    Ok(Json(stage))
+}
+
+pub async fn update_pipeline_stage(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(stage_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<UpdatePipelineStageRequest>,
+) -> Result<Json<pipeline_stage::Model>, ApiError> {
+    require_admin(&current_user)?;
+    let stage = PipelineService::find(&app_state.db, current_user.organization_id, stage_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let stage = PipelineService::update(
+        &app_state.db,
+        stage,
+        PipelineStageUpdate {
+            name: req.name,
+            outcome: req.outcome,
+            color: req.color,
+            sort_order: req.sort_order,
+            is_active: req.is_active,
+        },
+    )
+    .await
+    .map_err(pipeline_error)?;
+
+    Ok(Json(stage))
+}
+
+// Stages that still have customers must be deactivated instead
+pub async fn delete_pipeline_stage(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(stage_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&current_user)?;
+    let stage = PipelineService::find(&app_state.db, current_user.organization_id, stage_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    PipelineService::delete(&app_state.db, &stage)
+        .await
+        .map_err(pipeline_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,121 @@
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::api_token::ApiScope,
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        permission_service::PermissionService,
+        search_service::{SearchHit, SearchService},
+    },
+};
+
+/// Upper bound for `limit`, hits are ranked so more than this is rarely useful
+const MAX_LIMIT: u64 = 50;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    #[serde(default)]
+    pub q: String,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+fn default_limit() -> u64 { 20 }
+
+#[derive(Debug, Serialize)]
+pub struct SearchResponse {
+    pub query: String,
+    pub hits: Vec<SearchHit>,
+}
+
+// Full-text search over customers and their tracks; track hits need the tracks scope
+pub async fn search(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<SearchQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<SearchResponse>, ApiError> {
+    let query = params.q.trim().to_string();
+    if query.is_empty() {
+        return Err(ApiError::new(StatusCode::BAD_REQUEST, "搜索内容不能为空"));
+    }
+    if params.limit == 0 {
+        return Err(StatusCode::BAD_REQUEST.into());
+    }
+
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let hits = SearchService::search(
+        &app_state.db,
+        &scope,
+        &query,
+        current_user.has_scope(ApiScope::TracksRead),
+        params.limit.min(MAX_LIMIT),
+    )
+    .await
+    .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(SearchResponse { query, hits }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+
+    use super::*;
+    use crate::{
+        entities::user_role::UserRole,
+        services::api_token_service::ApiTokenService,
+        test_support::{self, TestApp},
+    };
+
+    #[tokio::test]
+    async fn track_hits_need_the_tracks_scope() {
+        let app = TestApp::new().await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let customer =
+            test_support::create_customer(app.db(), "晨练客户", None, Some(alice.id)).await;
+        test_support::create_track(app.db(), customer.id, Some(alice.id), chrono::Utc::now()).await;
+        let session = app.login("alice").await;
+        let api_token =
+            ApiTokenService::create(app.db(), alice.id, "ci", &[ApiScope::CustomersRead], None)
+                .await
+                .unwrap()
+                .plaintext;
+
+        // 测试跟进记录的内容为「电话沟通」
+        let (status, json) = app
+            .request(
+                Method::GET,
+                "/api/search?q=%E7%94%B5%E8%AF%9D%E6%B2%9F%E9%80%9A",
+                Some(&session),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["hits"][0]["kind"], "track");
+        assert_eq!(json["hits"][0]["customer_name"], "晨练客户");
+        let (status, json) = app
+            .request(
+                Method::GET,
+                "/api/search?q=%E7%94%B5%E8%AF%9D%E6%B2%9F%E9%80%9A",
+                Some(&api_token),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["hits"].as_array().unwrap().len(), 0);
+
+        for uri in ["/api/search?q=%20", "/api/search?q=x&limit=0"] {
+            let (status, _) = app.request(Method::GET, uri, Some(&session), None).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+}
@@ -0,0 +1,295 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::EntityTrait;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{login_history, session, user::Entity as User},
+    handlers::auth::AppState,
+    middleware::auth::CurrentUser,
+    services::{login_history_service::LoginHistoryService, session_service::SessionService},
+};
+
+#[derive(Debug, Serialize)]
+pub struct SessionInfo {
+    pub id: i32,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Last time the session's refresh token was used
+    pub last_active_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Whether this is the session making the request
+    pub current: bool,
+}
+
+impl SessionInfo {
+    fn new(session: session::Model, current_session_id: Option<i32>) -> Self {
+        Self {
+            current: Some(session.id) == current_session_id,
+            id: session.id,
+            ip_address: session.ip_address,
+            user_agent: session.user_agent,
+            created_at: session.created_at,
+            last_active_at: session.updated_at,
+            expires_at: session.expires_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RevokeSessionsResponse {
+    pub revoked: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoginHistoryQuery {
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+}
+
+fn default_limit() -> u64 { 20 }
+
+pub async fn list_sessions(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<SessionInfo>>, StatusCode> {
+    let sessions = SessionService::list_active(&app_state.db, current_user.id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        sessions
+            .into_iter()
+            .map(|session| SessionInfo::new(session, current_user.session_id))
+            .collect(),
+    ))
+}
+
+pub async fn revoke_session(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(session_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<StatusCode, StatusCode> {
+    let revoked = SessionService::revoke_for_user(&app_state.db, current_user.id, session_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    if !revoked {
+        return Err(StatusCode::NOT_FOUND);
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 退出其他所有设备，保留当前会话
+pub async fn revoke_other_sessions(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<RevokeSessionsResponse>, StatusCode> {
+    let current_session_id = current_user.session_id.ok_or(StatusCode::FORBIDDEN)?;
+
+    let revoked = SessionService::revoke_others(&app_state.db, current_user.id, current_session_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(RevokeSessionsResponse { revoked }))
+}
+
+pub async fn list_login_history(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<LoginHistoryQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<login_history::Model>>, StatusCode> {
+    let user = User::find_by_id(current_user.id)
+        .one(&app_state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let history = LoginHistoryService::recent_for_user(&app_state.db, &user, params.limit.min(100))
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(history))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::{
+        entities::user_role::UserRole,
+        test_support::{self, TestApp, PASSWORD},
+    };
+
+    async fn login(app: &TestApp, username: &str) -> (String, String) {
+        let body = json!({ "username": username, "password": PASSWORD });
+        let (status, json) = app
+            .request(Method::POST, "/api/auth/login", None, Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", json);
+        (
+            json["token"].as_str().unwrap().to_string(),
+            json["refresh_token"].as_str().unwrap().to_string(),
+        )
+    }
+
+    async fn refresh(app: &TestApp, refresh_token: &str) -> StatusCode {
+        let body = json!({ "refresh_token": refresh_token });
+        app.request(Method::POST, "/api/auth/refresh", None, Some(body))
+            .await
+            .0
+    }
+
+    fn ids(sessions: &Value) -> Vec<i64> {
+        sessions
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|session| session["id"].as_i64().unwrap())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn revoked_sessions_can_no_longer_refresh() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let (laptop, _) = login(&app, "alice").await;
+        let (phone, phone_refresh) = login(&app, "alice").await;
+
+        let (status, sessions) = app
+            .request(Method::GET, "/api/auth/sessions", Some(&laptop), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let sessions = sessions.as_array().unwrap();
+        assert_eq!(sessions.len(), 2);
+        let phone_session = sessions
+            .iter()
+            .find(|session| session["current"] == false)
+            .unwrap();
+        let uri = format!("/api/auth/sessions/{}", phone_session["id"]);
+
+        let (status, _) = app.request(Method::DELETE, &uri, Some(&laptop), None).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            refresh(&app, &phone_refresh).await,
+            StatusCode::UNAUTHORIZED
+        );
+        let (status, _) = app
+            .request(Method::GET, "/api/auth/me", Some(&phone), None)
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.request(Method::DELETE, &uri, Some(&laptop), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = app
+            .request(Method::GET, "/api/auth/me", Some(&laptop), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn revoking_other_sessions_keeps_the_current_one() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let (laptop, laptop_refresh) = login(&app, "alice").await;
+        let (_, phone_refresh) = login(&app, "alice").await;
+        let (_, tablet_refresh) = login(&app, "alice").await;
+
+        let (status, revoked) = app
+            .request(Method::DELETE, "/api/auth/sessions", Some(&laptop), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(revoked["revoked"], 2);
+        assert_eq!(
+            refresh(&app, &phone_refresh).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            refresh(&app, &tablet_refresh).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(refresh(&app, &laptop_refresh).await, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn users_only_see_and_revoke_their_own_sessions() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let (alice, _) = login(&app, "alice").await;
+        let (admin, admin_refresh) = login(&app, "admin").await;
+
+        let (_, alice_sessions) = app
+            .request(Method::GET, "/api/auth/sessions", Some(&alice), None)
+            .await;
+        let (_, admin_sessions) = app
+            .request(Method::GET, "/api/auth/sessions", Some(&admin), None)
+            .await;
+        let alice_ids = ids(&alice_sessions);
+        let admin_ids = ids(&admin_sessions);
+        assert_eq!(alice_ids.len(), 1);
+        assert_eq!(admin_ids.len(), 1);
+        assert_ne!(alice_ids, admin_ids);
+        assert_eq!(alice_sessions[0]["current"], true);
+
+        // 管理员也不能吊销别人的会话
+        let uri = format!("/api/auth/sessions/{}", alice_ids[0]);
+        let (status, _) = app.request(Method::DELETE, &uri, Some(&admin), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let uri = format!("/api/auth/sessions/{}", admin_ids[0]);
+        let (status, _) = app.request(Method::DELETE, &uri, Some(&alice), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(refresh(&app, &admin_refresh).await, StatusCode::OK);
+        let (status, _) = app
+            .request(Method::GET, "/api/auth/me", Some(&alice), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn login_history_records_failed_and_successful_logins() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        test_support::create_user(app.db(), "bob", UserRole::Sales).await;
+        let wrong = json!({ "username": "alice", "password": "wrong" });
+        let (status, _) = app
+            .request(Method::POST, "/api/auth/login", None, Some(wrong))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (alice, _) = login(&app, "alice").await;
+        login(&app, "bob").await;
+
+        let (status, history) = app
+            .request(Method::GET, "/api/auth/login-history", Some(&alice), None)
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", history);
+        let history = history.as_array().unwrap();
+        // 最新的在前，看不到其他用户的记录
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0]["success"], true);
+        assert!(history[0]["session_id"].is_i64());
+        assert_eq!(history[1]["success"], false);
+        assert_eq!(history[1]["failure_reason"], "invalid_credentials");
+        assert_eq!(history[1]["session_id"], Value::Null);
+        assert!(history.iter().all(|entry| entry["username"] == "alice"));
+
+        let (_, history) = app
+            .request(
+                Method::GET,
+                "/api/auth/login-history?limit=1",
+                Some(&alice),
+                None,
+            )
+            .await;
+        assert_eq!(history.as_array().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,204 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{tag, user_role::UserRole},
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        permission_service::PermissionService,
+        tag_service::{TagError, TagService},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct CreateTagRequest {
+    pub name: String,
+    pub color: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTagRequest {
+    pub name: Option<String>,
+    /// An empty string clears the colour
+    pub color: Option<String>,
+}
+
+/// A tag and how many customers visible to the caller carry it
+#[derive(Debug, Serialize)]
+pub struct TagWithCount {
+    #[serde(flatten)]
+    pub tag: tag::Model,
+    pub customer_count: i64,
+}
+
+pub fn tag_error(error: TagError) -> ApiError {
+    match error {
+        TagError::NameExists(_) => ApiError::new(StatusCode::CONFLICT, error.to_string()),
+        TagError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR.into(),
+        _ => ApiError::unprocessable(error.to_string()),
+    }
+}
+
+fn require_admin(current_user: &CurrentUser) -> Result<(), ApiError> {
+    if current_user.role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+    Ok(())
+}
+
+// Counts only include customers the caller can see, for building filter chips
+pub async fn list_tags(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Vec<TagWithCount>>, StatusCode> {
+    let scope = PermissionService::scope_for(&app_state.db, &current_user)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let tags = TagService::list_with_counts(&app_state.db, &scope)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(Json(
+        tags.into_iter()
+            .map(|(tag, customer_count)| TagWithCount { tag, customer_count })
+            .collect(),
+    ))
+}
+
+// Any user may create tags; renaming and deleting affect everyone so they are admin only
+pub async fn create_tag(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<CreateTagRequest>,
+) -> Result<Json<tag::Model>, ApiError> {
+    let tag = TagService::create(
+        &app_state.db,
+        current_user.organization_id,
+        Some(current_user.id),
+        &req.name,
+        req.color,
+    )
+    .await
+    .map_err(tag_error)?;
+
+    Ok(Json(tag))
+}
+
+pub async fn update_tag(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(tag_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<UpdateTagRequest>,
+) -> Result<Json<tag::Model>, ApiError> {
+    require_admin(&current_user)?;
+    let tag = TagService::find(&app_state.db, current_user.organization_id, tag_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    let tag = TagService::update(&app_state.db, tag, req.name, req.color)
+        .await
+        .map_err(tag_error)?;
+
+    Ok(Json(tag))
+}
+
+// Removes the tag from every customer as well
+pub async fn delete_tag(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(tag_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&current_user)?;
+    let tag = TagService::find(&app_state.db, current_user.organization_id, tag_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::NOT_FOUND)?;
+
+    TagService::delete(&app_state.db, &tag)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::test_support::{self, TestApp};
+
+    fn names(json: &Value) -> Vec<&str> {
+        let mut names: Vec<&str> = json["customers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|customer| customer["name"].as_str().unwrap())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[tokio::test]
+    async fn customers_are_filtered_by_any_and_all_tags() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let token = app.login("alice").await;
+
+        let mut tag_ids = Vec::new();
+        for name in ["VIP", "高意向"] {
+            let body = json!({ "name": name });
+            let (status, json) = app
+                .request(Method::POST, "/api/tags", Some(&token), Some(body))
+                .await;
+            assert_eq!(status, StatusCode::OK);
+            tag_ids.push(json["id"].as_i64().unwrap());
+        }
+        let (vip, hot) = (tag_ids[0], tag_ids[1]);
+        for (name, tags) in [
+            ("张三", vec![vip, hot]),
+            ("李四", vec![vip]),
+            ("王五", vec![]),
+        ] {
+            let body = json!({ "name": name, "tag_ids": tags });
+            let (status, _) = app
+                .request(Method::POST, "/api/customers", Some(&token), Some(body))
+                .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+
+        let uri = format!("/api/customers?any_tags={},{}", vip, hot);
+        let (_, json) = app.request(Method::GET, &uri, Some(&token), None).await;
+        assert_eq!(names(&json), vec!["张三", "李四"]);
+        let uri = format!("/api/customers?all_tags={}, {}", vip, hot).replace(' ', "%20");
+        let (_, json) = app.request(Method::GET, &uri, Some(&token), None).await;
+        assert_eq!(names(&json), vec!["张三"]);
+        assert_eq!(json["total"], 1);
+        let uri = format!("/api/customers?any_tags={}&all_tags={}", vip, hot);
+        let (_, json) = app.request(Method::GET, &uri, Some(&token), None).await;
+        assert_eq!(names(&json), vec!["张三"]);
+
+        let (status, _) = app
+            .request(
+                Method::GET,
+                "/api/customers?any_tags=1,x",
+                Some(&token),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let body = json!({ "name": "赵六", "tag_ids": [9999] });
+        let (status, _) = app
+            .request(Method::POST, "/api/customers", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -0,0 +1,143 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    entities::{user, user::Entity as User},
+    handlers::auth::AppState,
+    middleware::auth::CurrentUser,
+    services::two_factor_service::{TwoFactorError, TwoFactorService},
+    utils::password::verify_password,
+};
+
+#[derive(Debug, Serialize)]
+pub struct TwoFactorSetupResponse {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TwoFactorCodeRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DisableTwoFactorRequest {
+    pub password: String,
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecoveryCodesResponse {
+    pub recovery_codes: Vec<String>,
+}
+
+fn error_status(error: TwoFactorError) -> StatusCode {
+    match error {
+        TwoFactorError::AlreadyEnabled => StatusCode::CONFLICT,
+        TwoFactorError::NotEnabled
+        | TwoFactorError::NotEnrolled
+        | TwoFactorError::InvalidCode => StatusCode::BAD_REQUEST,
+        TwoFactorError::Secret(_) | TwoFactorError::Database(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+async fn load_user(app_state: &AppState, current_user: &CurrentUser) -> Result<user::Model, StatusCode> {
+    User::find_by_id(current_user.id)
+        .filter(user::Column::OrganizationId.eq(current_user.organization_id))
+        .filter(user::Column::IsActive.eq(true))
+        .one(&app_state.db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)
+}
+
+// 开始绑定：生成密钥和 otpauth URI，需调用 enable 确认后才生效
+pub async fn setup(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<TwoFactorSetupResponse>, StatusCode> {
+    let user = load_user(&app_state, &current_user).await?;
+
+    let enrollment = TwoFactorService::begin_enrollment(&app_state.db, user, &app_state.totp_issuer)
+        .await
+        .map_err(error_status)?;
+
+    Ok(Json(TwoFactorSetupResponse {
+        secret: enrollment.secret,
+        otpauth_uri: enrollment.otpauth_uri,
+    }))
+}
+
+// 提交验证码确认绑定，返回一次性恢复码
+pub async fn enable(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, StatusCode> {
+    let user = load_user(&app_state, &current_user).await?;
+
+    let recovery_codes =
+        TwoFactorService::enable(&app_state.db, user, &app_state.totp_issuer, &req.code)
+            .await
+            .map_err(error_status)?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
+
+// 关闭双因素认证，需要同时验证密码和验证码
+pub async fn disable(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<DisableTwoFactorRequest>,
+) -> Result<StatusCode, StatusCode> {
+    let user = load_user(&app_state, &current_user).await?;
+
+    if !verify_password(&req.password, &user.password_hash)
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+    {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let verified = TwoFactorService::verify(&app_state.db, &user, &app_state.totp_issuer, &req.code)
+        .await
+        .map_err(error_status)?;
+    if !verified {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    TwoFactorService::reset(&app_state.db, user)
+        .await
+        .map_err(error_status)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// 重新生成恢复码，旧恢复码全部作废
+pub async fn regenerate_recovery_codes(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<TwoFactorCodeRequest>,
+) -> Result<Json<RecoveryCodesResponse>, StatusCode> {
+    let user = load_user(&app_state, &current_user).await?;
+
+    let verified = TwoFactorService::verify(&app_state.db, &user, &app_state.totp_issuer, &req.code)
+        .await
+        .map_err(error_status)?;
+    if !verified {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let recovery_codes = TwoFactorService::regenerate_recovery_codes(&app_state.db, user.id)
+        .await
+        .map_err(error_status)?;
+
+    Ok(Json(RecoveryCodesResponse { recovery_codes }))
+}
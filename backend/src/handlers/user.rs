@@ -0,0 +1,477 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    Extension,
+};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    entities::{user, user_role::UserRole},
+    handlers::{auth::AppState, error::ApiError},
+    middleware::auth::CurrentUser,
+    services::{
+        audit_service::Actor,
+        user_service::{NewUser, UserError, UserListFilter, UserService, UserUpdate},
+    },
+};
+
+#[derive(Debug, Deserialize)]
+pub struct UserListQuery {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+    /// Matches username or name
+    pub search: Option<String>,
+}
+
+fn default_page() -> u64 { 1 }
+fn default_limit() -> u64 { 20 }
+
+#[derive(Debug, Serialize)]
+pub struct UserListResponse {
+    pub users: Vec<user::Model>,
+    pub total: u64,
+    pub page: u64,
+    pub limit: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserRequest {
+    pub username: String,
+    pub password: String,
+    pub name: String,
+    pub email: Option<String>,
+    #[serde(default)]
+    pub role: UserRole,
+    pub manager_id: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserRequest {
+    pub name: Option<String>,
+    /// Absent leaves the email unchanged, `null` clears it
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub email: Option<Option<String>>,
+    pub role: Option<UserRole>,
+    /// Absent leaves the manager unchanged, `null` clears it
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub manager_id: Option<Option<i32>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateUserStatusRequest {
+    pub is_active: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ResetPasswordRequest {
+    pub password: String,
+}
+
+/// Distinguishes a field explicitly set to `null` from a missing one
+fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+fn user_error(error: UserError) -> ApiError {
+    match error {
+        UserError::NotFound => StatusCode::NOT_FOUND.into(),
+        UserError::UsernameTaken(_) | UserError::EmailTaken(_) => {
+            ApiError::new(StatusCode::CONFLICT, error.to_string())
+        }
+        UserError::InvalidUsername
+        | UserError::InvalidName
+        | UserError::InvalidEmail
+        | UserError::InvalidPassword(_)
+        | UserError::OrganizationNotFound(_)
+        | UserError::ManagerNotFound
+        | UserError::NotAManager(_)
+        | UserError::SelfManager => ApiError::unprocessable(error.to_string()),
+        UserError::Hash(_) | UserError::Session(_) | UserError::Database(_) => {
+            StatusCode::INTERNAL_SERVER_ERROR.into()
+        }
+    }
+}
+
+fn require_admin(current_user: &CurrentUser) -> Result<(), ApiError> {
+    if current_user.role != UserRole::Admin {
+        return Err(StatusCode::FORBIDDEN.into());
+    }
+    Ok(())
+}
+
+// 管理员只能管理本组织的用户
+pub async fn list_users(
+    Extension(current_user): Extension<CurrentUser>,
+    Query(params): Query<UserListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<UserListResponse>, ApiError> {
+    require_admin(&current_user)?;
+
+    let filter = UserListFilter {
+        organization_id: Some(current_user.organization_id),
+        search: params.search,
+        page: params.page,
+        limit: params.limit,
+    };
+    let (users, total) = UserService::list(&app_state.db, &filter)
+        .await
+        .map_err(user_error)?;
+
+    Ok(Json(UserListResponse {
+        users,
+        total,
+        page: params.page,
+        limit: params.limit,
+    }))
+}
+
+pub async fn get_user(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(user_id): Path<i32>,
+    State(app_state): State<AppState>,
+) -> Result<Json<user::Model>, ApiError> {
+    require_admin(&current_user)?;
+
+    let user = UserService::find_in_organization(&app_state.db, current_user.organization_id, user_id)
+        .await
+        .map_err(user_error)?;
+
+    Ok(Json(user))
+}
+
+pub async fn create_user(
+    Extension(current_user): Extension<CurrentUser>,
+    State(app_state): State<AppState>,
+    Json(req): Json<CreateUserRequest>,
+) -> Result<(StatusCode, Json<user::Model>), ApiError> {
+    require_admin(&current_user)?;
+
+    let new_user = NewUser {
+        username: req.username,
+        password: Some(req.password),
+        name: req.name,
+        email: req.email,
+        role: req.role,
+        manager_id: req.manager_id,
+        organization_id: current_user.organization_id,
+    };
+    let user = UserService::create(
+        &app_state.db,
+        &Actor::from(&current_user),
+        &app_state.password_policy,
+        new_user,
+    )
+    .await
+    .map_err(user_error)?;
+
+    Ok((StatusCode::CREATED, Json(user)))
+}
+
+pub async fn update_user(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(user_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<UpdateUserRequest>,
+) -> Result<Json<user::Model>, ApiError> {
+    require_admin(&current_user)?;
+
+    // Keep at least the acting admin able to manage users
+    if user_id == current_user.id && req.role.is_some_and(|role| role != UserRole::Admin) {
+        return Err(ApiError::unprocessable("不能修改自己的管理员角色"));
+    }
+
+    let user = UserService::find_in_organization(&app_state.db, current_user.organization_id, user_id)
+        .await
+        .map_err(user_error)?;
+    let update = UserUpdate {
+        name: req.name,
+        email: req.email,
+        role: req.role,
+        manager_id: req.manager_id,
+    };
+    let user = UserService::update(&app_state.db, &Actor::from(&current_user), user, update)
+        .await
+        .map_err(user_error)?;
+
+    Ok(Json(user))
+}
+
+// 停用后该用户的会话立即吊销
+pub async fn update_user_status(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(user_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<UpdateUserStatusRequest>,
+) -> Result<Json<user::Model>, ApiError> {
+    require_admin(&current_user)?;
+
+    if user_id == current_user.id && !req.is_active {
+        return Err(ApiError::unprocessable("不能停用自己的账号"));
+    }
+
+    let user = UserService::find_in_organization(&app_state.db, current_user.organization_id, user_id)
+        .await
+        .map_err(user_error)?;
+    let user = UserService::set_active(&app_state.db, &Actor::from(&current_user), user, req.is_active)
+        .await
+        .map_err(user_error)?;
+
+    Ok(Json(user))
+}
+
+pub async fn reset_user_password(
+    Extension(current_user): Extension<CurrentUser>,
+    Path(user_id): Path<i32>,
+    State(app_state): State<AppState>,
+    Json(req): Json<ResetPasswordRequest>,
+) -> Result<StatusCode, ApiError> {
+    require_admin(&current_user)?;
+
+    let user = UserService::find_in_organization(&app_state.db, current_user.organization_id, user_id)
+        .await
+        .map_err(user_error)?;
+    UserService::reset_password(
+        &app_state.db,
+        &Actor::from(&current_user),
+        &app_state.password_policy,
+        user,
+        &req.password,
+    )
+    .await
+    .map_err(user_error)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QueryOrder};
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        entities::audit_log::{self, AuditAction, AuditEntity, Entity as AuditLog},
+        test_support::{self, TestApp, PASSWORD},
+    };
+
+    #[tokio::test]
+    async fn disabled_users_lose_their_tokens_immediately() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let admin_token = app.login("admin").await;
+
+        let body = json!({ "username": "alice", "password": PASSWORD });
+        let (status, login) = app
+            .request(Method::POST, "/api/auth/login", None, Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let token = login["token"].as_str().unwrap().to_string();
+        let refresh = json!({ "refresh_token": login["refresh_token"] });
+        let (status, created) = app
+            .request(
+                Method::POST,
+                "/api/auth/tokens",
+                Some(&token),
+                Some(json!({ "name": "script", "scopes": ["customers:read"] })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let api_token = created["token"].as_str().unwrap().to_string();
+
+        let status_uri = format!("/api/users/{}/status", alice.id);
+        let (status, json) = app
+            .request(
+                Method::PUT,
+                &status_uri,
+                Some(&admin_token),
+                Some(json!({ "is_active": false })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["is_active"], false);
+
+        // 未过期的访问令牌、刷新令牌和 API 令牌都立即失效，也不能重新登录
+        let (status, _) = app
+            .request(Method::GET, "/api/auth/me", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app
+            .request(
+                Method::POST,
+                "/api/auth/refresh",
+                None,
+                Some(refresh.clone()),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app
+            .request(Method::GET, "/api/customers", Some(&api_token), None)
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let body = json!({ "username": "alice", "password": PASSWORD });
+        let (status, _) = app
+            .request(Method::POST, "/api/auth/login", None, Some(body))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        // 重新启用后可以登录，但停用前的会话不会恢复
+        let (status, _) = app
+            .request(
+                Method::PUT,
+                &status_uri,
+                Some(&admin_token),
+                Some(json!({ "is_active": true })),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        app.login("alice").await;
+        let (status, _) = app
+            .request(Method::GET, "/api/auth/me", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app
+            .request(Method::POST, "/api/auth/refresh", None, Some(refresh))
+            .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let changes: Vec<serde_json::Value> = AuditLog::find()
+            .filter(audit_log::Column::Entity.eq(AuditEntity::User))
+            .filter(audit_log::Column::EntityId.eq(alice.id))
+            .order_by_asc(audit_log::Column::Id)
+            .all(app.db())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.changes)
+            .collect();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(changes[0]["after"]["is_active"], false);
+        assert_eq!(changes[1]["after"]["is_active"], true);
+    }
+
+    #[tokio::test]
+    async fn admins_create_and_update_users() {
+        let app = TestApp::new().await;
+        let admin = test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let manager = test_support::create_user(app.db(), "boss", UserRole::Manager).await;
+        test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let admin_token = app.login("admin").await;
+
+        let body = json!({
+            "username": "carol",
+            "password": PASSWORD,
+            "name": "卡罗尔",
+            "email": "Carol@Example.com",
+            "manager_id": manager.id,
+        });
+        let (status, carol) = app
+            .request(
+                Method::POST,
+                "/api/users",
+                Some(&admin_token),
+                Some(body.clone()),
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "{}", carol);
+        assert_eq!(carol["role"], "sales");
+        assert_eq!(carol["email"], "carol@example.com");
+        assert_eq!(carol["manager_id"], manager.id);
+        assert!(carol.get("password_hash").is_none());
+        let (status, _) = app
+            .request(Method::POST, "/api/users", Some(&admin_token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let body = json!({ "username": "dave", "password": "short", "name": "dave" });
+        let (status, _) = app
+            .request(Method::POST, "/api/users", Some(&admin_token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        app.login("carol").await;
+
+        let carol_uri = format!("/api/users/{}", carol["id"]);
+        let body = json!({ "name": "Carol", "email": null, "role": "manager", "manager_id": null });
+        let (status, json) = app
+            .request(Method::PUT, &carol_uri, Some(&admin_token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK, "{}", json);
+        assert_eq!(json["name"], "Carol");
+        assert_eq!(json["email"], serde_json::Value::Null);
+        assert_eq!(json["role"], "manager");
+        assert_eq!(json["manager_id"], serde_json::Value::Null);
+        let (_, json) = app
+            .request(Method::GET, &carol_uri, Some(&admin_token), None)
+            .await;
+        assert_eq!(json["name"], "Carol");
+
+        // 管理员不能取消自己的管理员角色
+        let body = json!({ "role": "sales" });
+        let (status, _) = app
+            .request(
+                Method::PUT,
+                &format!("/api/users/{}", admin.id),
+                Some(&admin_token),
+                Some(body),
+            )
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let actions: Vec<AuditAction> = AuditLog::find()
+            .filter(audit_log::Column::Entity.eq(AuditEntity::User))
+            .filter(audit_log::Column::EntityId.eq(carol["id"].as_i64().unwrap()))
+            .order_by_asc(audit_log::Column::Id)
+            .all(app.db())
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.action)
+            .collect();
+        assert_eq!(actions, vec![AuditAction::Create, AuditAction::Update]);
+    }
+
+    #[tokio::test]
+    async fn only_admins_manage_users() {
+        let app = TestApp::new().await;
+        let manager = test_support::create_user(app.db(), "boss", UserRole::Manager).await;
+        let alice = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+
+        for username in ["boss", "alice"] {
+            let token = app.login(username).await;
+            let body = json!({ "username": "carol", "password": PASSWORD, "name": "carol" });
+            let (status, _) = app
+                .request(Method::POST, "/api/users", Some(&token), Some(body))
+                .await;
+            assert_eq!(status, StatusCode::FORBIDDEN);
+            for user_id in [manager.id, alice.id] {
+                let uri = format!("/api/users/{}", user_id);
+                let (status, _) = app
+                    .request(
+                        Method::PUT,
+                        &uri,
+                        Some(&token),
+                        Some(json!({ "role": "admin" })),
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::FORBIDDEN);
+                let (status, _) = app
+                    .request(
+                        Method::PUT,
+                        &format!("{}/status", uri),
+                        Some(&token),
+                        Some(json!({ "is_active": false })),
+                    )
+                    .await;
+                assert_eq!(status, StatusCode::FORBIDDEN);
+            }
+        }
+        app.login("alice").await;
+    }
+}
@@ -9,5 +9,8 @@ pub mod routes;
 pub mod services;
 pub mod utils;
 
+#[cfg(test)]
+pub(crate) mod test_support;
+
 pub use config::Config;
 pub use database::Database;
\ No newline at end of file
@@ -5,11 +5,17 @@ use customer_tracker::{
     handlers::auth::AppState,
     migration::run_database_migrations,
     routes::create_routes,
+    services::{
+        customer_pool_service::{CustomerPoolService, PoolSettings},
+        jwt_key_service::JwtKeyStore,
+        login_throttle_service::{LoginThrottlePolicy, LoginThrottleService},
+        oidc_service::{OidcProvider, OidcSettings},
+        trash_service::{TrashService, TrashSettings},
+    },
 };
 use clap::Parser;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tracing::{info, Level};
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -57,11 +63,41 @@ async fn start_server(port: u16, host: String) -> Result<(), Box<dyn std::error:
     let db = create_database_connection(&config.database_url).await?;
     info!("Database connected successfully");
 
+    // Load the token signing keys, generating the first one on a fresh database
+    let jwt_keys = Arc::new(JwtKeyStore::load(&db).await?);
+    jwt_keys.clone().spawn_reload(db.clone());
+    info!("JWT signing keys loaded");
+
+    let oidc = OidcSettings::from_config(&config).map(|settings| {
+        info!("Single sign-on enabled with issuer {}", settings.issuer_url);
+        Arc::new(OidcProvider::new(settings))
+    });
+
+    // Periodically return customers nobody has followed up to the public pool
+    let pool = PoolSettings::from_config(&config);
+    CustomerPoolService::spawn_auto_release(db.clone(), pool.clone());
+
+    // Purge customers that have stayed in the trash past the retention period
+    let trash = TrashSettings::from_config(&config);
+    TrashService::spawn_auto_purge(db.clone(), trash.clone());
+
+    // Drop login failure records that no longer lock anything out
+    let login_throttle = LoginThrottlePolicy::from_config(&config);
+    LoginThrottleService::spawn_prune(db.clone(), login_throttle.clone());
+
     // Create application state
     let app_state = AppState {
         db,
-        jwt_secret: config.jwt_secret,
-        jwt_expire_hours: config.jwt_expire_hours,
+        oidc,
+        pool,
+        trash,
+        login_throttle,
+        trust_proxy_headers: config.trust_proxy_headers,
+        password_policy: config.password_policy(),
+        totp_issuer: config.totp_issuer,
+        jwt_keys,
+        access_token_expire_minutes: config.access_token_expire_minutes,
+        refresh_token_expire_days: config.refresh_token_expire_days,
     };
 
     // Create routes
@@ -77,7 +113,11 @@ async fn start_server(port: u16, host: String) -> Result<(), Box<dyn std::error:
 
     // Start the server
     let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
 
     Ok(())
 }
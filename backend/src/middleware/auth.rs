@@ -1,17 +1,39 @@
 use axum::{
     extract::{Request, State},
-    http::{header::AUTHORIZATION, StatusCode},
+    http::{header::AUTHORIZATION, Method, StatusCode},
     middleware::Next,
     response::Response,
 };
-use jsonwebtoken::{decode, DecodingKey, Validation};
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    entities::{
+        api_token::ApiScope,
+        user::{self, Entity as User},
+        user_role::UserRole,
+    },
+    services::{
+        api_token_service::ApiTokenService, jwt_key_service::JwtKeyStore,
+        session_service::SessionService,
+    },
+    utils::jwt::verify_token,
+};
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub user_id: i32,
     pub username: String,
     pub name: String,
+    pub role: UserRole,
+    /// 所属组织（租户）ID
+    pub org_id: i32,
+    /// 签发该令牌的会话ID
+    pub sid: i32,
+    /// 签发者，固定为 [`ISSUER`](crate::services::jwt_key_service::ISSUER)
+    pub iss: String,
+    /// 受众，固定为 [`ACCESS_AUDIENCE`](crate::utils::jwt::ACCESS_AUDIENCE)
+    pub aud: String,
     pub exp: usize,
     pub iat: usize,
 }
@@ -21,6 +43,20 @@ pub struct CurrentUser {
     pub id: i32,
     pub username: String,
     pub name: String,
+    pub role: UserRole,
+    pub organization_id: i32,
+    /// 登录会话ID，使用 API 令牌访问时为空
+    pub session_id: Option<i32>,
+    /// API 令牌的权限范围，登录会话为空表示不受限
+    pub scopes: Option<Vec<ApiScope>>,
+}
+
+impl CurrentUser {
+    pub fn has_scope(&self, scope: ApiScope) -> bool {
+        self.scopes
+            .as_ref()
+            .is_none_or(|scopes| scopes.contains(&scope))
+    }
 }
 
 impl From<Claims> for CurrentUser {
@@ -29,10 +65,21 @@ impl From<Claims> for CurrentUser {
             id: claims.user_id,
             username: claims.username,
             name: claims.name,
+            role: claims.role,
+            organization_id: claims.org_id,
+            session_id: Some(claims.sid),
+            scopes: None,
         }
     }
 }
 
+/// 一组路由需要的读写权限，GET 请求检查 `read`，其他方法检查 `write`
+#[derive(Debug, Clone, Copy)]
+pub struct RouteScopes {
+    pub read: ApiScope,
+    pub write: ApiScope,
+}
+
 pub async fn auth_middleware<T>(
     State(app_state): State<T>,
     mut request: Request,
@@ -40,7 +87,8 @@ pub async fn auth_middleware<T>(
 ) -> Result<Response, StatusCode>
 where
     T: Clone + Send + Sync + 'static,
-    T: AsRef<String>, // Assuming app_state has jwt_secret as String
+    T: AsRef<JwtKeyStore>,
+    T: AsRef<DatabaseConnection>,
 {
     let auth_header = request
         .headers()
@@ -53,16 +101,168 @@ where
         None => return Err(StatusCode::UNAUTHORIZED),
     };
 
-    let jwt_secret = app_state.as_ref();
-    let claims = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(jwt_secret.as_ref()),
-        &Validation::default(),
-    )
-    .map_err(|_| StatusCode::UNAUTHORIZED)?;
+    let db: &DatabaseConnection = app_state.as_ref();
+    let current_user = if ApiTokenService::is_api_token(token) {
+        authenticate_api_token(db, token).await?
+    } else {
+        let claims = verify_token(token, app_state.as_ref()).map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+        // 会话被吊销（登出或令牌重用）后，即使访问令牌未过期也拒绝
+        let session_active = SessionService::is_active(db, claims.sid, claims.user_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if !session_active {
+            return Err(StatusCode::UNAUTHORIZED);
+        }
+
+        CurrentUser::from(claims)
+    };
 
-    let current_user = CurrentUser::from(claims.claims);
     request.extensions_mut().insert(current_user);
     
     Ok(next.run(request).await)
-}
\ No newline at end of file
+}
+
+// API 令牌每次都从数据库读取用户，停用或调整角色后立即生效
+async fn authenticate_api_token(db: &DatabaseConnection, raw: &str) -> Result<CurrentUser, StatusCode> {
+    let api_token = ApiTokenService::authenticate(db, raw)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let user = User::find_by_id(api_token.user_id)
+        .filter(user::Column::IsActive.eq(true))
+        .one(db)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    Ok(CurrentUser {
+        id: user.id,
+        username: user.username,
+        name: user.name,
+        role: user.role,
+        organization_id: user.organization_id,
+        session_id: None,
+        scopes: Some(api_token.scope_list()),
+    })
+}
+
+/// 按请求方法检查 API 令牌的权限范围，需放在 `auth_middleware` 之内
+pub async fn require_scope(
+    State(scopes): State<RouteScopes>,
+    request: Request,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    let current_user = request
+        .extensions()
+        .get::<CurrentUser>()
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let required = match *request.method() {
+        Method::GET | Method::HEAD => scopes.read,
+        _ => scopes.write,
+    };
+    if !current_user.has_scope(required) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(next.run(request).await)
+}
+
+/// 账号相关的接口只允许登录会话访问，API 令牌一律拒绝
+pub async fn require_session(request: Request, next: Next) -> Result<Response, StatusCode> {
+    let current_user = request
+        .extensions()
+        .get::<CurrentUser>()
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    if current_user.session_id.is_none() {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(next.run(request).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::Method;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        services::api_token_service::ApiTokenService,
+        test_support::{self, TestApp},
+        utils::jwt::generate_two_factor_token,
+    };
+
+    async fn api_token(app: &TestApp, scopes: &[ApiScope]) -> String {
+        let user = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        ApiTokenService::create(app.db(), user.id, "ci", scopes, None).await.unwrap().plaintext
+    }
+
+    #[tokio::test]
+    async fn api_tokens_are_limited_to_their_scopes() {
+        let app = TestApp::new().await;
+        let token = api_token(&app, &[ApiScope::CustomersRead]).await;
+
+        let (status, _) = app.request(Method::GET, "/api/customers", Some(&token), None).await;
+        assert_eq!(status, StatusCode::OK);
+        let body = json!({ "name": "张三" });
+        let (status, _) =
+            app.request(Method::POST, "/api/customers", Some(&token), Some(body)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = app.request(Method::GET, "/api/tracks", Some(&token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn api_tokens_cannot_manage_the_account() {
+        let app = TestApp::new().await;
+        let token = api_token(&app, &ApiScope::ALL).await;
+
+        let (status, _) = app.request(Method::GET, "/api/auth/me", Some(&token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = app.request(Method::GET, "/api/auth/tokens", Some(&token), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn rejects_missing_and_unknown_tokens() {
+        let app = TestApp::new().await;
+
+        let (status, _) = app.request(Method::GET, "/api/customers", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.request(Method::GET, "/api/customers", Some("ctk_x"), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = app.request(Method::GET, "/api/customers", Some("not.a.jwt"), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn two_factor_tokens_are_not_access_tokens() {
+        let app = TestApp::new().await;
+        let user = test_support::create_user(app.db(), "alice", UserRole::Sales).await;
+        let token =
+            generate_two_factor_token(user.id, "challenge", &app.state.jwt_keys, 5).unwrap();
+
+        let (status, _) =
+            app.request(Method::GET, "/api/customers", Some(&token.access_token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn deactivated_users_lose_api_access_immediately() {
+        let app = TestApp::new().await;
+        let token = api_token(&app, &[ApiScope::CustomersRead]).await;
+
+        User::update_many()
+            .col_expr(user::Column::IsActive, false.into())
+            .exec(app.db())
+            .await
+            .unwrap();
+
+        let (status, _) = app.request(Method::GET, "/api/customers", Some(&token), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
@@ -1,9 +1,14 @@
-use sea_orm::{Database, DatabaseConnection, DbErr, ConnectionTrait};
+use sea_orm::{ConnectOptions, Database, DatabaseConnection, DbErr, ConnectionTrait};
 use std::fs;
 use std::path::Path;
 use tracing::{info, warn, error};
 use std::env;
 
+use crate::{
+    services::duplicate_service::{normalize_name, normalize_phone},
+    utils::pinyin,
+};
+
 pub struct DatabaseMigrator {
     db_url: String,
 }
@@ -21,11 +26,11 @@ impl DatabaseMigrator {
             let db_file = Path::new(db_path);
             
             // 创建父目录（如果不存在）
-            if let Some(parent_dir) = db_file.parent() {
-                if !parent_dir.exists() {
-                    info!("创建数据库目录: {}", parent_dir.display());
-                    fs::create_dir_all(parent_dir)?;
-                }
+            if let Some(parent_dir) = db_file.parent()
+                && !parent_dir.exists()
+            {
+                info!("创建数据库目录: {}", parent_dir.display());
+                fs::create_dir_all(parent_dir)?;
             }
             
             // 如果数据库文件不存在，创建空文件
@@ -43,20 +48,28 @@ impl DatabaseMigrator {
         // 确保数据库存在
         self.ensure_database_exists().await?;
         
-        // 连接数据库
-        let db = Database::connect(&self.db_url).await?;
-        
+        // 连接数据库。迁移中的 PRAGMA（如重建表时关闭外键）只对当前连接生效，
+        // 所有语句必须在同一个连接上执行
+        let mut options = ConnectOptions::new(self.db_url.clone());
+        options.max_connections(1).min_connections(1);
+        let db = Database::connect(options).await?;
+        self.migrate(&db).await
+    }
+
+    /// 在给定的连接上运行所有未应用的迁移，连接池中只能有一个连接，原因同上
+    pub async fn migrate(&self, db: &DatabaseConnection) -> Result<(), Box<dyn std::error::Error>> {
         // 创建迁移表（如果不存在）
-        self.create_migration_table(&db).await?;
+        self.create_migration_table(db).await?;
         
         // 获取迁移文件列表
         let migrations = self.get_migration_files()?;
         
         for migration in migrations {
-            if !self.is_migration_applied(&db, &migration.name).await? {
+            if !self.is_migration_applied(db, &migration.name).await? {
                 info!("应用迁移: {}", migration.name);
-                self.apply_migration(&db, &migration).await?;
-                self.record_migration(&db, &migration.name).await?;
+                self.apply_migration(db, &migration).await?;
+                self.apply_data_migration(db, &migration.name).await?;
+                self.record_migration(db, &migration.name).await?;
             } else {
                 info!("跳过已应用的迁移: {}", migration.name);
             }
@@ -100,7 +113,7 @@ impl DatabaseMigrator {
         .one(db)
         .await?;
         
-        Ok(result.map_or(false, |r| r.count > 0))
+        Ok(result.is_some_and(|r| r.count > 0))
     }
 
     /// 记录已应用的迁移
@@ -135,9 +148,13 @@ impl DatabaseMigrator {
             
             current_statement.push_str(line);
             current_statement.push(' ');
-            
-            // 如果行以分号结尾，表示语句结束
-            if line.ends_with(';') {
+
+            // 如果行以分号结尾，表示语句结束；触发器的 BEGIN ... END 中包含多条语句，到 END; 才结束
+            let in_trigger = current_statement
+                .trim_start()
+                .to_ascii_uppercase()
+                .starts_with("CREATE TRIGGER");
+            if line.ends_with(';') && (!in_trigger || line.eq_ignore_ascii_case("END;")) {
                 let stmt = current_statement.trim().trim_end_matches(';').trim();
                 if !stmt.is_empty() {
                     statements.push(stmt.to_string());
@@ -167,6 +184,90 @@ impl DatabaseMigrator {
         Ok(())
     }
 
+    /// 迁移中无法用 SQL 完成的数据处理，在对应的迁移文件执行后运行。
+    /// 这里只能使用当时的表结构，所以直接写 SQL，不使用实体
+    async fn apply_data_migration(&self, db: &DatabaseConnection, migration_name: &str) -> Result<(), DbErr> {
+        match migration_name {
+            "025_add_customer_name_pinyin" => self.backfill_name_pinyin(db).await?,
+            "030_customer_duplicate_keys" => self.backfill_duplicate_keys(db).await?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// 为已有客户计算名称的拼音
+    async fn backfill_name_pinyin(&self, db: &DatabaseConnection) -> Result<(), DbErr> {
+        use sea_orm::{FromQueryResult, TransactionTrait};
+
+        #[derive(FromQueryResult)]
+        struct CustomerName {
+            id: i32,
+            name: String,
+        }
+
+        let customers = CustomerName::find_by_statement(sea_orm::Statement::from_string(
+            sea_orm::DatabaseBackend::Sqlite,
+            "SELECT id, name FROM customers",
+        ))
+        .all(db)
+        .await?;
+
+        let txn = db.begin().await?;
+        for customer in &customers {
+            txn.execute(sea_orm::Statement::from_sql_and_values(
+                sea_orm::DatabaseBackend::Sqlite,
+                "UPDATE customers SET name_pinyin = ?, name_initials = ? WHERE id = ?",
+                vec![
+                    pinyin::full_pinyin(&customer.name).into(),
+                    pinyin::name_initials(&customer.name).into(),
+                    customer.id.into(),
+                ],
+            ))
+            .await?;
+        }
+        txn.commit().await?;
+
+        info!("已为 {} 位客户补齐名称拼音", customers.len());
+        Ok(())
+    }
+
+    /// 用与应用相同的规则为已有客户计算查重用的名称和电话
+    async fn backfill_duplicate_keys(&self, db: &DatabaseConnection) -> Result<(), DbErr> {
+        use sea_orm::{FromQueryResult, TransactionTrait};
+
+        #[derive(FromQueryResult)]
+        struct CustomerKeys {
+            id: i32,
+            name: String,
+            phone: Option<String>,
+        }
+
+        let customers = CustomerKeys::find_by_statement(sea_orm::Statement::from_string(
+            sea_orm::DatabaseBackend::Sqlite,
+            "SELECT id, name, phone FROM customers",
+        ))
+        .all(db)
+        .await?;
+
+        let txn = db.begin().await?;
+        for customer in &customers {
+            txn.execute(sea_orm::Statement::from_sql_and_values(
+                sea_orm::DatabaseBackend::Sqlite,
+                "UPDATE customers SET name_normalized = ?, phone_normalized = ? WHERE id = ?",
+                vec![
+                    normalize_name(&customer.name).into(),
+                    customer.phone.as_deref().and_then(normalize_phone).into(),
+                    customer.id.into(),
+                ],
+            ))
+            .await?;
+        }
+        txn.commit().await?;
+
+        info!("已为 {} 位客户重新计算查重用的名称和电话", customers.len());
+        Ok(())
+    }
+
     /// 获取所有迁移文件
     fn get_migration_files(&self) -> Result<Vec<Migration>, Box<dyn std::error::Error>> {
         let migrations_dir = Path::new("migrations");
@@ -182,13 +283,13 @@ impl DatabaseMigrator {
             let entry = entry?;
             let path = entry.path();
             
-            if path.extension().map_or(false, |ext| ext == "sql") {
-                if let Some(file_name) = path.file_stem().and_then(|n| n.to_str()) {
-                    migrations.push(Migration {
-                        name: file_name.to_string(),
-                        path: path.clone(),
-                    });
-                }
+            if path.extension().is_some_and(|ext| ext == "sql")
+                && let Some(file_name) = path.file_stem().and_then(|n| n.to_str())
+            {
+                migrations.push(Migration {
+                    name: file_name.to_string(),
+                    path: path.clone(),
+                });
             }
         }
         
@@ -298,4 +399,81 @@ pub async fn check_database_status() -> Result<(), Box<dyn std::error::Error>> {
     
     let migrator = DatabaseMigrator::new(database_url);
     migrator.check_status().await
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use sea_orm::EntityTrait;
+
+    use super::*;
+    use crate::{entities::customer::Entity as Customer, test_support};
+
+    #[tokio::test]
+    async fn backfills_duplicate_keys_with_the_application_rules() {
+        let db = test_support::test_db().await;
+        // 018 的 SQL 回填只去掉空格、横线、加号和括号
+        db.execute_unprepared(
+            "INSERT INTO customers (name, phone, phone_normalized, organization_id)
+             VALUES ('张\t三', '138.0000.0001', '138.0000.0001', 1)",
+        )
+        .await
+        .unwrap();
+
+        DatabaseMigrator::new(String::new())
+            .backfill_duplicate_keys(&db)
+            .await
+            .unwrap();
+
+        let customer = Customer::find().one(&db).await.unwrap().unwrap();
+        assert_eq!(customer.name_normalized, normalize_name("张\t三"));
+        assert_eq!(customer.phone_normalized.as_deref(), Some("13800000001"));
+    }
+
+    #[tokio::test]
+    async fn backfills_name_pinyin_for_existing_customers() {
+        let db = test_support::test_db().await;
+        db.execute_unprepared(
+            "INSERT INTO customers (name, organization_id) VALUES ('曾小贤', 1), ('Amy', 1)",
+        )
+        .await
+        .unwrap();
+
+        DatabaseMigrator::new(String::new())
+            .backfill_name_pinyin(&db)
+            .await
+            .unwrap();
+
+        let customers = Customer::find().all(&db).await.unwrap();
+        let pinyin: Vec<(&str, &str)> = customers
+            .iter()
+            .map(|customer| {
+                (
+                    customer.name_pinyin.as_str(),
+                    customer.name_initials.as_str(),
+                )
+            })
+            .collect();
+        assert_eq!(pinyin, vec![("zengxiaoxian", "zxx"), ("amy", "amy")]);
+    }
+
+    #[tokio::test]
+    async fn foreign_keys_are_enabled_again_after_rebuilding_tables() {
+        use sea_orm::FromQueryResult;
+
+        #[derive(FromQueryResult)]
+        struct ForeignKeys {
+            foreign_keys: i32,
+        }
+
+        // 016 和 022 重建 customers 表时临时关闭了外键
+        let db = test_support::test_db().await;
+        let pragma = ForeignKeys::find_by_statement(sea_orm::Statement::from_string(
+            sea_orm::DatabaseBackend::Sqlite,
+            "PRAGMA foreign_keys",
+        ))
+        .one(&db)
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(pragma.foreign_keys, 1);
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
     middleware,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 use tower_http::{
@@ -9,8 +9,13 @@ use tower_http::{
 };
 
 use crate::{
-    handlers::{auth, customer, customer_track},
-    middleware::auth::auth_middleware,
+    entities::api_token::ApiScope,
+    handlers::{
+        api_token, audit, auth, custom_field, customer, customer_collaborator, customer_duplicate,
+        customer_group, customer_pool, customer_track, customer_transfer, customer_trash, jwks,
+        notification, oidc, pipeline_stage, search, session, tag, two_factor, user,
+    },
+    middleware::auth::{auth_middleware, require_scope, require_session, RouteScopes},
     handlers::auth::AppState,
 };
 
@@ -18,16 +23,47 @@ pub fn create_routes(app_state: AppState) -> Router {
     // Public routes (no authentication required)
     let public_routes = Router::new()
         .route("/api/auth/login", post(auth::login))
+        .route("/api/auth/login/2fa", post(auth::login_two_factor))
+        .route("/api/auth/refresh", post(auth::refresh_token))
+        .route("/api/auth/oidc/config", get(oidc::get_config))
+        .route("/api/auth/oidc/authorize", post(oidc::authorize))
+        .route("/api/auth/oidc/callback", post(oidc::callback))
         .route("/api/health", get(health_check))
+        .route("/.well-known/jwks.json", get(jwks::get_jwks))
         .with_state(app_state.clone());
 
-    // Protected routes (authentication required)
-    let protected_routes = Router::new()
-        .route("/api/auth/me", get(auth::get_current_user))
+    // Account and admin routes: interactive sessions only, API tokens are rejected
+    let account_routes = Router::new()
+        .route("/api/auth/me", get(auth::get_current_user).put(auth::update_current_user))
+        .route("/api/auth/password", post(auth::change_password))
         .route("/api/auth/logout", post(auth::logout))
-        .route("/api/auth/refresh", post(auth::refresh_token))
-        
-        // Customer routes
+        .route("/api/auth/sessions",
+            get(session::list_sessions)
+            .delete(session::revoke_other_sessions)
+        )
+        .route("/api/auth/sessions/{id}", delete(session::revoke_session))
+        .route("/api/auth/login-history", get(session::list_login_history))
+        .route("/api/auth/2fa/setup", post(two_factor::setup))
+        .route("/api/auth/2fa/enable", post(two_factor::enable))
+        .route("/api/auth/2fa/disable", post(two_factor::disable))
+        .route("/api/auth/2fa/recovery-codes", post(two_factor::regenerate_recovery_codes))
+        .route("/api/auth/tokens",
+            get(api_token::list_api_tokens)
+            .post(api_token::create_api_token)
+        )
+        .route("/api/auth/tokens/{id}", delete(api_token::revoke_api_token))
+        .route("/api/notifications", get(notification::list_notifications))
+        .route("/api/notifications/read-all", post(notification::mark_all_notifications_read))
+        .route("/api/notifications/{id}/read", post(notification::mark_notification_read))
+        .route("/api/audit", get(audit::list_audit_log))
+        .route("/api/users", get(user::list_users).post(user::create_user))
+        .route("/api/users/{id}", get(user::get_user).put(user::update_user))
+        .route("/api/users/{id}/status", put(user::update_user_status))
+        .route("/api/users/{id}/password", post(user::reset_user_password))
+        .route_layer(middleware::from_fn(require_session));
+
+    // Customer routes
+    let customer_routes = Router::new()
         .route("/api/customers", 
             get(customer::list_customers)
             .post(customer::create_customer)
@@ -37,8 +73,60 @@ pub fn create_routes(app_state: AppState) -> Router {
             .put(customer::update_customer)
             .delete(customer::delete_customer)
         )
-        
-        // Customer tracking routes
+        .route("/api/search", get(search::search))
+        .route("/api/customers/transfer", post(customer_transfer::transfer_customers))
+        .route("/api/customers/duplicates", get(customer_duplicate::duplicate_report))
+        .route("/api/customers/duplicates/check", get(customer_duplicate::check_duplicates))
+        .route("/api/customers/{id}/merge", post(customer_duplicate::merge_customers))
+        .route("/api/customers/trash", get(customer_trash::list_trash))
+        .route("/api/customers/{id}/restore", post(customer_trash::restore_customer))
+        .route("/api/customers/{id}/purge", delete(customer_trash::purge_customer))
+        .route("/api/customers/{id}/release", post(customer_pool::release_customer))
+        .route("/api/customers/{id}/collaborators",
+            get(customer_collaborator::list_collaborators)
+        )
+        .route("/api/customers/{id}/collaborators/{user_id}",
+            put(customer_collaborator::set_collaborator)
+            .delete(customer_collaborator::remove_collaborator)
+        )
+        .route("/api/custom-fields",
+            get(custom_field::list_custom_fields)
+            .post(custom_field::create_custom_field)
+        )
+        .route("/api/custom-fields/{id}",
+            put(custom_field::update_custom_field)
+            .delete(custom_field::delete_custom_field)
+        )
+        .route("/api/tags", get(tag::list_tags).post(tag::create_tag))
+        .route("/api/tags/{id}", put(tag::update_tag).delete(tag::delete_tag))
+        .route("/api/customer-groups",
+            get(customer_group::list_customer_groups)
+            .post(customer_group::create_customer_group)
+        )
+        .route("/api/customer-groups/{id}",
+            put(customer_group::update_customer_group)
+            .delete(customer_group::delete_customer_group)
+        )
+        .route("/api/pipeline-stages",
+            get(pipeline_stage::list_pipeline_stages)
+            .post(pipeline_stage::create_pipeline_stage)
+        )
+        .route("/api/pipeline-stages/{id}",
+            put(pipeline_stage::update_pipeline_stage)
+            .delete(pipeline_stage::delete_pipeline_stage)
+        )
+        .route("/api/pool/customers", get(customer_pool::list_pool_customers))
+        .route("/api/pool/customers/{id}/claim", post(customer_pool::claim_customer))
+        .route("/api/customers/{id}/ownership-history",
+            get(customer_transfer::get_ownership_history)
+        )
+        .route_layer(middleware::from_fn_with_state(
+            RouteScopes { read: ApiScope::CustomersRead, write: ApiScope::CustomersWrite },
+            require_scope,
+        ));
+
+    // Customer tracking routes
+    let track_routes = Router::new()
         .route("/api/customers/{id}/tracks", 
             get(customer_track::list_customer_tracks)
             .post(customer_track::create_customer_track)
@@ -49,7 +137,16 @@ pub fn create_routes(app_state: AppState) -> Router {
             .delete(customer_track::delete_customer_track)
         )
         .route("/api/tracks/actions", get(customer_track::get_next_actions))
-        
+        .route_layer(middleware::from_fn_with_state(
+            RouteScopes { read: ApiScope::TracksRead, write: ApiScope::TracksWrite },
+            require_scope,
+        ));
+
+    // Protected routes (authentication required: session JWT or API token)
+    let protected_routes = Router::new()
+        .merge(account_routes)
+        .merge(customer_routes)
+        .merge(track_routes)
         .layer(middleware::from_fn_with_state(
             app_state.clone(), 
             auth_middleware::<AppState>
@@ -74,4 +171,287 @@ fn create_cors_layer(_app_state: &AppState) -> CorsLayer {
 
 async fn health_check() -> &'static str {
     "OK"
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use serde_json::{json, Value};
+
+    use crate::{
+        entities::user_role::UserRole,
+        test_support::{self, TestApp},
+    };
+
+    fn ids(list: &Value) -> Vec<i64> {
+        list.as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_i64().unwrap())
+            .collect()
+    }
+
+    /// 另一个组织的数据：管理员、销售、客户、跟进记录，以及管理员操作留下的审计日志
+    struct OtherOrganization {
+        admin_id: i32,
+        sales_id: i32,
+        customer_id: i64,
+        track_id: i64,
+        group_id: i64,
+        stage_id: i64,
+    }
+
+    async fn other_organization(app: &TestApp) -> OtherOrganization {
+        let org = test_support::create_organization(app.db(), "其他组织").await;
+        let admin =
+            test_support::create_user_in(app.db(), "admin_b", UserRole::Admin, org.id, None).await;
+        let sales =
+            test_support::create_user_in(app.db(), "sales_b", UserRole::Sales, org.id, None).await;
+        let token = app.login("admin_b").await;
+
+        let body = json!({ "name": "王五", "phone": "13800000002" });
+        let (status, customer) = app
+            .request(Method::POST, "/api/customers", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let body = json!({ "customer_id": customer["id"], "content": "电话沟通" });
+        let (status, track) = app
+            .request(Method::POST, "/api/tracks", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, groups) = app
+            .request(Method::GET, "/api/customer-groups", Some(&token), None)
+            .await;
+        let (_, stages) = app
+            .request(Method::GET, "/api/pipeline-stages", Some(&token), None)
+            .await;
+        let (_, audit) = app
+            .request(Method::GET, "/api/audit", Some(&token), None)
+            .await;
+        assert!(audit["total"].as_u64().unwrap() > 0);
+
+        OtherOrganization {
+            admin_id: admin.id,
+            sales_id: sales.id,
+            customer_id: customer["id"].as_i64().unwrap(),
+            track_id: track["id"].as_i64().unwrap(),
+            group_id: ids(&groups)[0],
+            stage_id: ids(&stages)[0],
+        }
+    }
+
+    #[tokio::test]
+    async fn customers_and_tracks_of_other_organizations_are_not_found() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let other = other_organization(&app).await;
+        let token = app.login("admin").await;
+
+        let customer_uri = format!("/api/customers/{}", other.customer_id);
+        let (status, _) = app
+            .request(Method::GET, &customer_uri, Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let body = json!({ "name": "改名" });
+        let (status, _) = app
+            .request(Method::PUT, &customer_uri, Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app
+            .request(Method::DELETE, &customer_uri, Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = app
+            .request(
+                Method::GET,
+                &format!("{}/tracks", customer_uri),
+                Some(&token),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app
+            .request(
+                Method::GET,
+                &format!("/api/tracks?customer_id={}", other.customer_id),
+                Some(&token),
+                None,
+            )
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let body = json!({ "customer_id": other.customer_id, "content": "越权跟进" });
+        let (status, _) = app
+            .request(Method::POST, "/api/tracks", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let track_uri = format!("/api/tracks/{}", other.track_id);
+        let body = json!({ "content": "改写" });
+        let (status, _) = app
+            .request(Method::PUT, &track_uri, Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app
+            .request(Method::DELETE, &track_uri, Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, json) = app
+            .request(Method::GET, "/api/customers", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert!(ids(&json["customers"]).is_empty());
+
+        // 另一个组织的数据没有被改动
+        let token = app.login("admin_b").await;
+        let (status, json) = app
+            .request(Method::GET, &customer_uri, Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["name"], "王五");
+        let (_, json) = app
+            .request(
+                Method::GET,
+                &format!("{}/tracks", customer_uri),
+                Some(&token),
+                None,
+            )
+            .await;
+        assert_eq!(ids(&json["tracks"]), vec![other.track_id]);
+    }
+
+    #[tokio::test]
+    async fn users_and_audit_logs_of_other_organizations_are_not_found() {
+        let app = TestApp::new().await;
+        let admin = test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let other = other_organization(&app).await;
+        let token = app.login("admin").await;
+
+        for user_id in [other.admin_id, other.sales_id] {
+            let uri = format!("/api/users/{}", user_id);
+            let (status, _) = app.request(Method::GET, &uri, Some(&token), None).await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            let body = json!({ "name": "改名" });
+            let (status, _) = app
+                .request(Method::PUT, &uri, Some(&token), Some(body))
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            let body = json!({ "is_active": false });
+            let (status, _) = app
+                .request(
+                    Method::PUT,
+                    &format!("{}/status", uri),
+                    Some(&token),
+                    Some(body),
+                )
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+            let body = json!({ "password": "another123" });
+            let (status, _) = app
+                .request(
+                    Method::POST,
+                    &format!("{}/password", uri),
+                    Some(&token),
+                    Some(body),
+                )
+                .await;
+            assert_eq!(status, StatusCode::NOT_FOUND);
+        }
+        // 不能把其他组织的用户设为上级
+        let body = json!({
+            "username": "carol",
+            "password": "secret123",
+            "name": "carol",
+            "role": "sales",
+            "manager_id": other.admin_id,
+        });
+        let (status, _) = app
+            .request(Method::POST, "/api/users", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let (status, json) = app
+            .request(Method::GET, "/api/users?limit=100", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ids(&json["users"]), vec![i64::from(admin.id)]);
+
+        let (status, json) = app
+            .request(Method::GET, "/api/audit?limit=100", Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(json["total"], 0);
+        let (_, json) = app
+            .request(
+                Method::GET,
+                &format!("/api/audit?user_id={}", other.admin_id),
+                Some(&token),
+                None,
+            )
+            .await;
+        assert_eq!(json["total"], 0);
+
+        // 另一个组织的用户仍然可以登录
+        app.login("sales_b").await;
+    }
+
+    #[tokio::test]
+    async fn groups_and_stages_of_other_organizations_are_not_found() {
+        let app = TestApp::new().await;
+        test_support::create_user(app.db(), "admin", UserRole::Admin).await;
+        let other = other_organization(&app).await;
+        let token = app.login("admin").await;
+
+        let group_uri = format!("/api/customer-groups/{}", other.group_id);
+        let body = json!({ "display_name": "改名" });
+        let (status, _) = app
+            .request(Method::PUT, &group_uri, Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app
+            .request(Method::DELETE, &group_uri, Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let stage_uri = format!("/api/pipeline-stages/{}", other.stage_id);
+        let body = json!({ "name": "改名" });
+        let (status, _) = app
+            .request(Method::PUT, &stage_uri, Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = app
+            .request(Method::DELETE, &stage_uri, Some(&token), None)
+            .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // 两个组织有同名的默认分组和阶段，但 ID 不同
+        let (_, groups) = app
+            .request(
+                Method::GET,
+                "/api/customer-groups?include_archived=true",
+                Some(&token),
+                None,
+            )
+            .await;
+        assert!(!groups.as_array().unwrap().is_empty());
+        assert!(!ids(&groups).contains(&other.group_id));
+        let (_, stages) = app
+            .request(Method::GET, "/api/pipeline-stages", Some(&token), None)
+            .await;
+        assert!(!stages.as_array().unwrap().is_empty());
+        assert!(!ids(&stages).contains(&other.stage_id));
+        // 另一个组织的客户不计入本组织分组的客户数
+        assert!(groups
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|group| group["customer_count"] == 0));
+
+        // 不能给客户设置其他组织的阶段
+        let customer = test_support::create_customer(app.db(), "张三", None, None).await;
+        let body = json!({ "customer_id": customer.id, "content": "电话沟通", "stage_id": other.stage_id });
+        let (status, _) = app
+            .request(Method::POST, "/api/tracks", Some(&token), Some(body))
+            .await;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+
+use crate::{
+    entities::api_token::{self, ApiScope, Entity as ApiToken},
+    utils::token::{generate_secret, hash_secret},
+};
+
+/// API 令牌的固定前缀，认证中间件据此区分 API 令牌和 JWT
+pub const API_TOKEN_PREFIX: &str = "ctk_";
+
+/// 列表中展示的明文前缀长度（含固定前缀）
+const DISPLAY_PREFIX_LEN: usize = 12;
+
+/// 新创建的令牌及其明文（明文只在此处出现一次）
+pub struct IssuedApiToken {
+    pub token: api_token::Model,
+    pub plaintext: String,
+}
+
+pub struct ApiTokenService;
+
+impl ApiTokenService {
+    pub fn is_api_token(raw: &str) -> bool {
+        raw.starts_with(API_TOKEN_PREFIX)
+    }
+
+    /// 创建令牌，`expires_at` 为空表示永不过期
+    pub async fn create(
+        db: &DatabaseConnection,
+        user_id: i32,
+        name: &str,
+        scopes: &[ApiScope],
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<IssuedApiToken, DbErr> {
+        let plaintext = format!("{}{}", API_TOKEN_PREFIX, generate_secret());
+
+        let mut scope_names: Vec<&str> = Vec::new();
+        for scope in ApiScope::ALL {
+            if scopes.contains(&scope) {
+                scope_names.push(scope.as_str());
+            }
+        }
+
+        let token = api_token::ActiveModel {
+            user_id: Set(user_id),
+            name: Set(name.to_string()),
+            token_prefix: Set(plaintext[..DISPLAY_PREFIX_LEN].to_string()),
+            token_hash: Set(hash_secret(&plaintext)),
+            scopes: Set(scope_names.join(",")),
+            expires_at: Set(expires_at),
+            last_used_at: Set(None),
+            revoked_at: Set(None),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(IssuedApiToken { token, plaintext })
+    }
+
+    /// 用户未吊销的令牌，按创建时间倒序
+    pub async fn list_for_user(
+        db: &DatabaseConnection,
+        user_id: i32,
+    ) -> Result<Vec<api_token::Model>, DbErr> {
+        ApiToken::find()
+            .filter(api_token::Column::UserId.eq(user_id))
+            .filter(api_token::Column::RevokedAt.is_null())
+            .order_by_desc(api_token::Column::CreatedAt)
+            .all(db)
+            .await
+    }
+
+    /// 吊销用户自己的令牌，返回是否找到该令牌
+    pub async fn revoke(db: &DatabaseConnection, user_id: i32, token_id: i32) -> Result<bool, DbErr> {
+        let result = ApiToken::update_many()
+            .col_expr(api_token::Column::RevokedAt, Utc::now().into())
+            .filter(api_token::Column::Id.eq(token_id))
+            .filter(api_token::Column::UserId.eq(user_id))
+            .filter(api_token::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// 吊销用户的所有令牌，例如修改密码之后，返回吊销的数量
+    pub async fn revoke_all_for_user<C: ConnectionTrait>(db: &C, user_id: i32) -> Result<u64, DbErr> {
+        let result = ApiToken::update_many()
+            .col_expr(api_token::Column::RevokedAt, Utc::now().into())
+            .filter(api_token::Column::UserId.eq(user_id))
+            .filter(api_token::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// 校验明文令牌，有效时记录最后使用时间并返回令牌
+    pub async fn authenticate(
+        db: &DatabaseConnection,
+        raw: &str,
+    ) -> Result<Option<api_token::Model>, DbErr> {
+        let token = ApiToken::find()
+            .filter(api_token::Column::TokenHash.eq(hash_secret(raw)))
+            .one(db)
+            .await?;
+
+        let Some(token) = token.filter(|t| t.is_active()) else {
+            return Ok(None);
+        };
+
+        let mut token_active: api_token::ActiveModel = token.into();
+        token_active.last_used_at = Set(Some(Utc::now()));
+        Ok(Some(token_active.update(db).await?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{entities::user_role::UserRole, test_support};
+
+    #[tokio::test]
+    async fn authenticates_only_active_tokens() {
+        let db = test_support::test_db().await;
+        let user = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let scopes = [ApiScope::CustomersRead];
+
+        let issued = ApiTokenService::create(&db, user.id, "ci", &scopes, None).await.unwrap();
+        assert!(ApiTokenService::is_api_token(&issued.plaintext));
+        assert!(issued.plaintext.starts_with(&issued.token.token_prefix));
+        let token = ApiTokenService::authenticate(&db, &issued.plaintext).await.unwrap().unwrap();
+        assert_eq!(token.scope_list(), scopes);
+        assert!(token.last_used_at.is_some());
+
+        let expired_at = Some(Utc::now() - Duration::minutes(1));
+        let expired = ApiTokenService::create(&db, user.id, "old", &scopes, expired_at).await.unwrap();
+        assert!(ApiTokenService::authenticate(&db, &expired.plaintext).await.unwrap().is_none());
+        assert!(ApiTokenService::authenticate(&db, "ctk_unknown").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn users_can_only_revoke_their_own_tokens() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let scopes = [ApiScope::CustomersRead];
+        let issued = ApiTokenService::create(&db, alice.id, "ci", &scopes, None).await.unwrap();
+
+        assert!(!ApiTokenService::revoke(&db, bob.id, issued.token.id).await.unwrap());
+        assert!(ApiTokenService::authenticate(&db, &issued.plaintext).await.unwrap().is_some());
+
+        assert!(ApiTokenService::revoke(&db, alice.id, issued.token.id).await.unwrap());
+        assert!(ApiTokenService::authenticate(&db, &issued.plaintext).await.unwrap().is_none());
+        assert!(ApiTokenService::list_for_user(&db, alice.id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn revoke_all_for_user_leaves_other_users_alone() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let scopes = [ApiScope::CustomersRead];
+        for name in ["a", "b"] {
+            ApiTokenService::create(&db, alice.id, name, &scopes, None).await.unwrap();
+        }
+        let bobs = ApiTokenService::create(&db, bob.id, "b", &scopes, None).await.unwrap();
+
+        assert_eq!(ApiTokenService::revoke_all_for_user(&db, alice.id).await.unwrap(), 2);
+        assert!(ApiTokenService::list_for_user(&db, alice.id).await.unwrap().is_empty());
+        assert!(ApiTokenService::authenticate(&db, &bobs.plaintext).await.unwrap().is_some());
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::Utc;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, Set};
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    entities::audit_log::{self, AuditAction, AuditEntity},
+    middleware::auth::CurrentUser,
+};
+
+/// 比较差异时忽略的字段，每次更新都会变化，没有审计意义
+const IGNORED_FIELDS: [&str; 1] = ["updated_at"];
+
+/// 操作人
+#[derive(Debug, Clone)]
+pub struct Actor {
+    pub user_id: Option<i32>,
+    pub name: String,
+    pub organization_id: i32,
+}
+
+impl Actor {
+    /// 命令行操作没有登录用户，记为 `cli`
+    pub fn cli(organization_id: i32) -> Self {
+        Self {
+            user_id: None,
+            name: "cli".to_string(),
+            organization_id,
+        }
+    }
+
+    /// 后台任务，如自动退回公海，记为 `system`
+    pub fn system(organization_id: i32) -> Self {
+        Self {
+            user_id: None,
+            name: "system".to_string(),
+            organization_id,
+        }
+    }
+
+    /// 单点登录首次登录时自动创建用户，记为 `sso`
+    pub fn sso(organization_id: i32) -> Self {
+        Self {
+            user_id: None,
+            name: "sso".to_string(),
+            organization_id,
+        }
+    }
+}
+
+impl From<&CurrentUser> for Actor {
+    fn from(current_user: &CurrentUser) -> Self {
+        Self {
+            user_id: Some(current_user.id),
+            name: current_user.username.clone(),
+            organization_id: current_user.organization_id,
+        }
+    }
+}
+
+pub struct AuditService;
+
+impl AuditService {
+    /// 写入一条审计记录。
+    ///
+    /// 新建时 `before` 为空，删除时 `after` 为空；更新时只保留前后不同的字段。
+    /// 传入事务连接即可与数据变更一起提交。
+    pub async fn record<C, T>(
+        db: &C,
+        actor: &Actor,
+        action: AuditAction,
+        entity: AuditEntity,
+        entity_id: i32,
+        before: Option<&T>,
+        after: Option<&T>,
+    ) -> Result<(), DbErr>
+    where
+        C: ConnectionTrait,
+        T: Serialize,
+    {
+        let before = before.and_then(|value| serde_json::to_value(value).ok());
+        let after = after.and_then(|value| serde_json::to_value(value).ok());
+
+        audit_log::ActiveModel {
+            organization_id: Set(actor.organization_id),
+            actor_id: Set(actor.user_id),
+            actor_name: Set(actor.name.clone()),
+            action: Set(action),
+            entity: Set(entity),
+            entity_id: Set(entity_id),
+            changes: Set(diff(before, after)),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+fn diff(before: Option<Value>, after: Option<Value>) -> Value {
+    match (before, after) {
+        (Some(Value::Object(before)), Some(Value::Object(after))) => {
+            let mut changed_before = Map::new();
+            let mut changed_after = Map::new();
+            for key in before.keys().chain(after.keys()) {
+                if IGNORED_FIELDS.contains(&key.as_str()) || changed_before.contains_key(key) {
+                    continue;
+                }
+                let old = before.get(key).cloned().unwrap_or(Value::Null);
+                let new = after.get(key).cloned().unwrap_or(Value::Null);
+                if old != new {
+                    changed_before.insert(key.clone(), old);
+                    changed_after.insert(key.clone(), new);
+                }
+            }
+            json!({ "before": changed_before, "after": changed_after })
+        }
+        (before, after) => json!({ "before": before, "after": after }),
+    }
+}
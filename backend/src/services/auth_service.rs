@@ -5,6 +5,9 @@ use serde::{Deserialize, Serialize};
 use crate::entities::{user, user::Entity as User};
 use crate::utils::password::verify_password;
 use crate::utils::jwt::generate_jwt_token;
+use crate::services::jwt_key_service::JwtKeyStore;
+use crate::services::session_service::SessionService;
+use crate::utils::client_ip::ClientInfo;
 
 #[derive(Debug, Deserialize)]
 pub struct LoginRequest {
@@ -16,6 +19,7 @@ pub struct LoginRequest {
 pub struct LoginResponse {
     pub token: String,
     pub expires_in: i64,
+    pub refresh_token: String,
     pub user: UserInfo,
 }
 
@@ -34,6 +38,8 @@ impl AuthService {
     pub async fn authenticate_user(
         db: &DatabaseConnection,
         login_request: LoginRequest,
+        client: &ClientInfo,
+        jwt_keys: &JwtKeyStore,
     ) -> Result<LoginResponse> {
         // 查找用户
         let user = User::find()
@@ -55,19 +61,18 @@ impl AuthService {
         let updated_user = active_user.update(db).await?;
 
         // 生成 JWT Token
-        // 需要一个JWT_SECRET - 这里暂时使用硬编码，实际应该从环境变量获取
-        let jwt_secret = "your-secret-key"; // TODO: 从环境变量获取
+        let issued = SessionService::create_session(db, updated_user.id, 30, client).await?;
         let token_pair = generate_jwt_token(
-            updated_user.id,
-            &updated_user.username,
-            &updated_user.name,
-            jwt_secret,
-            24, // 24小时过期
+            &updated_user,
+            issued.session.id,
+            jwt_keys,
+            15, // 15分钟过期
         )?;
 
         Ok(LoginResponse {
             token: token_pair.access_token,
             expires_in: token_pair.expires_in,
+            refresh_token: issued.refresh_token,
             user: UserInfo {
                 id: updated_user.id,
                 username: updated_user.username,
@@ -0,0 +1,170 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::Serialize;
+
+use crate::{
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        customer,
+        customer_collaborator::{self, CollaboratorPermission, Entity as CustomerCollaborator},
+        user::{self, Entity as User},
+    },
+    services::audit_service::{Actor, AuditService},
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum CollaboratorError {
+    #[error("用户不存在或已停用")]
+    UserNotFound,
+    #[error("负责人不需要添加为协作者")]
+    IsOwner,
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 审计日志中记录的协作者列表：用户ID -> 权限
+#[derive(Serialize)]
+struct CollaboratorSnapshot {
+    collaborators: BTreeMap<i32, CollaboratorPermission>,
+}
+
+pub struct CollaboratorService;
+
+impl CollaboratorService {
+    /// 客户的协作者及其用户信息，按添加顺序
+    pub async fn list(
+        db: &DatabaseConnection,
+        customer_id: i32,
+    ) -> Result<Vec<(customer_collaborator::Model, user::Model)>, DbErr> {
+        let collaborators = CustomerCollaborator::find()
+            .find_also_related(User)
+            .filter(customer_collaborator::Column::CustomerId.eq(customer_id))
+            .order_by_asc(customer_collaborator::Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(collaborators
+            .into_iter()
+            .filter_map(|(collaborator, user)| Some((collaborator, user?)))
+            .collect())
+    }
+
+    /// 添加协作者，已存在时更新权限
+    pub async fn set(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        customer: &customer::Model,
+        user_id: i32,
+        permission: CollaboratorPermission,
+    ) -> Result<customer_collaborator::Model, CollaboratorError> {
+        if customer.user_id == Some(user_id) {
+            return Err(CollaboratorError::IsOwner);
+        }
+        User::find_by_id(user_id)
+            .filter(user::Column::OrganizationId.eq(customer.organization_id))
+            .filter(user::Column::IsActive.eq(true))
+            .one(db)
+            .await?
+            .ok_or(CollaboratorError::UserNotFound)?;
+
+        let now = Utc::now();
+        let txn = db.begin().await?;
+        let before = snapshot(&txn, customer.id).await?;
+
+        let existing = CustomerCollaborator::find()
+            .filter(customer_collaborator::Column::CustomerId.eq(customer.id))
+            .filter(customer_collaborator::Column::UserId.eq(user_id))
+            .one(&txn)
+            .await?;
+        let collaborator = match existing {
+            Some(existing) => {
+                let mut active: customer_collaborator::ActiveModel = existing.into();
+                active.permission = Set(permission);
+                active.updated_at = Set(now);
+                active.update(&txn).await?
+            }
+            None => {
+                customer_collaborator::ActiveModel {
+                    customer_id: Set(customer.id),
+                    user_id: Set(user_id),
+                    permission: Set(permission),
+                    added_by: Set(actor.user_id),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                    ..Default::default()
+                }
+                .insert(&txn)
+                .await?
+            }
+        };
+
+        let after = snapshot(&txn, customer.id).await?;
+        record(&txn, actor, customer.id, &before, &after).await?;
+        txn.commit().await?;
+
+        Ok(collaborator)
+    }
+
+    /// 移除协作者，返回是否存在
+    pub async fn remove(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        customer_id: i32,
+        user_id: i32,
+    ) -> Result<bool, DbErr> {
+        let txn = db.begin().await?;
+        let before = snapshot(&txn, customer_id).await?;
+
+        let result = CustomerCollaborator::delete_many()
+            .filter(customer_collaborator::Column::CustomerId.eq(customer_id))
+            .filter(customer_collaborator::Column::UserId.eq(user_id))
+            .exec(&txn)
+            .await?;
+        if result.rows_affected == 0 {
+            return Ok(false);
+        }
+
+        let after = snapshot(&txn, customer_id).await?;
+        record(&txn, actor, customer_id, &before, &after).await?;
+        txn.commit().await?;
+
+        Ok(true)
+    }
+}
+
+async fn snapshot<C: ConnectionTrait>(db: &C, customer_id: i32) -> Result<CollaboratorSnapshot, DbErr> {
+    let collaborators = CustomerCollaborator::find()
+        .filter(customer_collaborator::Column::CustomerId.eq(customer_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|collaborator| (collaborator.user_id, collaborator.permission))
+        .collect();
+
+    Ok(CollaboratorSnapshot { collaborators })
+}
+
+// 协作者变更记为客户的更新
+async fn record<C: ConnectionTrait>(
+    db: &C,
+    actor: &Actor,
+    customer_id: i32,
+    before: &CollaboratorSnapshot,
+    after: &CollaboratorSnapshot,
+) -> Result<(), DbErr> {
+    AuditService::record(
+        db,
+        actor,
+        AuditAction::Update,
+        AuditEntity::Customer,
+        customer_id,
+        Some(before),
+        Some(after),
+    )
+    .await
+}
@@ -0,0 +1,873 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{NaiveDate, Utc};
+use sea_orm::{
+    sea_query::{Expr, OnConflict, Query},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde_json::Value;
+
+use crate::entities::{
+    custom_field::{self, CustomFieldType, Entity as CustomField, FieldGroups, FieldOptions},
+    customer,
+    customer_field_value::{self, Entity as CustomerFieldValue},
+    customer_group::CustomerGroup,
+};
+
+/// 文本字段的最大长度
+const MAX_TEXT_LENGTH: usize = 500;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CustomFieldError {
+    #[error("字段标识只能包含小写字母、数字和下划线，以字母开头，最多50个字符")]
+    InvalidKey,
+    #[error("字段标识 {0} 已存在")]
+    KeyExists(String),
+    #[error("字段名称不能为空")]
+    EmptyLabel,
+    #[error("单选和多选字段需要至少一个可选项，且可选项不能为空或重复")]
+    InvalidOptions,
+    #[error("只有单选和多选字段可以设置可选项")]
+    UnexpectedOptions,
+    #[error("未知的自定义字段: {0}")]
+    UnknownField(String),
+    #[error("字段「{0}」不适用于该分组的客户")]
+    NotApplicable(String),
+    #[error("字段「{0}」为必填项")]
+    Required(String),
+    #[error("字段「{label}」的值无效：{reason}")]
+    InvalidValue { label: String, reason: String },
+    #[error("字段「{0}」不支持范围筛选")]
+    RangeNotSupported(String),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+#[derive(Debug, Clone)]
+pub struct NewCustomField {
+    pub field_key: String,
+    pub label: String,
+    pub field_type: CustomFieldType,
+    pub options: Vec<String>,
+    pub customer_groups: Vec<CustomerGroup>,
+    pub required: bool,
+    pub sort_order: i32,
+}
+
+/// 字段标识和类型创建后不能修改，已有的值依赖它们
+#[derive(Debug, Clone, Default)]
+pub struct CustomFieldUpdate {
+    pub label: Option<String>,
+    pub options: Option<Vec<String>>,
+    pub customer_groups: Option<Vec<CustomerGroup>>,
+    pub required: Option<bool>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+/// 校验后待保存的值，`None` 表示清除
+#[derive(Debug, Clone)]
+pub struct PreparedValue {
+    pub field_id: i32,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterOp {
+    /// 文本包含、多选包含某项，其他类型相等
+    Match,
+    /// 数字、日期不小于
+    From,
+    /// 数字、日期不大于
+    To,
+}
+
+/// 客户列表上的自定义字段筛选，对应查询参数 `field.<key>`、`field.<key>.from`、`field.<key>.to`
+#[derive(Debug, Clone)]
+pub struct FieldFilter {
+    pub key: String,
+    pub op: FilterOp,
+    pub value: String,
+}
+
+impl FieldFilter {
+    /// 从全部查询参数中取出自定义字段筛选，忽略空值
+    pub fn from_query(params: &HashMap<String, String>) -> Vec<FieldFilter> {
+        let mut filters: Vec<FieldFilter> = params
+            .iter()
+            .filter(|(_, value)| !value.trim().is_empty())
+            .filter_map(|(name, value)| {
+                let rest = name.strip_prefix("field.")?;
+                let (key, op) = match rest.rsplit_once('.') {
+                    Some((key, "from")) => (key, FilterOp::From),
+                    Some((key, "to")) => (key, FilterOp::To),
+                    _ => (rest, FilterOp::Match),
+                };
+                Some(FieldFilter {
+                    key: key.to_string(),
+                    op,
+                    value: value.trim().to_string(),
+                })
+            })
+            .collect();
+        filters.sort_by(|a, b| a.key.cmp(&b.key));
+        filters
+    }
+}
+
+pub struct CustomFieldService;
+
+impl CustomFieldService {
+    /// 组织的自定义字段，按排序值和创建顺序
+    pub async fn list(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        include_inactive: bool,
+    ) -> Result<Vec<custom_field::Model>, DbErr> {
+        let mut query =
+            CustomField::find().filter(custom_field::Column::OrganizationId.eq(organization_id));
+        if !include_inactive {
+            query = query.filter(custom_field::Column::IsActive.eq(true));
+        }
+        query
+            .order_by_asc(custom_field::Column::SortOrder)
+            .order_by_asc(custom_field::Column::Id)
+            .all(db)
+            .await
+    }
+
+    pub async fn find(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        field_id: i32,
+    ) -> Result<Option<custom_field::Model>, DbErr> {
+        CustomField::find_by_id(field_id)
+            .filter(custom_field::Column::OrganizationId.eq(organization_id))
+            .one(db)
+            .await
+    }
+
+    pub async fn create(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        field: NewCustomField,
+    ) -> Result<custom_field::Model, CustomFieldError> {
+        let field_key = field.field_key.trim().to_string();
+        if !valid_key(&field_key) {
+            return Err(CustomFieldError::InvalidKey);
+        }
+        let label = checked_label(&field.label)?;
+        let options = checked_options(field.field_type, field.options)?;
+
+        let existing = CustomField::find()
+            .filter(custom_field::Column::OrganizationId.eq(organization_id))
+            .filter(custom_field::Column::FieldKey.eq(&field_key))
+            .one(db)
+            .await?;
+        if existing.is_some() {
+            return Err(CustomFieldError::KeyExists(field_key));
+        }
+
+        let now = Utc::now();
+        let field = custom_field::ActiveModel {
+            organization_id: Set(organization_id),
+            field_key: Set(field_key),
+            label: Set(label),
+            field_type: Set(field.field_type),
+            options: Set(FieldOptions(options)),
+            customer_groups: Set(FieldGroups(dedup_groups(field.customer_groups))),
+            required: Set(field.required),
+            sort_order: Set(field.sort_order),
+            is_active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(field)
+    }
+
+    /// 修改字段定义；删掉的可选项不影响已经保存的值
+    pub async fn update(
+        db: &DatabaseConnection,
+        field: custom_field::Model,
+        update: CustomFieldUpdate,
+    ) -> Result<custom_field::Model, CustomFieldError> {
+        let field_type = field.field_type;
+        let mut active: custom_field::ActiveModel = field.into();
+
+        if let Some(label) = update.label {
+            active.label = Set(checked_label(&label)?);
+        }
+        if let Some(options) = update.options {
+            active.options = Set(FieldOptions(checked_options(field_type, options)?));
+        }
+        if let Some(customer_groups) = update.customer_groups {
+            active.customer_groups = Set(FieldGroups(dedup_groups(customer_groups)));
+        }
+        if let Some(required) = update.required {
+            active.required = Set(required);
+        }
+        if let Some(sort_order) = update.sort_order {
+            active.sort_order = Set(sort_order);
+        }
+        if let Some(is_active) = update.is_active {
+            active.is_active = Set(is_active);
+        }
+        active.updated_at = Set(Utc::now());
+
+        Ok(active.update(db).await?)
+    }
+
+    /// 删除字段及所有客户在该字段上的值；只想隐藏时应停用字段
+    pub async fn delete(db: &DatabaseConnection, field: &custom_field::Model) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        CustomerFieldValue::delete_many()
+            .filter(customer_field_value::Column::FieldId.eq(field.id))
+            .exec(&txn)
+            .await?;
+        CustomField::delete_by_id(field.id).exec(&txn).await?;
+        txn.commit().await
+    }
+
+    /// 校验请求中的字段值（字段标识 -> 值，`null` 表示清除）。
+    ///
+    /// 新建客户时还会检查该分组所有必填字段都已填写；修改时只检查请求中出现的字段，
+    /// 避免字段设为必填之前创建的客户无法修改其他信息。
+    pub async fn prepare_values(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        customer_group: &CustomerGroup,
+        values: &BTreeMap<String, Value>,
+        creating: bool,
+    ) -> Result<Vec<PreparedValue>, CustomFieldError> {
+        if values.is_empty() && !creating {
+            return Ok(Vec::new());
+        }
+        let fields = Self::list(db, organization_id, false).await?;
+
+        let mut prepared = Vec::with_capacity(values.len());
+        for (key, value) in values {
+            let field = fields
+                .iter()
+                .find(|field| &field.field_key == key)
+                .ok_or_else(|| CustomFieldError::UnknownField(key.clone()))?;
+            if !field.applies_to(customer_group) {
+                return Err(CustomFieldError::NotApplicable(field.label.clone()));
+            }
+            let value = normalize_value(field, value)?;
+            if field.required && value.is_none() {
+                return Err(CustomFieldError::Required(field.label.clone()));
+            }
+            prepared.push(PreparedValue {
+                field_id: field.id,
+                value,
+            });
+        }
+
+        if creating
+            && let Some(missing) = fields.iter().find(|field| {
+                field.required
+                    && field.applies_to(customer_group)
+                    && !values.contains_key(&field.field_key)
+            })
+        {
+            return Err(CustomFieldError::Required(missing.label.clone()));
+        }
+
+        Ok(prepared)
+    }
+
+    /// 保存 `prepare_values` 校验过的值
+    pub async fn save_values<C: ConnectionTrait>(
+        db: &C,
+        customer_id: i32,
+        values: Vec<PreparedValue>,
+    ) -> Result<(), DbErr> {
+        let now = Utc::now();
+        for PreparedValue { field_id, value } in values {
+            match value {
+                Some(value) => {
+                    CustomerFieldValue::insert(customer_field_value::ActiveModel {
+                        customer_id: Set(customer_id),
+                        field_id: Set(field_id),
+                        value: Set(value),
+                        created_at: Set(now),
+                        updated_at: Set(now),
+                        ..Default::default()
+                    })
+                    .on_conflict(
+                        OnConflict::columns([
+                            customer_field_value::Column::CustomerId,
+                            customer_field_value::Column::FieldId,
+                        ])
+                        .update_columns([
+                            customer_field_value::Column::Value,
+                            customer_field_value::Column::UpdatedAt,
+                        ])
+                        .to_owned(),
+                    )
+                    .exec(db)
+                    .await?;
+                }
+                None => {
+                    CustomerFieldValue::delete_many()
+                        .filter(customer_field_value::Column::CustomerId.eq(customer_id))
+                        .filter(customer_field_value::Column::FieldId.eq(field_id))
+                        .exec(db)
+                        .await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 适用于客户分组的启用字段及客户的值，未填写的值为 `Null`
+    pub async fn values_for(
+        db: &DatabaseConnection,
+        customer: &customer::Model,
+    ) -> Result<Vec<(custom_field::Model, Value)>, DbErr> {
+        let fields = Self::list(db, customer.organization_id, false).await?;
+        let mut stored: HashMap<i32, String> = CustomerFieldValue::find()
+            .filter(customer_field_value::Column::CustomerId.eq(customer.id))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|value| (value.field_id, value.value))
+            .collect();
+
+        Ok(fields
+            .into_iter()
+            .filter(|field| field.applies_to(&customer.customer_group))
+            .map(|field| {
+                let value = stored
+                    .remove(&field.id)
+                    .map(|value| value_to_json(field.field_type, &value))
+                    .unwrap_or(Value::Null);
+                (field, value)
+            })
+            .collect())
+    }
+
+    /// 客户所有已填写的值（字段标识 -> 值），用于审计日志
+    pub async fn snapshot<C: ConnectionTrait>(
+        db: &C,
+        customer_id: i32,
+    ) -> Result<BTreeMap<String, Value>, DbErr> {
+        Ok(CustomerFieldValue::find()
+            .find_also_related(CustomField)
+            .filter(customer_field_value::Column::CustomerId.eq(customer_id))
+            .all(db)
+            .await?
+            .into_iter()
+            .filter_map(|(value, field)| {
+                let field = field?;
+                Some((field.field_key, value_to_json(field.field_type, &value.value)))
+            })
+            .collect())
+    }
+
+    /// 自定义字段筛选对应的客户条件，所有筛选同时满足
+    pub async fn filter_condition(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        filters: &[FieldFilter],
+    ) -> Result<Condition, CustomFieldError> {
+        let mut condition = Condition::all();
+        if filters.is_empty() {
+            return Ok(condition);
+        }
+        let fields = Self::list(db, organization_id, false).await?;
+
+        for filter in filters {
+            let field = fields
+                .iter()
+                .find(|field| field.field_key == filter.key)
+                .ok_or_else(|| CustomFieldError::UnknownField(filter.key.clone()))?;
+            let (sql, value) = filter_sql(field, filter)?;
+
+            let matching = Query::select()
+                .column(customer_field_value::Column::CustomerId)
+                .from(CustomerFieldValue)
+                .and_where(customer_field_value::Column::FieldId.eq(field.id))
+                .and_where(Expr::cust_with_values(sql, [value]))
+                .to_owned();
+            condition = condition.add(customer::Column::Id.in_subquery(matching));
+        }
+
+        Ok(condition)
+    }
+}
+
+fn valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    key.len() <= 50
+        && chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn checked_label(label: &str) -> Result<String, CustomFieldError> {
+    let label = label.trim();
+    if label.is_empty() {
+        return Err(CustomFieldError::EmptyLabel);
+    }
+    Ok(label.to_string())
+}
+
+fn checked_options(
+    field_type: CustomFieldType,
+    options: Vec<String>,
+) -> Result<Vec<String>, CustomFieldError> {
+    if !field_type.has_options() {
+        return if options.is_empty() {
+            Ok(options)
+        } else {
+            Err(CustomFieldError::UnexpectedOptions)
+        };
+    }
+
+    let options: Vec<String> = options.iter().map(|option| option.trim().to_string()).collect();
+    let has_duplicates = options
+        .iter()
+        .enumerate()
+        .any(|(i, option)| options[..i].contains(option));
+    if options.is_empty() || options.iter().any(String::is_empty) || has_duplicates {
+        return Err(CustomFieldError::InvalidOptions);
+    }
+    Ok(options)
+}
+
+fn dedup_groups(groups: Vec<CustomerGroup>) -> Vec<CustomerGroup> {
+    let mut unique = Vec::with_capacity(groups.len());
+    for group in groups {
+        if !unique.contains(&group) {
+            unique.push(group);
+        }
+    }
+    unique
+}
+
+/// 把请求中的值转换为保存的文本，空值返回 `None`
+fn normalize_value(
+    field: &custom_field::Model,
+    value: &Value,
+) -> Result<Option<String>, CustomFieldError> {
+    let invalid = |reason: &str| CustomFieldError::InvalidValue {
+        label: field.label.clone(),
+        reason: reason.to_string(),
+    };
+    if value.is_null() {
+        return Ok(None);
+    }
+
+    match field.field_type {
+        CustomFieldType::Text => {
+            let text = value.as_str().ok_or_else(|| invalid("需要文本"))?.trim();
+            if text.chars().count() > MAX_TEXT_LENGTH {
+                return Err(invalid("不能超过500个字符"));
+            }
+            Ok((!text.is_empty()).then(|| text.to_string()))
+        }
+        CustomFieldType::Number => {
+            let number = match value {
+                Value::Number(number) => number.as_f64(),
+                Value::String(text) if text.trim().is_empty() => return Ok(None),
+                Value::String(text) => text.trim().parse::<f64>().ok(),
+                _ => None,
+            }
+            .filter(|number| number.is_finite())
+            .ok_or_else(|| invalid("需要数字"))?;
+            Ok(Some(format_number(number)))
+        }
+        CustomFieldType::Date => {
+            let text = value.as_str().ok_or_else(|| invalid("日期格式应为 YYYY-MM-DD"))?.trim();
+            if text.is_empty() {
+                return Ok(None);
+            }
+            let date = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                .map_err(|_| invalid("日期格式应为 YYYY-MM-DD"))?;
+            Ok(Some(date.format("%Y-%m-%d").to_string()))
+        }
+        CustomFieldType::Select => {
+            let option = value.as_str().ok_or_else(|| invalid("需要选择一项"))?.trim();
+            if option.is_empty() {
+                return Ok(None);
+            }
+            if !field.options.0.iter().any(|candidate| candidate == option) {
+                return Err(invalid(&format!("{} 不是可选项", option)));
+            }
+            Ok(Some(option.to_string()))
+        }
+        CustomFieldType::MultiSelect => {
+            let items = value.as_array().ok_or_else(|| invalid("需要可选项数组"))?;
+            let mut selected = Vec::with_capacity(items.len());
+            for item in items {
+                let option = item.as_str().ok_or_else(|| invalid("需要可选项数组"))?.trim();
+                if !field.options.0.iter().any(|candidate| candidate == option) {
+                    return Err(invalid(&format!("{} 不是可选项", option)));
+                }
+                selected.push(option);
+            }
+            // 按定义中的顺序保存，去掉重复项
+            let selected: Vec<&String> = field
+                .options
+                .0
+                .iter()
+                .filter(|option| selected.contains(&option.as_str()))
+                .collect();
+            if selected.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(serde_json::to_string(&selected).unwrap_or_default()))
+        }
+    }
+}
+
+// 整数不带小数点保存，便于按文本比较和显示
+fn format_number(number: f64) -> String {
+    if number.fract() == 0.0 && number.abs() < 1e15 {
+        format!("{}", number as i64)
+    } else {
+        number.to_string()
+    }
+}
+
+fn value_to_json(field_type: CustomFieldType, value: &str) -> Value {
+    match field_type {
+        CustomFieldType::Number | CustomFieldType::MultiSelect => {
+            serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+        }
+        _ => Value::String(value.to_string()),
+    }
+}
+
+// 筛选条件作用于 `customer_field_values` 的一行
+fn filter_sql(
+    field: &custom_field::Model,
+    filter: &FieldFilter,
+) -> Result<(&'static str, sea_orm::Value), CustomFieldError> {
+    let invalid = |reason: &str| CustomFieldError::InvalidValue {
+        label: field.label.clone(),
+        reason: reason.to_string(),
+    };
+
+    match (field.field_type, filter.op) {
+        (CustomFieldType::Text, FilterOp::Match) => Ok((
+            "INSTR(customer_field_values.value, ?) > 0",
+            filter.value.clone().into(),
+        )),
+        (CustomFieldType::Number, op) => {
+            let number: f64 = filter.value.parse().map_err(|_| invalid("需要数字"))?;
+            let sql = match op {
+                FilterOp::Match => "CAST(customer_field_values.value AS REAL) = ?",
+                FilterOp::From => "CAST(customer_field_values.value AS REAL) >= ?",
+                FilterOp::To => "CAST(customer_field_values.value AS REAL) <= ?",
+            };
+            Ok((sql, number.into()))
+        }
+        (CustomFieldType::Date, op) => {
+            let date = NaiveDate::parse_from_str(&filter.value, "%Y-%m-%d")
+                .map_err(|_| invalid("日期格式应为 YYYY-MM-DD"))?;
+            let sql = match op {
+                FilterOp::Match => "customer_field_values.value = ?",
+                FilterOp::From => "customer_field_values.value >= ?",
+                FilterOp::To => "customer_field_values.value <= ?",
+            };
+            Ok((sql, date.format("%Y-%m-%d").to_string().into()))
+        }
+        (CustomFieldType::Select, FilterOp::Match) => {
+            Ok(("customer_field_values.value = ?", filter.value.clone().into()))
+        }
+        (CustomFieldType::MultiSelect, FilterOp::Match) => Ok((
+            "EXISTS (SELECT 1 FROM json_each(customer_field_values.value) WHERE json_each.value = ?)",
+            filter.value.clone().into(),
+        )),
+        _ => Err(CustomFieldError::RangeNotSupported(field.label.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{entities::customer::Entity as Customer, test_support};
+
+    fn group(name: &str) -> CustomerGroup {
+        CustomerGroup(name.to_string())
+    }
+
+    fn field(key: &str, field_type: CustomFieldType, options: &[&str]) -> NewCustomField {
+        NewCustomField {
+            field_key: key.to_string(),
+            label: key.to_string(),
+            field_type,
+            options: options.iter().map(|option| option.to_string()).collect(),
+            customer_groups: Vec::new(),
+            required: false,
+            sort_order: 0,
+        }
+    }
+
+    fn values(value: Value) -> BTreeMap<String, Value> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[tokio::test]
+    async fn validates_field_definitions() {
+        let db = test_support::test_db().await;
+
+        let result =
+            CustomFieldService::create(&db, 1, field("Budget", CustomFieldType::Number, &[])).await;
+        assert!(matches!(result, Err(CustomFieldError::InvalidKey)));
+        let result =
+            CustomFieldService::create(&db, 1, field("level", CustomFieldType::Select, &[])).await;
+        assert!(matches!(result, Err(CustomFieldError::InvalidOptions)));
+        let result = CustomFieldService::create(
+            &db,
+            1,
+            field("level", CustomFieldType::Select, &["A", " A"]),
+        )
+        .await;
+        assert!(matches!(result, Err(CustomFieldError::InvalidOptions)));
+        let result =
+            CustomFieldService::create(&db, 1, field("note", CustomFieldType::Text, &["A"])).await;
+        assert!(matches!(result, Err(CustomFieldError::UnexpectedOptions)));
+
+        CustomFieldService::create(&db, 1, field("budget", CustomFieldType::Number, &[]))
+            .await
+            .unwrap();
+        let result =
+            CustomFieldService::create(&db, 1, field("budget", CustomFieldType::Text, &[])).await;
+        assert!(matches!(result, Err(CustomFieldError::KeyExists(key)) if key == "budget"));
+    }
+
+    #[tokio::test]
+    async fn normalizes_and_validates_values() {
+        let db = test_support::test_db().await;
+        CustomFieldService::create(&db, 1, field("budget", CustomFieldType::Number, &[]))
+            .await
+            .unwrap();
+        CustomFieldService::create(&db, 1, field("visit", CustomFieldType::Date, &[]))
+            .await
+            .unwrap();
+        CustomFieldService::create(
+            &db,
+            1,
+            field("source", CustomFieldType::Select, &["转介绍", "广告"]),
+        )
+        .await
+        .unwrap();
+        CustomFieldService::create(
+            &db,
+            1,
+            field(
+                "goals",
+                CustomFieldType::MultiSelect,
+                &["减脂", "增肌", "塑形"],
+            ),
+        )
+        .await
+        .unwrap();
+        let group = group("团课");
+
+        let prepared = CustomFieldService::prepare_values(
+            &db,
+            1,
+            &group,
+            &values(json!({
+                "budget": "1200.0",
+                "visit": "2024-03-01",
+                "source": "广告",
+                "goals": ["塑形", "减脂", "塑形"],
+            })),
+            false,
+        )
+        .await
+        .unwrap();
+        let saved: Vec<Option<&str>> = prepared
+            .iter()
+            .map(|value| value.value.as_deref())
+            .collect();
+        // 按字段标识排序：budget、goals、source、visit
+        assert_eq!(
+            saved,
+            vec![
+                Some("1200"),
+                Some(r#"["减脂","塑形"]"#),
+                Some("广告"),
+                Some("2024-03-01")
+            ]
+        );
+
+        for invalid in [
+            json!({ "budget": "很多" }),
+            json!({ "visit": "2024/03/01" }),
+            json!({ "source": "电话" }),
+            json!({ "goals": ["跑步"] }),
+        ] {
+            let result =
+                CustomFieldService::prepare_values(&db, 1, &group, &values(invalid), false).await;
+            assert!(
+                matches!(result, Err(CustomFieldError::InvalidValue { .. })),
+                "{:?}",
+                result
+            );
+        }
+        let result = CustomFieldService::prepare_values(
+            &db,
+            1,
+            &group,
+            &values(json!({ "color": "red" })),
+            false,
+        )
+        .await;
+        assert!(matches!(result, Err(CustomFieldError::UnknownField(key)) if key == "color"));
+    }
+
+    #[tokio::test]
+    async fn required_and_group_specific_fields() {
+        let db = test_support::test_db().await;
+        let mut coach = field("coach", CustomFieldType::Text, &[]);
+        coach.customer_groups = vec![group("私教")];
+        coach.required = true;
+        CustomFieldService::create(&db, 1, coach).await.unwrap();
+
+        let private = group("私教");
+        let result =
+            CustomFieldService::prepare_values(&db, 1, &private, &BTreeMap::new(), true).await;
+        assert!(matches!(result, Err(CustomFieldError::Required(label)) if label == "coach"));
+        let result = CustomFieldService::prepare_values(
+            &db,
+            1,
+            &private,
+            &values(json!({ "coach": " " })),
+            true,
+        )
+        .await;
+        assert!(matches!(result, Err(CustomFieldError::Required(_))));
+        // 修改时只检查请求中出现的字段
+        let prepared =
+            CustomFieldService::prepare_values(&db, 1, &private, &BTreeMap::new(), false)
+                .await
+                .unwrap();
+        assert!(prepared.is_empty());
+
+        let other = group("团课");
+        CustomFieldService::prepare_values(&db, 1, &other, &BTreeMap::new(), true)
+            .await
+            .unwrap();
+        let result = CustomFieldService::prepare_values(
+            &db,
+            1,
+            &other,
+            &values(json!({ "coach": "王教练" })),
+            true,
+        )
+        .await;
+        assert!(matches!(result, Err(CustomFieldError::NotApplicable(_))));
+    }
+
+    #[tokio::test]
+    async fn filters_customers_by_field_values() {
+        let db = test_support::test_db().await;
+        let budget =
+            CustomFieldService::create(&db, 1, field("budget", CustomFieldType::Number, &[]))
+                .await
+                .unwrap();
+        CustomFieldService::create(
+            &db,
+            1,
+            field("goals", CustomFieldType::MultiSelect, &["减脂", "增肌"]),
+        )
+        .await
+        .unwrap();
+        let group = group("团课");
+        let mut customers = Vec::new();
+        for (name, value) in [
+            ("张三", json!({ "budget": 800, "goals": ["减脂"] })),
+            ("李四", json!({ "budget": 1500, "goals": ["减脂", "增肌"] })),
+            ("王五", json!({ "budget": 3000 })),
+        ] {
+            let customer = test_support::create_customer(&db, name, None, None).await;
+            let prepared =
+                CustomFieldService::prepare_values(&db, 1, &group, &values(value), false)
+                    .await
+                    .unwrap();
+            CustomFieldService::save_values(&db, customer.id, prepared)
+                .await
+                .unwrap();
+            customers.push(customer);
+        }
+
+        let matching = |query: Value| {
+            let db = &db;
+            async move {
+                let params: HashMap<String, String> = serde_json::from_value(query).unwrap();
+                let condition =
+                    CustomFieldService::filter_condition(db, 1, &FieldFilter::from_query(&params))
+                        .await
+                        .unwrap();
+                Customer::find()
+                    .filter(condition)
+                    .order_by_asc(customer::Column::Id)
+                    .all(db)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|customer| customer.name)
+                    .collect::<Vec<_>>()
+            }
+        };
+        assert_eq!(
+            matching(json!({ "field.budget.from": "1000" })).await,
+            vec!["李四", "王五"]
+        );
+        assert_eq!(
+            matching(json!({ "field.budget.from": "1000", "field.budget.to": "2000" })).await,
+            vec!["李四"]
+        );
+        assert_eq!(
+            matching(json!({ "field.goals": "减脂" })).await,
+            vec!["张三", "李四"]
+        );
+        assert_eq!(
+            matching(json!({ "field.goals": "增肌", "field.budget": "" })).await,
+            vec!["李四"]
+        );
+
+        let filters = [FieldFilter {
+            key: "goals".to_string(),
+            op: FilterOp::From,
+            value: "减脂".to_string(),
+        }];
+        let result = CustomFieldService::filter_condition(&db, 1, &filters).await;
+        assert!(matches!(
+            result,
+            Err(CustomFieldError::RangeNotSupported(_))
+        ));
+
+        // 清除值后不再出现在详情中
+        let cleared = vec![PreparedValue {
+            field_id: budget.id,
+            value: None,
+        }];
+        CustomFieldService::save_values(&db, customers[0].id, cleared)
+            .await
+            .unwrap();
+        let values = CustomFieldService::values_for(&db, &customers[0])
+            .await
+            .unwrap();
+        let values: Vec<(&str, &Value)> = values
+            .iter()
+            .map(|(field, value)| (field.field_key.as_str(), value))
+            .collect();
+        assert_eq!(
+            values,
+            vec![("budget", &Value::Null), ("goals", &json!(["减脂"]))]
+        );
+    }
+}
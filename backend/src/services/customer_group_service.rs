@@ -0,0 +1,547 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+
+use crate::entities::{
+    customer::{self, Entity as Customer},
+    customer_group::CustomerGroup,
+    customer_group_config::{self, Entity as CustomerGroupConfig},
+};
+
+/// 新建组织时带上的分组：名称、排序
+const DEFAULT_GROUPS: [(&str, i32); 4] = [("团课", 1), ("小班", 2), ("私教", 3), ("教培", 4)];
+
+/// 显示名称的最大长度
+const MAX_DISPLAY_NAME_LENGTH: usize = 50;
+
+#[derive(Debug, thiserror::Error)]
+pub enum CustomerGroupError {
+    #[error("分组 {0} 已存在")]
+    NameExists(String),
+    #[error("显示名称不能为空，且不能超过50个字符")]
+    InvalidDisplayName,
+    #[error("未知的客户分组: {0}")]
+    UnknownGroup(String),
+    #[error("分组 {0} 已归档，不能再分配给客户")]
+    Archived(String),
+    #[error("还有 {0} 位客户属于该分组，请先归档或调整这些客户的分组")]
+    InUse(u64),
+    #[error("组织没有可用的客户分组")]
+    NoGroups,
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+#[derive(Debug, Clone)]
+pub struct NewCustomerGroup {
+    pub name: CustomerGroup,
+    /// 为空时使用名称
+    pub display_name: Option<String>,
+    pub color: Option<String>,
+    pub sort_order: i32,
+}
+
+/// 名称创建后不能修改，客户上保存的是名称
+#[derive(Debug, Clone, Default)]
+pub struct CustomerGroupUpdate {
+    pub display_name: Option<String>,
+    /// 空字符串表示清除颜色
+    pub color: Option<String>,
+    pub sort_order: Option<i32>,
+    pub is_archived: Option<bool>,
+}
+
+pub struct CustomerGroupService;
+
+impl CustomerGroupService {
+    /// 组织的分组，按排序和创建顺序
+    pub async fn list(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        include_archived: bool,
+    ) -> Result<Vec<customer_group_config::Model>, DbErr> {
+        let mut query = CustomerGroupConfig::find()
+            .filter(customer_group_config::Column::OrganizationId.eq(organization_id));
+        if !include_archived {
+            query = query.filter(customer_group_config::Column::IsArchived.eq(false));
+        }
+
+        query
+            .order_by_asc(customer_group_config::Column::SortOrder)
+            .order_by_asc(customer_group_config::Column::Id)
+            .all(db)
+            .await
+    }
+
+    /// 组织内各分组未删除的客户数
+    pub async fn customer_counts(
+        db: &DatabaseConnection,
+        organization_id: i32,
+    ) -> Result<HashMap<CustomerGroup, i64>, DbErr> {
+        let counts = Customer::find()
+            .select_only()
+            .column(customer::Column::CustomerGroup)
+            .column_as(Expr::col(customer::Column::Id).count(), "count")
+            .filter(customer::Column::OrganizationId.eq(organization_id))
+            .filter(customer::Column::IsDeleted.eq(false))
+            .group_by(customer::Column::CustomerGroup)
+            .into_tuple::<(CustomerGroup, i64)>()
+            .all(db)
+            .await?;
+
+        Ok(counts.into_iter().collect())
+    }
+
+    pub async fn find(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        group_id: i32,
+    ) -> Result<Option<customer_group_config::Model>, DbErr> {
+        CustomerGroupConfig::find_by_id(group_id)
+            .filter(customer_group_config::Column::OrganizationId.eq(organization_id))
+            .one(db)
+            .await
+    }
+
+    pub async fn create(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        group: NewCustomerGroup,
+    ) -> Result<customer_group_config::Model, CustomerGroupError> {
+        if Self::find_by_name(db, organization_id, &group.name).await?.is_some() {
+            return Err(CustomerGroupError::NameExists(group.name.to_string()));
+        }
+        let display_name = match group.display_name {
+            Some(display_name) => checked_display_name(&display_name)?,
+            None => group.name.to_string(),
+        };
+
+        let now = Utc::now();
+        let group = customer_group_config::ActiveModel {
+            organization_id: Set(organization_id),
+            name: Set(group.name),
+            display_name: Set(display_name),
+            color: Set(group.color.filter(|color| !color.trim().is_empty())),
+            sort_order: Set(group.sort_order),
+            is_archived: Set(false),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(group)
+    }
+
+    pub async fn update(
+        db: &DatabaseConnection,
+        group: customer_group_config::Model,
+        update: CustomerGroupUpdate,
+    ) -> Result<customer_group_config::Model, CustomerGroupError> {
+        let mut active: customer_group_config::ActiveModel = group.into();
+
+        if let Some(display_name) = update.display_name {
+            active.display_name = Set(checked_display_name(&display_name)?);
+        }
+        if let Some(color) = update.color {
+            active.color = Set((!color.trim().is_empty()).then_some(color));
+        }
+        if let Some(sort_order) = update.sort_order {
+            active.sort_order = Set(sort_order);
+        }
+        if let Some(is_archived) = update.is_archived {
+            active.is_archived = Set(is_archived);
+        }
+        active.updated_at = Set(Utc::now());
+
+        Ok(active.update(db).await?)
+    }
+
+    /// 只能删除没有客户（包括回收站中的客户）的分组，其余的应当归档
+    pub async fn delete(
+        db: &DatabaseConnection,
+        group: &customer_group_config::Model,
+    ) -> Result<(), CustomerGroupError> {
+        let customers = Customer::find()
+            .filter(customer::Column::OrganizationId.eq(group.organization_id))
+            .filter(customer::Column::CustomerGroup.eq(group.name.clone()))
+            .count(db)
+            .await?;
+        if customers > 0 {
+            return Err(CustomerGroupError::InUse(customers));
+        }
+
+        CustomerGroupConfig::delete_by_id(group.id).exec(db).await?;
+        Ok(())
+    }
+
+    /// 检查分组可以分配给客户：存在且未归档
+    pub async fn ensure_assignable(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        customer_group: &CustomerGroup,
+    ) -> Result<(), CustomerGroupError> {
+        match Self::find_by_name(db, organization_id, customer_group).await? {
+            None => Err(CustomerGroupError::UnknownGroup(customer_group.to_string())),
+            Some(group) if group.is_archived => {
+                Err(CustomerGroupError::Archived(customer_group.to_string()))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// 检查分组都存在，归档的也可以
+    pub async fn ensure_known(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        customer_groups: &[CustomerGroup],
+    ) -> Result<(), CustomerGroupError> {
+        let known: Vec<CustomerGroup> = Self::list(db, organization_id, true)
+            .await?
+            .into_iter()
+            .map(|group| group.name)
+            .collect();
+        if let Some(unknown) = customer_groups.iter().find(|group| !known.contains(group)) {
+            return Err(CustomerGroupError::UnknownGroup(unknown.to_string()));
+        }
+        Ok(())
+    }
+
+    /// 新建客户未指定分组时使用排在最前的未归档分组
+    pub async fn default_group(
+        db: &DatabaseConnection,
+        organization_id: i32,
+    ) -> Result<CustomerGroup, CustomerGroupError> {
+        Self::list(db, organization_id, false)
+            .await?
+            .into_iter()
+            .next()
+            .map(|group| group.name)
+            .ok_or(CustomerGroupError::NoGroups)
+    }
+
+    /// 给新建的组织加上默认分组
+    pub async fn create_defaults<C: ConnectionTrait>(
+        db: &C,
+        organization_id: i32,
+    ) -> Result<(), DbErr> {
+        let now = Utc::now();
+        CustomerGroupConfig::insert_many(DEFAULT_GROUPS.iter().map(|(name, sort_order)| {
+            customer_group_config::ActiveModel {
+                organization_id: Set(organization_id),
+                name: Set(CustomerGroup(name.to_string())),
+                display_name: Set(name.to_string()),
+                color: Set(None),
+                sort_order: Set(*sort_order),
+                is_archived: Set(false),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+        }))
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+
+    async fn find_by_name(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        name: &CustomerGroup,
+    ) -> Result<Option<customer_group_config::Model>, DbErr> {
+        CustomerGroupConfig::find()
+            .filter(customer_group_config::Column::OrganizationId.eq(organization_id))
+            .filter(customer_group_config::Column::Name.eq(name.clone()))
+            .one(db)
+            .await
+    }
+}
+
+fn checked_display_name(display_name: &str) -> Result<String, CustomerGroupError> {
+    let display_name = display_name.trim();
+    if display_name.is_empty() || display_name.chars().count() > MAX_DISPLAY_NAME_LENGTH {
+        return Err(CustomerGroupError::InvalidDisplayName);
+    }
+    Ok(display_name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entities::user_role::UserRole,
+        services::{
+            customer_list_service::{CustomerListFilter, CustomerListService, CustomerSort},
+            permission_service::AccessScope,
+        },
+        test_support,
+    };
+
+    fn group(name: &str) -> CustomerGroup {
+        CustomerGroup(name.to_string())
+    }
+
+    fn new_group(name: &str, sort_order: i32) -> NewCustomerGroup {
+        NewCustomerGroup {
+            name: group(name),
+            display_name: None,
+            color: None,
+            sort_order,
+        }
+    }
+
+    async fn names(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        include_archived: bool,
+    ) -> Vec<String> {
+        CustomerGroupService::list(db, organization_id, include_archived)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|group| group.name.to_string())
+            .collect()
+    }
+
+    async fn assign(
+        db: &DatabaseConnection,
+        customer: customer::Model,
+        name: &str,
+    ) -> Result<(), CustomerGroupError> {
+        CustomerGroupService::ensure_assignable(db, customer.organization_id, &group(name)).await?;
+        let mut active: customer::ActiveModel = customer.into();
+        active.customer_group = Set(group(name));
+        active.update(db).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn creates_updates_and_deletes_groups() {
+        let db = test_support::test_db().await;
+        assert_eq!(
+            names(&db, 1, false).await,
+            vec!["团课", "小班", "私教", "教培"]
+        );
+
+        let created = CustomerGroupService::create(
+            &db,
+            1,
+            NewCustomerGroup {
+                display_name: Some("  线上课  ".to_string()),
+                color: Some(" ".to_string()),
+                ..new_group("线上", 0)
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(created.display_name, "线上课");
+        assert_eq!(created.color, None);
+        assert_eq!(names(&db, 1, false).await[0], "线上");
+        assert!(matches!(
+            CustomerGroupService::create(&db, 1, new_group("线上", 5)).await,
+            Err(CustomerGroupError::NameExists(_))
+        ));
+        assert!(matches!(
+            CustomerGroupService::create(
+                &db,
+                1,
+                NewCustomerGroup {
+                    display_name: Some(" ".to_string()),
+                    ..new_group("空名", 5)
+                },
+            )
+            .await,
+            Err(CustomerGroupError::InvalidDisplayName)
+        ));
+
+        let updated = CustomerGroupService::update(
+            &db,
+            created,
+            CustomerGroupUpdate {
+                display_name: Some("网课".to_string()),
+                color: Some("#ff0000".to_string()),
+                sort_order: Some(10),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.name, group("线上"));
+        assert_eq!(updated.display_name, "网课");
+        assert_eq!(updated.color.as_deref(), Some("#ff0000"));
+        assert_eq!(names(&db, 1, false).await.last().unwrap(), "线上");
+
+        let archived = CustomerGroupService::update(
+            &db,
+            updated,
+            CustomerGroupUpdate {
+                color: Some(String::new()),
+                is_archived: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(archived.color, None);
+        assert!(!names(&db, 1, false).await.contains(&"线上".to_string()));
+        assert!(names(&db, 1, true).await.contains(&"线上".to_string()));
+
+        CustomerGroupService::delete(&db, &archived).await.unwrap();
+        assert!(CustomerGroupService::find(&db, 1, archived.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn groups_in_use_are_archived_instead_of_deleted() {
+        let db = test_support::test_db().await;
+        let owner = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let online = CustomerGroupService::create(&db, 1, new_group("线上", 5))
+            .await
+            .unwrap();
+        let customer = test_support::create_customer(&db, "张三", None, Some(owner.id)).await;
+        assign(&db, customer.clone(), "线上").await.unwrap();
+
+        // 回收站中的客户也算
+        let mut deleted: customer::ActiveModel = customer.into();
+        deleted.is_deleted = Set(true);
+        deleted.update(&db).await.unwrap();
+        assert!(matches!(
+            CustomerGroupService::delete(&db, &online).await,
+            Err(CustomerGroupError::InUse(1))
+        ));
+
+        let archived = CustomerGroupService::update(
+            &db,
+            online,
+            CustomerGroupUpdate {
+                is_archived: Some(true),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let other = test_support::create_customer(&db, "李四", None, Some(owner.id)).await;
+        assert!(matches!(
+            assign(&db, other, "线上").await,
+            Err(CustomerGroupError::Archived(_))
+        ));
+        // 归档的分组仍然可以用于筛选和自定义字段
+        CustomerGroupService::ensure_known(&db, 1, std::slice::from_ref(&archived.name))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn customers_are_assigned_and_filtered_by_group() {
+        let db = test_support::test_db().await;
+        let owner = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        CustomerGroupService::create(&db, 1, new_group("线上", 5))
+            .await
+            .unwrap();
+        let zhang = test_support::create_customer(&db, "张三", None, Some(owner.id)).await;
+        let li = test_support::create_customer(&db, "李四", None, Some(owner.id)).await;
+        test_support::create_customer(&db, "王五", None, Some(owner.id)).await;
+        assign(&db, zhang.clone(), "线上").await.unwrap();
+        assign(&db, li.clone(), "私教").await.unwrap();
+        assert!(matches!(
+            assign(&db, li.clone(), "不存在").await,
+            Err(CustomerGroupError::UnknownGroup(_))
+        ));
+
+        let filter = CustomerListFilter {
+            customer_group: Some(group("线上")),
+            ..Default::default()
+        };
+        let (rows, total) = CustomerListService::list(
+            &db,
+            &AccessScope::organization(1),
+            &filter,
+            CustomerSort::default(),
+            1,
+            20,
+        )
+        .await
+        .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(rows[0].id, zhang.id);
+
+        let counts = CustomerGroupService::customer_counts(&db, 1).await.unwrap();
+        assert_eq!(counts.get(&group("线上")), Some(&1));
+        assert_eq!(counts.get(&group("私教")), Some(&1));
+        assert_eq!(counts.get(&group("团课")), Some(&1));
+        assert_eq!(counts.get(&group("小班")), None);
+        assert!(matches!(
+            CustomerGroupService::ensure_known(&db, 1, &[group("线上"), group("不存在")]).await,
+            Err(CustomerGroupError::UnknownGroup(name)) if name == "不存在"
+        ));
+    }
+
+    #[tokio::test]
+    async fn groups_are_scoped_to_the_organization() {
+        let db = test_support::test_db().await;
+        let other = test_support::create_organization(&db, "其他机构").await;
+        let online = CustomerGroupService::create(&db, 1, new_group("线上", 5))
+            .await
+            .unwrap();
+
+        // 其他组织看不到、也不能分配本组织的分组，但可以创建同名分组
+        assert!(CustomerGroupService::find(&db, other.id, online.id)
+            .await
+            .unwrap()
+            .is_none());
+        assert!(!names(&db, other.id, true)
+            .await
+            .contains(&"线上".to_string()));
+        assert!(matches!(
+            CustomerGroupService::ensure_assignable(&db, other.id, &group("线上")).await,
+            Err(CustomerGroupError::UnknownGroup(_))
+        ));
+        CustomerGroupService::create(&db, other.id, new_group("线上", 5))
+            .await
+            .unwrap();
+
+        // 客户数只统计本组织的客户
+        let owner = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        test_support::create_customer(&db, "张三", None, Some(owner.id)).await;
+        test_support::create_customer_in(&db, "李四", None, None, other.id).await;
+        test_support::create_customer_in(&db, "王五", None, None, other.id).await;
+        let counts = CustomerGroupService::customer_counts(&db, 1).await.unwrap();
+        assert_eq!(counts.get(&group("团课")), Some(&1));
+        let counts = CustomerGroupService::customer_counts(&db, other.id)
+            .await
+            .unwrap();
+        assert_eq!(counts.get(&group("团课")), Some(&2));
+
+        // 没有可用分组时不能新建客户
+        for group in CustomerGroupService::list(&db, other.id, false)
+            .await
+            .unwrap()
+        {
+            CustomerGroupService::update(
+                &db,
+                group,
+                CustomerGroupUpdate {
+                    is_archived: Some(true),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        }
+        assert!(matches!(
+            CustomerGroupService::default_group(&db, other.id).await,
+            Err(CustomerGroupError::NoGroups)
+        ));
+        assert_eq!(
+            CustomerGroupService::default_group(&db, 1).await.unwrap(),
+            group("团课")
+        );
+    }
+}
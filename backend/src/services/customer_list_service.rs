@@ -0,0 +1,885 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use sea_orm::{
+    sea_query::{Alias, Expr, Func, IntoCondition, NullOrdering, Query, SimpleExpr},
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, JoinType,
+    Order, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Select,
+};
+
+use crate::{
+    entities::{
+        customer::{self, Entity as Customer},
+        customer_group::CustomerGroup,
+        customer_track::{self, Entity as CustomerTrack},
+        next_action::NextAction,
+    },
+    services::{
+        permission_service::AccessScope, search_service::SearchService, tag_service::TagService,
+    },
+};
+
+/// 列表中关联的最新跟进记录的别名
+const LATEST_TRACK: &str = "latest_track";
+
+#[derive(Debug, thiserror::Error)]
+pub enum CustomerListError {
+    #[error(
+        "未知的排序字段: {0}，可选值: name, rate, created_at, updated_at, latest_track_time, \
+         next_track_time"
+    )]
+    UnknownSortField(String),
+    #[error("未知的排序方向: {0}，可选值: asc, desc")]
+    UnknownSortOrder(String),
+    #[error("{0} 的日期格式应为 YYYY-MM-DD")]
+    InvalidDate(&'static str),
+    #[error("{0} 不能大于 {1}")]
+    InvalidRange(&'static str, &'static str),
+    #[error("{0} 超出日期范围")]
+    DateOutOfRange(&'static str),
+    #[error("页码超出范围")]
+    PageOutOfRange,
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 列表的排序字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CustomerSortField {
+    /// 名称按全拼排序，中文名称也能按字母顺序排列
+    Name,
+    Rate,
+    CreatedAt,
+    #[default]
+    UpdatedAt,
+    /// 最新一次跟进的时间
+    LatestTrackTime,
+    /// 最新一次跟进约定的下次跟进时间
+    NextTrackTime,
+}
+
+impl FromStr for CustomerSortField {
+    type Err = CustomerListError;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "name" => Ok(Self::Name),
+            "rate" => Ok(Self::Rate),
+            "created_at" => Ok(Self::CreatedAt),
+            "updated_at" => Ok(Self::UpdatedAt),
+            "latest_track_time" => Ok(Self::LatestTrackTime),
+            "next_track_time" => Ok(Self::NextTrackTime),
+            _ => Err(CustomerListError::UnknownSortField(value.to_string())),
+        }
+    }
+}
+
+/// 排序字段和方向，没有跟进记录等值为空的客户总是排在最后
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CustomerSort {
+    pub field: CustomerSortField,
+    pub ascending: bool,
+}
+
+impl Default for CustomerSort {
+    fn default() -> Self {
+        Self::new(CustomerSortField::default())
+    }
+}
+
+impl CustomerSort {
+    /// 按字段的默认方向排序：名称和下次跟进时间从小到大，其余从大到小
+    pub fn new(field: CustomerSortField) -> Self {
+        Self {
+            field,
+            ascending: matches!(field, CustomerSortField::Name | CustomerSortField::NextTrackTime),
+        }
+    }
+
+    /// 解析查询参数中的排序字段和方向（asc / desc），都可以省略
+    pub fn parse(field: Option<&str>, order: Option<&str>) -> Result<Self, CustomerListError> {
+        let mut sort = Self::new(field.map(str::parse).transpose()?.unwrap_or_default());
+        match order {
+            None => {}
+            Some("asc") => sort.ascending = true,
+            Some("desc") => sort.ascending = false,
+            Some(order) => return Err(CustomerListError::UnknownSortOrder(order.to_string())),
+        }
+        Ok(sort)
+    }
+
+    fn order(self) -> Order {
+        if self.ascending { Order::Asc } else { Order::Desc }
+    }
+}
+
+/// 解析 YYYY-MM-DD 格式的日期参数
+pub fn parse_date(param: &'static str, value: &str) -> Result<NaiveDate, CustomerListError> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| CustomerListError::InvalidDate(param))
+}
+
+/// 客户列表的筛选条件
+#[derive(Debug, Clone)]
+pub struct CustomerListFilter {
+    /// 名称（包括全拼和简拼）或电话
+    pub search: Option<String>,
+    /// 最新跟进的下一步动作，没有跟进的客户不会出现
+    pub status: Option<NextAction>,
+    pub customer_group: Option<CustomerGroup>,
+    pub stage_id: Option<i32>,
+    /// 有其中任意一个标签
+    pub any_tags: Vec<i32>,
+    /// 有全部标签
+    pub all_tags: Vec<i32>,
+    /// 自定义字段的筛选条件，见 `CustomFieldService::filter_condition`
+    pub field_condition: Condition,
+    /// 评分范围，包含两端
+    pub rate_min: Option<f32>,
+    pub rate_max: Option<f32>,
+    /// 创建日期范围（UTC），包含两端
+    pub created_from: Option<NaiveDate>,
+    pub created_to: Option<NaiveDate>,
+    /// 只看逾期的客户：最新跟进为继续跟进，约定的下次跟进时间已过
+    pub overdue: bool,
+    /// 是否有跟进记录
+    pub has_tracks: Option<bool>,
+    /// 地址中包含
+    pub address: Option<String>,
+    /// 只看公海中没有负责人的客户
+    pub in_pool: bool,
+}
+
+impl Default for CustomerListFilter {
+    fn default() -> Self {
+        Self {
+            search: None,
+            status: None,
+            customer_group: None,
+            stage_id: None,
+            any_tags: Vec::new(),
+            all_tags: Vec::new(),
+            field_condition: Condition::all(),
+            rate_min: None,
+            rate_max: None,
+            created_from: None,
+            created_to: None,
+            overdue: false,
+            has_tracks: None,
+            address: None,
+            in_pool: false,
+        }
+    }
+}
+
+impl CustomerListFilter {
+    /// 检查范围的两端是否颠倒
+    pub fn validate(&self) -> Result<(), CustomerListError> {
+        if let (Some(min), Some(max)) = (self.rate_min, self.rate_max)
+            && min > max
+        {
+            return Err(CustomerListError::InvalidRange("rate_min", "rate_max"));
+        }
+        if let (Some(from), Some(to)) = (self.created_from, self.created_to)
+            && from > to
+        {
+            return Err(CustomerListError::InvalidRange("created_from", "created_to"));
+        }
+        Ok(())
+    }
+}
+
+/// 列表中的一行：客户和它的最新跟进、跟进次数
+#[derive(Debug, Clone, FromQueryResult)]
+pub struct CustomerListRow {
+    pub id: i32,
+    pub name: String,
+    pub phone: Option<String>,
+    pub address: Option<String>,
+    pub notes: Option<String>,
+    pub rate: f32,
+    pub customer_group: CustomerGroup,
+    pub user_id: Option<i32>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub is_deleted: bool,
+    pub stage_id: Option<i32>,
+    pub latest_track_time: Option<DateTime<Utc>>,
+    pub latest_next_action: Option<NextAction>,
+    pub latest_content: Option<String>,
+    pub track_count: i64,
+}
+
+pub struct CustomerListService;
+
+impl CustomerListService {
+    /// 一页客户和符合条件的客户总数。筛选、计数和分页都在数据库中完成：
+    /// 最新跟进通过按客户取最新一条的子查询关联，跟进次数由子查询统计
+    pub async fn list(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        filter: &CustomerListFilter,
+        sort: CustomerSort,
+        page: u64,
+        limit: u64,
+    ) -> Result<(Vec<CustomerListRow>, u64), CustomerListError> {
+        let offset = page_offset(page, limit)?;
+        let query = filtered(scope, filter)?;
+        let total = query.clone().count(db).await?;
+
+        let latest = Alias::new(LATEST_TRACK);
+        let rows = query
+            .join_as(
+                JoinType::LeftJoin,
+                customer::Relation::CustomerTrack.def().on_condition(|_, right| {
+                    Expr::col((right, customer_track::Column::Id))
+                        .eq(latest_track(customer_track::Column::Id))
+                        .into_condition()
+                }),
+                latest.clone(),
+            )
+            .column_as(
+                Expr::col((latest.clone(), customer_track::Column::TrackTime)),
+                "latest_track_time",
+            )
+            .column_as(
+                Expr::col((latest.clone(), customer_track::Column::NextAction)),
+                "latest_next_action",
+            )
+            .column_as(Expr::col((latest, customer_track::Column::Content)), "latest_content")
+            .column_as(track_count(), "track_count")
+            .order_by_with_nulls(sort_expr(sort.field), sort.order(), NullOrdering::Last)
+            .order_by(customer::Column::Id, sort.order())
+            .limit(limit)
+            .offset(offset)
+            .into_model::<CustomerListRow>()
+            .all(db)
+            .await?;
+
+        Ok((rows, total))
+    }
+}
+
+/// 符合条件的客户。计数时不需要关联跟进记录，按最新跟进筛选也使用子查询
+fn filtered(
+    scope: &AccessScope,
+    filter: &CustomerListFilter,
+) -> Result<Select<Customer>, CustomerListError> {
+    let mut query = Customer::find()
+        .filter(scope.visible_condition())
+        .filter(customer::Column::IsDeleted.eq(false))
+        .filter(filter.field_condition.clone());
+
+    if let Some(search) = &filter.search {
+        query = query.filter(SearchService::name_or_phone_condition(search));
+    }
+    if let Some(status) = &filter.status {
+        query = query.filter(
+            Expr::expr(latest_track(customer_track::Column::NextAction)).eq(status.clone()),
+        );
+    }
+    if let Some(customer_group) = &filter.customer_group {
+        query = query.filter(customer::Column::CustomerGroup.eq(customer_group.clone()));
+    }
+    if let Some(stage_id) = filter.stage_id {
+        query = query.filter(customer::Column::StageId.eq(stage_id));
+    }
+    if !filter.any_tags.is_empty() {
+        query = query.filter(TagService::any_of_condition(&filter.any_tags));
+    }
+    if !filter.all_tags.is_empty() {
+        query = query.filter(TagService::all_of_condition(&filter.all_tags));
+    }
+    if let Some(rate_min) = filter.rate_min {
+        query = query.filter(customer::Column::Rate.gte(rate_min));
+    }
+    if let Some(rate_max) = filter.rate_max {
+        query = query.filter(customer::Column::Rate.lte(rate_max));
+    }
+    let created_at = || datetime(Expr::col((Customer, customer::Column::CreatedAt)));
+    if let Some(created_from) = filter.created_from {
+        query = query.filter(Expr::expr(created_at()).gte(datetime(start_of_day(created_from))));
+    }
+    if let Some(created_to) = filter.created_to {
+        let next_day = created_to
+            .checked_add_days(Days::new(1))
+            .ok_or(CustomerListError::DateOutOfRange("created_to"))?;
+        query = query.filter(Expr::expr(created_at()).lt(datetime(start_of_day(next_day))));
+    }
+    if filter.overdue {
+        let next_action = latest_track(customer_track::Column::NextAction);
+        let next_track_time = datetime(latest_track(customer_track::Column::NextTrackTime));
+        query = query
+            .filter(Expr::expr(next_action).eq(NextAction::Continue))
+            .filter(Expr::expr(next_track_time).lt(datetime(Utc::now())));
+    }
+    if let Some(has_tracks) = filter.has_tracks {
+        let tracks = Query::select()
+            .expr(Expr::val(1))
+            .from(CustomerTrack)
+            .and_where(
+                Expr::col((CustomerTrack, customer_track::Column::CustomerId))
+                    .equals((Customer, customer::Column::Id)),
+            )
+            .to_owned();
+        let exists = Expr::exists(tracks);
+        query = query.filter(if has_tracks { exists } else { exists.not() });
+    }
+    if let Some(address) = &filter.address {
+        query = query.filter(customer::Column::Address.contains(address.trim()));
+    }
+    if filter.in_pool {
+        query = query.filter(customer::Column::UserId.is_null());
+    }
+    Ok(query)
+}
+
+/// 第 `page` 页（从 1 开始）跳过的行数，超出 SQLite 整数范围时报错
+fn page_offset(page: u64, limit: u64) -> Result<u64, CustomerListError> {
+    page.checked_sub(1)
+        .and_then(|skipped| skipped.checked_mul(limit))
+        .filter(|offset| i64::try_from(*offset).is_ok())
+        .ok_or(CustomerListError::PageOutOfRange)
+}
+
+fn start_of_day(date: NaiveDate) -> DateTime<Utc> {
+    date.and_time(chrono::NaiveTime::MIN).and_utc()
+}
+
+/// 时间有 `CURRENT_TIMESTAMP` 和 RFC 3339 两种存储格式，不能直接按文本比较和排序，
+/// 统一经 `datetime()` 转换。迁移 031 和 032 为客户和跟进记录的时间建立了相应的表达式索引
+fn datetime(value: impl Into<SimpleExpr>) -> SimpleExpr {
+    Func::cust(Alias::new("datetime")).arg(value).into()
+}
+
+/// 排序字段对应的表达式，跟进相关的字段取自关联的最新跟进记录
+fn sort_expr(field: CustomerSortField) -> SimpleExpr {
+    let latest = Alias::new(LATEST_TRACK);
+    match field {
+        CustomerSortField::Name => Expr::col((Customer, customer::Column::NamePinyin)).into(),
+        CustomerSortField::Rate => Expr::col((Customer, customer::Column::Rate)).into(),
+        CustomerSortField::CreatedAt => datetime(Expr::col((Customer, customer::Column::CreatedAt))),
+        CustomerSortField::UpdatedAt => datetime(Expr::col((Customer, customer::Column::UpdatedAt))),
+        CustomerSortField::LatestTrackTime => {
+            datetime(Expr::col((latest, customer_track::Column::TrackTime)))
+        }
+        CustomerSortField::NextTrackTime => {
+            datetime(Expr::col((latest, customer_track::Column::NextTrackTime)))
+        }
+    }
+}
+
+/// 客户最新一条跟进记录的某一列，没有跟进时为 NULL。
+/// 使用 customer_tracks(customer_id, datetime(track_time), id) 索引
+fn latest_track(column: customer_track::Column) -> SimpleExpr {
+    let track = Alias::new("t");
+    let query = Query::select()
+        .column((track.clone(), column))
+        .from_as(CustomerTrack, track.clone())
+        .and_where(
+            Expr::col((track.clone(), customer_track::Column::CustomerId))
+                .equals((Customer, customer::Column::Id)),
+        )
+        .order_by_expr(
+            datetime(Expr::col((track.clone(), customer_track::Column::TrackTime))),
+            Order::Desc,
+        )
+        .order_by((track, customer_track::Column::Id), Order::Desc)
+        .limit(1)
+        .to_owned();
+    SimpleExpr::SubQuery(None, Box::new(query.into_sub_query_statement()))
+}
+
+/// 客户的跟进次数
+fn track_count() -> SimpleExpr {
+    let track = Alias::new("t");
+    let query = Query::select()
+        .expr(Expr::col((track.clone(), customer_track::Column::Id)).count())
+        .from_as(CustomerTrack, track.clone())
+        .and_where(
+            Expr::col((track, customer_track::Column::CustomerId))
+                .equals((Customer, customer::Column::Id)),
+        )
+        .to_owned();
+    SimpleExpr::SubQuery(None, Box::new(query.into_sub_query_statement()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+    use sea_orm::{ActiveModelTrait, ConnectionTrait, Set};
+
+    use super::*;
+    use crate::{
+        entities::{
+            customer_collaborator::{self, CollaboratorPermission},
+            user_role::UserRole,
+        },
+        test_support,
+    };
+
+    /// 改写前的列表做法：取出全部客户，逐个查询最新跟进和跟进次数，筛选后在内存中分页
+    async fn legacy_list(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        filter: &CustomerListFilter,
+        page: u64,
+        limit: u64,
+    ) -> (Vec<Summary>, u64) {
+        let mut query = Customer::find()
+            .filter(scope.visible_condition())
+            .filter(customer::Column::IsDeleted.eq(false));
+        if let Some(search) = &filter.search {
+            query = query.filter(SearchService::name_or_phone_condition(search));
+        }
+        let customers = query
+            .order_by_desc(customer::Column::UpdatedAt)
+            .all(db)
+            .await
+            .unwrap();
+
+        let mut rows = Vec::new();
+        for customer in customers {
+            let latest_track = CustomerTrack::find()
+                .filter(customer_track::Column::CustomerId.eq(customer.id))
+                .order_by_desc(customer_track::Column::TrackTime)
+                .one(db)
+                .await
+                .unwrap();
+            if let Some(status) = &filter.status
+                && latest_track
+                    .as_ref()
+                    .is_none_or(|track| track.next_action != *status)
+            {
+                continue;
+            }
+            if let Some(customer_group) = &filter.customer_group
+                && customer.customer_group != *customer_group
+            {
+                continue;
+            }
+            let track_count = CustomerTrack::find()
+                .filter(customer_track::Column::CustomerId.eq(customer.id))
+                .count(db)
+                .await
+                .unwrap();
+            rows.push(Summary {
+                id: customer.id,
+                latest_track_time: latest_track.as_ref().map(|track| track.track_time),
+                latest_next_action: latest_track.as_ref().map(|track| track.next_action.clone()),
+                latest_content: latest_track.map(|track| track.content),
+                track_count: track_count as i64,
+            });
+        }
+
+        let total = rows.len() as u64;
+        let skip = ((page - 1) * limit) as usize;
+        (
+            rows.into_iter().skip(skip).take(limit as usize).collect(),
+            total,
+        )
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Summary {
+        id: i32,
+        latest_track_time: Option<DateTime<Utc>>,
+        latest_next_action: Option<NextAction>,
+        latest_content: Option<String>,
+        track_count: i64,
+    }
+
+    impl From<CustomerListRow> for Summary {
+        fn from(row: CustomerListRow) -> Self {
+            Self {
+                id: row.id,
+                latest_track_time: row.latest_track_time,
+                latest_next_action: row.latest_next_action,
+                latest_content: row.latest_content,
+                track_count: row.track_count,
+            }
+        }
+    }
+
+    #[test]
+    fn page_offsets_are_checked() {
+        assert_eq!(page_offset(1, 20).unwrap(), 0);
+        assert_eq!(page_offset(3, 20).unwrap(), 40);
+        assert!(matches!(
+            page_offset(0, 20),
+            Err(CustomerListError::PageOutOfRange)
+        ));
+        assert!(matches!(
+            page_offset(u64::MAX, 100),
+            Err(CustomerListError::PageOutOfRange)
+        ));
+        // 超出 SQLite 整数范围的偏移量同样拒绝
+        assert!(matches!(
+            page_offset(i64::MAX as u64 / 10 + 2, 10),
+            Err(CustomerListError::PageOutOfRange)
+        ));
+    }
+
+    #[tokio::test]
+    async fn matches_the_per_customer_queries_it_replaced() {
+        let db = test_support::test_db().await;
+        let admin = test_support::create_user(&db, "admin", UserRole::Admin).await;
+        let manager = test_support::create_user(&db, "manager", UserRole::Manager).await;
+        let alice =
+            test_support::create_user_in(&db, "alice", UserRole::Sales, 1, Some(manager.id)).await;
+        let bob =
+            test_support::create_user_in(&db, "bob", UserRole::Sales, 1, Some(manager.id)).await;
+        let carol = test_support::create_user(&db, "carol", UserRole::Sales).await;
+
+        // 负责人轮流为 alice、bob、carol 和公海；部分客户为私教分组、在回收站中，
+        // 跟进次数为 0 到 3，最新跟进有继续和结束两种
+        let owners = [Some(alice.id), Some(bob.id), Some(carol.id), None];
+        let base = Utc::now() - Duration::days(30);
+        let mut customers = Vec::new();
+        for i in 0..16usize {
+            let name = format!("客户{}", i);
+            let customer =
+                test_support::create_customer(&db, &name, None, owners[i % owners.len()]).await;
+            for n in 0..i % 4 {
+                let time = base + Duration::hours((i * 5 + n) as i64);
+                let track = test_support::create_track(&db, customer.id, None, time).await;
+                let mut track: customer_track::ActiveModel = track.into();
+                track.content = Set(format!("{}-{}", name, n));
+                if (i + n) % 3 == 0 {
+                    track.next_action = Set(NextAction::End);
+                }
+                track.update(&db).await.unwrap();
+            }
+            let mut active: customer::ActiveModel = customer.into();
+            // 更新时间和创建顺序不同，且互不相同
+            active.updated_at = Set(base + Duration::minutes(((i * 7) % 16) as i64));
+            if i % 3 == 0 {
+                active.customer_group = Set(CustomerGroup("私教".to_string()));
+            }
+            if i % 5 == 4 {
+                active.is_deleted = Set(true);
+            }
+            customers.push(active.update(&db).await.unwrap());
+        }
+        // carol 协作 alice 的一个客户和回收站中的一个客户，alice 协作 bob 的一个客户
+        for (customer, user_id) in [(0, carol.id), (4, carol.id), (1, alice.id)] {
+            let now = Utc::now();
+            customer_collaborator::ActiveModel {
+                customer_id: Set(customers[customer].id),
+                user_id: Set(user_id),
+                permission: Set(CollaboratorPermission::Read),
+                added_by: Set(None),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&db)
+            .await
+            .unwrap();
+        }
+
+        let filters = [
+            CustomerListFilter::default(),
+            CustomerListFilter {
+                status: Some(NextAction::Continue),
+                ..Default::default()
+            },
+            CustomerListFilter {
+                status: Some(NextAction::End),
+                ..Default::default()
+            },
+            CustomerListFilter {
+                customer_group: Some(CustomerGroup("私教".to_string())),
+                ..Default::default()
+            },
+            CustomerListFilter {
+                search: Some("客户1".to_string()),
+                ..Default::default()
+            },
+        ];
+        for user in [&admin, &manager, &alice, &bob, &carol] {
+            let (scope, _) = test_support::scope_of(&db, user).await;
+            for filter in &filters {
+                for page in 1..=4 {
+                    let expected = legacy_list(&db, &scope, filter, page, 3).await;
+                    let (rows, total) = CustomerListService::list(
+                        &db,
+                        &scope,
+                        filter,
+                        CustomerSort::default(),
+                        page,
+                        3,
+                    )
+                    .await
+                    .unwrap();
+                    let rows: Vec<Summary> = rows.into_iter().map(Summary::from).collect();
+                    assert_eq!(
+                        (rows, total),
+                        expected,
+                        "{} 第{}页 {:?}",
+                        user.username,
+                        page,
+                        filter
+                    );
+                }
+            }
+        }
+
+        // 协作的客户可见，回收站中的不可见；公海中的客户只有管理员能看到
+        let (scope, _) = test_support::scope_of(&db, &carol).await;
+        let filter = CustomerListFilter::default();
+        let (rows, _) =
+            CustomerListService::list(&db, &scope, &filter, CustomerSort::default(), 1, 20)
+                .await
+                .unwrap();
+        let ids: Vec<i32> = rows.iter().map(|row| row.id).collect();
+        assert!(ids.contains(&customers[0].id));
+        assert!(!ids.contains(&customers[4].id));
+        let (scope, _) = test_support::scope_of(&db, &admin).await;
+        let (_, total) =
+            CustomerListService::list(&db, &scope, &filter, CustomerSort::default(), 1, 20)
+                .await
+                .unwrap();
+        assert_eq!(total, 13);
+    }
+
+    async fn ids(
+        db: &DatabaseConnection,
+        filter: &CustomerListFilter,
+        sort: CustomerSort,
+    ) -> Vec<i32> {
+        let scope = AccessScope::organization(1);
+        let (rows, total) = CustomerListService::list(db, &scope, filter, sort, 1, 20)
+            .await
+            .unwrap();
+        assert_eq!(total, rows.len() as u64);
+        rows.into_iter().map(|row| row.id).collect()
+    }
+
+    async fn set_created_at(db: &DatabaseConnection, customer_id: i32, created_at: &str) {
+        db.execute_unprepared(&format!(
+            "UPDATE customers SET created_at = '{created_at}' WHERE id = {customer_id}"
+        ))
+        .await
+        .unwrap();
+    }
+
+    #[test]
+    fn sorts_and_ranges_are_validated() {
+        let sort = CustomerSort::parse(None, None).unwrap();
+        assert_eq!(
+            sort,
+            CustomerSort {
+                field: CustomerSortField::UpdatedAt,
+                ascending: false
+            }
+        );
+        let sort = CustomerSort::parse(Some("next_track_time"), None).unwrap();
+        assert!(sort.ascending);
+        let sort = CustomerSort::parse(Some("name"), Some("desc")).unwrap();
+        assert_eq!(
+            sort,
+            CustomerSort {
+                field: CustomerSortField::Name,
+                ascending: false
+            }
+        );
+        assert!(matches!(
+            CustomerSort::parse(Some("phone"), None),
+            Err(CustomerListError::UnknownSortField(_))
+        ));
+        assert!(matches!(
+            CustomerSort::parse(None, Some("up")),
+            Err(CustomerListError::UnknownSortOrder(_))
+        ));
+
+        assert!(matches!(
+            parse_date("created_from", "2025-2-30"),
+            Err(CustomerListError::InvalidDate(_))
+        ));
+        let filter = CustomerListFilter {
+            rate_min: Some(4.0),
+            rate_max: Some(3.0),
+            ..Default::default()
+        };
+        assert!(matches!(
+            filter.validate(),
+            Err(CustomerListError::InvalidRange("rate_min", "rate_max"))
+        ));
+        let filter = CustomerListFilter {
+            created_from: Some(parse_date("created_from", "2025-03-02").unwrap()),
+            created_to: Some(parse_date("created_to", "2025-03-01").unwrap()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            filter.validate(),
+            Err(CustomerListError::InvalidRange(_, _))
+        ));
+    }
+
+    #[tokio::test]
+    async fn dates_are_compared_across_storage_formats() {
+        let db = test_support::test_db().await;
+        let early = test_support::create_customer(&db, "张三", None, None).await;
+        let late = test_support::create_customer(&db, "李四", None, None).await;
+        let next_day = test_support::create_customer(&db, "王五", None, None).await;
+        // 按文本比较时空格排在 T 之前，中午的客户会排在早上的客户之前
+        set_created_at(&db, early.id, "2025-03-01T08:00:00+00:00").await;
+        set_created_at(&db, late.id, "2025-03-01 12:00:00").await;
+        set_created_at(&db, next_day.id, "2025-03-02 00:00:00").await;
+
+        let on = |from: &str, to: &str| CustomerListFilter {
+            created_from: Some(parse_date("created_from", from).unwrap()),
+            created_to: Some(parse_date("created_to", to).unwrap()),
+            ..Default::default()
+        };
+        let sort = CustomerSort {
+            field: CustomerSortField::CreatedAt,
+            ascending: true,
+        };
+        assert_eq!(
+            ids(&db, &on("2025-03-01", "2025-03-01"), sort).await,
+            vec![early.id, late.id]
+        );
+        assert_eq!(
+            ids(&db, &on("2025-03-02", "2025-03-02"), sort).await,
+            vec![next_day.id]
+        );
+        let sort = CustomerSort {
+            field: CustomerSortField::CreatedAt,
+            ascending: false,
+        };
+        let all = CustomerListFilter::default();
+        assert_eq!(
+            ids(&db, &all, sort).await,
+            vec![next_day.id, late.id, early.id]
+        );
+
+        let filter = CustomerListFilter {
+            created_to: Some(NaiveDate::MAX),
+            ..Default::default()
+        };
+        let scope = AccessScope::organization(1);
+        assert!(matches!(
+            CustomerListService::list(&db, &scope, &filter, CustomerSort::default(), 1, 20).await,
+            Err(CustomerListError::DateOutOfRange("created_to"))
+        ));
+    }
+
+    #[tokio::test]
+    async fn filters_and_sorts_by_the_latest_track() {
+        let db = test_support::test_db().await;
+        let now = Utc::now();
+        let overdue = test_support::create_customer(&db, "张三", None, None).await;
+        let upcoming = test_support::create_customer(&db, "李四", None, None).await;
+        let ended = test_support::create_customer(&db, "王五", None, None).await;
+        let untracked = test_support::create_customer(&db, "赵六", None, None).await;
+        for (customer, next_track_time, next_action) in [
+            (&overdue, now - Duration::days(1), NextAction::Continue),
+            (&upcoming, now + Duration::days(1), NextAction::Continue),
+            (&ended, now - Duration::days(2), NextAction::End),
+        ] {
+            // 较早的一条逾期跟进不影响结果，只看最新一条
+            let older =
+                test_support::create_track(&db, customer.id, None, now - Duration::days(10)).await;
+            let mut older: customer_track::ActiveModel = older.into();
+            older.next_track_time = Set(Some(now - Duration::days(9)));
+            older.update(&db).await.unwrap();
+
+            let track_time = now - Duration::hours(customer.id as i64);
+            let latest = test_support::create_track(&db, customer.id, None, track_time).await;
+            let mut latest: customer_track::ActiveModel = latest.into();
+            latest.next_track_time = Set(Some(next_track_time));
+            latest.next_action = Set(next_action);
+            latest.update(&db).await.unwrap();
+        }
+        let mut active: customer::ActiveModel = untracked.clone().into();
+        active.rate = Set(4.5);
+        active.address = Set(Some("上海市徐汇区".to_string()));
+        active.update(&db).await.unwrap();
+
+        let sort = CustomerSort::default();
+        let filter = CustomerListFilter {
+            overdue: true,
+            ..Default::default()
+        };
+        assert_eq!(ids(&db, &filter, sort).await, vec![overdue.id]);
+        let filter = CustomerListFilter {
+            has_tracks: Some(false),
+            ..Default::default()
+        };
+        assert_eq!(ids(&db, &filter, sort).await, vec![untracked.id]);
+        let filter = CustomerListFilter {
+            has_tracks: Some(true),
+            status: Some(NextAction::End),
+            ..Default::default()
+        };
+        assert_eq!(ids(&db, &filter, sort).await, vec![ended.id]);
+        let filter = CustomerListFilter {
+            rate_min: Some(4.0),
+            rate_max: Some(5.0),
+            address: Some(" 徐汇 ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(&db, &filter, sort).await, vec![untracked.id]);
+
+        // 没有跟进的客户在两个方向上都排在最后
+        let all = CustomerListFilter::default();
+        let sort = CustomerSort::new(CustomerSortField::NextTrackTime);
+        assert_eq!(
+            ids(&db, &all, sort).await,
+            vec![ended.id, overdue.id, upcoming.id, untracked.id]
+        );
+        let sort = CustomerSort {
+            field: CustomerSortField::LatestTrackTime,
+            ascending: false,
+        };
+        assert_eq!(
+            ids(&db, &all, sort).await,
+            vec![overdue.id, upcoming.id, ended.id, untracked.id]
+        );
+        let sort = CustomerSort {
+            field: CustomerSortField::LatestTrackTime,
+            ascending: true,
+        };
+        assert_eq!(
+            ids(&db, &all, sort).await,
+            vec![ended.id, upcoming.id, overdue.id, untracked.id]
+        );
+        let sort = CustomerSort::new(CustomerSortField::Name);
+        assert_eq!(
+            ids(&db, &all, sort).await,
+            vec![upcoming.id, ended.id, overdue.id, untracked.id]
+        );
+    }
+
+    #[tokio::test]
+    async fn the_latest_track_is_compared_by_time_across_storage_formats() {
+        let db = test_support::test_db().await;
+        let customer = test_support::create_customer(&db, "张三", None, None).await;
+        let morning = "2026-01-01T08:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        test_support::create_track(&db, customer.id, None, morning).await;
+        // 同一天中午的跟进由 CURRENT_TIMESTAMP 格式写入，按文本比较会排在早上那条之前
+        let noon = test_support::create_track(&db, customer.id, None, morning).await;
+        db.execute_unprepared(&format!(
+            "UPDATE customer_tracks SET track_time = '2026-01-01 12:00:00', content = '午间回访' \
+             WHERE id = {}",
+            noon.id
+        ))
+        .await
+        .unwrap();
+
+        let scope = AccessScope::organization(1);
+        let filter = CustomerListFilter::default();
+        let (rows, _) =
+            CustomerListService::list(&db, &scope, &filter, CustomerSort::default(), 1, 20)
+                .await
+                .unwrap();
+        assert_eq!(rows[0].latest_content.as_deref(), Some("午间回访"));
+        assert_eq!(
+            rows[0].latest_track_time,
+            Some(morning + Duration::hours(4))
+        );
+        assert_eq!(rows[0].track_count, 2);
+    }
+}
@@ -0,0 +1,761 @@
+use std::{collections::BTreeMap, time::Duration as StdDuration};
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    sea_query::{Condition, Expr, SimpleExpr},
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait, Value,
+};
+
+use crate::{
+    config::Config,
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        customer::{self, Entity as Customer},
+        customer_group::CustomerGroup,
+        customer_ownership_history,
+    },
+    middleware::auth::CurrentUser,
+    services::{
+        audit_service::{Actor, AuditService},
+        customer_list_service::{
+            CustomerListError, CustomerListFilter, CustomerListRow, CustomerListService,
+            CustomerSort,
+        },
+        notification_service::{summarize_names, NotificationService},
+        permission_service::{AccessScope, CustomerAccess, PermissionService},
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum PoolError {
+    #[error("客户不存在")]
+    CustomerNotFound,
+    #[error("客户已被领取")]
+    AlreadyClaimed,
+    #[error("客户已在公海中")]
+    AlreadyInPool,
+    #[error("协作者不能将客户退回公海")]
+    NotOwner,
+    #[error("24小时内最多领取 {0} 位客户")]
+    ClaimLimitReached(u64),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 公海规则
+#[derive(Debug, Clone)]
+pub struct PoolSettings {
+    /// 超过多少天没有跟进就退回公海，0 表示不自动退回
+    pub release_days: i64,
+    /// 按分组覆盖 `release_days`
+    pub release_days_by_group: Vec<(CustomerGroup, i64)>,
+    /// 每个用户24小时内最多领取的客户数，0 表示不限制
+    pub claim_limit: u64,
+    /// 后台检查的间隔
+    pub release_interval_minutes: u64,
+}
+
+impl PoolSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            release_days: config.pool_release_days,
+            release_days_by_group: parse_days_by_group(&config.pool_release_days_by_group),
+            claim_limit: config.pool_claim_limit,
+            release_interval_minutes: config.pool_release_interval_minutes,
+        }
+    }
+
+    /// 该分组的客户多少天没有跟进后退回公海
+    pub fn release_days(&self, customer_group: &CustomerGroup) -> i64 {
+        self.release_days_by_group
+            .iter()
+            .find(|(group, _)| group == customer_group)
+            .map(|(_, days)| *days)
+            .unwrap_or(self.release_days)
+    }
+
+    /// 自动退回的规则：适用的分组条件和天数，不退回的分组不出现
+    fn release_rules(&self) -> Vec<(Condition, i64)> {
+        let mut rules: Vec<(Condition, i64)> = self
+            .release_days_by_group
+            .iter()
+            .filter(|(_, days)| *days > 0)
+            .map(|(group, days)| {
+                let condition = Condition::all().add(customer::Column::CustomerGroup.eq(group.clone()));
+                (condition, *days)
+            })
+            .collect();
+        if self.release_days > 0 {
+            let overridden = self.release_days_by_group.iter().map(|(group, _)| group.clone());
+            let condition =
+                Condition::all().add(customer::Column::CustomerGroup.is_not_in(overridden));
+            rules.push((condition, self.release_days));
+        }
+        rules
+    }
+
+    fn auto_release_enabled(&self) -> bool {
+        self.release_days > 0 || self.release_days_by_group.iter().any(|(_, days)| *days > 0)
+    }
+}
+
+/// 解析 `私教:60,教培:45`，无法识别的项忽略并记录警告
+fn parse_days_by_group(value: &str) -> Vec<(CustomerGroup, i64)> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .filter_map(|item| {
+            let parsed = item
+                .split_once(':')
+                .and_then(|(group, days)| Some((group.trim().parse().ok()?, days.trim().parse().ok()?)));
+            if parsed.is_none() {
+                tracing::warn!("忽略无法识别的 POOL_RELEASE_DAYS_BY_GROUP 配置项: {}", item);
+            }
+            parsed
+        })
+        .collect()
+}
+
+/// 公海客户列表的筛选条件
+#[derive(Debug, Default)]
+pub struct PoolListFilter {
+    pub search: Option<String>,
+    pub customer_group: Option<CustomerGroup>,
+}
+
+pub struct CustomerPoolService;
+
+impl CustomerPoolService {
+    /// 组织内公海中的客户，最近退回的在前。与客户列表共用查询，最新跟进和跟进次数一并取出
+    pub async fn list(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        filter: PoolListFilter,
+        page: u64,
+        limit: u64,
+    ) -> Result<(Vec<CustomerListRow>, u64), CustomerListError> {
+        let filter = CustomerListFilter {
+            search: filter.search.filter(|search| !search.trim().is_empty()),
+            customer_group: filter.customer_group,
+            in_pool: true,
+            ..Default::default()
+        };
+        CustomerListService::list(
+            db,
+            &AccessScope::organization(organization_id),
+            &filter,
+            CustomerSort::default(),
+            page,
+            limit,
+        )
+        .await
+    }
+
+    /// 从公海领取客户，受24小时领取数量限制
+    pub async fn claim(
+        db: &DatabaseConnection,
+        settings: &PoolSettings,
+        current_user: &CurrentUser,
+        customer_id: i32,
+    ) -> Result<customer::Model, PoolError> {
+        let customer = Customer::find_by_id(customer_id)
+            .filter(customer::Column::OrganizationId.eq(current_user.organization_id))
+            .filter(customer::Column::IsDeleted.eq(false))
+            .one(db)
+            .await?
+            .ok_or(PoolError::CustomerNotFound)?;
+        if customer.user_id.is_some() {
+            return Err(PoolError::AlreadyClaimed);
+        }
+
+        let now = Utc::now();
+        let txn = db.begin().await?;
+
+        // 只更新仍在公海中的客户，领取数量也在同一条 UPDATE 中检查：
+        // 同时发起的领取在写锁上排队，后执行的能看到先提交的领取记录
+        let mut update = Customer::update_many()
+            .col_expr(customer::Column::UserId, Expr::value(current_user.id))
+            .col_expr(customer::Column::UpdatedAt, Expr::value(now))
+            .filter(customer::Column::Id.eq(customer_id))
+            .filter(customer::Column::UserId.is_null())
+            .filter(customer::Column::IsDeleted.eq(false));
+        if settings.claim_limit > 0 {
+            update = update.filter(below_claim_limit(
+                current_user.id,
+                now - Duration::hours(24),
+                settings.claim_limit,
+            ));
+        }
+        let result = update.exec(&txn).await?;
+        if result.rows_affected == 0 {
+            let still_in_pool = Customer::find_by_id(customer_id)
+                .filter(customer::Column::UserId.is_null())
+                .filter(customer::Column::IsDeleted.eq(false))
+                .count(&txn)
+                .await?
+                > 0;
+            if still_in_pool && settings.claim_limit > 0 {
+                return Err(PoolError::ClaimLimitReached(settings.claim_limit));
+            }
+            return Err(PoolError::AlreadyClaimed);
+        }
+
+        let claimed = customer::Model {
+            user_id: Some(current_user.id),
+            updated_at: now,
+            ..customer.clone()
+        };
+        let actor = Actor::from(current_user);
+        record_ownership_change(&txn, &actor, &customer, &claimed, None, now).await?;
+        txn.commit().await?;
+
+        Ok(claimed)
+    }
+
+    /// 将客户退回公海。主管和管理员退回他人的客户时通知原负责人
+    pub async fn release(
+        db: &DatabaseConnection,
+        current_user: &CurrentUser,
+        scope: &AccessScope,
+        customer_id: i32,
+        reason: Option<String>,
+    ) -> Result<customer::Model, PoolError> {
+        let (customer, access) = PermissionService::find_customer(db, scope, customer_id)
+            .await?
+            .ok_or(PoolError::CustomerNotFound)?;
+        if access < CustomerAccess::Owner {
+            return Err(PoolError::NotOwner);
+        }
+        let Some(previous_owner) = customer.user_id else {
+            return Err(PoolError::AlreadyInPool);
+        };
+        let reason = reason
+            .map(|reason| reason.trim().to_string())
+            .filter(|reason| !reason.is_empty());
+
+        let now = Utc::now();
+        let actor = Actor::from(current_user);
+        let txn = db.begin().await?;
+
+        let mut active: customer::ActiveModel = customer.clone().into();
+        active.user_id = Set(None);
+        active.updated_at = Set(now);
+        let released = active.update(&txn).await?;
+        record_ownership_change(&txn, &actor, &customer, &released, reason.clone(), now).await?;
+
+        if previous_owner != current_user.id {
+            let mut body = format!("{} 已被 {} 退回公海", customer.name, current_user.name);
+            if let Some(reason) = &reason {
+                body.push_str(&format!("，原因：{}", reason));
+            }
+            NotificationService::notify(&txn, previous_owner, "客户已退回公海", &body).await?;
+        }
+        txn.commit().await?;
+
+        Ok(released)
+    }
+
+    /// 将长时间没有跟进的客户退回公海，返回退回的数量。
+    ///
+    /// 最近活动时间取最新跟进时间、分配给当前负责人的时间和创建时间中最晚的一个，
+    /// 刚领取或刚转移的客户不会因为以前的跟进记录而马上被退回。
+    pub async fn release_stale(
+        db: &DatabaseConnection,
+        settings: &PoolSettings,
+        now: DateTime<Utc>,
+    ) -> Result<usize, DbErr> {
+        if !settings.auto_release_enabled() {
+            return Ok(0);
+        }
+
+        let mut stale: Vec<(customer::Model, i64)> = Vec::new();
+        for (groups, days) in settings.release_rules() {
+            let customers = Customer::find()
+                .filter(customer::Column::UserId.is_not_null())
+                .filter(customer::Column::IsDeleted.eq(false))
+                .filter(groups)
+                .filter(inactive_since(now - Duration::days(days)))
+                .order_by_asc(customer::Column::Id)
+                .all(db)
+                .await?;
+            stale.extend(customers.into_iter().map(|customer| (customer, days)));
+        }
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        // 原负责人 -> 退回的客户名
+        let mut by_previous_owner: BTreeMap<i32, Vec<String>> = BTreeMap::new();
+        let mut released = 0;
+
+        let txn = db.begin().await?;
+        for (customer, days) in stale {
+            let Some(previous_owner) = customer.user_id else {
+                continue;
+            };
+            // 期间被转移或领取的客户保持不变
+            let result = Customer::update_many()
+                .col_expr(customer::Column::UserId, Expr::value(Option::<i32>::None))
+                .col_expr(customer::Column::UpdatedAt, Expr::value(now))
+                .filter(customer::Column::Id.eq(customer.id))
+                .filter(customer::Column::UserId.eq(previous_owner))
+                .exec(&txn)
+                .await?;
+            if result.rows_affected == 0 {
+                continue;
+            }
+
+            let updated = customer::Model {
+                user_id: None,
+                updated_at: now,
+                ..customer.clone()
+            };
+            let actor = Actor::system(customer.organization_id);
+            let reason = format!("超过{}天未跟进，自动退回公海", days);
+            record_ownership_change(&txn, &actor, &customer, &updated, Some(reason), now).await?;
+
+            by_previous_owner
+                .entry(previous_owner)
+                .or_default()
+                .push(customer.name);
+            released += 1;
+        }
+
+        for (user_id, names) in &by_previous_owner {
+            let body = format!("{} 长时间未跟进，已自动退回公海", summarize_names(names));
+            NotificationService::notify(&txn, *user_id, "客户已退回公海", &body).await?;
+        }
+        txn.commit().await?;
+
+        Ok(released)
+    }
+
+    /// 后台定期将长时间没有跟进的客户退回公海
+    pub fn spawn_auto_release(db: DatabaseConnection, settings: PoolSettings) {
+        if !settings.auto_release_enabled() {
+            tracing::info!("公海自动退回未启用");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let minutes = settings.release_interval_minutes.max(1);
+            let mut interval = tokio::time::interval(StdDuration::from_secs(minutes * 60));
+            loop {
+                interval.tick().await;
+                match Self::release_stale(&db, &settings, Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(released) => tracing::info!("已将 {} 位客户自动退回公海", released),
+                    Err(e) => tracing::warn!("自动退回公海失败: {}", e),
+                }
+            }
+        });
+    }
+}
+
+/// 创建时间、最新跟进时间和最近分配给负责人的时间都早于 `cutoff` 的客户
+fn inactive_since(cutoff: DateTime<Utc>) -> SimpleExpr {
+    Expr::cust_with_values(
+        "MAX(
+            datetime(customers.created_at),
+            COALESCE((SELECT MAX(datetime(customer_tracks.track_time)) FROM customer_tracks
+                WHERE customer_tracks.customer_id = customers.id), ''),
+            COALESCE((SELECT MAX(datetime(customer_ownership_history.created_at))
+                FROM customer_ownership_history
+                WHERE customer_ownership_history.customer_id = customers.id
+                AND customer_ownership_history.to_user_id IS NOT NULL), '')
+        ) < datetime(?)",
+        [cutoff],
+    )
+}
+
+/// 用户 `since` 之后从公海领取的客户数少于 `limit`
+fn below_claim_limit(user_id: i32, since: DateTime<Utc>, limit: u64) -> SimpleExpr {
+    Expr::cust_with_values(
+        "(SELECT COUNT(*) FROM customer_ownership_history
+            WHERE customer_ownership_history.to_user_id = ?
+            AND customer_ownership_history.from_user_id IS NULL
+            AND customer_ownership_history.transferred_by = ?
+            AND datetime(customer_ownership_history.created_at) > datetime(?)) < ?",
+        [
+            Value::from(user_id),
+            Value::from(user_id),
+            Value::from(since),
+            Value::from(limit as i64),
+        ],
+    )
+}
+
+/// 记录一次负责人变更：写入变更历史和审计日志
+async fn record_ownership_change<C: ConnectionTrait>(
+    db: &C,
+    actor: &Actor,
+    before: &customer::Model,
+    after: &customer::Model,
+    reason: Option<String>,
+    now: DateTime<Utc>,
+) -> Result<(), DbErr> {
+    customer_ownership_history::ActiveModel {
+        customer_id: Set(before.id),
+        organization_id: Set(before.organization_id),
+        from_user_id: Set(before.user_id),
+        to_user_id: Set(after.user_id),
+        transferred_by: Set(actor.user_id),
+        transferred_by_name: Set(actor.name.clone()),
+        reason: Set(reason),
+        created_at: Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    AuditService::record(
+        db,
+        actor,
+        AuditAction::Update,
+        AuditEntity::Customer,
+        before.id,
+        Some(before),
+        Some(after),
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+
+    use super::*;
+    use crate::{
+        entities::{
+            customer_ownership_history::Entity as CustomerOwnershipHistory,
+            notification::Entity as Notification, user_role::UserRole,
+        },
+        migration::DatabaseMigrator,
+        test_support,
+    };
+
+    fn settings(release_days: i64, by_group: &str) -> PoolSettings {
+        PoolSettings {
+            release_days,
+            release_days_by_group: parse_days_by_group(by_group),
+            claim_limit: 2,
+            release_interval_minutes: 60,
+        }
+    }
+
+    async fn backdate(db: &DatabaseConnection, customer_id: i32, days: i64) {
+        Customer::update_many()
+            .col_expr(
+                customer::Column::CreatedAt,
+                Expr::value(Utc::now() - Duration::days(days)),
+            )
+            .filter(customer::Column::Id.eq(customer_id))
+            .exec(db)
+            .await
+            .unwrap();
+    }
+
+    async fn owner_of(db: &DatabaseConnection, customer_id: i32) -> Option<i32> {
+        Customer::find_by_id(customer_id)
+            .one(db)
+            .await
+            .unwrap()
+            .unwrap()
+            .user_id
+    }
+
+    #[tokio::test]
+    async fn releases_only_customers_without_recent_activity() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let now = Utc::now();
+
+        let stale = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        backdate(&db, stale.id, 40).await;
+        test_support::create_track(&db, stale.id, Some(alice.id), now - Duration::days(35)).await;
+        let tracked = test_support::create_customer(&db, "李四", None, Some(alice.id)).await;
+        backdate(&db, tracked.id, 40).await;
+        test_support::create_track(&db, tracked.id, Some(alice.id), now - Duration::days(3)).await;
+        let fresh = test_support::create_customer(&db, "王五", None, Some(alice.id)).await;
+        // 以前的跟进记录很旧，但刚分配给当前负责人
+        let assigned = test_support::create_customer(&db, "赵六", None, Some(alice.id)).await;
+        backdate(&db, assigned.id, 40).await;
+        test_support::create_track(&db, assigned.id, None, now - Duration::days(35)).await;
+        let actor = Actor::system(1);
+        record_ownership_change(
+            &db,
+            &actor,
+            &assigned,
+            &assigned,
+            None,
+            now - Duration::days(1),
+        )
+        .await
+        .unwrap();
+
+        let released = CustomerPoolService::release_stale(&db, &settings(30, ""), now)
+            .await
+            .unwrap();
+
+        assert_eq!(released, 1);
+        assert_eq!(owner_of(&db, stale.id).await, None);
+        assert_eq!(owner_of(&db, tracked.id).await, Some(alice.id));
+        assert_eq!(owner_of(&db, fresh.id).await, Some(alice.id));
+        assert_eq!(owner_of(&db, assigned.id).await, Some(alice.id));
+
+        let history = CustomerOwnershipHistory::find()
+            .filter(customer_ownership_history::Column::CustomerId.eq(stale.id))
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(history.from_user_id, Some(alice.id));
+        assert_eq!(history.to_user_id, None);
+        assert_eq!(
+            history.reason.as_deref(),
+            Some("超过30天未跟进，自动退回公海")
+        );
+        assert_eq!(
+            NotificationService::unread_count(&db, alice.id)
+                .await
+                .unwrap(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn does_nothing_unless_enabled() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let customer = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        backdate(&db, customer.id, 400).await;
+
+        let released = CustomerPoolService::release_stale(&db, &settings(0, "小班:0"), Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(released, 0);
+        assert_eq!(owner_of(&db, customer.id).await, Some(alice.id));
+        assert_eq!(Notification::find().count(&db).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn applies_per_group_overrides() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let default_group = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        let longer = test_support::create_customer(&db, "李四", None, Some(alice.id)).await;
+        let never = test_support::create_customer(&db, "王五", None, Some(alice.id)).await;
+        for (customer, group) in [(&longer, "私教"), (&never, "小班")] {
+            Customer::update_many()
+                .col_expr(customer::Column::CustomerGroup, Expr::value(group))
+                .filter(customer::Column::Id.eq(customer.id))
+                .exec(&db)
+                .await
+                .unwrap();
+        }
+        for customer in [&default_group, &longer, &never] {
+            backdate(&db, customer.id, 45).await;
+        }
+
+        let released =
+            CustomerPoolService::release_stale(&db, &settings(30, "私教:60,小班:0"), Utc::now())
+                .await
+                .unwrap();
+
+        assert_eq!(released, 1);
+        assert_eq!(owner_of(&db, default_group.id).await, None);
+        assert_eq!(owner_of(&db, longer.id).await, Some(alice.id));
+        assert_eq!(owner_of(&db, never.id).await, Some(alice.id));
+    }
+
+    #[tokio::test]
+    async fn compares_times_stored_by_sqlite_and_by_the_application() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let customer = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        backdate(&db, customer.id, 40).await;
+        // 旧数据的跟进时间由 CURRENT_TIMESTAMP 生成，格式为 `YYYY-MM-DD HH:MM:SS`
+        db.execute_unprepared(&format!(
+            "INSERT INTO customer_tracks (customer_id, content) VALUES ({}, '电话沟通')",
+            customer.id
+        ))
+        .await
+        .unwrap();
+
+        let released = CustomerPoolService::release_stale(&db, &settings(30, ""), Utc::now())
+            .await
+            .unwrap();
+
+        assert_eq!(released, 0);
+        assert_eq!(owner_of(&db, customer.id).await, Some(alice.id));
+    }
+
+    #[tokio::test]
+    async fn claims_are_exclusive_and_limited() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let settings = settings(0, "");
+        let mut pool = Vec::new();
+        for name in ["张三", "李四", "王五"] {
+            pool.push(test_support::create_customer(&db, name, None, None).await);
+        }
+        let alice_user = test_support::current_user(&alice);
+        let bob_user = test_support::current_user(&bob);
+
+        let claimed = CustomerPoolService::claim(&db, &settings, &alice_user, pool[0].id)
+            .await
+            .unwrap();
+        assert_eq!(claimed.user_id, Some(alice.id));
+        let result = CustomerPoolService::claim(&db, &settings, &bob_user, pool[0].id).await;
+        assert!(matches!(result, Err(PoolError::AlreadyClaimed)));
+
+        CustomerPoolService::claim(&db, &settings, &alice_user, pool[1].id)
+            .await
+            .unwrap();
+        let result = CustomerPoolService::claim(&db, &settings, &alice_user, pool[2].id).await;
+        assert!(matches!(result, Err(PoolError::ClaimLimitReached(2))));
+        assert_eq!(owner_of(&db, pool[2].id).await, None);
+    }
+
+    #[tokio::test]
+    async fn concurrent_claims_respect_the_limit() {
+        // 内存数据库只有一个连接，领取无法真正并发，这里用有多个连接的临时文件数据库
+        let path = std::env::temp_dir().join(format!("pool-claims-{}.db", uuid::Uuid::new_v4()));
+        let url = format!("sqlite://{}?mode=rwc", path.display());
+        let mut options = sea_orm::ConnectOptions::new(url.clone());
+        options.max_connections(1).sqlx_logging(false);
+        let setup = sea_orm::Database::connect(options).await.unwrap();
+        DatabaseMigrator::new(url.clone())
+            .migrate(&setup)
+            .await
+            .unwrap();
+        let alice = test_support::create_user(&setup, "alice", UserRole::Sales).await;
+        let mut pool = Vec::new();
+        for i in 0..6 {
+            pool.push(
+                test_support::create_customer(&setup, &format!("客户{}", i), None, None).await,
+            );
+        }
+        setup.close().await.unwrap();
+
+        let mut options = sea_orm::ConnectOptions::new(url);
+        options.max_connections(6).sqlx_logging(false);
+        let db = sea_orm::Database::connect(options).await.unwrap();
+        let settings = settings(0, "");
+        let alice_user = test_support::current_user(&alice);
+        let claims = pool.iter().map(|customer| {
+            let (db, settings, alice_user) = (db.clone(), settings.clone(), alice_user.clone());
+            let customer_id = customer.id;
+            tokio::spawn(async move {
+                CustomerPoolService::claim(&db, &settings, &alice_user, customer_id).await
+            })
+        });
+        let mut claimed = 0;
+        for claim in claims.collect::<Vec<_>>() {
+            match claim.await.unwrap() {
+                Ok(_) => claimed += 1,
+                Err(PoolError::ClaimLimitReached(2)) => {}
+                Err(error) => panic!("领取失败: {}", error),
+            }
+        }
+
+        assert_eq!(claimed, 2);
+        let owned = Customer::find()
+            .filter(customer::Column::UserId.eq(alice.id))
+            .count(&db)
+            .await
+            .unwrap();
+        assert_eq!(owned, 2);
+        db.close().await.unwrap();
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[tokio::test]
+    async fn releasing_someone_elses_customer_notifies_them() {
+        let db = test_support::test_db().await;
+        let manager = test_support::create_user(&db, "manager", UserRole::Manager).await;
+        let alice =
+            test_support::create_user_in(&db, "alice", UserRole::Sales, 1, Some(manager.id)).await;
+        let customer = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        let (scope, _) = test_support::scope_of(&db, &manager).await;
+        let manager_user = test_support::current_user(&manager);
+
+        let released = CustomerPoolService::release(
+            &db,
+            &manager_user,
+            &scope,
+            customer.id,
+            Some("长期无人跟进".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(released.user_id, None);
+
+        let (notifications, _) = NotificationService::list_for_user(&db, alice.id, false, 1, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            notifications[0].body,
+            "张三 已被 manager 退回公海，原因：长期无人跟进"
+        );
+
+        let result =
+            CustomerPoolService::release(&db, &manager_user, &scope, customer.id, None).await;
+        assert!(matches!(result, Err(PoolError::CustomerNotFound)));
+    }
+
+    #[tokio::test]
+    async fn lists_pool_customers_with_their_latest_track() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let now = Utc::now();
+        let first = test_support::create_customer(&db, "张三", Some("13800000001"), None).await;
+        let second = test_support::create_customer(&db, "李四", None, None).await;
+        test_support::create_customer(&db, "王五", None, Some(alice.id)).await;
+        test_support::create_track(&db, first.id, None, now - Duration::days(3)).await;
+        test_support::create_track(&db, first.id, None, now - Duration::days(1)).await;
+        // 最近退回的排在前面
+        Customer::update_many()
+            .col_expr(
+                customer::Column::UpdatedAt,
+                Expr::value(now - Duration::days(1)),
+            )
+            .filter(customer::Column::Id.eq(first.id))
+            .exec(&db)
+            .await
+            .unwrap();
+
+        let (rows, total) = CustomerPoolService::list(&db, 1, PoolListFilter::default(), 1, 20)
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+        let ids: Vec<i32> = rows.iter().map(|row| row.id).collect();
+        assert_eq!(ids, vec![second.id, first.id]);
+        assert_eq!(rows[0].track_count, 0);
+        assert_eq!(rows[0].latest_track_time, None);
+        assert_eq!(rows[1].track_count, 2);
+        assert_eq!(rows[1].latest_track_time, Some(now - Duration::days(1)));
+
+        let filter = PoolListFilter {
+            search: Some("zhangsan".to_string()),
+            ..Default::default()
+        };
+        let (rows, total) = CustomerPoolService::list(&db, 1, filter, 1, 20)
+            .await
+            .unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(rows[0].id, first.id);
+        let filter = PoolListFilter {
+            search: Some(" ".to_string()),
+            ..Default::default()
+        };
+        let (_, total) = CustomerPoolService::list(&db, 1, filter, 1, 20)
+            .await
+            .unwrap();
+        assert_eq!(total, 2);
+    }
+}
@@ -0,0 +1,419 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+
+use crate::{
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        customer::{self, Entity as Customer},
+        customer_group::CustomerGroup,
+        customer_ownership_history::{self, Entity as CustomerOwnershipHistory},
+        user::{self, Entity as User},
+    },
+    services::{
+        audit_service::{Actor, AuditService},
+        notification_service::{summarize_names, NotificationService},
+        permission_service::AccessScope,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TransferError {
+    #[error("接收人不存在或已停用")]
+    TargetNotFound,
+    #[error("无权将客户转给该用户")]
+    TargetOutOfScope,
+    #[error("无权转移该用户的客户")]
+    SourceOutOfScope,
+    #[error("请指定要转移的客户")]
+    EmptySelection,
+    #[error("客户 {0} 不存在")]
+    CustomerNotFound(i32),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 要转移的客户
+#[derive(Debug, Clone)]
+pub enum TransferSelection {
+    /// 指定的客户
+    Customers(Vec<i32>),
+    /// 某个用户名下的客户，可按分组和名称/电话进一步筛选；不筛选即全部转移
+    OwnedBy {
+        user_id: i32,
+        customer_group: Option<CustomerGroup>,
+        search: Option<String>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct TransferRequest {
+    pub to_user_id: i32,
+    pub selection: TransferSelection,
+    pub reason: Option<String>,
+    /// 是否给原负责人发送站内通知
+    pub notify_previous_owner: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TransferOutcome {
+    pub transferred: Vec<i32>,
+    /// 已经属于接收人而跳过的客户数
+    pub skipped: usize,
+}
+
+pub struct CustomerTransferService;
+
+impl CustomerTransferService {
+    /// 在 `scope` 范围内转移客户负责人，跟进记录随客户保留。
+    ///
+    /// 每个客户的变更写入负责人变更历史和审计日志，全部在同一事务中完成。
+    pub async fn transfer(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        scope: &AccessScope,
+        request: TransferRequest,
+    ) -> Result<TransferOutcome, TransferError> {
+        let target = User::find_by_id(request.to_user_id)
+            .filter(user::Column::OrganizationId.eq(scope.organization_id))
+            .filter(user::Column::IsActive.eq(true))
+            .one(db)
+            .await?
+            .ok_or(TransferError::TargetNotFound)?;
+        if !scope.includes_owner(target.id) {
+            return Err(TransferError::TargetOutOfScope);
+        }
+
+        let customers = Self::select(db, scope, &request.selection).await?;
+        let reason = request
+            .reason
+            .map(|reason| reason.trim().to_string())
+            .filter(|reason| !reason.is_empty());
+
+        let now = Utc::now();
+        let mut transferred = Vec::new();
+        let mut skipped = 0;
+        // 原负责人 -> 转出的客户名
+        let mut by_previous_owner: BTreeMap<i32, Vec<String>> = BTreeMap::new();
+
+        let txn = db.begin().await?;
+        for customer in customers {
+            if customer.user_id == Some(target.id) {
+                skipped += 1;
+                continue;
+            }
+
+            let mut active: customer::ActiveModel = customer.clone().into();
+            active.user_id = Set(Some(target.id));
+            active.updated_at = Set(now);
+            let updated = active.update(&txn).await?;
+
+            customer_ownership_history::ActiveModel {
+                customer_id: Set(customer.id),
+                organization_id: Set(customer.organization_id),
+                from_user_id: Set(customer.user_id),
+                to_user_id: Set(Some(target.id)),
+                transferred_by: Set(actor.user_id),
+                transferred_by_name: Set(actor.name.clone()),
+                reason: Set(reason.clone()),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(&txn)
+            .await?;
+
+            AuditService::record(
+                &txn,
+                actor,
+                AuditAction::Update,
+                AuditEntity::Customer,
+                customer.id,
+                Some(&customer),
+                Some(&updated),
+            )
+            .await?;
+
+            // 从公海转出的客户没有原负责人需要通知
+            if let Some(previous_owner) = customer.user_id {
+                by_previous_owner
+                    .entry(previous_owner)
+                    .or_default()
+                    .push(customer.name);
+            }
+            transferred.push(customer.id);
+        }
+
+        if request.notify_previous_owner {
+            for (user_id, names) in &by_previous_owner {
+                // 自己转出的客户不需要通知自己
+                if actor.user_id == Some(*user_id) {
+                    continue;
+                }
+                let body = notification_body(names, &target.name, reason.as_deref());
+                NotificationService::notify(&txn, *user_id, "客户已转移", &body).await?;
+            }
+        }
+        txn.commit().await?;
+
+        Ok(TransferOutcome {
+            transferred,
+            skipped,
+        })
+    }
+
+    /// 客户的负责人变更历史，最新的在前
+    pub async fn history_for_customer(
+        db: &DatabaseConnection,
+        customer_id: i32,
+    ) -> Result<Vec<customer_ownership_history::Model>, DbErr> {
+        CustomerOwnershipHistory::find()
+            .filter(customer_ownership_history::Column::CustomerId.eq(customer_id))
+            .order_by_desc(customer_ownership_history::Column::Id)
+            .all(db)
+            .await
+    }
+
+    async fn select(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        selection: &TransferSelection,
+    ) -> Result<Vec<customer::Model>, TransferError> {
+        let query = Customer::find()
+            .filter(scope.customer_condition())
+            .filter(customer::Column::IsDeleted.eq(false));
+
+        match selection {
+            TransferSelection::Customers(ids) => {
+                if ids.is_empty() {
+                    return Err(TransferError::EmptySelection);
+                }
+                let customers = query
+                    .filter(customer::Column::Id.is_in(ids.clone()))
+                    .order_by_asc(customer::Column::Id)
+                    .all(db)
+                    .await?;
+                // 任何一个客户不可见都整体拒绝，避免只转移了一部分
+                if let Some(missing) = ids
+                    .iter()
+                    .find(|id| !customers.iter().any(|customer| customer.id == **id))
+                {
+                    return Err(TransferError::CustomerNotFound(*missing));
+                }
+                Ok(customers)
+            }
+            TransferSelection::OwnedBy {
+                user_id,
+                customer_group,
+                search,
+            } => {
+                if !scope.includes_owner(*user_id) {
+                    return Err(TransferError::SourceOutOfScope);
+                }
+                let mut query = query.filter(customer::Column::UserId.eq(*user_id));
+                if let Some(customer_group) = customer_group {
+                    query = query.filter(customer::Column::CustomerGroup.eq(customer_group.clone()));
+                }
+                if let Some(search) = search.as_deref().filter(|search| !search.is_empty()) {
+                    query = query.filter(
+                        customer::Column::Name
+                            .contains(search)
+                            .or(customer::Column::Phone.contains(search)),
+                    );
+                }
+                Ok(query.order_by_asc(customer::Column::Id).all(db).await?)
+            }
+        }
+    }
+}
+
+fn notification_body(names: &[String], target_name: &str, reason: Option<&str>) -> String {
+    let mut body = format!("{} 已转给 {}", summarize_names(names), target_name);
+    if let Some(reason) = reason {
+        body.push_str(&format!("，原因：{}", reason));
+    }
+    body
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::PaginatorTrait;
+
+    use super::*;
+    use crate::{
+        entities::{notification::Entity as Notification, user_role::UserRole},
+        test_support,
+    };
+
+    fn request(to_user_id: i32, selection: TransferSelection) -> TransferRequest {
+        TransferRequest {
+            to_user_id,
+            selection,
+            reason: Some(" 离职交接 ".to_string()),
+            notify_previous_owner: true,
+        }
+    }
+
+    async fn owner_of(db: &DatabaseConnection, customer_id: i32) -> Option<i32> {
+        Customer::find_by_id(customer_id).one(db).await.unwrap().unwrap().user_id
+    }
+
+    #[tokio::test]
+    async fn transfers_customers_with_history_and_notifies_the_previous_owner() {
+        let db = test_support::test_db().await;
+        let admin = test_support::create_user(&db, "admin", UserRole::Admin).await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let first = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        let second = test_support::create_customer(&db, "李四", None, Some(alice.id)).await;
+        let owned = test_support::create_customer(&db, "王五", None, Some(bob.id)).await;
+        let (scope, actor) = test_support::scope_of(&db, &admin).await;
+
+        let selection = TransferSelection::Customers(vec![first.id, second.id, owned.id]);
+        let outcome =
+            CustomerTransferService::transfer(&db, &actor, &scope, request(bob.id, selection))
+                .await
+                .unwrap();
+
+        assert_eq!(outcome.transferred, vec![first.id, second.id]);
+        assert_eq!(outcome.skipped, 1);
+        assert_eq!(owner_of(&db, first.id).await, Some(bob.id));
+        assert_eq!(owner_of(&db, second.id).await, Some(bob.id));
+
+        let history = CustomerTransferService::history_for_customer(&db, first.id).await.unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].from_user_id, Some(alice.id));
+        assert_eq!(history[0].to_user_id, Some(bob.id));
+        assert_eq!(history[0].transferred_by, Some(admin.id));
+        assert_eq!(history[0].reason.as_deref(), Some("离职交接"));
+        assert!(CustomerTransferService::history_for_customer(&db, owned.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // 同一原负责人的客户合并成一条通知，接收人不收到通知
+        let (notifications, total) =
+            NotificationService::list_for_user(&db, alice.id, false, 1, 10).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(notifications[0].body, "张三、李四 已转给 bob，原因：离职交接");
+        assert_eq!(NotificationService::unread_count(&db, bob.id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn skips_notifications_for_the_actor_and_when_disabled() {
+        let db = test_support::test_db().await;
+        let manager = test_support::create_user(&db, "manager", UserRole::Manager).await;
+        let alice =
+            test_support::create_user_in(&db, "alice", UserRole::Sales, 1, Some(manager.id)).await;
+        let bob =
+            test_support::create_user_in(&db, "bob", UserRole::Sales, 1, Some(manager.id)).await;
+        let own = test_support::create_customer(&db, "张三", None, Some(manager.id)).await;
+        let other = test_support::create_customer(&db, "李四", None, Some(alice.id)).await;
+        let (scope, actor) = test_support::scope_of(&db, &manager).await;
+
+        let selection = TransferSelection::Customers(vec![own.id]);
+        CustomerTransferService::transfer(&db, &actor, &scope, request(bob.id, selection))
+            .await
+            .unwrap();
+
+        let mut quiet = request(bob.id, TransferSelection::Customers(vec![other.id]));
+        quiet.notify_previous_owner = false;
+        CustomerTransferService::transfer(&db, &actor, &scope, quiet).await.unwrap();
+
+        assert_eq!(Notification::find().count(&db).await.unwrap(), 0);
+        assert_eq!(owner_of(&db, other.id).await, Some(bob.id));
+    }
+
+    #[tokio::test]
+    async fn transfers_everything_owned_by_a_user_matching_the_search() {
+        let db = test_support::test_db().await;
+        let admin = test_support::create_user(&db, "admin", UserRole::Admin).await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let matched =
+            test_support::create_customer(&db, "张三", Some("13800000001"), Some(alice.id)).await;
+        let unmatched = test_support::create_customer(&db, "李四", None, Some(alice.id)).await;
+        let (scope, actor) = test_support::scope_of(&db, &admin).await;
+
+        let selection = TransferSelection::OwnedBy {
+            user_id: alice.id,
+            customer_group: None,
+            search: Some("0001".to_string()),
+        };
+        let outcome =
+            CustomerTransferService::transfer(&db, &actor, &scope, request(bob.id, selection))
+                .await
+                .unwrap();
+
+        assert_eq!(outcome.transferred, vec![matched.id]);
+        assert_eq!(owner_of(&db, unmatched.id).await, Some(alice.id));
+    }
+
+    #[tokio::test]
+    async fn sales_cannot_move_customers_outside_their_scope() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let own = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        let foreign = test_support::create_customer(&db, "李四", None, Some(bob.id)).await;
+        let (scope, actor) = test_support::scope_of(&db, &alice).await;
+
+        let selection = TransferSelection::Customers(vec![own.id]);
+        let result =
+            CustomerTransferService::transfer(&db, &actor, &scope, request(bob.id, selection)).await;
+        assert!(matches!(result, Err(TransferError::TargetOutOfScope)));
+
+        let selection = TransferSelection::OwnedBy {
+            user_id: bob.id,
+            customer_group: None,
+            search: None,
+        };
+        let result =
+            CustomerTransferService::transfer(&db, &actor, &scope, request(alice.id, selection))
+                .await;
+        assert!(matches!(result, Err(TransferError::SourceOutOfScope)));
+
+        // 选中不可见的客户时整体拒绝
+        let selection = TransferSelection::Customers(vec![own.id, foreign.id]);
+        let result =
+            CustomerTransferService::transfer(&db, &actor, &scope, request(alice.id, selection))
+                .await;
+        assert!(matches!(result, Err(TransferError::CustomerNotFound(id)) if id == foreign.id));
+        assert_eq!(owner_of(&db, foreign.id).await, Some(bob.id));
+    }
+
+    #[tokio::test]
+    async fn rejects_inactive_targets_and_empty_selections() {
+        let db = test_support::test_db().await;
+        let admin = test_support::create_user(&db, "admin", UserRole::Admin).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let (scope, actor) = test_support::scope_of(&db, &admin).await;
+
+        let result = CustomerTransferService::transfer(
+            &db,
+            &actor,
+            &scope,
+            request(bob.id, TransferSelection::Customers(Vec::new())),
+        )
+        .await;
+        assert!(matches!(result, Err(TransferError::EmptySelection)));
+
+        User::update_many()
+            .col_expr(user::Column::IsActive, false.into())
+            .filter(user::Column::Id.eq(bob.id))
+            .exec(&db)
+            .await
+            .unwrap();
+        let result = CustomerTransferService::transfer(
+            &db,
+            &actor,
+            &scope,
+            request(bob.id, TransferSelection::Customers(vec![1])),
+        )
+        .await;
+        assert!(matches!(result, Err(TransferError::TargetNotFound)));
+    }
+}
@@ -0,0 +1,586 @@
+use std::collections::BTreeMap;
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::Serialize;
+
+use crate::{
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        customer::{self, Entity as Customer},
+        customer_collaborator::{self, CollaboratorPermission, Entity as CustomerCollaborator},
+        customer_field_value::{self, Entity as CustomerFieldValue},
+        customer_track::{self, Entity as CustomerTrack},
+    },
+    services::{
+        audit_service::{Actor, AuditService},
+        permission_service::AccessScope,
+        tag_service::TagService,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum MergeError {
+    #[error("不能将客户合并到自身")]
+    SameCustomer,
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 判定为重复的依据
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DuplicateReason {
+    /// 规范化后的电话相同
+    Phone,
+    /// 名称相同或相近
+    Name,
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateMatch {
+    pub customer: customer::Model,
+    pub reasons: Vec<DuplicateReason>,
+}
+
+/// 查重报告中的一组重复客户
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub reason: DuplicateReason,
+    /// 规范化后的电话或名称
+    pub key: String,
+    pub customers: Vec<customer::Model>,
+}
+
+#[derive(Debug, Clone)]
+pub struct MergeOutcome {
+    pub customer: customer::Model,
+    /// 转到保留客户名下的跟进记录数
+    pub moved_tracks: u64,
+}
+
+/// 电话只保留数字，并去掉 86 / 0086 国家码，便于比较不同写法的同一号码
+pub fn normalize_phone(phone: &str) -> Option<String> {
+    let digits: String = phone.chars().filter(|c| c.is_ascii_digit()).collect();
+    let digits = match digits.len() {
+        15 if digits.starts_with("0086") => digits[4..].to_string(),
+        13 if digits.starts_with("86") => digits[2..].to_string(),
+        _ => digits,
+    };
+    (!digits.is_empty()).then_some(digits)
+}
+
+/// 名称去掉空白并转小写
+pub fn normalize_name(name: &str) -> String {
+    name.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+/// 名称相近：规范化后相同、互相包含（至少两个字），或三个字以上只差一个字
+fn names_similar(a: &str, b: &str) -> bool {
+    let a: Vec<char> = normalize_name(a).chars().collect();
+    let b: Vec<char> = normalize_name(b).chars().collect();
+    if a.is_empty() || b.is_empty() {
+        return false;
+    }
+    if a == b {
+        return true;
+    }
+    let (shorter, longer) = if a.len() <= b.len() { (&a, &b) } else { (&b, &a) };
+    if shorter.len() >= 2 && longer.windows(shorter.len()).any(|window| window == &shorter[..]) {
+        return true;
+    }
+    shorter.len() >= 3 && within_one_edit(shorter, longer)
+}
+
+// `shorter` 不长于 `longer` 时，判断两者编辑距离是否不超过 1
+fn within_one_edit(shorter: &[char], longer: &[char]) -> bool {
+    if longer.len() - shorter.len() > 1 {
+        return false;
+    }
+    let prefix = shorter
+        .iter()
+        .zip(longer)
+        .take_while(|(a, b)| a == b)
+        .count();
+    if shorter.len() == longer.len() {
+        shorter[prefix + 1..] == longer[prefix + 1..]
+    } else {
+        shorter[prefix..] == longer[prefix + 1..]
+    }
+}
+
+pub struct DuplicateService;
+
+impl DuplicateService {
+    /// 在整个组织中查找与给定名称或电话重复的客户，不限负责人，便于发现别人已经在跟进的客户
+    pub async fn find_matches(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        name: &str,
+        phone: Option<&str>,
+        exclude_id: Option<i32>,
+    ) -> Result<Vec<DuplicateMatch>, DbErr> {
+        let name_key = normalize_name(name);
+        let phone_key = phone.and_then(normalize_phone);
+        if name_key.is_empty() && phone_key.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let mut candidates = Condition::any();
+        if let Some(phone_key) = &phone_key {
+            candidates = candidates.add(customer::Column::PhoneNormalized.eq(phone_key.clone()));
+        }
+        if !name_key.is_empty() {
+            // 先在数据库中粗筛：相同、互相包含，或长度相差不超过 1 且首字或尾字相同（只差一个字时必然满足）
+            candidates = candidates.add(Expr::cust_with_values(
+                "(name_normalized = ? OR INSTR(name_normalized, ?) > 0 OR INSTR(?, name_normalized) > 0 \
+                 OR (ABS(LENGTH(name_normalized) - LENGTH(?)) <= 1 \
+                 AND (SUBSTR(name_normalized, 1, 1) = SUBSTR(?, 1, 1) \
+                 OR SUBSTR(name_normalized, -1) = SUBSTR(?, -1))))",
+                std::iter::repeat_n(name_key.clone(), 6),
+            ));
+        }
+
+        let mut query = Customer::find()
+            .filter(customer::Column::OrganizationId.eq(organization_id))
+            .filter(customer::Column::IsDeleted.eq(false))
+            .filter(candidates);
+        if let Some(exclude_id) = exclude_id {
+            query = query.filter(customer::Column::Id.ne(exclude_id));
+        }
+        let customers = query
+            .order_by_asc(customer::Column::Id)
+            .all(db)
+            .await?;
+
+        Ok(customers
+            .into_iter()
+            .filter_map(|customer| {
+                let mut reasons = Vec::new();
+                if phone_key.is_some() && customer.phone_normalized == phone_key {
+                    reasons.push(DuplicateReason::Phone);
+                }
+                if names_similar(&customer.name, name) {
+                    reasons.push(DuplicateReason::Name);
+                }
+                (!reasons.is_empty()).then_some(DuplicateMatch { customer, reasons })
+            })
+            .collect())
+    }
+
+    /// `scope` 范围内电话相同或名称相同的客户分组
+    pub async fn report(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+    ) -> Result<Vec<DuplicateGroup>, DbErr> {
+        let base = Customer::find()
+            .filter(scope.customer_condition())
+            .filter(customer::Column::IsDeleted.eq(false));
+
+        let phones: Vec<String> = base
+            .clone()
+            .select_only()
+            .column(customer::Column::PhoneNormalized)
+            .filter(customer::Column::PhoneNormalized.is_not_null())
+            .group_by(customer::Column::PhoneNormalized)
+            .having(Expr::cust("COUNT(*) > 1"))
+            .into_tuple()
+            .all(db)
+            .await?;
+        let names: Vec<String> = base
+            .clone()
+            .select_only()
+            .column(customer::Column::NameNormalized)
+            .filter(customer::Column::NameNormalized.ne(""))
+            .group_by(customer::Column::NameNormalized)
+            .having(Expr::cust("COUNT(*) > 1"))
+            .into_tuple()
+            .all(db)
+            .await?;
+
+        let mut groups = Vec::new();
+        if !phones.is_empty() {
+            let mut by_phone: BTreeMap<String, Vec<customer::Model>> = BTreeMap::new();
+            for customer in base
+                .clone()
+                .filter(customer::Column::PhoneNormalized.is_in(phones))
+                .order_by_asc(customer::Column::Id)
+                .all(db)
+                .await?
+            {
+                if let Some(phone) = customer.phone_normalized.clone() {
+                    by_phone.entry(phone).or_default().push(customer);
+                }
+            }
+            groups.extend(by_phone.into_iter().map(|(key, customers)| DuplicateGroup {
+                reason: DuplicateReason::Phone,
+                key,
+                customers,
+            }));
+        }
+        if !names.is_empty() {
+            let mut by_name: BTreeMap<String, Vec<customer::Model>> = BTreeMap::new();
+            for customer in base
+                .filter(customer::Column::NameNormalized.is_in(names))
+                .order_by_asc(customer::Column::Id)
+                .all(db)
+                .await?
+            {
+                by_name
+                    .entry(customer.name_normalized.clone())
+                    .or_default()
+                    .push(customer);
+            }
+            groups.extend(by_name.into_iter().map(|(key, customers)| DuplicateGroup {
+                reason: DuplicateReason::Name,
+                key,
+                customers,
+            }));
+        }
+
+        Ok(groups)
+    }
+
+    /// 将 `duplicate` 合并到 `survivor`：跟进记录和协作者转到保留客户，
+    /// 保留客户缺少的电话、地址用重复客户的补上，备注合并，评分取较高者；
+    /// 重复客户被软删除并记录合并到的客户。调用方需确认对两个客户都有负责人权限。
+    pub async fn merge(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        survivor: customer::Model,
+        duplicate: customer::Model,
+    ) -> Result<MergeOutcome, MergeError> {
+        if survivor.id == duplicate.id {
+            return Err(MergeError::SameCustomer);
+        }
+
+        let now = Utc::now();
+        let txn = db.begin().await?;
+
+        let moved_tracks = CustomerTrack::update_many()
+            .col_expr(customer_track::Column::CustomerId, Expr::value(survivor.id))
+            .filter(customer_track::Column::CustomerId.eq(duplicate.id))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+
+        move_collaborators(&txn, &survivor, &duplicate, actor, now).await?;
+        copy_field_values(&txn, &survivor, &duplicate, now).await?;
+
+        // 保留客户得到两者标签的并集
+        let duplicate_tags: Vec<i32> = TagService::tags_for_customer(&txn, duplicate.id)
+            .await?
+            .into_iter()
+            .map(|tag| tag.id)
+            .collect();
+        TagService::add_customer_tags(&txn, survivor.id, &duplicate_tags).await?;
+
+        // 之前合并到重复客户的记录改为指向保留客户
+        Customer::update_many()
+            .col_expr(customer::Column::MergedIntoId, Expr::value(survivor.id))
+            .filter(customer::Column::MergedIntoId.eq(duplicate.id))
+            .exec(&txn)
+            .await?;
+
+        let phone = filled(&survivor.phone).or_else(|| filled(&duplicate.phone));
+        let mut active: customer::ActiveModel = survivor.clone().into();
+        active.phone_normalized = Set(phone.as_deref().and_then(normalize_phone));
+        active.phone = Set(phone);
+        active.address = Set(filled(&survivor.address).or_else(|| filled(&duplicate.address)));
+        active.notes = Set(merge_notes(survivor.notes.as_deref(), duplicate.notes.as_deref()));
+        active.rate = Set(survivor.rate.max(duplicate.rate));
+        active.stage_id = Set(survivor.stage_id.or(duplicate.stage_id));
+        active.updated_at = Set(now);
+        let merged = active.update(&txn).await?;
+
+        let mut active: customer::ActiveModel = duplicate.clone().into();
+        active.is_deleted = Set(true);
+        active.merged_into_id = Set(Some(survivor.id));
+        active.deleted_at = Set(Some(now));
+        active.deleted_by = Set(actor.user_id);
+        active.updated_at = Set(now);
+        let removed = active.update(&txn).await?;
+
+        AuditService::record(
+            &txn,
+            actor,
+            AuditAction::Update,
+            AuditEntity::Customer,
+            survivor.id,
+            Some(&survivor),
+            Some(&merged),
+        )
+        .await?;
+        AuditService::record(
+            &txn,
+            actor,
+            AuditAction::Delete,
+            AuditEntity::Customer,
+            duplicate.id,
+            Some(&duplicate),
+            Some(&removed),
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(MergeOutcome {
+            customer: merged,
+            moved_tracks,
+        })
+    }
+}
+
+// 保留客户没有填写的自定义字段沿用重复客户的值，已填写的保持不变
+async fn copy_field_values<C: ConnectionTrait>(
+    db: &C,
+    survivor: &customer::Model,
+    duplicate: &customer::Model,
+    now: chrono::DateTime<Utc>,
+) -> Result<(), DbErr> {
+    let values = CustomerFieldValue::find()
+        .filter(customer_field_value::Column::CustomerId.eq(duplicate.id))
+        .all(db)
+        .await?;
+    if values.is_empty() {
+        return Ok(());
+    }
+
+    CustomerFieldValue::insert_many(values.into_iter().map(|value| {
+        customer_field_value::ActiveModel {
+            customer_id: Set(survivor.id),
+            field_id: Set(value.field_id),
+            value: Set(value.value),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+    }))
+    .on_conflict(
+        OnConflict::columns([
+            customer_field_value::Column::CustomerId,
+            customer_field_value::Column::FieldId,
+        ])
+        .do_nothing()
+        .to_owned(),
+    )
+    .exec_without_returning(db)
+    .await?;
+    Ok(())
+}
+
+// 重复客户的协作者转到保留客户，同一用户保留较高的权限；
+// 重复客户原负责人不是保留客户负责人时，作为可编辑的协作者继续跟进
+async fn move_collaborators<C: ConnectionTrait>(
+    db: &C,
+    survivor: &customer::Model,
+    duplicate: &customer::Model,
+    actor: &Actor,
+    now: chrono::DateTime<Utc>,
+) -> Result<(), DbErr> {
+    let mut wanted: BTreeMap<i32, CollaboratorPermission> = CustomerCollaborator::find()
+        .filter(customer_collaborator::Column::CustomerId.eq(duplicate.id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|collaborator| (collaborator.user_id, collaborator.permission))
+        .collect();
+    if let Some(owner) = duplicate.user_id {
+        wanted.insert(owner, CollaboratorPermission::Write);
+    }
+    if let Some(owner) = survivor.user_id {
+        wanted.remove(&owner);
+    }
+
+    CustomerCollaborator::delete_many()
+        .filter(customer_collaborator::Column::CustomerId.eq(duplicate.id))
+        .exec(db)
+        .await?;
+
+    for (user_id, permission) in wanted {
+        let existing = CustomerCollaborator::find()
+            .filter(customer_collaborator::Column::CustomerId.eq(survivor.id))
+            .filter(customer_collaborator::Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+        match existing {
+            Some(existing) => {
+                if existing.permission == CollaboratorPermission::Read
+                    && permission == CollaboratorPermission::Write
+                {
+                    let mut active: customer_collaborator::ActiveModel = existing.into();
+                    active.permission = Set(permission);
+                    active.updated_at = Set(now);
+                    active.update(db).await?;
+                }
+            }
+            None => {
+                customer_collaborator::ActiveModel {
+                    customer_id: Set(survivor.id),
+                    user_id: Set(user_id),
+                    permission: Set(permission),
+                    added_by: Set(actor.user_id),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn filled(value: &Option<String>) -> Option<String> {
+    value.clone().filter(|value| !value.trim().is_empty())
+}
+
+fn merge_notes(survivor: Option<&str>, duplicate: Option<&str>) -> Option<String> {
+    match (survivor, duplicate) {
+        (Some(a), Some(b)) if a.trim() == b.trim() || b.trim().is_empty() => Some(a.to_string()),
+        (Some(a), Some(b)) if a.trim().is_empty() => Some(b.to_string()),
+        (Some(a), Some(b)) => Some(format!("{}\n{}", a, b)),
+        (a, b) => a.or(b).map(str::to_string),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entities::{custom_field::CustomFieldType, user_role::UserRole},
+        services::custom_field_service::{CustomFieldService, NewCustomField, PreparedValue},
+        test_support,
+    };
+
+    #[test]
+    fn normalizes_phones_and_names() {
+        assert_eq!(
+            normalize_phone("+86 (138) 0000-0001").as_deref(),
+            Some("13800000001")
+        );
+        assert_eq!(
+            normalize_phone("0086.138.0000.0001").as_deref(),
+            Some("13800000001")
+        );
+        assert_eq!(normalize_phone("无"), None);
+        assert_eq!(normalize_name(" Zhang\t三\u{3000}\u{a0}"), "zhang三");
+    }
+
+    #[test]
+    fn similar_names() {
+        assert!(names_similar("张三", "张 三"));
+        assert!(names_similar("张三丰", "张三"));
+        assert!(names_similar("北京科技", "北京科枝"));
+        assert!(!names_similar("张三", "李四"));
+        assert!(!names_similar("张", "张三"));
+    }
+
+    #[tokio::test]
+    async fn matches_use_the_same_keys_as_the_application() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let by_name = test_support::create_customer(&db, "张\t三", None, Some(alice.id)).await;
+        let by_phone =
+            test_support::create_customer(&db, "李四", Some("138.0000.0001"), Some(alice.id)).await;
+        test_support::create_customer(&db, "王五", None, Some(alice.id)).await;
+
+        let matches = DuplicateService::find_matches(&db, 1, "张\u{3000}三", None, None)
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].customer.id, by_name.id);
+        assert_eq!(matches[0].reasons, vec![DuplicateReason::Name]);
+
+        let matches = DuplicateService::find_matches(&db, 1, "赵六", Some("+86 13800000001"), None)
+            .await
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].customer.id, by_phone.id);
+        assert_eq!(matches[0].reasons, vec![DuplicateReason::Phone]);
+    }
+
+    #[tokio::test]
+    async fn report_groups_by_the_normalized_keys() {
+        let db = test_support::test_db().await;
+        let admin = test_support::create_user(&db, "admin", UserRole::Admin).await;
+        let first = test_support::create_customer(&db, "张三", Some("13800000001"), None).await;
+        let second =
+            test_support::create_customer(&db, "张\u{a0}三", Some("138-0000-0001"), None).await;
+        test_support::create_customer(&db, "李四", Some("13900000001"), None).await;
+        let (scope, _) = test_support::scope_of(&db, &admin).await;
+
+        let groups = DuplicateService::report(&db, &scope).await.unwrap();
+
+        assert_eq!(groups.len(), 2);
+        for group in &groups {
+            let ids: Vec<i32> = group.customers.iter().map(|customer| customer.id).collect();
+            assert_eq!(ids, vec![first.id, second.id]);
+        }
+        assert_eq!(groups[0].reason, DuplicateReason::Phone);
+        assert_eq!(groups[0].key, "13800000001");
+        assert_eq!(groups[1].reason, DuplicateReason::Name);
+        assert_eq!(groups[1].key, "张三");
+    }
+
+    #[tokio::test]
+    async fn merge_copies_missing_custom_field_values() {
+        let db = test_support::test_db().await;
+        let admin = test_support::create_user(&db, "admin", UserRole::Admin).await;
+        let mut field_ids = Vec::new();
+        for key in ["budget", "source"] {
+            let field = CustomFieldService::create(
+                &db,
+                1,
+                NewCustomField {
+                    field_key: key.to_string(),
+                    label: key.to_string(),
+                    field_type: CustomFieldType::Text,
+                    options: Vec::new(),
+                    customer_groups: Vec::new(),
+                    required: false,
+                    sort_order: 0,
+                },
+            )
+            .await
+            .unwrap();
+            field_ids.push(field.id);
+        }
+        let survivor = test_support::create_customer(&db, "张三", None, Some(admin.id)).await;
+        let duplicate = test_support::create_customer(&db, "张 三", None, Some(admin.id)).await;
+        let save = |customer_id, field_id, value: &str| {
+            let value = PreparedValue {
+                field_id,
+                value: Some(value.to_string()),
+            };
+            CustomFieldService::save_values(&db, customer_id, vec![value])
+        };
+        save(survivor.id, field_ids[0], "1000").await.unwrap();
+        save(duplicate.id, field_ids[0], "2000").await.unwrap();
+        save(duplicate.id, field_ids[1], "转介绍").await.unwrap();
+
+        let (_, actor) = test_support::scope_of(&db, &admin).await;
+        DuplicateService::merge(&db, &actor, survivor.clone(), duplicate.clone())
+            .await
+            .unwrap();
+
+        // 保留客户已有的值不被覆盖，缺少的值从重复客户复制
+        let merged = CustomFieldService::snapshot(&db, survivor.id)
+            .await
+            .unwrap();
+        assert_eq!(merged.get("budget"), Some(&serde_json::json!("1000")));
+        assert_eq!(merged.get("source"), Some(&serde_json::json!("转介绍")));
+        let kept = CustomFieldService::snapshot(&db, duplicate.id)
+            .await
+            .unwrap();
+        assert_eq!(kept.len(), 2);
+    }
+}
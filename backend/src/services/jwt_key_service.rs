@@ -0,0 +1,464 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, RwLock},
+    time::Duration as StdDuration,
+};
+
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use chrono::{DateTime, Duration, Utc};
+use jsonwebtoken::{
+    decode, decode_header, encode,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse,
+    },
+    Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use uuid::Uuid;
+
+use crate::entities::jwt_signing_key::{self, Entity as JwtSigningKey};
+
+/// 签名算法，目前只生成 Ed25519 密钥
+const ALGORITHM: Algorithm = Algorithm::EdDSA;
+const ALGORITHM_NAME: &str = "EdDSA";
+
+/// 令牌的签发者，校验时要求 `iss` 与之一致
+pub const ISSUER: &str = "customer-tracker";
+
+/// 服务器重新读取密钥的间隔，命令行轮换后最迟在这个时间内生效
+const RELOAD_INTERVAL_SECONDS: u64 = 60;
+
+#[derive(Debug, thiserror::Error)]
+pub enum JwtKeyError {
+    #[error("没有可用的签名密钥")]
+    NoSigningKey,
+    #[error("令牌使用了未知的密钥")]
+    UnknownKey,
+    #[error("生成密钥失败")]
+    KeyGeneration,
+    #[error("密钥 {0} 无效")]
+    InvalidKey(String),
+    #[error(transparent)]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 签名密钥的管理：生成、轮换和列出，供命令行和服务器启动时使用
+pub struct JwtKeyService;
+
+impl JwtKeyService {
+    /// 所有密钥，最新的在前
+    pub async fn list(db: &DatabaseConnection) -> Result<Vec<jwt_signing_key::Model>, JwtKeyError> {
+        let keys = JwtSigningKey::find()
+            .order_by_desc(jwt_signing_key::Column::Id)
+            .all(db)
+            .await?;
+        Ok(keys)
+    }
+
+    /// 生成新的签名密钥，旧密钥退役但在 `grace` 内仍可校验已签发的令牌。
+    ///
+    /// `grace` 为零时旧密钥立即失效，用于密钥泄露的情况；
+    /// 用户会在访问令牌失效后用刷新令牌重新换取，不需要重新登录。
+    pub async fn rotate(
+        db: &DatabaseConnection,
+        grace: Duration,
+    ) -> Result<jwt_signing_key::Model, JwtKeyError> {
+        let (private_key, public_key) = generate_key_pair()?;
+        let now = Utc::now();
+
+        let txn = db.begin().await?;
+        // 过期的密钥已经没有用处
+        JwtSigningKey::delete_many()
+            .filter(jwt_signing_key::Column::ExpiresAt.lt(now))
+            .exec(&txn)
+            .await?;
+        JwtSigningKey::update_many()
+            .col_expr(jwt_signing_key::Column::RetiredAt, now.into())
+            .col_expr(jwt_signing_key::Column::ExpiresAt, (now + grace).into())
+            .filter(jwt_signing_key::Column::RetiredAt.is_null())
+            .exec(&txn)
+            .await?;
+        let key = jwt_signing_key::ActiveModel {
+            kid: Set(Uuid::new_v4().simple().to_string()),
+            algorithm: Set(ALGORITHM_NAME.to_string()),
+            private_key: Set(private_key),
+            public_key: Set(public_key),
+            created_at: Set(now),
+            retired_at: Set(None),
+            expires_at: Set(None),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+
+        Ok(key)
+    }
+}
+
+struct SigningKey {
+    kid: String,
+    key: EncodingKey,
+}
+
+struct VerificationKey {
+    key: DecodingKey,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+struct KeySet {
+    signing: Option<SigningKey>,
+    verification: HashMap<String, VerificationKey>,
+    jwks: JwkSet,
+}
+
+impl Default for KeySet {
+    fn default() -> Self {
+        Self {
+            signing: None,
+            verification: HashMap::new(),
+            jwks: JwkSet { keys: Vec::new() },
+        }
+    }
+}
+
+/// 服务器内存中的密钥，定期从数据库重新读取
+pub struct JwtKeyStore {
+    keys: RwLock<KeySet>,
+}
+
+impl JwtKeyStore {
+    /// 读取密钥；首次启动还没有密钥时自动生成一个
+    pub async fn load(db: &DatabaseConnection) -> Result<Self, JwtKeyError> {
+        let store = Self {
+            keys: RwLock::new(KeySet::default()),
+        };
+        store.reload(db).await?;
+
+        if store.keys.read().unwrap().signing.is_none() {
+            let key = JwtKeyService::rotate(db, Duration::zero()).await?;
+            tracing::info!("已生成 JWT 签名密钥 {}", key.kid);
+            store.reload(db).await?;
+        }
+
+        Ok(store)
+    }
+
+    pub async fn reload(&self, db: &DatabaseConnection) -> Result<(), JwtKeyError> {
+        let models = JwtKeyService::list(db).await?;
+
+        let mut key_set = KeySet::default();
+        for model in models.into_iter().filter(|model| model.is_valid()) {
+            let public_key = DecodingKey::from_ed_components(&model.public_key)
+                .map_err(|_| JwtKeyError::InvalidKey(model.kid.clone()))?;
+
+            // 列表按ID倒序，第一个未退役的就是当前签名密钥
+            if key_set.signing.is_none() && model.retired_at.is_none() {
+                let private_key = STANDARD
+                    .decode(&model.private_key)
+                    .map_err(|_| JwtKeyError::InvalidKey(model.kid.clone()))?;
+                key_set.signing = Some(SigningKey {
+                    kid: model.kid.clone(),
+                    key: EncodingKey::from_ed_der(&private_key),
+                });
+            }
+
+            key_set.jwks.keys.push(public_jwk(&model));
+            key_set.verification.insert(
+                model.kid,
+                VerificationKey {
+                    key: public_key,
+                    expires_at: model.expires_at,
+                },
+            );
+        }
+
+        *self.keys.write().unwrap() = key_set;
+        Ok(())
+    }
+
+    /// 后台定期重新读取，使命令行轮换的密钥无需重启即可生效
+    pub fn spawn_reload(self: Arc<Self>, db: DatabaseConnection) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(StdDuration::from_secs(RELOAD_INTERVAL_SECONDS));
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(e) = self.reload(&db).await {
+                    tracing::warn!("重新读取 JWT 签名密钥失败: {}", e);
+                }
+            }
+        });
+    }
+
+    /// 用当前密钥签名，令牌头部带上 `kid`
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, JwtKeyError> {
+        let keys = self.keys.read().unwrap();
+        let signing = keys.signing.as_ref().ok_or(JwtKeyError::NoSigningKey)?;
+
+        let mut header = Header::new(ALGORITHM);
+        header.kid = Some(signing.kid.clone());
+        Ok(encode(&header, claims, &signing.key)?)
+    }
+
+    /// 按 `kid` 选择公钥校验签名和有效期，并要求 `iss` 为 [`ISSUER`]、`aud` 为 `audience`
+    pub fn verify<T: DeserializeOwned>(&self, token: &str, audience: &str) -> Result<T, JwtKeyError> {
+        let header = decode_header(token)?;
+        let kid = header.kid.ok_or(JwtKeyError::UnknownKey)?;
+
+        let keys = self.keys.read().unwrap();
+        let verification = keys
+            .verification
+            .get(&kid)
+            .filter(|key| key.expires_at.is_none_or(|expires_at| expires_at > Utc::now()))
+            .ok_or(JwtKeyError::UnknownKey)?;
+
+        let mut validation = Validation::new(ALGORITHM);
+        validation.set_issuer(&[ISSUER]);
+        validation.set_audience(&[audience]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud"]);
+        let token_data = decode::<T>(token, &verification.key, &validation)?;
+        Ok(token_data.claims)
+    }
+
+    /// 当前可用于校验的公钥，供 `/.well-known/jwks.json` 使用
+    pub fn jwks(&self) -> JwkSet {
+        self.keys.read().unwrap().jwks.clone()
+    }
+}
+
+/// 生成 Ed25519 密钥对，返回 (PKCS#8 私钥 base64, 公钥 base64url)
+fn generate_key_pair() -> Result<(String, String), JwtKeyError> {
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+        .map_err(|_| JwtKeyError::KeyGeneration)?;
+    let key_pair =
+        Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).map_err(|_| JwtKeyError::KeyGeneration)?;
+
+    Ok((
+        STANDARD.encode(pkcs8.as_ref()),
+        URL_SAFE_NO_PAD.encode(key_pair.public_key().as_ref()),
+    ))
+}
+
+fn public_jwk(model: &jwt_signing_key::Model) -> Jwk {
+    Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(KeyAlgorithm::EdDSA),
+            key_id: Some(model.kid.clone()),
+            ..Default::default()
+        },
+        algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: model.public_key.clone(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::test_support;
+
+    const AUDIENCE: &str = "test";
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct TestClaims {
+        sub: String,
+        iss: String,
+        aud: String,
+        exp: usize,
+    }
+
+    fn claims() -> TestClaims {
+        TestClaims {
+            sub: "alice".to_string(),
+            iss: ISSUER.to_string(),
+            aud: AUDIENCE.to_string(),
+            exp: (Utc::now() + Duration::minutes(5)).timestamp() as usize,
+        }
+    }
+
+    fn kid_of(token: &str) -> String {
+        decode_header(token).unwrap().kid.unwrap()
+    }
+
+    fn claims_of(token: &str) -> TestClaims {
+        let payload = token.split('.').nth(1).unwrap();
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap()
+    }
+
+    fn verify(store: &JwtKeyStore, token: &str) -> Result<TestClaims, JwtKeyError> {
+        store.verify(token, AUDIENCE)
+    }
+
+    #[tokio::test]
+    async fn old_tokens_verify_during_the_grace_period() {
+        let db = test_support::test_db().await;
+        let store = JwtKeyStore::load(&db).await.unwrap();
+        let old_token = store.sign(&claims()).unwrap();
+
+        let new_key = JwtKeyService::rotate(&db, Duration::hours(1))
+            .await
+            .unwrap();
+        store.reload(&db).await.unwrap();
+        let new_token = store.sign(&claims()).unwrap();
+
+        assert_eq!(kid_of(&new_token), new_key.kid);
+        assert_ne!(kid_of(&old_token), new_key.kid);
+        assert_eq!(verify(&store, &old_token).unwrap(), claims_of(&old_token));
+        assert!(verify(&store, &new_token).is_ok());
+    }
+
+    #[tokio::test]
+    async fn retired_keys_fail_after_the_grace_period() {
+        let db = test_support::test_db().await;
+        let store = JwtKeyStore::load(&db).await.unwrap();
+        let old_token = store.sign(&claims()).unwrap();
+
+        // 宽限期在两次重新读取之间结束时，内存中的密钥也不再接受
+        JwtKeyService::rotate(&db, Duration::milliseconds(500))
+            .await
+            .unwrap();
+        store.reload(&db).await.unwrap();
+        tokio::time::sleep(StdDuration::from_millis(600)).await;
+        assert!(matches!(
+            verify(&store, &old_token),
+            Err(JwtKeyError::UnknownKey)
+        ));
+        // 重新读取后过期的密钥不再加载，也不再公布
+        store.reload(&db).await.unwrap();
+        assert!(matches!(
+            verify(&store, &old_token),
+            Err(JwtKeyError::UnknownKey)
+        ));
+        assert_eq!(store.jwks().keys.len(), 1);
+
+        // 没有宽限期的轮换立即生效
+        let token = store.sign(&claims()).unwrap();
+        JwtKeyService::rotate(&db, Duration::zero()).await.unwrap();
+        store.reload(&db).await.unwrap();
+        assert!(matches!(
+            verify(&store, &token),
+            Err(JwtKeyError::UnknownKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn unknown_and_missing_kids_are_rejected() {
+        let db = test_support::test_db().await;
+        let store = JwtKeyStore::load(&db).await.unwrap();
+        let other = JwtKeyStore::load(&test_support::test_db().await)
+            .await
+            .unwrap();
+
+        // 其他服务器的密钥签发的令牌
+        let token = other.sign(&claims()).unwrap();
+        assert!(matches!(
+            verify(&store, &token),
+            Err(JwtKeyError::UnknownKey)
+        ));
+
+        let keys = store.keys.read().unwrap();
+        let signing = keys.signing.as_ref().unwrap();
+        let token = encode(&Header::new(ALGORITHM), &claims(), &signing.key).unwrap();
+        let mut header = Header::new(ALGORITHM);
+        header.kid = Some("unknown".to_string());
+        let unknown = encode(&header, &claims(), &signing.key).unwrap();
+        drop(keys);
+        assert!(matches!(
+            verify(&store, &token),
+            Err(JwtKeyError::UnknownKey)
+        ));
+        assert!(matches!(
+            verify(&store, &unknown),
+            Err(JwtKeyError::UnknownKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn issuer_and_audience_are_checked() {
+        let db = test_support::test_db().await;
+        let store = JwtKeyStore::load(&db).await.unwrap();
+
+        let token = store.sign(&claims()).unwrap();
+        assert!(matches!(
+            store.verify::<TestClaims>(&token, "other"),
+            Err(JwtKeyError::Jwt(_))
+        ));
+
+        let forged = TestClaims {
+            iss: "someone-else".to_string(),
+            ..claims()
+        };
+        let token = store.sign(&forged).unwrap();
+        assert!(matches!(verify(&store, &token), Err(JwtKeyError::Jwt(_))));
+
+        #[derive(Serialize)]
+        struct WithoutIssuer {
+            sub: String,
+            aud: String,
+            exp: usize,
+        }
+        let token = store
+            .sign(&WithoutIssuer {
+                sub: "alice".to_string(),
+                aud: AUDIENCE.to_string(),
+                exp: claims().exp,
+            })
+            .unwrap();
+        assert!(matches!(verify(&store, &token), Err(JwtKeyError::Jwt(_))));
+    }
+
+    #[tokio::test]
+    async fn jwks_publishes_only_the_public_keys() {
+        let db = test_support::test_db().await;
+        let store = JwtKeyStore::load(&db).await.unwrap();
+        JwtKeyService::rotate(&db, Duration::hours(1))
+            .await
+            .unwrap();
+        store.reload(&db).await.unwrap();
+
+        let models = JwtKeyService::list(&db).await.unwrap();
+        let jwks = store.jwks();
+        assert_eq!(jwks.keys.len(), 2);
+        for (jwk, model) in jwks.keys.iter().zip(&models) {
+            assert_eq!(jwk.common.key_id.as_deref(), Some(model.kid.as_str()));
+            assert_eq!(jwk.common.key_algorithm, Some(KeyAlgorithm::EdDSA));
+            assert_eq!(jwk.common.public_key_use, Some(PublicKeyUse::Signature));
+            match &jwk.algorithm {
+                AlgorithmParameters::OctetKeyPair(params) => {
+                    assert_eq!(params.curve, EllipticCurve::Ed25519);
+                    assert_eq!(params.x, model.public_key);
+                }
+                other => panic!("不是 Ed25519 公钥: {:?}", other),
+            }
+        }
+
+        // 序列化后不含私钥
+        let json = serde_json::to_value(&jwks).unwrap();
+        for (key, model) in json["keys"].as_array().unwrap().iter().zip(&models) {
+            assert!(key.get("d").is_none());
+            assert_eq!(key["kid"], model.kid);
+            assert_eq!(key["alg"], ALGORITHM_NAME);
+            assert!(!json.to_string().contains(&model.private_key));
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Condition, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set,
+};
+
+use crate::{
+    entities::{
+        login_history::{self, Entity as LoginHistory, LoginFailureReason},
+        user,
+    },
+    utils::client_ip::ClientInfo,
+};
+
+pub struct LoginHistoryService;
+
+impl LoginHistoryService {
+    /// 记录一次成功登录及其创建的会话
+    pub async fn record_success(
+        db: &DatabaseConnection,
+        user: &user::Model,
+        client: &ClientInfo,
+        session_id: i32,
+    ) -> Result<(), DbErr> {
+        insert(db, Some(user.id), &user.username, client, None, Some(session_id)).await
+    }
+
+    /// 记录一次失败的登录，用户名不存在时 `user_id` 为空
+    pub async fn record_failure(
+        db: &DatabaseConnection,
+        user_id: Option<i32>,
+        username: &str,
+        client: &ClientInfo,
+        reason: LoginFailureReason,
+    ) -> Result<(), DbErr> {
+        insert(db, user_id, username, client, Some(reason), None).await
+    }
+
+    /// 用户最近的登录记录，包括登录前未能识别出用户ID的失败记录
+    pub async fn recent_for_user(
+        db: &DatabaseConnection,
+        user: &user::Model,
+        limit: u64,
+    ) -> Result<Vec<login_history::Model>, DbErr> {
+        LoginHistory::find()
+            .filter(
+                Condition::any()
+                    .add(login_history::Column::UserId.eq(user.id))
+                    .add(
+                        Condition::all()
+                            .add(login_history::Column::UserId.is_null())
+                            .add(login_history::Column::Username.eq(&user.username)),
+                    ),
+            )
+            .order_by_desc(login_history::Column::CreatedAt)
+            .order_by_desc(login_history::Column::Id)
+            .limit(limit)
+            .all(db)
+            .await
+    }
+}
+
+async fn insert(
+    db: &DatabaseConnection,
+    user_id: Option<i32>,
+    username: &str,
+    client: &ClientInfo,
+    failure_reason: Option<LoginFailureReason>,
+    session_id: Option<i32>,
+) -> Result<(), DbErr> {
+    login_history::ActiveModel {
+        user_id: Set(user_id),
+        username: Set(username.chars().take(50).collect()),
+        ip_address: Set(client.ip.clone()),
+        user_agent: Set(client.user_agent.clone()),
+        success: Set(failure_reason.is_none()),
+        failure_reason: Set(failure_reason),
+        session_id: Set(session_id),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+    Ok(())
+}
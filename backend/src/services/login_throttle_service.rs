@@ -0,0 +1,341 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    sea_query::{Expr, OnConflict},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+
+use crate::{
+    config::Config,
+    entities::login_throttle::{self, Entity as LoginThrottle, ThrottleKind},
+};
+
+/// 清理过期记录的间隔
+const PRUNE_INTERVAL: StdDuration = StdDuration::from_secs(60 * 60);
+
+/// 登录限流策略
+#[derive(Debug, Clone)]
+pub struct LoginThrottlePolicy {
+    /// 同一用户名允许的连续失败次数
+    pub max_failed_per_username: i32,
+    /// 同一IP允许的连续失败次数
+    pub max_failed_per_ip: i32,
+    /// 第一次锁定的时长，之后每次锁定翻倍
+    pub lockout_base_seconds: i64,
+    /// 锁定时长上限
+    pub lockout_max_seconds: i64,
+}
+
+impl LoginThrottlePolicy {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            max_failed_per_username: config.login_max_failed_attempts,
+            max_failed_per_ip: config.login_max_failed_attempts_per_ip,
+            lockout_base_seconds: config.login_lockout_base_seconds,
+            lockout_max_seconds: config.login_lockout_max_seconds,
+        }
+    }
+
+    fn threshold(&self, kind: ThrottleKind) -> i32 {
+        match kind {
+            ThrottleKind::Username => self.max_failed_per_username,
+            ThrottleKind::Ip => self.max_failed_per_ip,
+        }
+    }
+
+    /// 第 n 次锁定的时长：base * 2^(n-1)，不超过上限
+    fn lockout_duration(&self, lockout_count: i32) -> Duration {
+        let exponent = (lockout_count - 1).clamp(0, 30) as u32;
+        let seconds = self
+            .lockout_base_seconds
+            .saturating_mul(2_i64.saturating_pow(exponent))
+            .min(self.lockout_max_seconds);
+        Duration::seconds(seconds)
+    }
+}
+
+pub struct LoginThrottleService;
+
+impl LoginThrottleService {
+    /// 返回用户名或IP当前的锁定截止时间（取较晚者），未锁定时返回 `None`
+    pub async fn locked_until(
+        db: &DatabaseConnection,
+        username: &str,
+        ip: Option<&str>,
+    ) -> Result<Option<DateTime<Utc>>, DbErr> {
+        let now = Utc::now();
+        let mut until = None;
+
+        for (kind, key) in keys(username, ip) {
+            if let Some(row) = find(db, kind, key).await?
+                && let Some(locked_until) = row.locked_until
+                && locked_until > now
+            {
+                until = until.max(Some(locked_until));
+            }
+        }
+
+        Ok(until)
+    }
+
+    /// 记录一次登录失败，达到阈值时锁定。
+    ///
+    /// 计数在数据库中原子地加一，是否锁定取决于加一之后保存的值，
+    /// 并发的失败请求不会读到同一个旧计数而少算次数
+    pub async fn record_failure(
+        db: &DatabaseConnection,
+        policy: &LoginThrottlePolicy,
+        username: &str,
+        ip: Option<&str>,
+    ) -> Result<(), DbErr> {
+        let now = Utc::now();
+        let stale_before = now - Duration::seconds(policy.lockout_max_seconds);
+
+        // 事务的第一条语句就是写入，从这里开始持有写锁，并发的失败记录依次执行
+        let txn = db.begin().await?;
+        for (kind, key) in keys(username, ip) {
+            LoginThrottle::insert(login_throttle::ActiveModel {
+                kind: Set(kind),
+                key: Set(key.to_string()),
+                failed_count: Set(0),
+                lockout_count: Set(0),
+                locked_until: Set(None),
+                last_failed_at: Set(None),
+                updated_at: Set(now),
+                ..Default::default()
+            })
+            .on_conflict(
+                OnConflict::columns([login_throttle::Column::Kind, login_throttle::Column::Key])
+                    .do_nothing()
+                    .to_owned(),
+            )
+            .exec_without_returning(&txn)
+            .await?;
+
+            // 距离上次失败已超过最长锁定时间，重新开始计数
+            let stale = Expr::col(login_throttle::Column::LastFailedAt).lt(stale_before);
+            LoginThrottle::update_many()
+                .col_expr(
+                    login_throttle::Column::FailedCount,
+                    Expr::case(stale.clone(), 1)
+                        .finally(Expr::col(login_throttle::Column::FailedCount).add(1))
+                        .into(),
+                )
+                .col_expr(
+                    login_throttle::Column::LockoutCount,
+                    Expr::case(stale, 0)
+                        .finally(Expr::col(login_throttle::Column::LockoutCount))
+                        .into(),
+                )
+                .col_expr(login_throttle::Column::LastFailedAt, now.into())
+                .col_expr(login_throttle::Column::UpdatedAt, now.into())
+                .filter(login_throttle::Column::Kind.eq(kind))
+                .filter(login_throttle::Column::Key.eq(key))
+                .exec(&txn)
+                .await?;
+
+            let row = find(&txn, kind, key).await?.ok_or(DbErr::RecordNotFound(
+                "login_throttles".to_string(),
+            ))?;
+            if row.failed_count >= policy.threshold(kind) {
+                let lockout_count = row.lockout_count + 1;
+                let locked_until = now + policy.lockout_duration(lockout_count);
+                let mut active: login_throttle::ActiveModel = row.into();
+                active.failed_count = Set(0);
+                active.lockout_count = Set(lockout_count);
+                active.locked_until = Set(Some(locked_until));
+                active.update(&txn).await?;
+                tracing::warn!("登录失败次数过多，已锁定 {:?} '{}' 至 {}", kind, key, locked_until);
+            }
+        }
+        txn.commit().await?;
+
+        Ok(())
+    }
+
+    /// 删除已经过期的记录：没有处于锁定中，且距离上次失败已超过最长锁定时间，
+    /// 这些记录下次失败时也会重新计数。不存在的用户名也会产生记录，需要定期清理
+    pub async fn prune_expired(
+        db: &DatabaseConnection,
+        policy: &LoginThrottlePolicy,
+        now: DateTime<Utc>,
+    ) -> Result<u64, DbErr> {
+        let stale_before = now - Duration::seconds(policy.lockout_max_seconds);
+        let result = LoginThrottle::delete_many()
+            .filter(
+                Condition::any()
+                    .add(login_throttle::Column::LockedUntil.is_null())
+                    .add(login_throttle::Column::LockedUntil.lte(now)),
+            )
+            .filter(
+                Condition::any()
+                    .add(login_throttle::Column::LastFailedAt.is_null())
+                    .add(login_throttle::Column::LastFailedAt.lt(stale_before)),
+            )
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// 定期清理过期的记录
+    pub fn spawn_prune(db: DatabaseConnection, policy: LoginThrottlePolicy) {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(PRUNE_INTERVAL);
+            loop {
+                interval.tick().await;
+                match Self::prune_expired(&db, &policy, Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(pruned) => tracing::info!("已清理 {} 条过期的登录失败记录", pruned),
+                    Err(e) => tracing::warn!("清理登录失败记录失败: {}", e),
+                }
+            }
+        });
+    }
+
+    /// 登录成功后清除该用户名的失败计数
+    pub async fn record_success(db: &DatabaseConnection, username: &str) -> Result<(), DbErr> {
+        Self::clear(db, ThrottleKind::Username, username).await?;
+        Ok(())
+    }
+
+    /// 解除锁定，返回是否存在锁定记录
+    pub async fn clear(
+        db: &DatabaseConnection,
+        kind: ThrottleKind,
+        key: &str,
+    ) -> Result<bool, DbErr> {
+        let result = LoginThrottle::delete_many()
+            .filter(login_throttle::Column::Kind.eq(kind))
+            .filter(login_throttle::Column::Key.eq(key))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+fn keys<'a>(username: &'a str, ip: Option<&'a str>) -> Vec<(ThrottleKind, &'a str)> {
+    let mut keys = vec![(ThrottleKind::Username, username)];
+    if let Some(ip) = ip {
+        keys.push((ThrottleKind::Ip, ip));
+    }
+    keys
+}
+
+async fn find<C: ConnectionTrait>(
+    db: &C,
+    kind: ThrottleKind,
+    key: &str,
+) -> Result<Option<login_throttle::Model>, DbErr> {
+    LoginThrottle::find()
+        .filter(login_throttle::Column::Kind.eq(kind))
+        .filter(login_throttle::Column::Key.eq(key))
+        .one(db)
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn policy() -> LoginThrottlePolicy {
+        LoginThrottlePolicy {
+            max_failed_per_username: 3,
+            max_failed_per_ip: 5,
+            lockout_base_seconds: 60,
+            lockout_max_seconds: 3600,
+        }
+    }
+
+    #[test]
+    fn lockout_doubles_up_to_the_limit() {
+        let policy = policy();
+        assert_eq!(policy.lockout_duration(1), Duration::seconds(60));
+        assert_eq!(policy.lockout_duration(2), Duration::seconds(120));
+        assert_eq!(policy.lockout_duration(7), Duration::seconds(3600));
+        assert_eq!(policy.lockout_duration(100), Duration::seconds(3600));
+    }
+
+    #[tokio::test]
+    async fn locks_after_the_threshold() {
+        let db = test_support::test_db().await;
+        let policy = policy();
+
+        for _ in 0..2 {
+            LoginThrottleService::record_failure(&db, &policy, "alice", None).await.unwrap();
+        }
+        assert!(LoginThrottleService::locked_until(&db, "alice", None).await.unwrap().is_none());
+
+        LoginThrottleService::record_failure(&db, &policy, "alice", None).await.unwrap();
+        let until = LoginThrottleService::locked_until(&db, "alice", None).await.unwrap();
+        assert!(until.is_some_and(|until| until > Utc::now()));
+        assert!(LoginThrottleService::locked_until(&db, "bob", None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn ip_is_counted_across_usernames() {
+        let db = test_support::test_db().await;
+        let policy = policy();
+
+        for username in ["a", "b", "c", "d", "e"] {
+            LoginThrottleService::record_failure(&db, &policy, username, Some("10.0.0.1"))
+                .await
+                .unwrap();
+        }
+        let locked = LoginThrottleService::locked_until(&db, "new", Some("10.0.0.1")).await;
+        assert!(locked.unwrap().is_some());
+        let other_ip = LoginThrottleService::locked_until(&db, "new", Some("10.0.0.2")).await;
+        assert!(other_ip.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn concurrent_failures_are_all_counted() {
+        let db = test_support::test_db().await;
+        let policy = policy();
+
+        let (a, b, c) = tokio::join!(
+            LoginThrottleService::record_failure(&db, &policy, "alice", None),
+            LoginThrottleService::record_failure(&db, &policy, "alice", None),
+            LoginThrottleService::record_failure(&db, &policy, "alice", None),
+        );
+        a.unwrap();
+        b.unwrap();
+        c.unwrap();
+
+        assert!(LoginThrottleService::locked_until(&db, "alice", None).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn success_clears_the_username_counter() {
+        let db = test_support::test_db().await;
+        let policy = policy();
+
+        for _ in 0..2 {
+            LoginThrottleService::record_failure(&db, &policy, "alice", None).await.unwrap();
+        }
+        LoginThrottleService::record_success(&db, "alice").await.unwrap();
+        for _ in 0..2 {
+            LoginThrottleService::record_failure(&db, &policy, "alice", None).await.unwrap();
+        }
+
+        assert!(LoginThrottleService::locked_until(&db, "alice", None).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn prune_keeps_active_lockouts_and_recent_failures() {
+        let db = test_support::test_db().await;
+        let policy = policy();
+
+        for _ in 0..3 {
+            LoginThrottleService::record_failure(&db, &policy, "locked", None).await.unwrap();
+        }
+        LoginThrottleService::record_failure(&db, &policy, "recent", None).await.unwrap();
+
+        assert_eq!(LoginThrottleService::prune_expired(&db, &policy, Utc::now()).await.unwrap(), 0);
+
+        let later = Utc::now() + Duration::seconds(policy.lockout_max_seconds + 60);
+        assert_eq!(LoginThrottleService::prune_expired(&db, &policy, later).await.unwrap(), 2);
+    }
+}
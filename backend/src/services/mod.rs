@@ -1,2 +1,24 @@
+pub mod api_token_service;
+pub mod audit_service;
 pub mod auth_service;
-pub mod track_service;
\ No newline at end of file
+pub mod collaborator_service;
+pub mod custom_field_service;
+pub mod customer_list_service;
+pub mod customer_group_service;
+pub mod customer_pool_service;
+pub mod customer_transfer_service;
+pub mod duplicate_service;
+pub mod jwt_key_service;
+pub mod login_history_service;
+pub mod login_throttle_service;
+pub mod notification_service;
+pub mod oidc_service;
+pub mod permission_service;
+pub mod pipeline_service;
+pub mod search_service;
+pub mod session_service;
+pub mod tag_service;
+pub mod track_service;
+pub mod trash_service;
+pub mod two_factor_service;
+pub mod user_service;
\ No newline at end of file
@@ -0,0 +1,147 @@
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+
+use crate::entities::notification::{self, Entity as Notification};
+
+/// 通知正文中最多列出的客户名数量
+const SUMMARY_NAME_LIMIT: usize = 5;
+
+pub struct NotificationService;
+
+impl NotificationService {
+    /// 给用户发送一条站内通知，传入事务连接即可与业务变更一起提交
+    pub async fn notify<C: ConnectionTrait>(
+        db: &C,
+        user_id: i32,
+        title: &str,
+        body: &str,
+    ) -> Result<notification::Model, DbErr> {
+        notification::ActiveModel {
+            user_id: Set(user_id),
+            title: Set(title.to_string()),
+            body: Set(body.to_string()),
+            read_at: Set(None),
+            created_at: Set(Utc::now()),
+            ..Default::default()
+        }
+        .insert(db)
+        .await
+    }
+
+    /// 分页查询用户的通知，最新的在前；返回当前页和总数
+    pub async fn list_for_user(
+        db: &DatabaseConnection,
+        user_id: i32,
+        unread_only: bool,
+        page: u64,
+        limit: u64,
+    ) -> Result<(Vec<notification::Model>, u64), DbErr> {
+        let mut query = Notification::find().filter(notification::Column::UserId.eq(user_id));
+        if unread_only {
+            query = query.filter(notification::Column::ReadAt.is_null());
+        }
+
+        let paginator = query
+            .order_by_desc(notification::Column::Id)
+            .paginate(db, limit.max(1));
+        let notifications = paginator.fetch_page(page.saturating_sub(1)).await?;
+        let total = paginator.num_items().await?;
+
+        Ok((notifications, total))
+    }
+
+    pub async fn unread_count(db: &DatabaseConnection, user_id: i32) -> Result<u64, DbErr> {
+        Notification::find()
+            .filter(notification::Column::UserId.eq(user_id))
+            .filter(notification::Column::ReadAt.is_null())
+            .count(db)
+            .await
+    }
+
+    /// 标记用户自己的某条通知为已读，返回是否找到该通知
+    pub async fn mark_read(
+        db: &DatabaseConnection,
+        user_id: i32,
+        notification_id: i32,
+    ) -> Result<bool, DbErr> {
+        let exists = Notification::find_by_id(notification_id)
+            .filter(notification::Column::UserId.eq(user_id))
+            .one(db)
+            .await?
+            .is_some();
+        if exists {
+            Notification::update_many()
+                .col_expr(notification::Column::ReadAt, Utc::now().into())
+                .filter(notification::Column::Id.eq(notification_id))
+                .filter(notification::Column::ReadAt.is_null())
+                .exec(db)
+                .await?;
+        }
+        Ok(exists)
+    }
+
+    /// 标记用户的全部通知为已读，返回标记的数量
+    pub async fn mark_all_read(db: &DatabaseConnection, user_id: i32) -> Result<u64, DbErr> {
+        let result = Notification::update_many()
+            .col_expr(notification::Column::ReadAt, Utc::now().into())
+            .filter(notification::Column::UserId.eq(user_id))
+            .filter(notification::Column::ReadAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+}
+
+/// 通知正文中的客户名列表，超过数量时只列出前几个
+pub fn summarize_names(names: &[String]) -> String {
+    let mut summary = names
+        .iter()
+        .take(SUMMARY_NAME_LIMIT)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join("、");
+    if names.len() > SUMMARY_NAME_LIMIT {
+        summary.push_str(&format!(" 等{}位客户", names.len()));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entities::user_role::UserRole, test_support};
+
+    #[tokio::test]
+    async fn users_can_only_mark_their_own_notifications_read() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let first = NotificationService::notify(&db, alice.id, "客户已转移", "一").await.unwrap();
+        NotificationService::notify(&db, alice.id, "客户已转移", "二").await.unwrap();
+
+        assert!(!NotificationService::mark_read(&db, bob.id, first.id).await.unwrap());
+        assert_eq!(NotificationService::unread_count(&db, alice.id).await.unwrap(), 2);
+
+        assert!(NotificationService::mark_read(&db, alice.id, first.id).await.unwrap());
+        let (unread, total) =
+            NotificationService::list_for_user(&db, alice.id, true, 1, 10).await.unwrap();
+        assert_eq!(total, 1);
+        assert_eq!(unread[0].body, "二");
+
+        assert_eq!(NotificationService::mark_all_read(&db, alice.id).await.unwrap(), 1);
+        assert_eq!(NotificationService::unread_count(&db, alice.id).await.unwrap(), 0);
+    }
+
+    #[test]
+    fn summarizes_long_name_lists() {
+        let names: Vec<String> = (1..=7).map(|i| format!("客户{}", i)).collect();
+        assert_eq!(summarize_names(&names[..2]), "客户1、客户2");
+        assert_eq!(
+            summarize_names(&names),
+            "客户1、客户2、客户3、客户4、客户5 等7位客户"
+        );
+    }
+}
@@ -0,0 +1,604 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{decode, decode_header, jwk::JwkSet, Algorithm, DecodingKey, Validation};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::sync::OnceCell;
+
+use crate::{
+    config::Config,
+    entities::{
+        oidc_login_state::{self, Entity as OidcLoginState},
+        user::{self, Entity as User},
+        user_role::UserRole,
+    },
+    services::{
+        audit_service::Actor,
+        user_service::{NewUser, UserError, UserService},
+    },
+    utils::{
+        token::{generate_secret, hash_secret},
+        validation::PasswordPolicy,
+    },
+};
+
+/// 授权请求的有效期，超时后回调会被拒绝
+const LOGIN_STATE_EXPIRE_MINUTES: i64 = 10;
+
+/// 自动创建用户时用户名的最大长度，留出重名后缀的位置
+const MAX_USERNAME_BASE_LENGTH: usize = 40;
+
+#[derive(Debug, thiserror::Error)]
+pub enum OidcError {
+    #[error("登录请求无效或已过期，请重新登录")]
+    InvalidState,
+    #[error("身份提供方请求失败: {0}")]
+    Provider(String),
+    #[error("身份令牌无效: {0}")]
+    InvalidIdToken(String),
+    #[error("身份提供方返回的邮箱未经验证")]
+    EmailNotVerified,
+    #[error("没有与该单点登录账号对应的用户，请联系管理员")]
+    UserNotFound,
+    #[error("用户已停用")]
+    UserDisabled,
+    #[error(transparent)]
+    User(#[from] UserError),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+impl From<reqwest::Error> for OidcError {
+    fn from(error: reqwest::Error) -> Self {
+        OidcError::Provider(error.to_string())
+    }
+}
+
+/// 单点登录配置，由配置项 `OIDC_*` 控制
+#[derive(Debug, Clone)]
+pub struct OidcSettings {
+    pub issuer_url: String,
+    pub client_id: String,
+    /// 为空时按公开客户端处理，只依赖 PKCE
+    pub client_secret: Option<String>,
+    pub redirect_uri: String,
+    pub scopes: String,
+    /// 没有匹配的用户时是否自动创建
+    pub jit_provisioning: bool,
+    pub default_role: UserRole,
+    pub default_organization_id: i32,
+    /// 只信任身份提供方标记为已验证的邮箱
+    pub require_verified_email: bool,
+    /// 身份令牌接受的签名算法；为空时使用发现文档声明的非对称算法，都没有时为 RS256。
+    /// 用客户端密钥签名的 HS* 算法只有在这里列出才接受
+    pub id_token_algorithms: Vec<Algorithm>,
+}
+
+impl OidcSettings {
+    /// 未配置 `OIDC_ISSUER_URL` 或 `OIDC_CLIENT_ID` 时不启用单点登录
+    pub fn from_config(config: &Config) -> Option<Self> {
+        let issuer_url = config.oidc_issuer_url.clone()?;
+        let client_id = config.oidc_client_id.clone()?;
+
+        let default_role = config.oidc_default_role.parse().unwrap_or_else(|e| {
+            tracing::warn!("OIDC_DEFAULT_ROLE 无效，使用销售角色: {}", e);
+            UserRole::Sales
+        });
+
+        let id_token_algorithms = config
+            .oidc_id_token_signing_algs
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .filter_map(|name| match name.parse() {
+                Ok(algorithm) => Some(algorithm),
+                Err(_) => {
+                    tracing::warn!("OIDC_ID_TOKEN_SIGNING_ALGS 中的 {} 无效，已忽略", name);
+                    None
+                }
+            })
+            .collect();
+
+        Some(Self {
+            issuer_url: issuer_url.trim_end_matches('/').to_string(),
+            client_id,
+            client_secret: config.oidc_client_secret.clone(),
+            redirect_uri: config.oidc_redirect_uri.clone(),
+            scopes: config.oidc_scopes.clone(),
+            jit_provisioning: config.oidc_jit_provisioning,
+            default_role,
+            default_organization_id: config.oidc_default_organization_id,
+            require_verified_email: config.oidc_require_verified_email,
+            id_token_algorithms,
+        })
+    }
+}
+
+/// 身份提供方的发现文档中用到的字段
+#[derive(Debug, Clone, Deserialize)]
+struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: Option<String>,
+    #[serde(default)]
+    id_token_signing_alg_values_supported: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    id_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IdTokenClaims {
+    sub: String,
+    nonce: Option<String>,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    name: Option<String>,
+    preferred_username: Option<String>,
+}
+
+/// OpenID Connect 授权码 + PKCE 登录
+pub struct OidcProvider {
+    settings: OidcSettings,
+    http: reqwest::Client,
+    /// 发现文档首次使用时获取，之后一直复用
+    metadata: OnceCell<ProviderMetadata>,
+}
+
+impl OidcProvider {
+    pub fn new(settings: OidcSettings) -> Self {
+        Self {
+            settings,
+            http: reqwest::Client::new(),
+            metadata: OnceCell::new(),
+        }
+    }
+
+    /// 发起登录：保存 state、nonce 和 PKCE code_verifier，返回跳转到身份提供方的地址
+    pub async fn begin(&self, db: &DatabaseConnection) -> Result<String, OidcError> {
+        let metadata = self.metadata().await?;
+
+        let now = Utc::now();
+        // 顺便清理过期未完成的登录请求
+        OidcLoginState::delete_many()
+            .filter(oidc_login_state::Column::ExpiresAt.lt(now))
+            .exec(db)
+            .await?;
+
+        let state = generate_secret();
+        let nonce = generate_secret();
+        let code_verifier = generate_secret();
+        let code_challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(code_verifier.as_bytes()));
+
+        oidc_login_state::ActiveModel {
+            state_hash: Set(hash_secret(&state)),
+            nonce: Set(nonce.clone()),
+            code_verifier: Set(code_verifier),
+            created_at: Set(now),
+            expires_at: Set(now + Duration::minutes(LOGIN_STATE_EXPIRE_MINUTES)),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        let url = reqwest::Url::parse_with_params(
+            &metadata.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.settings.client_id.as_str()),
+                ("redirect_uri", self.settings.redirect_uri.as_str()),
+                ("scope", self.settings.scopes.as_str()),
+                ("state", state.as_str()),
+                ("nonce", nonce.as_str()),
+                ("code_challenge", code_challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .map_err(|e| OidcError::Provider(format!("授权地址无效: {}", e)))?;
+
+        Ok(url.to_string())
+    }
+
+    /// 处理回调：用授权码换取身份令牌，校验后返回对应的用户。
+    ///
+    /// 身份提供方已完成认证，因此不再要求本地的双因素验证码。
+    pub async fn complete(
+        &self,
+        db: &DatabaseConnection,
+        code: &str,
+        state: &str,
+    ) -> Result<user::Model, OidcError> {
+        let login_state = take_login_state(db, state).await?;
+        let metadata = self.metadata().await?;
+
+        let mut form = vec![
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.settings.redirect_uri.as_str()),
+            ("client_id", self.settings.client_id.as_str()),
+            ("code_verifier", login_state.code_verifier.as_str()),
+        ];
+        if let Some(client_secret) = &self.settings.client_secret {
+            form.push(("client_secret", client_secret.as_str()));
+        }
+
+        let response = self
+            .http
+            .post(&metadata.token_endpoint)
+            .form(&form)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(OidcError::Provider(format!("换取令牌失败 ({}): {}", status, body)));
+        }
+        let id_token = response
+            .json::<TokenResponse>()
+            .await?
+            .id_token
+            .ok_or_else(|| OidcError::Provider("令牌响应中没有 id_token".to_string()))?;
+
+        let claims = self.verify_id_token(metadata, &id_token).await?;
+        if claims.nonce.as_deref() != Some(login_state.nonce.as_str()) {
+            return Err(OidcError::InvalidIdToken("nonce 不匹配".to_string()));
+        }
+
+        self.resolve_user(db, claims).await
+    }
+
+    async fn metadata(&self) -> Result<&ProviderMetadata, OidcError> {
+        self.metadata
+            .get_or_try_init(|| async {
+                let url = format!("{}/.well-known/openid-configuration", self.settings.issuer_url);
+                let metadata = self
+                    .http
+                    .get(url)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<ProviderMetadata>()
+                    .await?;
+                // 发现文档声明的签发方必须与配置一致（OIDC Discovery 4.3）
+                if metadata.issuer.trim_end_matches('/') != self.settings.issuer_url {
+                    return Err(OidcError::Provider(format!(
+                        "发现文档的签发方 {} 与配置的 {} 不一致",
+                        metadata.issuer, self.settings.issuer_url
+                    )));
+                }
+                Ok(metadata)
+            })
+            .await
+    }
+
+    /// 身份令牌接受的签名算法，见 [`OidcSettings::id_token_algorithms`]
+    fn accepted_algorithms(&self, metadata: &ProviderMetadata) -> Vec<Algorithm> {
+        if !self.settings.id_token_algorithms.is_empty() {
+            return self.settings.id_token_algorithms.clone();
+        }
+        let advertised: Vec<Algorithm> = metadata
+            .id_token_signing_alg_values_supported
+            .iter()
+            .filter_map(|name| name.parse().ok())
+            .filter(|algorithm| !is_hmac(*algorithm))
+            .collect();
+        if advertised.is_empty() {
+            vec![Algorithm::RS256]
+        } else {
+            advertised
+        }
+    }
+
+    /// 校验签名、签发方、受众和有效期。
+    ///
+    /// 算法由配置和发现文档决定，不采信令牌头部；HS* 算法用客户端密钥验签，
+    /// 其余算法按 `kid` 从身份提供方的 JWKS 中取公钥。
+    async fn verify_id_token(
+        &self,
+        metadata: &ProviderMetadata,
+        id_token: &str,
+    ) -> Result<IdTokenClaims, OidcError> {
+        let header =
+            decode_header(id_token).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?;
+        if !self.accepted_algorithms(metadata).contains(&header.alg) {
+            return Err(OidcError::InvalidIdToken(format!(
+                "不接受 {:?} 签名的身份令牌",
+                header.alg
+            )));
+        }
+
+        let key = match header.alg {
+            algorithm if is_hmac(algorithm) => {
+                let secret = self.settings.client_secret.as_deref().ok_or_else(|| {
+                    OidcError::InvalidIdToken("未配置客户端密钥，无法校验 HS 签名".to_string())
+                })?;
+                DecodingKey::from_secret(secret.as_bytes())
+            }
+            _ => {
+                let jwks_uri = metadata.jwks_uri.as_deref().ok_or_else(|| {
+                    OidcError::Provider("发现文档中没有 jwks_uri".to_string())
+                })?;
+                let jwks = self
+                    .http
+                    .get(jwks_uri)
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<JwkSet>()
+                    .await?;
+                let jwk = match &header.kid {
+                    Some(kid) => jwks.find(kid),
+                    None if jwks.keys.len() == 1 => jwks.keys.first(),
+                    None => None,
+                }
+                .ok_or_else(|| OidcError::InvalidIdToken("找不到签名公钥".to_string()))?;
+                DecodingKey::from_jwk(jwk).map_err(|e| OidcError::InvalidIdToken(e.to_string()))?
+            }
+        };
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&self.settings.client_id]);
+        validation.set_issuer(&[&metadata.issuer]);
+
+        decode::<IdTokenClaims>(id_token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| OidcError::InvalidIdToken(e.to_string()))
+    }
+
+    /// 依次按已绑定的 subject、邮箱匹配用户，都没有时按配置自动创建
+    async fn resolve_user(
+        &self,
+        db: &DatabaseConnection,
+        claims: IdTokenClaims,
+    ) -> Result<user::Model, OidcError> {
+        let bound_user = User::find()
+            .filter(user::Column::OidcSubject.eq(&claims.sub))
+            .one(db)
+            .await?;
+        if let Some(user) = bound_user {
+            return ensure_active(user);
+        }
+
+        let email = match claims.email.as_deref().map(|e| e.trim().to_lowercase()) {
+            Some(_) if self.settings.require_verified_email && !claims.email_verified => {
+                return Err(OidcError::EmailNotVerified);
+            }
+            Some(email) if !email.is_empty() => Some(email),
+            _ => None,
+        };
+
+        if let Some(email) = &email {
+            let matched_user = User::find()
+                .filter(user::Column::Email.eq(email))
+                .one(db)
+                .await?;
+            if let Some(user) = matched_user {
+                let user = ensure_active(user)?;
+                return bind_subject(db, user, &claims.sub).await;
+            }
+        }
+
+        if !self.settings.jit_provisioning {
+            return Err(OidcError::UserNotFound);
+        }
+
+        let username = available_username(db, &claims, email.as_deref()).await?;
+        let name = claims
+            .name
+            .as_deref()
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .unwrap_or(&username)
+            .chars()
+            .take(100)
+            .collect();
+        let new_user = NewUser {
+            username,
+            password: None,
+            name,
+            email,
+            role: self.settings.default_role,
+            manager_id: None,
+            organization_id: self.settings.default_organization_id,
+        };
+        let user = UserService::create(
+            db,
+            &Actor::sso(self.settings.default_organization_id),
+            &PasswordPolicy::default(),
+            new_user,
+        )
+        .await?;
+        tracing::info!("单点登录自动创建用户 {} (subject {})", user.username, claims.sub);
+
+        bind_subject(db, user, &claims.sub).await
+    }
+}
+
+fn is_hmac(algorithm: Algorithm) -> bool {
+    matches!(algorithm, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512)
+}
+
+/// 取出并删除登录请求，每个 state 只能使用一次
+async fn take_login_state(
+    db: &DatabaseConnection,
+    state: &str,
+) -> Result<oidc_login_state::Model, OidcError> {
+    let login_state = OidcLoginState::find()
+        .filter(oidc_login_state::Column::StateHash.eq(hash_secret(state)))
+        .one(db)
+        .await?
+        .ok_or(OidcError::InvalidState)?;
+
+    // 并发回调时只有删除成功的一方可以继续
+    let result = OidcLoginState::delete_by_id(login_state.id).exec(db).await?;
+    if result.rows_affected == 0 || login_state.expires_at < Utc::now() {
+        return Err(OidcError::InvalidState);
+    }
+
+    Ok(login_state)
+}
+
+fn ensure_active(user: user::Model) -> Result<user::Model, OidcError> {
+    if !user.is_active {
+        return Err(OidcError::UserDisabled);
+    }
+    Ok(user)
+}
+
+async fn bind_subject(
+    db: &DatabaseConnection,
+    user: user::Model,
+    subject: &str,
+) -> Result<user::Model, OidcError> {
+    let mut user_active: user::ActiveModel = user.into();
+    user_active.oidc_subject = Set(Some(subject.to_string()));
+    user_active.updated_at = Set(Utc::now());
+    Ok(user_active.update(db).await?)
+}
+
+/// 由 preferred_username 或邮箱前缀生成合法且未被占用的用户名
+async fn available_username(
+    db: &DatabaseConnection,
+    claims: &IdTokenClaims,
+    email: Option<&str>,
+) -> Result<String, OidcError> {
+    let source = claims
+        .preferred_username
+        .as_deref()
+        .or_else(|| email.and_then(|email| email.split('@').next()))
+        .unwrap_or("sso");
+    let mut base: String = source
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(MAX_USERNAME_BASE_LENGTH)
+        .collect();
+    if base.len() < 3 {
+        base = format!("sso_{}", base);
+    }
+
+    let mut candidate = base.clone();
+    for suffix in 2..100 {
+        let taken = User::find()
+            .filter(user::Column::Username.eq(&candidate))
+            .one(db)
+            .await?
+            .is_some();
+        if !taken {
+            return Ok(candidate);
+        }
+        candidate = format!("{}_{}", base, suffix);
+    }
+
+    Ok(format!("{}_{}", base, &generate_secret()[..8]))
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    use super::*;
+
+    const ISSUER: &str = "https://idp.example.com";
+    const CLIENT_SECRET: &str = "client-secret";
+
+    fn provider(id_token_algorithms: Vec<Algorithm>) -> OidcProvider {
+        OidcProvider::new(OidcSettings {
+            issuer_url: ISSUER.to_string(),
+            client_id: "customer-tracker".to_string(),
+            client_secret: Some(CLIENT_SECRET.to_string()),
+            redirect_uri: "http://127.0.0.1/sso/callback".to_string(),
+            scopes: "openid".to_string(),
+            jit_provisioning: false,
+            default_role: UserRole::Sales,
+            default_organization_id: 1,
+            require_verified_email: true,
+            id_token_algorithms,
+        })
+    }
+
+    fn metadata(algorithms: &[&str]) -> ProviderMetadata {
+        ProviderMetadata {
+            issuer: ISSUER.to_string(),
+            authorization_endpoint: format!("{}/authorize", ISSUER),
+            token_endpoint: format!("{}/token", ISSUER),
+            jwks_uri: Some(format!("{}/jwks", ISSUER)),
+            id_token_signing_alg_values_supported: algorithms
+                .iter()
+                .map(|name| name.to_string())
+                .collect(),
+        }
+    }
+
+    /// 用客户端密钥签名、其他声明都正确的身份令牌
+    fn hs256_token() -> String {
+        let claims = json!({
+            "iss": ISSUER,
+            "aud": "customer-tracker",
+            "sub": "someone",
+            "exp": (Utc::now() + Duration::minutes(5)).timestamp(),
+        });
+        encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(CLIENT_SECRET.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn rejects_id_tokens_with_a_switched_algorithm() {
+        // 身份提供方使用 RS256，攻击者改用 HS256 并以客户端密钥签名
+        let result = provider(Vec::new())
+            .verify_id_token(&metadata(&["RS256"]), &hs256_token())
+            .await;
+        assert!(matches!(result, Err(OidcError::InvalidIdToken(_))));
+
+        // 发现文档声明的 HS* 算法也不会自动接受
+        let result = provider(Vec::new())
+            .verify_id_token(&metadata(&["RS256", "HS256"]), &hs256_token())
+            .await;
+        assert!(matches!(result, Err(OidcError::InvalidIdToken(_))));
+
+        // 只配置了 ES256 时，头部声明 RS256 的令牌在取公钥之前就被拒绝
+        let header = URL_SAFE_NO_PAD.encode(br#"{"alg":"RS256","typ":"JWT"}"#);
+        let token = format!("{}.e30.c2ln", header);
+        let result = provider(vec![Algorithm::ES256])
+            .verify_id_token(&metadata(&["RS256"]), &token)
+            .await;
+        assert!(matches!(result, Err(OidcError::InvalidIdToken(_))));
+    }
+
+    #[tokio::test]
+    async fn accepts_hs256_when_configured() {
+        let claims = provider(vec![Algorithm::HS256])
+            .verify_id_token(&metadata(&[]), &hs256_token())
+            .await
+            .unwrap();
+        assert_eq!(claims.sub, "someone");
+    }
+
+    #[test]
+    fn accepted_algorithms_default_to_rs256() {
+        let provider = provider(Vec::new());
+        assert_eq!(
+            provider.accepted_algorithms(&metadata(&[])),
+            vec![Algorithm::RS256]
+        );
+        assert_eq!(
+            provider.accepted_algorithms(&metadata(&["HS256", "none"])),
+            vec![Algorithm::RS256]
+        );
+        assert_eq!(
+            provider.accepted_algorithms(&metadata(&["ES256", "RS256"])),
+            vec![Algorithm::ES256, Algorithm::RS256]
+        );
+    }
+}
@@ -0,0 +1,459 @@
+use sea_orm::{
+    sea_query::{Condition, Query},
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QuerySelect,
+};
+use serde::Serialize;
+
+use crate::{
+    entities::{
+        customer::{self, Entity as Customer},
+        customer_collaborator::{self, CollaboratorPermission, Entity as CustomerCollaborator},
+        customer_track::{self, Entity as CustomerTrack},
+        user::{self, Entity as User},
+        user_role::UserRole,
+    },
+    middleware::auth::CurrentUser,
+};
+
+/// 当前用户可以访问的客户负责人范围
+#[derive(Debug, Clone)]
+pub enum Owners {
+    /// 管理员：组织内所有客户
+    All,
+    /// 主管和销售：指定负责人名下的客户
+    Only(Vec<i32>),
+}
+
+/// 当前用户可以访问的客户范围，始终限定在其所属组织内
+#[derive(Debug, Clone)]
+pub struct AccessScope {
+    pub organization_id: i32,
+    pub owners: Owners,
+    /// 当前用户，用于匹配其作为协作者的客户；命令行操作时为空
+    pub collaborator_id: Option<i32>,
+}
+
+/// 对某个客户的访问级别，从低到高
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustomerAccess {
+    /// 只读协作者
+    Read,
+    /// 可编辑的协作者
+    Write,
+    /// 负责人、其主管或管理员
+    Owner,
+}
+
+impl CustomerAccess {
+    /// 负责人可以修改所有跟进记录，可编辑的协作者只能修改自己添加的
+    pub fn can_modify_track(self, track: &customer_track::Model, user_id: i32) -> bool {
+        match self {
+            CustomerAccess::Owner => true,
+            CustomerAccess::Write => track.user_id == Some(user_id),
+            CustomerAccess::Read => false,
+        }
+    }
+}
+
+impl From<CollaboratorPermission> for CustomerAccess {
+    fn from(permission: CollaboratorPermission) -> Self {
+        match permission {
+            CollaboratorPermission::Read => CustomerAccess::Read,
+            CollaboratorPermission::Write => CustomerAccess::Write,
+        }
+    }
+}
+
+impl AccessScope {
+    /// 组织内全部客户，供命令行使用
+    pub fn organization(organization_id: i32) -> Self {
+        Self {
+            organization_id,
+            owners: Owners::All,
+            collaborator_id: None,
+        }
+    }
+
+    /// 指定用户是否在可管理的负责人范围内
+    pub fn includes_owner(&self, user_id: i32) -> bool {
+        match &self.owners {
+            Owners::All => true,
+            Owners::Only(ids) => ids.contains(&user_id),
+        }
+    }
+
+    /// 用于 `customers` 表查询的过滤条件：负责人范围内的客户
+    pub fn customer_condition(&self) -> Condition {
+        let condition =
+            Condition::all().add(customer::Column::OrganizationId.eq(self.organization_id));
+        match &self.owners {
+            Owners::All => condition,
+            Owners::Only(ids) => condition.add(customer::Column::UserId.is_in(ids.clone())),
+        }
+    }
+
+    /// 负责人范围内的客户，加上当前用户作为协作者的客户
+    pub fn visible_condition(&self) -> Condition {
+        let Some(collaborator_id) = self.collaborator_id else {
+            return self.customer_condition();
+        };
+        let shared = Query::select()
+            .column(customer_collaborator::Column::CustomerId)
+            .from(CustomerCollaborator)
+            .and_where(customer_collaborator::Column::UserId.eq(collaborator_id))
+            .to_owned();
+
+        Condition::all()
+            .add(customer::Column::OrganizationId.eq(self.organization_id))
+            .add(
+                Condition::any()
+                    .add(self.customer_condition())
+                    .add(customer::Column::Id.in_subquery(shared)),
+            )
+    }
+
+    /// 客户是否在负责人范围内（不含协作者）
+    pub fn allows(&self, customer: &customer::Model) -> bool {
+        if customer.organization_id != self.organization_id {
+            return false;
+        }
+        // 公海中的客户只有管理员可以直接访问，其他人需要先领取
+        match &self.owners {
+            Owners::All => true,
+            Owners::Only(_) => customer.user_id.is_some_and(|user_id| self.includes_owner(user_id)),
+        }
+    }
+}
+
+pub struct PermissionService;
+
+impl PermissionService {
+    /// 根据角色计算访问范围：管理员看组织内全部，主管看本人及团队成员，销售只看本人
+    pub async fn scope_for(
+        db: &DatabaseConnection,
+        current_user: &CurrentUser,
+    ) -> Result<AccessScope, DbErr> {
+        let owners = match current_user.role {
+            UserRole::Admin => Owners::All,
+            UserRole::Manager => {
+                let mut ids: Vec<i32> = User::find()
+                    .select_only()
+                    .column(user::Column::Id)
+                    .filter(user::Column::ManagerId.eq(current_user.id))
+                    .filter(user::Column::OrganizationId.eq(current_user.organization_id))
+                    .into_tuple()
+                    .all(db)
+                    .await?;
+                ids.push(current_user.id);
+                Owners::Only(ids)
+            }
+            UserRole::Sales => Owners::Only(vec![current_user.id]),
+        };
+
+        Ok(AccessScope {
+            organization_id: current_user.organization_id,
+            owners,
+            collaborator_id: Some(current_user.id),
+        })
+    }
+
+    /// 当前用户对客户的访问级别，无权访问时为 `None`
+    pub async fn customer_access(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        customer: &customer::Model,
+    ) -> Result<Option<CustomerAccess>, DbErr> {
+        if scope.allows(customer) {
+            return Ok(Some(CustomerAccess::Owner));
+        }
+        let Some(collaborator_id) = scope.collaborator_id else {
+            return Ok(None);
+        };
+        if customer.organization_id != scope.organization_id {
+            return Ok(None);
+        }
+
+        let collaborator = CustomerCollaborator::find()
+            .filter(customer_collaborator::Column::CustomerId.eq(customer.id))
+            .filter(customer_collaborator::Column::UserId.eq(collaborator_id))
+            .one(db)
+            .await?;
+        Ok(collaborator.map(|collaborator| collaborator.permission.into()))
+    }
+
+    /// 查找当前用户有权访问且未删除的客户及访问级别，需要编辑时由调用方检查级别
+    pub async fn find_customer(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        customer_id: i32,
+    ) -> Result<Option<(customer::Model, CustomerAccess)>, DbErr> {
+        let Some(customer) = Customer::find_by_id(customer_id)
+            .filter(customer::Column::IsDeleted.eq(false))
+            .one(db)
+            .await?
+        else {
+            return Ok(None);
+        };
+
+        let access = Self::customer_access(db, scope, &customer).await?;
+        Ok(access.map(|access| (customer, access)))
+    }
+
+    /// 查找当前用户有权访问的跟进记录、所属客户及访问级别
+    pub async fn find_track(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        track_id: i32,
+    ) -> Result<Option<(customer_track::Model, customer::Model, CustomerAccess)>, DbErr> {
+        let found = CustomerTrack::find_by_id(track_id)
+            .find_also_related(Customer)
+            .one(db)
+            .await?;
+
+        let Some((track, Some(customer))) = found else {
+            return Ok(None);
+        };
+        if customer.is_deleted {
+            return Ok(None);
+        }
+
+        let access = Self::customer_access(db, scope, &customer).await?;
+        Ok(access.map(|access| (track, customer, access)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::{Method, StatusCode};
+    use chrono::Utc;
+    use sea_orm::{ActiveModelTrait, Set};
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::{self, TestApp};
+
+    /// 各角色的用户：负责人 alice 的主管 boss，其他团队的主管 other_boss，
+    /// 同组织的其他销售 bob，以及客户的只读和可编辑协作者
+    const ROLES: [&str; 7] = [
+        "admin",
+        "boss",
+        "alice",
+        "other_boss",
+        "bob",
+        "reader",
+        "writer",
+    ];
+
+    struct Matrix {
+        app: TestApp,
+        alice: user::Model,
+        reader: user::Model,
+        writer: user::Model,
+    }
+
+    impl Matrix {
+        async fn new() -> Self {
+            let app = TestApp::new().await;
+            let db = app.db();
+            test_support::create_user(db, "admin", UserRole::Admin).await;
+            let boss = test_support::create_user(db, "boss", UserRole::Manager).await;
+            test_support::create_user(db, "other_boss", UserRole::Manager).await;
+            let alice =
+                test_support::create_user_in(db, "alice", UserRole::Sales, 1, Some(boss.id)).await;
+            test_support::create_user(db, "bob", UserRole::Sales).await;
+            let reader = test_support::create_user(db, "reader", UserRole::Sales).await;
+            let writer = test_support::create_user(db, "writer", UserRole::Sales).await;
+            Self {
+                app,
+                alice,
+                reader,
+                writer,
+            }
+        }
+
+        /// alice 名下的客户，带上两个协作者
+        async fn customer(&self) -> customer::Model {
+            let db = self.app.db();
+            let customer =
+                test_support::create_customer(db, "张三", None, Some(self.alice.id)).await;
+            for (user, permission) in [
+                (&self.reader, CollaboratorPermission::Read),
+                (&self.writer, CollaboratorPermission::Write),
+            ] {
+                customer_collaborator::ActiveModel {
+                    customer_id: Set(customer.id),
+                    user_id: Set(user.id),
+                    permission: Set(permission),
+                    added_by: Set(Some(self.alice.id)),
+                    created_at: Set(Utc::now()),
+                    updated_at: Set(Utc::now()),
+                    ..Default::default()
+                }
+                .insert(db)
+                .await
+                .unwrap();
+            }
+            customer
+        }
+
+        async fn status(
+            &self,
+            username: &str,
+            method: Method,
+            uri: &str,
+            body: Option<serde_json::Value>,
+        ) -> StatusCode {
+            let token = self.app.login(username).await;
+            self.app.request(method, uri, Some(&token), body).await.0
+        }
+    }
+
+    #[tokio::test]
+    async fn customer_access_follows_the_role_matrix() {
+        let matrix = Matrix::new().await;
+        let customer = matrix.customer().await;
+        let uri = format!("/api/customers/{}", customer.id);
+
+        let expected = [
+            ("admin", StatusCode::OK, StatusCode::OK),
+            ("boss", StatusCode::OK, StatusCode::OK),
+            ("alice", StatusCode::OK, StatusCode::OK),
+            ("other_boss", StatusCode::NOT_FOUND, StatusCode::NOT_FOUND),
+            ("bob", StatusCode::NOT_FOUND, StatusCode::NOT_FOUND),
+            ("reader", StatusCode::OK, StatusCode::FORBIDDEN),
+            ("writer", StatusCode::OK, StatusCode::OK),
+        ];
+        assert_eq!(expected.map(|(role, ..)| role), ROLES);
+        for (username, get, update) in expected {
+            assert_eq!(
+                matrix.status(username, Method::GET, &uri, None).await,
+                get,
+                "{} 查看",
+                username
+            );
+            let body = json!({ "notes": format!("{} 修改", username) });
+            assert_eq!(
+                matrix.status(username, Method::PUT, &uri, Some(body)).await,
+                update,
+                "{} 修改",
+                username
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn only_owners_delete_customers() {
+        let matrix = Matrix::new().await;
+
+        let expected = [
+            ("admin", StatusCode::NO_CONTENT),
+            ("boss", StatusCode::NO_CONTENT),
+            ("alice", StatusCode::NO_CONTENT),
+            ("other_boss", StatusCode::NOT_FOUND),
+            ("bob", StatusCode::NOT_FOUND),
+            ("reader", StatusCode::FORBIDDEN),
+            ("writer", StatusCode::FORBIDDEN),
+        ];
+        assert_eq!(expected.map(|(role, _)| role), ROLES);
+        for (username, delete) in expected {
+            let customer = matrix.customer().await;
+            let uri = format!("/api/customers/{}", customer.id);
+            assert_eq!(
+                matrix.status(username, Method::DELETE, &uri, None).await,
+                delete,
+                "{}",
+                username
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn track_access_follows_the_role_matrix() {
+        let matrix = Matrix::new().await;
+        let customer = matrix.customer().await;
+        let db = matrix.app.db();
+
+        // 创建、修改和删除负责人的跟进记录
+        let expected = [
+            (
+                "admin",
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::NO_CONTENT,
+            ),
+            (
+                "boss",
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::NO_CONTENT,
+            ),
+            (
+                "alice",
+                StatusCode::OK,
+                StatusCode::OK,
+                StatusCode::NO_CONTENT,
+            ),
+            (
+                "other_boss",
+                StatusCode::NOT_FOUND,
+                StatusCode::NOT_FOUND,
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                "bob",
+                StatusCode::NOT_FOUND,
+                StatusCode::NOT_FOUND,
+                StatusCode::NOT_FOUND,
+            ),
+            (
+                "reader",
+                StatusCode::FORBIDDEN,
+                StatusCode::FORBIDDEN,
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                "writer",
+                StatusCode::OK,
+                StatusCode::FORBIDDEN,
+                StatusCode::FORBIDDEN,
+            ),
+        ];
+        assert_eq!(expected.map(|(role, ..)| role), ROLES);
+        for (username, create, update, delete) in expected {
+            let body = json!({ "customer_id": customer.id, "content": "电话沟通" });
+            let status = matrix
+                .status(username, Method::POST, "/api/tracks", Some(body))
+                .await;
+            assert_eq!(status, create, "{} 添加", username);
+
+            let track =
+                test_support::create_track(db, customer.id, Some(matrix.alice.id), Utc::now())
+                    .await;
+            let uri = format!("/api/tracks/{}", track.id);
+            let body = json!({ "content": "改写" });
+            let status = matrix.status(username, Method::PUT, &uri, Some(body)).await;
+            assert_eq!(status, update, "{} 修改", username);
+            let status = matrix.status(username, Method::DELETE, &uri, None).await;
+            assert_eq!(status, delete, "{} 删除", username);
+        }
+
+        // 可编辑的协作者可以修改和删除自己添加的跟进记录
+        let own =
+            test_support::create_track(db, customer.id, Some(matrix.writer.id), Utc::now()).await;
+        let uri = format!("/api/tracks/{}", own.id);
+        let body = json!({ "content": "改写" });
+        assert_eq!(
+            matrix.status("writer", Method::PUT, &uri, Some(body)).await,
+            StatusCode::OK
+        );
+        assert_eq!(
+            matrix.status("reader", Method::DELETE, &uri, None).await,
+            StatusCode::FORBIDDEN
+        );
+        assert_eq!(
+            matrix.status("writer", Method::DELETE, &uri, None).await,
+            StatusCode::NO_CONTENT
+        );
+    }
+}
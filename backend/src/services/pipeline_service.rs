@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+
+use crate::{
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        customer::{self, Entity as Customer},
+        customer_track::{self, Entity as CustomerTrack},
+        pipeline_stage::{self, Entity as PipelineStage, StageOutcome},
+    },
+    services::audit_service::{Actor, AuditService},
+};
+
+/// 新建组织时带上的阶段：标识、名称、结果
+const DEFAULT_STAGES: [(&str, &str, StageOutcome); 6] = [
+    ("new", "新线索", StageOutcome::Open),
+    ("contacted", "已联系", StageOutcome::Open),
+    ("trial_booked", "已约体验", StageOutcome::Open),
+    ("trial_done", "已体验", StageOutcome::Open),
+    ("signed", "已签约", StageOutcome::Won),
+    ("lost", "已流失", StageOutcome::Lost),
+];
+
+/// 名称的最大长度
+const MAX_NAME_LENGTH: usize = 50;
+
+#[derive(Debug, thiserror::Error)]
+pub enum PipelineError {
+    #[error("阶段标识只能包含小写字母、数字和下划线，以字母开头，最多30个字符")]
+    InvalidKey,
+    #[error("阶段标识 {0} 已存在")]
+    KeyExists(String),
+    #[error("阶段名称不能为空，且不能超过50个字符")]
+    InvalidName,
+    #[error("未知的销售阶段: {0}")]
+    UnknownStage(i32),
+    #[error("阶段「{0}」已停用")]
+    Inactive(String),
+    #[error("还有 {0} 位客户处于该阶段，请先停用该阶段或调整这些客户的阶段")]
+    InUse(u64),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+#[derive(Debug, Clone)]
+pub struct NewPipelineStage {
+    pub stage_key: String,
+    pub name: String,
+    pub outcome: StageOutcome,
+    pub color: Option<String>,
+    pub sort_order: i32,
+}
+
+/// 标识创建后不能修改
+#[derive(Debug, Clone, Default)]
+pub struct PipelineStageUpdate {
+    pub name: Option<String>,
+    pub outcome: Option<StageOutcome>,
+    /// 空字符串表示清除颜色
+    pub color: Option<String>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+pub struct PipelineService;
+
+impl PipelineService {
+    /// 组织的阶段，按顺序排列
+    pub async fn list<C: ConnectionTrait>(
+        db: &C,
+        organization_id: i32,
+        include_inactive: bool,
+    ) -> Result<Vec<pipeline_stage::Model>, DbErr> {
+        let mut query = PipelineStage::find()
+            .filter(pipeline_stage::Column::OrganizationId.eq(organization_id));
+        if !include_inactive {
+            query = query.filter(pipeline_stage::Column::IsActive.eq(true));
+        }
+
+        query
+            .order_by_asc(pipeline_stage::Column::SortOrder)
+            .order_by_asc(pipeline_stage::Column::Id)
+            .all(db)
+            .await
+    }
+
+    /// 组织内各阶段未删除的客户数
+    pub async fn customer_counts(
+        db: &DatabaseConnection,
+        organization_id: i32,
+    ) -> Result<HashMap<i32, i64>, DbErr> {
+        let counts = Customer::find()
+            .select_only()
+            .column(customer::Column::StageId)
+            .column_as(Expr::col(customer::Column::Id).count(), "count")
+            .filter(customer::Column::OrganizationId.eq(organization_id))
+            .filter(customer::Column::IsDeleted.eq(false))
+            .filter(customer::Column::StageId.is_not_null())
+            .group_by(customer::Column::StageId)
+            .into_tuple::<(i32, i64)>()
+            .all(db)
+            .await?;
+
+        Ok(counts.into_iter().collect())
+    }
+
+    pub async fn find<C: ConnectionTrait>(
+        db: &C,
+        organization_id: i32,
+        stage_id: i32,
+    ) -> Result<Option<pipeline_stage::Model>, DbErr> {
+        PipelineStage::find_by_id(stage_id)
+            .filter(pipeline_stage::Column::OrganizationId.eq(organization_id))
+            .one(db)
+            .await
+    }
+
+    /// 可以推进客户到的阶段：属于该组织且未停用
+    pub async fn find_active(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        stage_id: i32,
+    ) -> Result<pipeline_stage::Model, PipelineError> {
+        let stage = Self::find(db, organization_id, stage_id)
+            .await?
+            .ok_or(PipelineError::UnknownStage(stage_id))?;
+        if !stage.is_active {
+            return Err(PipelineError::Inactive(stage.name));
+        }
+        Ok(stage)
+    }
+
+    pub async fn create(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        stage: NewPipelineStage,
+    ) -> Result<pipeline_stage::Model, PipelineError> {
+        let stage_key = stage.stage_key.trim().to_string();
+        if !valid_key(&stage_key) {
+            return Err(PipelineError::InvalidKey);
+        }
+        let name = checked_name(&stage.name)?;
+
+        let existing = PipelineStage::find()
+            .filter(pipeline_stage::Column::OrganizationId.eq(organization_id))
+            .filter(pipeline_stage::Column::StageKey.eq(&stage_key))
+            .one(db)
+            .await?;
+        if existing.is_some() {
+            return Err(PipelineError::KeyExists(stage_key));
+        }
+
+        let now = Utc::now();
+        let stage = pipeline_stage::ActiveModel {
+            organization_id: Set(organization_id),
+            stage_key: Set(stage_key),
+            name: Set(name),
+            outcome: Set(stage.outcome),
+            color: Set(stage.color.filter(|color| !color.trim().is_empty())),
+            sort_order: Set(stage.sort_order),
+            is_active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(stage)
+    }
+
+    pub async fn update(
+        db: &DatabaseConnection,
+        stage: pipeline_stage::Model,
+        update: PipelineStageUpdate,
+    ) -> Result<pipeline_stage::Model, PipelineError> {
+        let mut active: pipeline_stage::ActiveModel = stage.into();
+
+        if let Some(name) = update.name {
+            active.name = Set(checked_name(&name)?);
+        }
+        if let Some(outcome) = update.outcome {
+            active.outcome = Set(outcome);
+        }
+        if let Some(color) = update.color {
+            active.color = Set((!color.trim().is_empty()).then_some(color));
+        }
+        if let Some(sort_order) = update.sort_order {
+            active.sort_order = Set(sort_order);
+        }
+        if let Some(is_active) = update.is_active {
+            active.is_active = Set(is_active);
+        }
+        active.updated_at = Set(Utc::now());
+
+        Ok(active.update(db).await?)
+    }
+
+    /// 只能删除没有客户（包括回收站中的客户）的阶段，跟进记录上的阶段会被清空
+    pub async fn delete(
+        db: &DatabaseConnection,
+        stage: &pipeline_stage::Model,
+    ) -> Result<(), PipelineError> {
+        let customers = Customer::find()
+            .filter(customer::Column::StageId.eq(stage.id))
+            .count(db)
+            .await?;
+        if customers > 0 {
+            return Err(PipelineError::InUse(customers));
+        }
+
+        PipelineStage::delete_by_id(stage.id).exec(db).await?;
+        Ok(())
+    }
+
+    /// 把客户推进到某个阶段，阶段没有变化时不做任何事
+    pub async fn move_customer<C: ConnectionTrait>(
+        db: &C,
+        actor: &Actor,
+        customer: &customer::Model,
+        stage_id: i32,
+    ) -> Result<(), DbErr> {
+        if customer.stage_id == Some(stage_id) {
+            return Ok(());
+        }
+
+        let mut active: customer::ActiveModel = customer.clone().into();
+        active.stage_id = Set(Some(stage_id));
+        active.updated_at = Set(Utc::now());
+        let moved = active.update(db).await?;
+
+        AuditService::record(
+            db,
+            actor,
+            AuditAction::Update,
+            AuditEntity::Customer,
+            customer.id,
+            Some(customer),
+            Some(&moved),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// 最新一条跟进被删除后重新确定客户的阶段：剩下的跟进中最新一条设置过阶段的，
+    /// 都没有时回到新建客户所在的阶段
+    pub async fn restore_from_tracks<C: ConnectionTrait>(
+        db: &C,
+        actor: &Actor,
+        customer: &customer::Model,
+    ) -> Result<(), DbErr> {
+        let latest = CustomerTrack::find()
+            .filter(customer_track::Column::CustomerId.eq(customer.id))
+            .filter(customer_track::Column::StageId.is_not_null())
+            .order_by_desc(customer_track::Column::TrackTime)
+            .order_by_desc(customer_track::Column::Id)
+            .one(db)
+            .await?;
+        let stage_id = match latest.and_then(|track| track.stage_id) {
+            Some(stage_id) => Some(stage_id),
+            None => Self::initial_stage(db, customer.organization_id).await?.map(|stage| stage.id),
+        };
+
+        match stage_id {
+            Some(stage_id) => Self::move_customer(db, actor, customer, stage_id).await,
+            None => Ok(()),
+        }
+    }
+
+    /// 新建客户所在的阶段：排在最前的启用阶段
+    pub async fn initial_stage<C: ConnectionTrait>(
+        db: &C,
+        organization_id: i32,
+    ) -> Result<Option<pipeline_stage::Model>, DbErr> {
+        Ok(Self::list(db, organization_id, false).await?.into_iter().next())
+    }
+
+    /// 给新建的组织加上默认阶段
+    pub async fn create_defaults<C: ConnectionTrait>(
+        db: &C,
+        organization_id: i32,
+    ) -> Result<(), DbErr> {
+        let now = Utc::now();
+        PipelineStage::insert_many(DEFAULT_STAGES.iter().zip(1..).map(
+            |((stage_key, name, outcome), sort_order)| pipeline_stage::ActiveModel {
+                organization_id: Set(organization_id),
+                stage_key: Set(stage_key.to_string()),
+                name: Set(name.to_string()),
+                outcome: Set(*outcome),
+                color: Set(None),
+                sort_order: Set(sort_order),
+                is_active: Set(true),
+                created_at: Set(now),
+                updated_at: Set(now),
+                ..Default::default()
+            },
+        ))
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+}
+
+fn valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    key.len() <= 30
+        && chars.next().is_some_and(|c| c.is_ascii_lowercase())
+        && chars.all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+fn checked_name(name: &str) -> Result<String, PipelineError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(PipelineError::InvalidName);
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entities::{
+            audit_log::{self, Entity as AuditLog},
+            next_action::NextAction,
+            organization,
+            user_role::UserRole,
+        },
+        test_support,
+    };
+
+    fn new_stage(stage_key: &str, name: &str, sort_order: i32) -> NewPipelineStage {
+        NewPipelineStage {
+            stage_key: stage_key.to_string(),
+            name: name.to_string(),
+            outcome: StageOutcome::Open,
+            color: None,
+            sort_order,
+        }
+    }
+
+    fn keys(stages: &[pipeline_stage::Model]) -> Vec<&str> {
+        stages
+            .iter()
+            .map(|stage| stage.stage_key.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn won_and_lost_stages_end_the_follow_up() {
+        assert_eq!(StageOutcome::Open.next_action(), NextAction::Continue);
+        assert_eq!(StageOutcome::Won.next_action(), NextAction::End);
+        assert_eq!(StageOutcome::Lost.next_action(), NextAction::End);
+    }
+
+    #[test]
+    fn keys_are_lowercase_identifiers() {
+        assert!(valid_key("trial_booked"));
+        assert!(valid_key("stage2"));
+        assert!(!valid_key(""));
+        assert!(!valid_key("2nd"));
+        assert!(!valid_key("_new"));
+        assert!(!valid_key("Trial"));
+        assert!(!valid_key("trial-done"));
+        assert!(!valid_key(&"a".repeat(31)));
+    }
+
+    #[tokio::test]
+    async fn organizations_start_with_the_default_stages_in_order() {
+        let db = test_support::test_db().await;
+        let stages = PipelineService::list(&db, 1, false).await.unwrap();
+        assert_eq!(
+            keys(&stages),
+            vec![
+                "new",
+                "contacted",
+                "trial_booked",
+                "trial_done",
+                "signed",
+                "lost"
+            ]
+        );
+        assert_eq!(stages[4].outcome, StageOutcome::Won);
+        assert_eq!(stages[5].outcome, StageOutcome::Lost);
+
+        let now = Utc::now();
+        let other = organization::ActiveModel {
+            name: Set("其他组织".to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        PipelineService::create_defaults(&db, other.id)
+            .await
+            .unwrap();
+        let others = PipelineService::list(&db, other.id, false).await.unwrap();
+        assert_eq!(keys(&others), keys(&stages));
+        // 其他组织的阶段不能使用
+        assert!(matches!(
+            PipelineService::find_active(&db, 1, others[0].id).await,
+            Err(PipelineError::UnknownStage(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn stages_are_ordered_and_inactive_ones_cannot_be_chosen() {
+        let db = test_support::test_db().await;
+        let first = PipelineService::create(&db, 1, new_stage("referral", "转介绍", 0))
+            .await
+            .unwrap();
+        assert_eq!(
+            PipelineService::initial_stage(&db, 1).await.unwrap(),
+            Some(first.clone())
+        );
+
+        assert!(matches!(
+            PipelineService::create(&db, 1, new_stage("referral", "重复", 9)).await,
+            Err(PipelineError::KeyExists(_))
+        ));
+        assert!(matches!(
+            PipelineService::create(&db, 1, new_stage("Bad Key", "错误", 9)).await,
+            Err(PipelineError::InvalidKey)
+        ));
+        assert!(matches!(
+            PipelineService::create(&db, 1, new_stage("blank", " ", 9)).await,
+            Err(PipelineError::InvalidName)
+        ));
+
+        let update = PipelineStageUpdate {
+            is_active: Some(false),
+            ..Default::default()
+        };
+        let first = PipelineService::update(&db, first, update).await.unwrap();
+        assert!(matches!(
+            PipelineService::find_active(&db, 1, first.id).await,
+            Err(PipelineError::Inactive(_))
+        ));
+        let initial = PipelineService::initial_stage(&db, 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(initial.stage_key, "new");
+        let all = PipelineService::list(&db, 1, true).await.unwrap();
+        assert_eq!(all[0].id, first.id);
+        assert_eq!(PipelineService::list(&db, 1, false).await.unwrap().len(), 6);
+    }
+
+    #[tokio::test]
+    async fn moving_a_customer_is_audited_and_blocks_deleting_the_stage() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let (_, actor) = test_support::scope_of(&db, &alice).await;
+        let stages = PipelineService::list(&db, 1, false).await.unwrap();
+        let signed = &stages[4];
+        let customer = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+
+        PipelineService::move_customer(&db, &actor, &customer, signed.id)
+            .await
+            .unwrap();
+        let customer = Customer::find_by_id(customer.id)
+            .one(&db)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(customer.stage_id, Some(signed.id));
+        // 阶段没有变化时不再记录
+        PipelineService::move_customer(&db, &actor, &customer, signed.id)
+            .await
+            .unwrap();
+        let audits = AuditLog::find()
+            .filter(audit_log::Column::Entity.eq(AuditEntity::Customer))
+            .filter(audit_log::Column::EntityId.eq(customer.id))
+            .count(&db)
+            .await
+            .unwrap();
+        assert_eq!(audits, 1);
+
+        let counts = PipelineService::customer_counts(&db, 1).await.unwrap();
+        assert_eq!(counts.get(&signed.id), Some(&1));
+        assert!(matches!(
+            PipelineService::delete(&db, signed).await,
+            Err(PipelineError::InUse(1))
+        ));
+        PipelineService::delete(&db, &stages[5]).await.unwrap();
+        assert_eq!(PipelineService::list(&db, 1, true).await.unwrap().len(), 5);
+    }
+}
@@ -0,0 +1,666 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    sea_query::{Alias, Condition, Expr, Order, Query, SelectStatement},
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::Serialize;
+
+use crate::{
+    entities::{
+        customer::{self, Entity as Customer},
+        customer_track::{self, Entity as CustomerTrack},
+    },
+    services::permission_service::AccessScope,
+    utils::pinyin,
+};
+
+/// 全文索引表，见迁移 024_create_search_index.sql
+const CUSTOMERS_FTS: &str = "customers_fts";
+const TRACKS_FTS: &str = "customer_tracks_fts";
+
+/// trigram 分词只能匹配不少于三个字符的片段，更短的词改用 LIKE
+const MIN_MATCH_CHARS: usize = 3;
+/// 最多使用的搜索词数量
+const MAX_TERMS: usize = 10;
+/// 摘要中匹配内容前后保留的字符数
+const SNIPPET_CONTEXT: usize = 16;
+
+/// 摘要中高亮的开始和结束标记，生成摘要时使用控制字符，转义 HTML 后再替换为标签
+const MARK_START: char = '\u{2}';
+const MARK_END: char = '\u{3}';
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchHitKind {
+    Customer,
+    Track,
+}
+
+/// 一条搜索结果。摘要已转义 HTML，匹配内容用 `<mark>` 标出
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub kind: SearchHitKind,
+    pub customer_id: i32,
+    pub customer_name: String,
+    /// 跟进记录的结果才有
+    pub track_id: Option<i32>,
+    pub track_time: Option<DateTime<Utc>>,
+    pub snippet: String,
+    /// 越小越相关；只有短词的搜索和按拼音匹配的客户无法评分，为 0
+    pub rank: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CustomerRow {
+    id: i32,
+    name: String,
+    phone: Option<String>,
+    address: Option<String>,
+    notes: Option<String>,
+    snippet: Option<String>,
+    score: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct TrackRow {
+    id: i32,
+    customer_id: i32,
+    customer_name: String,
+    content: String,
+    track_time: DateTime<Utc>,
+    snippet: Option<String>,
+    score: f64,
+}
+
+/// 按空白拆分后的搜索词
+#[derive(Debug, Clone)]
+struct SearchTerms {
+    terms: Vec<String>,
+}
+
+impl SearchTerms {
+    fn parse(query: &str) -> Option<Self> {
+        let mut terms: Vec<String> = Vec::new();
+        for term in query.split_whitespace().map(|term| term.replace('"', "")) {
+            if !term.is_empty() && !terms.contains(&term) && terms.len() < MAX_TERMS {
+                terms.push(term);
+            }
+        }
+        (!terms.is_empty()).then_some(Self { terms })
+    }
+
+    /// 可以用全文索引匹配的词组成的 FTS5 查询，每个词作为一个短语
+    fn match_query(&self) -> Option<String> {
+        let phrases: Vec<String> = self
+            .terms
+            .iter()
+            .filter(|term| term.chars().count() >= MIN_MATCH_CHARS)
+            .map(|term| format!("\"{term}\""))
+            .collect();
+        (!phrases.is_empty()).then(|| phrases.join(" "))
+    }
+
+    fn short_terms(&self) -> impl Iterator<Item = &String> {
+        self.terms
+            .iter()
+            .filter(|term| term.chars().count() < MIN_MATCH_CHARS)
+    }
+}
+
+pub struct SearchService;
+
+impl SearchService {
+    /// 在可见的客户和它们的跟进记录中搜索，按相关度排序。
+    /// 所有词都要匹配；没有可搜索的内容时返回空结果
+    pub async fn search(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        query: &str,
+        include_tracks: bool,
+        limit: u64,
+    ) -> Result<Vec<SearchHit>, DbErr> {
+        let Some(terms) = SearchTerms::parse(query) else {
+            return Ok(Vec::new());
+        };
+
+        let mut hits = Self::search_customers(db, scope, &terms, limit).await?;
+        if include_tracks {
+            hits.extend(Self::search_tracks(db, scope, &terms, limit).await?);
+        }
+
+        // 两类结果都按 bm25 评分，合并后重新排序；评分相同时保持各自的顺序
+        hits.sort_by(|a, b| a.rank.total_cmp(&b.rank));
+        hits.truncate(limit as usize);
+        Ok(hits)
+    }
+
+    /// 客户列表的搜索条件：名称、名称的全拼或简拼、电话中包含搜索内容
+    pub fn name_or_phone_condition(search: &str) -> Condition {
+        let search = search.trim();
+        let condition = Condition::any()
+            .add(customer::Column::Name.contains(search))
+            .add(customer::Column::Phone.contains(search));
+        match pinyin_condition(search) {
+            Some(pinyin) => condition.add(pinyin),
+            None => condition,
+        }
+    }
+
+    async fn search_customers(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        terms: &SearchTerms,
+        limit: u64,
+    ) -> Result<Vec<SearchHit>, DbErr> {
+        let fts = Alias::new(CUSTOMERS_FTS);
+        let mut select = Query::select();
+        select
+            .column((Customer, customer::Column::Id))
+            .column((Customer, customer::Column::Name))
+            .column((Customer, customer::Column::Phone))
+            .column((Customer, customer::Column::Address))
+            .column((Customer, customer::Column::Notes))
+            .from(fts.clone())
+            .inner_join(
+                Customer,
+                Expr::col((Customer, customer::Column::Id)).equals((fts, Alias::new("rowid"))),
+            )
+            .cond_where(scope.visible_condition())
+            .and_where(customer::Column::IsDeleted.eq(false));
+
+        for term in terms.short_terms() {
+            select.cond_where(customer_text_condition(term));
+        }
+
+        // 名称的权重最高，其次是电话、地址、备注
+        ranked(
+            &mut select,
+            CUSTOMERS_FTS,
+            terms.match_query(),
+            "10.0, 5.0, 2.0, 1.0",
+            (Customer, customer::Column::UpdatedAt),
+        );
+        select.limit(limit);
+
+        let rows = CustomerRow::find_by_statement(db.get_database_backend().build(&select))
+            .all(db)
+            .await?;
+
+        let mut hits: Vec<SearchHit> = rows
+            .into_iter()
+            .map(|row| {
+                let snippet = row.snippet.unwrap_or_else(|| {
+                    [Some(&row.name), row.phone.as_ref(), row.address.as_ref(), row.notes.as_ref()]
+                        .into_iter()
+                        .flatten()
+                        .find_map(|text| mark_snippet(text, &terms.terms))
+                        .unwrap_or_else(|| row.name.clone())
+                });
+                SearchHit {
+                    kind: SearchHitKind::Customer,
+                    customer_id: row.id,
+                    customer_name: row.name,
+                    track_id: None,
+                    track_time: None,
+                    snippet: render_snippet(&snippet),
+                    rank: row.score,
+                }
+            })
+            .collect();
+
+        let remaining = limit.saturating_sub(hits.len() as u64);
+        if remaining > 0 {
+            let found: Vec<i32> = hits.iter().map(|hit| hit.customer_id).collect();
+            hits.extend(Self::search_customer_pinyin(db, scope, terms, found, remaining).await?);
+        }
+        Ok(hits)
+    }
+
+    /// 按拼音匹配的客户：每个词匹配客户的内容，或者匹配名称的全拼或简拼，
+    /// 词中的汉字也转为拼音，所以「张s」可以找到「张三」。
+    /// 这些结果排在全文匹配之后，摘要为客户名称
+    async fn search_customer_pinyin(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        terms: &SearchTerms,
+        exclude: Vec<i32>,
+        limit: u64,
+    ) -> Result<Vec<SearchHit>, DbErr> {
+        let pinyin_conditions: Vec<Option<Condition>> =
+            terms.terms.iter().map(|term| pinyin_condition(term)).collect();
+        if pinyin_conditions.iter().all(Option::is_none) {
+            return Ok(Vec::new());
+        }
+
+        let mut condition = Condition::all()
+            .add(scope.visible_condition())
+            .add(customer::Column::IsDeleted.eq(false))
+            .add(customer::Column::Id.is_not_in(exclude));
+        for (term, pinyin) in terms.terms.iter().zip(pinyin_conditions) {
+            let text = customer_text_condition(term);
+            condition = condition.add(match pinyin {
+                Some(pinyin) => text.add(pinyin),
+                None => text,
+            });
+        }
+
+        let customers = Customer::find()
+            .filter(condition)
+            .order_by_desc(customer::Column::UpdatedAt)
+            .limit(limit)
+            .all(db)
+            .await?;
+
+        Ok(customers
+            .into_iter()
+            .map(|customer| SearchHit {
+                kind: SearchHitKind::Customer,
+                customer_id: customer.id,
+                snippet: render_snippet(&format!("{MARK_START}{}{MARK_END}", customer.name)),
+                customer_name: customer.name,
+                track_id: None,
+                track_time: None,
+                rank: 0.0,
+            })
+            .collect())
+    }
+
+    async fn search_tracks(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        terms: &SearchTerms,
+        limit: u64,
+    ) -> Result<Vec<SearchHit>, DbErr> {
+        let fts = Alias::new(TRACKS_FTS);
+        let mut select = Query::select();
+        select
+            .column((CustomerTrack, customer_track::Column::Id))
+            .column((CustomerTrack, customer_track::Column::CustomerId))
+            .expr_as(Expr::col((Customer, customer::Column::Name)), Alias::new("customer_name"))
+            .column((CustomerTrack, customer_track::Column::Content))
+            .column((CustomerTrack, customer_track::Column::TrackTime))
+            .from(fts.clone())
+            .inner_join(
+                CustomerTrack,
+                Expr::col((CustomerTrack, customer_track::Column::Id))
+                    .equals((fts, Alias::new("rowid"))),
+            )
+            .inner_join(
+                Customer,
+                Expr::col((Customer, customer::Column::Id))
+                    .equals((CustomerTrack, customer_track::Column::CustomerId)),
+            )
+            .cond_where(scope.visible_condition())
+            .and_where(customer::Column::IsDeleted.eq(false));
+
+        for term in terms.short_terms() {
+            select.and_where(customer_track::Column::Content.contains(term));
+        }
+
+        ranked(
+            &mut select,
+            TRACKS_FTS,
+            terms.match_query(),
+            "1.0",
+            (CustomerTrack, customer_track::Column::TrackTime),
+        );
+        select.limit(limit);
+
+        let rows = TrackRow::find_by_statement(db.get_database_backend().build(&select))
+            .all(db)
+            .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let snippet = row
+                    .snippet
+                    .or_else(|| mark_snippet(&row.content, &terms.terms))
+                    .unwrap_or(row.content);
+                SearchHit {
+                    kind: SearchHitKind::Track,
+                    customer_id: row.customer_id,
+                    customer_name: row.customer_name,
+                    track_id: Some(row.id),
+                    track_time: Some(row.track_time),
+                    snippet: render_snippet(&snippet),
+                    rank: row.score,
+                }
+            })
+            .collect())
+    }
+}
+
+/// 客户的名称、电话、地址、备注中包含这个词
+fn customer_text_condition(term: &str) -> Condition {
+    Condition::any()
+        .add(customer::Column::Name.contains(term))
+        .add(customer::Column::Phone.contains(term))
+        .add(customer::Column::Address.contains(term))
+        .add(customer::Column::Notes.contains(term))
+}
+
+/// 名称的全拼或简拼中包含这个词，词中的汉字按同样的方式转为拼音。
+/// 只有包含字母的词才按拼音匹配，纯汉字或数字的词不会匹配到同音字
+fn pinyin_condition(term: &str) -> Option<Condition> {
+    if !term.chars().any(|c| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    let mut condition =
+        Condition::any().add(customer::Column::NamePinyin.contains(pinyin::full_pinyin(term)));
+    for initials in [pinyin::initials(term), pinyin::initials_with_zh(term)] {
+        condition = condition.add(customer::Column::NameInitials.contains(initials));
+    }
+    Some(condition)
+}
+
+/// 加上全文匹配条件、摘要和评分；没有可以用索引匹配的词时按时间倒序，摘要由 [`mark_snippet`] 生成
+fn ranked<T>(
+    select: &mut SelectStatement,
+    table: &str,
+    match_query: Option<String>,
+    weights: &str,
+    fallback_order: T,
+) where
+    T: sea_orm::sea_query::IntoColumnRef,
+{
+    match match_query {
+        Some(match_query) => {
+            select
+                .expr_as(
+                    Expr::cust(format!(
+                        "snippet({table}, -1, char(2), char(3), '…', {})",
+                        SNIPPET_CONTEXT * 2
+                    )),
+                    Alias::new("snippet"),
+                )
+                .expr_as(Expr::cust(format!("bm25({table}, {weights})")), Alias::new("score"))
+                .and_where(Expr::cust_with_values(format!("{table} MATCH ?"), [match_query]))
+                .order_by(Alias::new("score"), Order::Asc);
+        }
+        None => {
+            select
+                .expr_as(Expr::cust("NULL"), Alias::new("snippet"))
+                .expr_as(Expr::cust("0.0"), Alias::new("score"))
+                .order_by(fallback_order, Order::Desc);
+        }
+    }
+}
+
+/// 在文本中找到第一个匹配的词，截取前后的内容并标出所有匹配，没有匹配时返回 None
+fn mark_snippet(text: &str, terms: &[String]) -> Option<String> {
+    let lower = text.to_ascii_lowercase();
+    let start = terms
+        .iter()
+        .filter_map(|term| lower.find(&term.to_ascii_lowercase()))
+        .min()?;
+
+    let total = text.chars().count();
+    let skip = text[..start].chars().count().saturating_sub(SNIPPET_CONTEXT);
+    let window: String = text.chars().skip(skip).take(SNIPPET_CONTEXT * 2).collect();
+    let taken = window.chars().count();
+
+    let mut snippet = String::new();
+    if skip > 0 {
+        snippet.push('…');
+    }
+    snippet.push_str(&mark_terms(&window, terms));
+    if skip + taken < total {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+/// 用高亮标记包住文本中出现的所有词，不区分 ASCII 大小写，优先匹配较长的词
+fn mark_terms(text: &str, terms: &[String]) -> String {
+    let lower = text.to_ascii_lowercase();
+    let mut terms: Vec<String> = terms.iter().map(|term| term.to_ascii_lowercase()).collect();
+    terms.sort_by_key(|term| std::cmp::Reverse(term.len()));
+
+    let mut marked = String::with_capacity(text.len());
+    let mut position = 0;
+    while position < text.len() {
+        if let Some(term) = terms.iter().find(|term| lower[position..].starts_with(term.as_str())) {
+            marked.push(MARK_START);
+            marked.push_str(&text[position..position + term.len()]);
+            marked.push(MARK_END);
+            position += term.len();
+        } else {
+            let next = text[position..].chars().next().map_or(1, char::len_utf8);
+            marked.push_str(&text[position..position + next]);
+            position += next;
+        }
+    }
+    marked
+}
+
+/// 转义 HTML，再把高亮标记换成 `<mark>` 标签
+fn render_snippet(snippet: &str) -> String {
+    let mut html = String::with_capacity(snippet.len());
+    for c in snippet.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            MARK_START => html.push_str("<mark>"),
+            MARK_END => html.push_str("</mark>"),
+            c => html.push(c),
+        }
+    }
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::{ActiveModelTrait, Set, Statement};
+
+    use super::*;
+    use crate::{entities::user_role::UserRole, test_support};
+
+    fn terms(query: &str) -> Vec<String> {
+        SearchTerms::parse(query)
+            .map(|terms| terms.terms)
+            .unwrap_or_default()
+    }
+
+    async fn customer_with_notes(
+        db: &DatabaseConnection,
+        name: &str,
+        notes: &str,
+        owner_id: i32,
+    ) -> customer::Model {
+        let customer = test_support::create_customer(db, name, None, Some(owner_id)).await;
+        let mut active: customer::ActiveModel = customer.into();
+        active.notes = Set(Some(notes.to_string()));
+        active.update(db).await.unwrap()
+    }
+
+    async fn search(db: &DatabaseConnection, scope: &AccessScope, query: &str) -> Vec<SearchHit> {
+        SearchService::search(db, scope, query, true, 20)
+            .await
+            .unwrap()
+    }
+
+    fn hit_ids(hits: &[SearchHit]) -> Vec<(SearchHitKind, i32)> {
+        hits.iter()
+            .map(|hit| (hit.kind, hit.track_id.unwrap_or(hit.customer_id)))
+            .collect()
+    }
+
+    #[test]
+    fn terms_are_split_deduplicated_and_stripped_of_quotes() {
+        assert_eq!(terms("  瑜伽  \"morning\" 瑜伽 "), vec!["瑜伽", "morning"]);
+        assert_eq!(terms("\"\" "), Vec::<String>::new());
+        let many: Vec<String> = (0..20).map(|i| format!("term{i}")).collect();
+        assert_eq!(terms(&many.join(" ")).len(), MAX_TERMS);
+
+        // 少于三个字符的词不进入全文查询
+        let parsed = SearchTerms::parse("早上 瑜伽课 yo").unwrap();
+        assert_eq!(parsed.match_query().as_deref(), Some("\"瑜伽课\""));
+        assert_eq!(parsed.short_terms().collect::<Vec<_>>(), vec!["早上", "yo"]);
+        assert_eq!(SearchTerms::parse("早上").unwrap().match_query(), None);
+    }
+
+    #[test]
+    fn snippets_keep_context_around_the_first_match() {
+        let text = format!("{}想上早上的瑜伽课{}", "前".repeat(30), "后".repeat(30));
+        let snippet = mark_snippet(&text, &["瑜伽".to_string()]).unwrap();
+        assert!(snippet.starts_with('…') && snippet.ends_with('…'));
+        assert!(snippet.contains(&format!("早上的{MARK_START}瑜伽{MARK_END}课")));
+        assert_eq!(
+            snippet.chars().filter(|c| *c != '…').count(),
+            SNIPPET_CONTEXT * 2 + 2
+        );
+
+        assert_eq!(
+            mark_snippet("Morning Yoga", &["yoga".to_string()]).unwrap(),
+            format!("Morning {MARK_START}Yoga{MARK_END}")
+        );
+        assert_eq!(mark_snippet("瑜伽", &["普拉提".to_string()]), None);
+        // 较长的词优先，不会被较短的词拆开
+        assert_eq!(
+            mark_terms("yoga class", &["yo".to_string(), "yoga".to_string()]),
+            format!("{MARK_START}yoga{MARK_END} class")
+        );
+    }
+
+    #[test]
+    fn snippets_are_escaped_before_marks_become_tags() {
+        let snippet = format!("<script>alert('x')</script> & {MARK_START}\"瑜伽\"{MARK_END}");
+        assert_eq!(
+            render_snippet(&snippet),
+            "&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt; &amp; <mark>&quot;瑜伽&quot;</mark>"
+        );
+    }
+
+    #[tokio::test]
+    async fn finds_customers_and_tracks_by_full_text_and_like() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let (scope, _) = test_support::scope_of(&db, &alice).await;
+        let yoga = customer_with_notes(&db, "张三", "wants morning yoga", alice.id).await;
+        let named = test_support::create_customer(&db, "Yoga Studio", None, Some(alice.id)).await;
+        let other = customer_with_notes(&db, "李四", "普拉提", alice.id).await;
+        let track = test_support::create_track(&db, other.id, Some(alice.id), Utc::now()).await;
+        let mut active: customer_track::ActiveModel = track.clone().into();
+        active.content = Set("下次带朋友来上早上的瑜伽课".to_string());
+        active.update(&db).await.unwrap();
+
+        // 名称的权重高于备注
+        let hits = search(&db, &scope, "yoga").await;
+        assert_eq!(
+            hit_ids(&hits),
+            vec![
+                (SearchHitKind::Customer, named.id),
+                (SearchHitKind::Customer, yoga.id)
+            ]
+        );
+        assert_eq!(hits[1].snippet, "wants morning <mark>yoga</mark>");
+
+        // 所有词都要匹配，短词改用 LIKE
+        let hits = search(&db, &scope, "morning 张三").await;
+        assert_eq!(hit_ids(&hits), vec![(SearchHitKind::Customer, yoga.id)]);
+        let hits = search(&db, &scope, "瑜伽").await;
+        assert_eq!(hit_ids(&hits), vec![(SearchHitKind::Track, track.id)]);
+        assert_eq!(hits[0].customer_name, "李四");
+        assert!(hits[0].snippet.contains("<mark>瑜伽</mark>"));
+        let hits = search(&db, &scope, "瑜伽课").await;
+        assert_eq!(hit_ids(&hits), vec![(SearchHitKind::Track, track.id)]);
+
+        let hits = SearchService::search(&db, &scope, "瑜伽课", false, 20)
+            .await
+            .unwrap();
+        assert!(hits.is_empty());
+        assert!(search(&db, &scope, "  ").await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn the_index_follows_edits_deletes_and_visibility() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let (scope, _) = test_support::scope_of(&db, &alice).await;
+        let customer = customer_with_notes(&db, "张三", "<b>morning</b> yoga", alice.id).await;
+        let others = customer_with_notes(&db, "李四", "morning yoga", bob.id).await;
+
+        let hits = search(&db, &scope, "morning").await;
+        assert_eq!(hit_ids(&hits), vec![(SearchHitKind::Customer, customer.id)]);
+        // 全文索引返回的摘要同样会转义
+        assert_eq!(
+            hits[0].snippet,
+            "&lt;b&gt;<mark>morning</mark>&lt;/b&gt; yoga"
+        );
+
+        let mut active: customer::ActiveModel = customer.clone().into();
+        active.notes = Set(Some("evening pilates".to_string()));
+        let customer = active.update(&db).await.unwrap();
+        assert!(search(&db, &scope, "morning").await.is_empty());
+        assert_eq!(search(&db, &scope, "pilates").await.len(), 1);
+
+        let mut active: customer::ActiveModel = customer.into();
+        active.is_deleted = Set(true);
+        active.update(&db).await.unwrap();
+        assert!(search(&db, &scope, "pilates").await.is_empty());
+
+        let (scope, _) = test_support::scope_of(&db, &bob).await;
+        assert_eq!(search(&db, &scope, "morning").await.len(), 1);
+        customer::Entity::delete_by_id(others.id)
+            .exec(&db)
+            .await
+            .unwrap();
+        assert!(search(&db, &scope, "morning").await.is_empty());
+        let indexed = db
+            .query_one(Statement::from_string(
+                db.get_database_backend(),
+                format!("SELECT COUNT(*) AS count FROM {CUSTOMERS_FTS} WHERE {CUSTOMERS_FTS} MATCH 'morning'"),
+            ))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(indexed.try_get::<i64>("", "count").unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn matches_full_pinyin_initials_and_mixed_input() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let (scope, _) = test_support::scope_of(&db, &alice).await;
+        let zhang = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        let zeng = test_support::create_customer(&db, "曾山", None, Some(alice.id)).await;
+        let li =
+            test_support::create_customer(&db, "李四", Some("13800000001"), Some(alice.id)).await;
+
+        async fn listed(db: &DatabaseConnection, search: &str) -> Vec<i32> {
+            Customer::find()
+                .filter(SearchService::name_or_phone_condition(search))
+                .order_by_asc(customer::Column::Id)
+                .all(db)
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|customer| customer.id)
+                .collect()
+        }
+        assert_eq!(listed(&db, "zhangsan").await, vec![zhang.id]);
+        assert_eq!(listed(&db, "zhs").await, vec![zhang.id]);
+        assert_eq!(listed(&db, " 张sa ").await, vec![zhang.id]);
+        // 词中的汉字也转为首字母，「张s」和「zs」一样会匹配到曾山
+        assert_eq!(listed(&db, "张s").await, vec![zhang.id, zeng.id]);
+        assert_eq!(listed(&db, "zs").await, vec![zhang.id, zeng.id]);
+        assert_eq!(listed(&db, "ZENG").await, vec![zeng.id]);
+        assert_eq!(listed(&db, "0001").await, vec![li.id]);
+        // 纯汉字不按拼音匹配同音字
+        assert_eq!(listed(&db, "章").await, Vec::<i32>::new());
+
+        let hits = search(&db, &scope, "张sa").await;
+        assert_eq!(hit_ids(&hits), vec![(SearchHitKind::Customer, zhang.id)]);
+        assert_eq!(hits[0].snippet, "<mark>张三</mark>");
+        assert_eq!(hits[0].rank, 0.0);
+        let hits = search(&db, &scope, "ls 138").await;
+        assert_eq!(hit_ids(&hits), vec![(SearchHitKind::Customer, li.id)]);
+    }
+}
@@ -0,0 +1,326 @@
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder,
+    Set,
+};
+
+use crate::{
+    entities::{session, session::Entity as Session},
+    utils::{
+        client_ip::ClientInfo,
+        token::{generate_secret, hash_secret},
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum SessionError {
+    #[error("刷新令牌无效")]
+    InvalidToken,
+    #[error("会话已失效")]
+    Inactive,
+    #[error("检测到刷新令牌被重复使用，会话已吊销")]
+    TokenReused,
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 新签发的会话及其明文刷新令牌（明文只在此处出现一次）
+pub struct IssuedSession {
+    pub session: session::Model,
+    pub refresh_token: String,
+}
+
+pub struct SessionService;
+
+impl SessionService {
+    /// 登录成功后创建会话，记录登录时的IP和客户端
+    pub async fn create_session(
+        db: &DatabaseConnection,
+        user_id: i32,
+        expire_days: i64,
+        client: &ClientInfo,
+    ) -> Result<IssuedSession, SessionError> {
+        let now = Utc::now();
+        let secret = generate_secret();
+
+        // 令牌中的会话ID不参与哈希，插入时就能写入最终的令牌哈希
+        let session = session::ActiveModel {
+            user_id: Set(user_id),
+            refresh_token_hash: Set(hash_secret(&secret)),
+            created_at: Set(now),
+            updated_at: Set(now),
+            expires_at: Set(now + Duration::days(expire_days)),
+            revoked_at: Set(None),
+            ip_address: Set(Some(client.ip.clone())),
+            user_agent: Set(client.user_agent.clone()),
+            previous_token_hash: Set(None),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(IssuedSession {
+            refresh_token: format_token(session.id, &secret),
+            session,
+        })
+    }
+
+    /// 使用刷新令牌换取新令牌。
+    ///
+    /// 令牌格式为 `<会话ID>.<随机串>`，每次刷新都会轮换随机串。
+    /// 只有提交的令牌正是该会话上一次轮换掉的令牌时，才说明旧令牌被重复使用，
+    /// 此时吊销整个会话；其他不匹配的令牌只拒绝，否则任何人都能按会话ID注销别人。
+    /// 令牌哈希以条件更新的方式替换，同一个令牌并发刷新时只有一个请求成功。
+    pub async fn rotate(
+        db: &DatabaseConnection,
+        refresh_token: &str,
+        expire_days: i64,
+    ) -> Result<IssuedSession, SessionError> {
+        let (session_id, secret) =
+            parse_token(refresh_token).ok_or(SessionError::InvalidToken)?;
+
+        let session = Session::find_by_id(session_id)
+            .one(db)
+            .await?
+            .ok_or(SessionError::InvalidToken)?;
+
+        if !session.is_active() {
+            return Err(SessionError::Inactive);
+        }
+
+        let token_hash = hash_secret(secret);
+        if session.previous_token_hash.as_deref() == Some(token_hash.as_str()) {
+            Self::revoke(db, session.id).await?;
+            tracing::warn!(
+                "会话 {} (用户 {}) 的刷新令牌被重复使用，已吊销",
+                session.id,
+                session.user_id
+            );
+            return Err(SessionError::TokenReused);
+        }
+        if session.refresh_token_hash != token_hash {
+            return Err(SessionError::InvalidToken);
+        }
+
+        let now = Utc::now();
+        let new_secret = generate_secret();
+        let result = Session::update_many()
+            .col_expr(session::Column::RefreshTokenHash, hash_secret(&new_secret).into())
+            .col_expr(session::Column::PreviousTokenHash, token_hash.clone().into())
+            .col_expr(session::Column::ExpiresAt, (now + Duration::days(expire_days)).into())
+            .col_expr(session::Column::UpdatedAt, now.into())
+            .filter(session::Column::Id.eq(session.id))
+            .filter(session::Column::RefreshTokenHash.eq(token_hash))
+            .filter(session::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+        // 其他请求已经用同一个令牌完成了刷新，或者会话刚被吊销
+        if result.rows_affected == 0 {
+            return Err(SessionError::InvalidToken);
+        }
+
+        let session = Session::find_by_id(session.id)
+            .one(db)
+            .await?
+            .ok_or(SessionError::InvalidToken)?;
+
+        Ok(IssuedSession {
+            refresh_token: format_token(session.id, &new_secret),
+            session,
+        })
+    }
+
+    /// 吊销单个会话
+    pub async fn revoke(db: &DatabaseConnection, session_id: i32) -> Result<(), SessionError> {
+        let now = Utc::now();
+        Session::update_many()
+            .col_expr(session::Column::RevokedAt, now.into())
+            .col_expr(session::Column::UpdatedAt, now.into())
+            .filter(session::Column::Id.eq(session_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// 吊销用户自己的某个会话，返回是否找到该会话
+    pub async fn revoke_for_user(
+        db: &DatabaseConnection,
+        user_id: i32,
+        session_id: i32,
+    ) -> Result<bool, SessionError> {
+        let now = Utc::now();
+        let result = Session::update_many()
+            .col_expr(session::Column::RevokedAt, now.into())
+            .col_expr(session::Column::UpdatedAt, now.into())
+            .filter(session::Column::Id.eq(session_id))
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// 吊销除当前会话之外的所有会话，返回吊销的数量
+    pub async fn revoke_others(
+        db: &DatabaseConnection,
+        user_id: i32,
+        current_session_id: i32,
+    ) -> Result<u64, SessionError> {
+        let now = Utc::now();
+        let result = Session::update_many()
+            .col_expr(session::Column::RevokedAt, now.into())
+            .col_expr(session::Column::UpdatedAt, now.into())
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::Id.ne(current_session_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// 吊销用户的所有会话，例如修改密码之后
+    pub async fn revoke_all_for_user<C: ConnectionTrait>(
+        db: &C,
+        user_id: i32,
+    ) -> Result<(), SessionError> {
+        let now = Utc::now();
+        Session::update_many()
+            .col_expr(session::Column::RevokedAt, now.into())
+            .col_expr(session::Column::UpdatedAt, now.into())
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .exec(db)
+            .await?;
+        Ok(())
+    }
+
+    /// 用户当前有效的会话，最近活跃的在前
+    pub async fn list_active(
+        db: &DatabaseConnection,
+        user_id: i32,
+    ) -> Result<Vec<session::Model>, SessionError> {
+        let sessions = Session::find()
+            .filter(session::Column::UserId.eq(user_id))
+            .filter(session::Column::RevokedAt.is_null())
+            .filter(session::Column::ExpiresAt.gt(Utc::now()))
+            .order_by_desc(session::Column::UpdatedAt)
+            .all(db)
+            .await?;
+        Ok(sessions)
+    }
+
+    /// 检查访问令牌所属的会话是否仍然有效
+    pub async fn is_active(
+        db: &DatabaseConnection,
+        session_id: i32,
+        user_id: i32,
+    ) -> Result<bool, SessionError> {
+        let session = Session::find_by_id(session_id)
+            .filter(session::Column::UserId.eq(user_id))
+            .one(db)
+            .await?;
+
+        Ok(session.is_some_and(|s| s.is_active()))
+    }
+}
+
+fn format_token(session_id: i32, secret: &str) -> String {
+    format!("{}.{}", session_id, secret)
+}
+
+fn parse_token(token: &str) -> Option<(i32, &str)> {
+    let (id, secret) = token.split_once('.')?;
+    let id = id.parse().ok()?;
+    if secret.is_empty() {
+        return None;
+    }
+    Some((id, secret))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{entities::user_role::UserRole, test_support};
+
+    fn client() -> ClientInfo {
+        ClientInfo { ip: "127.0.0.1".to_string(), user_agent: None }
+    }
+
+    async fn issue(db: &DatabaseConnection) -> IssuedSession {
+        let user = test_support::create_user(db, "sales", UserRole::Sales).await;
+        SessionService::create_session(db, user.id, 30, &client()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn rotate_replaces_the_refresh_token() {
+        let db = test_support::test_db().await;
+        let issued = issue(&db).await;
+
+        let rotated = SessionService::rotate(&db, &issued.refresh_token, 30).await.unwrap();
+        assert_eq!(rotated.session.id, issued.session.id);
+        assert_ne!(rotated.refresh_token, issued.refresh_token);
+
+        let again = SessionService::rotate(&db, &rotated.refresh_token, 30).await.unwrap();
+        assert_eq!(again.session.id, issued.session.id);
+    }
+
+    #[tokio::test]
+    async fn reusing_the_previous_token_revokes_the_session() {
+        let db = test_support::test_db().await;
+        let issued = issue(&db).await;
+        let rotated = SessionService::rotate(&db, &issued.refresh_token, 30).await.unwrap();
+
+        let reused = SessionService::rotate(&db, &issued.refresh_token, 30).await;
+        assert!(matches!(reused, Err(SessionError::TokenReused)));
+
+        let current = SessionService::rotate(&db, &rotated.refresh_token, 30).await;
+        assert!(matches!(current, Err(SessionError::Inactive)));
+        let user_id = issued.session.user_id;
+        assert!(!SessionService::is_active(&db, issued.session.id, user_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn guessed_token_is_rejected_without_revoking() {
+        let db = test_support::test_db().await;
+        let issued = issue(&db).await;
+
+        let guessed = format!("{}.guess", issued.session.id);
+        let result = SessionService::rotate(&db, &guessed, 30).await;
+        assert!(matches!(result, Err(SessionError::InvalidToken)));
+        for token in ["", "abc", "1.", "x.y"] {
+            let result = SessionService::rotate(&db, token, 30).await;
+            assert!(matches!(result, Err(SessionError::InvalidToken)));
+        }
+
+        let user_id = issued.session.user_id;
+        assert!(SessionService::is_active(&db, issued.session.id, user_id).await.unwrap());
+        assert!(SessionService::rotate(&db, &issued.refresh_token, 30).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn concurrent_refreshes_with_one_token_yield_one_pair() {
+        let db = test_support::test_db().await;
+        let issued = issue(&db).await;
+
+        let (first, second) = tokio::join!(
+            SessionService::rotate(&db, &issued.refresh_token, 30),
+            SessionService::rotate(&db, &issued.refresh_token, 30),
+        );
+        assert_eq!(first.is_ok() as u8 + second.is_ok() as u8, 1);
+    }
+
+    #[tokio::test]
+    async fn logout_stops_the_session() {
+        let db = test_support::test_db().await;
+        let issued = issue(&db).await;
+        let user_id = issued.session.user_id;
+
+        SessionService::revoke(&db, issued.session.id).await.unwrap();
+
+        assert!(!SessionService::is_active(&db, issued.session.id, user_id).await.unwrap());
+        let result = SessionService::rotate(&db, &issued.refresh_token, 30).await;
+        assert!(matches!(result, Err(SessionError::Inactive)));
+        assert!(SessionService::list_active(&db, user_id).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,496 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::Utc;
+use sea_orm::{
+    sea_query::{Expr, OnConflict, Query},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, JoinType, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
+    TransactionTrait,
+};
+
+use crate::{
+    entities::{
+        customer,
+        customer_tag::{self, Entity as CustomerTag},
+        tag::{self, Entity as Tag},
+    },
+    services::permission_service::AccessScope,
+};
+
+/// 标签名称的最大长度
+const MAX_NAME_LENGTH: usize = 50;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TagError {
+    #[error("标签名称不能为空，且不能超过50个字符")]
+    InvalidName,
+    #[error("标签 {0} 已存在")]
+    NameExists(String),
+    #[error("标签不存在: {0}")]
+    UnknownTag(i32),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+pub struct TagService;
+
+impl TagService {
+    /// 组织的标签及 `scope` 范围内使用该标签的客户数，按名称排序
+    pub async fn list_with_counts(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+    ) -> Result<Vec<(tag::Model, i64)>, DbErr> {
+        let tags = Tag::find()
+            .filter(tag::Column::OrganizationId.eq(scope.organization_id))
+            .order_by_asc(tag::Column::Name)
+            .all(db)
+            .await?;
+
+        let counts: HashMap<i32, i64> = CustomerTag::find()
+            .select_only()
+            .column(customer_tag::Column::TagId)
+            .column_as(Expr::col(customer_tag::Column::CustomerId).count(), "count")
+            .join(JoinType::InnerJoin, customer_tag::Relation::Customer.def())
+            .filter(scope.visible_condition())
+            .filter(customer::Column::IsDeleted.eq(false))
+            .group_by(customer_tag::Column::TagId)
+            .into_tuple::<(i32, i64)>()
+            .all(db)
+            .await?
+            .into_iter()
+            .collect();
+
+        Ok(tags
+            .into_iter()
+            .map(|tag| {
+                let count = counts.get(&tag.id).copied().unwrap_or(0);
+                (tag, count)
+            })
+            .collect())
+    }
+
+    pub async fn find(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        tag_id: i32,
+    ) -> Result<Option<tag::Model>, DbErr> {
+        Tag::find_by_id(tag_id)
+            .filter(tag::Column::OrganizationId.eq(organization_id))
+            .one(db)
+            .await
+    }
+
+    pub async fn create(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        created_by: Option<i32>,
+        name: &str,
+        color: Option<String>,
+    ) -> Result<tag::Model, TagError> {
+        let name = checked_name(name)?;
+        Self::ensure_unique(db, organization_id, &name, None).await?;
+
+        let now = Utc::now();
+        let tag = tag::ActiveModel {
+            organization_id: Set(organization_id),
+            name: Set(name),
+            color: Set(color.filter(|color| !color.trim().is_empty())),
+            created_by: Set(created_by),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(tag)
+    }
+
+    pub async fn update(
+        db: &DatabaseConnection,
+        tag: tag::Model,
+        name: Option<String>,
+        color: Option<String>,
+    ) -> Result<tag::Model, TagError> {
+        let organization_id = tag.organization_id;
+        let tag_id = tag.id;
+        let mut active: tag::ActiveModel = tag.into();
+
+        if let Some(name) = name {
+            let name = checked_name(&name)?;
+            Self::ensure_unique(db, organization_id, &name, Some(tag_id)).await?;
+            active.name = Set(name);
+        }
+        if let Some(color) = color {
+            active.color = Set((!color.trim().is_empty()).then_some(color));
+        }
+        active.updated_at = Set(Utc::now());
+
+        Ok(active.update(db).await?)
+    }
+
+    /// 删除标签，同时从所有客户上移除
+    pub async fn delete(db: &DatabaseConnection, tag: &tag::Model) -> Result<(), DbErr> {
+        let txn = db.begin().await?;
+        CustomerTag::delete_many()
+            .filter(customer_tag::Column::TagId.eq(tag.id))
+            .exec(&txn)
+            .await?;
+        Tag::delete_by_id(tag.id).exec(&txn).await?;
+        txn.commit().await
+    }
+
+    /// 检查标签都属于该组织，返回去重后的标签ID
+    pub async fn checked_ids(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        tag_ids: &[i32],
+    ) -> Result<Vec<i32>, TagError> {
+        let tag_ids: Vec<i32> = tag_ids
+            .iter()
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        if tag_ids.is_empty() {
+            return Ok(tag_ids);
+        }
+
+        let found: Vec<i32> = Tag::find()
+            .select_only()
+            .column(tag::Column::Id)
+            .filter(tag::Column::OrganizationId.eq(organization_id))
+            .filter(tag::Column::Id.is_in(tag_ids.clone()))
+            .into_tuple()
+            .all(db)
+            .await?;
+        if let Some(missing) = tag_ids.iter().find(|id| !found.contains(id)) {
+            return Err(TagError::UnknownTag(*missing));
+        }
+
+        Ok(tag_ids)
+    }
+
+    /// 用 `checked_ids` 校验过的标签替换客户的全部标签
+    pub async fn set_customer_tags<C: ConnectionTrait>(
+        db: &C,
+        customer_id: i32,
+        tag_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        CustomerTag::delete_many()
+            .filter(customer_tag::Column::CustomerId.eq(customer_id))
+            .filter(customer_tag::Column::TagId.is_not_in(tag_ids.to_vec()))
+            .exec(db)
+            .await?;
+        Self::add_customer_tags(db, customer_id, tag_ids).await
+    }
+
+    /// 给客户加上标签，已有的保持不变
+    pub async fn add_customer_tags<C: ConnectionTrait>(
+        db: &C,
+        customer_id: i32,
+        tag_ids: &[i32],
+    ) -> Result<(), DbErr> {
+        if tag_ids.is_empty() {
+            return Ok(());
+        }
+        let now = Utc::now();
+        CustomerTag::insert_many(tag_ids.iter().map(|tag_id| customer_tag::ActiveModel {
+            customer_id: Set(customer_id),
+            tag_id: Set(*tag_id),
+            created_at: Set(now),
+        }))
+        .on_conflict(
+            OnConflict::columns([customer_tag::Column::CustomerId, customer_tag::Column::TagId])
+                .do_nothing()
+                .to_owned(),
+        )
+        .do_nothing()
+        .exec(db)
+        .await?;
+        Ok(())
+    }
+
+    /// 客户的标签，按名称排序
+    pub async fn tags_for_customer<C: ConnectionTrait>(
+        db: &C,
+        customer_id: i32,
+    ) -> Result<Vec<tag::Model>, DbErr> {
+        Ok(Self::tags_for_customers(db, &[customer_id])
+            .await?
+            .remove(&customer_id)
+            .unwrap_or_default())
+    }
+
+    /// 一批客户的标签：客户ID -> 按名称排序的标签
+    pub async fn tags_for_customers<C: ConnectionTrait>(
+        db: &C,
+        customer_ids: &[i32],
+    ) -> Result<HashMap<i32, Vec<tag::Model>>, DbErr> {
+        let mut tags: HashMap<i32, Vec<tag::Model>> = HashMap::new();
+        if customer_ids.is_empty() {
+            return Ok(tags);
+        }
+
+        let rows = CustomerTag::find()
+            .find_also_related(Tag)
+            .filter(customer_tag::Column::CustomerId.is_in(customer_ids.to_vec()))
+            .order_by_asc(tag::Column::Name)
+            .all(db)
+            .await?;
+        for (customer_tag, tag) in rows {
+            if let Some(tag) = tag {
+                tags.entry(customer_tag.customer_id).or_default().push(tag);
+            }
+        }
+
+        Ok(tags)
+    }
+
+    /// 带有任意一个标签的客户
+    pub fn any_of_condition(tag_ids: &[i32]) -> Condition {
+        let tagged = Query::select()
+            .column(customer_tag::Column::CustomerId)
+            .from(CustomerTag)
+            .and_where(customer_tag::Column::TagId.is_in(tag_ids.to_vec()))
+            .to_owned();
+        Condition::all().add(customer::Column::Id.in_subquery(tagged))
+    }
+
+    /// 带有全部标签的客户
+    pub fn all_of_condition(tag_ids: &[i32]) -> Condition {
+        let tag_ids: BTreeSet<i32> = tag_ids.iter().copied().collect();
+        let tagged = Query::select()
+            .column(customer_tag::Column::CustomerId)
+            .from(CustomerTag)
+            .and_where(customer_tag::Column::TagId.is_in(tag_ids.iter().copied()))
+            .group_by_col(customer_tag::Column::CustomerId)
+            .and_having(Expr::col(customer_tag::Column::TagId).count().eq(tag_ids.len() as i64))
+            .to_owned();
+        Condition::all().add(customer::Column::Id.in_subquery(tagged))
+    }
+
+    async fn ensure_unique(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        name: &str,
+        except: Option<i32>,
+    ) -> Result<(), TagError> {
+        let mut query = Tag::find()
+            .filter(tag::Column::OrganizationId.eq(organization_id))
+            .filter(tag::Column::Name.eq(name));
+        if let Some(except) = except {
+            query = query.filter(tag::Column::Id.ne(except));
+        }
+        if query.one(db).await?.is_some() {
+            return Err(TagError::NameExists(name.to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn checked_name(name: &str) -> Result<String, TagError> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(TagError::InvalidName);
+    }
+    Ok(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        entities::{customer::Entity as Customer, organization, user_role::UserRole},
+        test_support,
+    };
+
+    async fn tag(db: &DatabaseConnection, organization_id: i32, name: &str) -> tag::Model {
+        TagService::create(db, organization_id, None, name, None)
+            .await
+            .unwrap()
+    }
+
+    async fn matching(db: &DatabaseConnection, condition: Condition) -> Vec<i32> {
+        Customer::find()
+            .filter(condition)
+            .order_by_asc(customer::Column::Id)
+            .all(db)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|customer| customer.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn any_of_matches_one_tag_and_all_of_requires_every_tag() {
+        let db = test_support::test_db().await;
+        let vip = tag(&db, 1, "VIP").await;
+        let hot = tag(&db, 1, "高意向").await;
+        let both = test_support::create_customer(&db, "张三", None, None).await;
+        let only_vip = test_support::create_customer(&db, "李四", None, None).await;
+        let untagged = test_support::create_customer(&db, "王五", None, None).await;
+        TagService::add_customer_tags(&db, both.id, &[vip.id, hot.id])
+            .await
+            .unwrap();
+        TagService::add_customer_tags(&db, only_vip.id, &[vip.id])
+            .await
+            .unwrap();
+
+        let any = matching(&db, TagService::any_of_condition(&[vip.id, hot.id])).await;
+        assert_eq!(any, vec![both.id, only_vip.id]);
+        let all = matching(&db, TagService::all_of_condition(&[vip.id, hot.id])).await;
+        assert_eq!(all, vec![both.id]);
+        // 重复的标签ID不会让“全部”变得无法满足
+        let all = matching(&db, TagService::all_of_condition(&[hot.id, vip.id, hot.id])).await;
+        assert_eq!(all, vec![both.id]);
+        let all = matching(&db, TagService::all_of_condition(&[vip.id])).await;
+        assert_eq!(all, vec![both.id, only_vip.id]);
+        assert!(!any.contains(&untagged.id));
+    }
+
+    #[tokio::test]
+    async fn setting_tags_replaces_them_and_adding_keeps_existing() {
+        let db = test_support::test_db().await;
+        let vip = tag(&db, 1, "VIP").await;
+        let hot = tag(&db, 1, "高意向").await;
+        let cold = tag(&db, 1, "沉睡").await;
+        let customer = test_support::create_customer(&db, "张三", None, None).await;
+
+        TagService::set_customer_tags(&db, customer.id, &[vip.id, hot.id])
+            .await
+            .unwrap();
+        TagService::add_customer_tags(&db, customer.id, &[hot.id, cold.id])
+            .await
+            .unwrap();
+        let names: Vec<String> = TagService::tags_for_customer(&db, customer.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|tag| tag.name)
+            .collect();
+        assert_eq!(names, vec!["VIP", "沉睡", "高意向"]);
+
+        TagService::set_customer_tags(&db, customer.id, &[cold.id])
+            .await
+            .unwrap();
+        let tags = TagService::tags_for_customer(&db, customer.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            tags.into_iter().map(|tag| tag.id).collect::<Vec<_>>(),
+            vec![cold.id]
+        );
+    }
+
+    #[tokio::test]
+    async fn checked_ids_rejects_tags_of_other_organizations() {
+        let db = test_support::test_db().await;
+        let now = Utc::now();
+        let other = organization::ActiveModel {
+            name: Set("其他组织".to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            ..Default::default()
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        let vip = tag(&db, 1, "VIP").await;
+        let hot = tag(&db, 1, "高意向").await;
+        let foreign = tag(&db, other.id, "VIP").await;
+
+        let ids = TagService::checked_ids(&db, 1, &[hot.id, vip.id, hot.id])
+            .await
+            .unwrap();
+        assert_eq!(ids, vec![vip.id, hot.id]);
+        assert!(TagService::checked_ids(&db, 1, &[])
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(matches!(
+            TagService::checked_ids(&db, 1, &[vip.id, foreign.id]).await,
+            Err(TagError::UnknownTag(id)) if id == foreign.id
+        ));
+        assert!(matches!(
+            TagService::checked_ids(&db, 1, &[9999]).await,
+            Err(TagError::UnknownTag(9999))
+        ));
+    }
+
+    #[tokio::test]
+    async fn names_are_trimmed_and_unique_within_the_organization() {
+        let db = test_support::test_db().await;
+        let vip = TagService::create(&db, 1, None, "  VIP ", Some(" ".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(vip.name, "VIP");
+        assert_eq!(vip.color, None);
+
+        assert!(matches!(
+            TagService::create(&db, 1, None, "VIP", None).await,
+            Err(TagError::NameExists(_))
+        ));
+        assert!(matches!(
+            TagService::create(&db, 1, None, "   ", None).await,
+            Err(TagError::InvalidName)
+        ));
+        assert!(matches!(
+            TagService::create(&db, 1, None, &"标".repeat(MAX_NAME_LENGTH + 1), None).await,
+            Err(TagError::InvalidName)
+        ));
+
+        let hot = tag(&db, 1, "高意向").await;
+        assert!(matches!(
+            TagService::update(&db, hot.clone(), Some("VIP".to_string()), None).await,
+            Err(TagError::NameExists(_))
+        ));
+        // 保持原名不算重名
+        let hot = TagService::update(
+            &db,
+            hot,
+            Some("高意向".to_string()),
+            Some("#f00".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(hot.color.as_deref(), Some("#f00"));
+    }
+
+    #[tokio::test]
+    async fn counts_only_visible_customers_that_are_not_deleted() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let vip = tag(&db, 1, "VIP").await;
+        let unused = tag(&db, 1, "沉睡").await;
+        let mine = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        let deleted = test_support::create_customer(&db, "李四", None, Some(alice.id)).await;
+        let others = test_support::create_customer(&db, "王五", None, Some(bob.id)).await;
+        for customer in [&mine, &deleted, &others] {
+            TagService::add_customer_tags(&db, customer.id, &[vip.id])
+                .await
+                .unwrap();
+        }
+        let mut active: customer::ActiveModel = deleted.into();
+        active.is_deleted = Set(true);
+        active.update(&db).await.unwrap();
+
+        let (scope, _) = test_support::scope_of(&db, &alice).await;
+        let counts: Vec<(i32, i64)> = TagService::list_with_counts(&db, &scope)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|(tag, count)| (tag.id, count))
+            .collect();
+        assert_eq!(counts, vec![(vip.id, 1), (unused.id, 0)]);
+
+        // 删除标签时一并从客户上移除
+        TagService::delete(&db, &vip).await.unwrap();
+        assert!(TagService::tags_for_customer(&db, mine.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
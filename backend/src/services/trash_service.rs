@@ -0,0 +1,322 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+
+use crate::{
+    config::Config,
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        customer::{self, Entity as Customer},
+        customer_collaborator::{self, Entity as CustomerCollaborator},
+        customer_ownership_history::{self, Entity as CustomerOwnershipHistory},
+        customer_track::{self, Entity as CustomerTrack},
+    },
+    services::{
+        audit_service::{Actor, AuditService},
+        permission_service::AccessScope,
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum TrashError {
+    #[error("客户已合并到客户 {0}，无法恢复")]
+    Merged(i32),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 回收站规则
+#[derive(Debug, Clone)]
+pub struct TrashSettings {
+    /// 删除超过多少天后彻底清除，0 表示一直保留
+    pub retention_days: i64,
+    /// 后台检查的间隔
+    pub purge_interval_minutes: u64,
+}
+
+impl TrashSettings {
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            retention_days: config.trash_retention_days,
+            purge_interval_minutes: config.trash_purge_interval_minutes,
+        }
+    }
+
+    /// 回收站中的客户将被自动清除的时间
+    pub fn purge_at(&self, deleted_at: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.retention_days > 0).then(|| deleted_at + Duration::days(self.retention_days))
+    }
+}
+
+pub struct TrashService;
+
+impl TrashService {
+    /// `scope` 范围内已删除的客户，最近删除的在前
+    pub async fn list(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        search: Option<&str>,
+        page: u64,
+        limit: u64,
+    ) -> Result<(Vec<customer::Model>, u64), DbErr> {
+        let mut query = Customer::find()
+            .filter(scope.customer_condition())
+            .filter(customer::Column::IsDeleted.eq(true));
+
+        if let Some(search) = search.filter(|search| !search.is_empty()) {
+            query = query.filter(
+                customer::Column::Name
+                    .contains(search)
+                    .or(customer::Column::Phone.contains(search)),
+            );
+        }
+
+        let paginator = query
+            .order_by_desc(customer::Column::DeletedAt)
+            .order_by_desc(customer::Column::Id)
+            .paginate(db, limit.max(1));
+        let customers = paginator.fetch_page(page.saturating_sub(1)).await?;
+        let total = paginator.num_items().await?;
+
+        Ok((customers, total))
+    }
+
+    /// 回收站中 `scope` 范围内的客户
+    pub async fn find(
+        db: &DatabaseConnection,
+        scope: &AccessScope,
+        customer_id: i32,
+    ) -> Result<Option<customer::Model>, DbErr> {
+        Customer::find_by_id(customer_id)
+            .filter(scope.customer_condition())
+            .filter(customer::Column::IsDeleted.eq(true))
+            .one(db)
+            .await
+    }
+
+    /// 从回收站恢复客户；合并产生的删除不能恢复，跟进记录已经转到了保留的客户
+    pub async fn restore(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        customer: customer::Model,
+    ) -> Result<customer::Model, TrashError> {
+        if let Some(merged_into_id) = customer.merged_into_id {
+            return Err(TrashError::Merged(merged_into_id));
+        }
+
+        let txn = db.begin().await?;
+        let mut active: customer::ActiveModel = customer.clone().into();
+        active.is_deleted = Set(false);
+        active.deleted_at = Set(None);
+        active.deleted_by = Set(None);
+        active.updated_at = Set(Utc::now());
+        let restored = active.update(&txn).await?;
+
+        AuditService::record(
+            &txn,
+            actor,
+            AuditAction::Update,
+            AuditEntity::Customer,
+            customer.id,
+            Some(&customer),
+            Some(&restored),
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(restored)
+    }
+
+    /// 彻底删除回收站中的客户及其跟进记录、协作者和负责人变更历史，返回删除的跟进记录数
+    pub async fn purge(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        customer: &customer::Model,
+    ) -> Result<u64, DbErr> {
+        let txn = db.begin().await?;
+
+        let tracks = CustomerTrack::delete_many()
+            .filter(customer_track::Column::CustomerId.eq(customer.id))
+            .exec(&txn)
+            .await?
+            .rows_affected;
+        CustomerCollaborator::delete_many()
+            .filter(customer_collaborator::Column::CustomerId.eq(customer.id))
+            .exec(&txn)
+            .await?;
+        CustomerOwnershipHistory::delete_many()
+            .filter(customer_ownership_history::Column::CustomerId.eq(customer.id))
+            .exec(&txn)
+            .await?;
+        // 合并到该客户的记录不再有可指向的客户
+        Customer::update_many()
+            .col_expr(customer::Column::MergedIntoId, Expr::value(Option::<i32>::None))
+            .filter(customer::Column::MergedIntoId.eq(customer.id))
+            .exec(&txn)
+            .await?;
+        Customer::delete_by_id(customer.id).exec(&txn).await?;
+
+        AuditService::record(
+            &txn,
+            actor,
+            AuditAction::Delete,
+            AuditEntity::Customer,
+            customer.id,
+            Some(customer),
+            None,
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(tracks)
+    }
+
+    /// 清除删除时间早于 `now - retention_days` 的客户，返回清除的客户数
+    pub async fn purge_expired(
+        db: &DatabaseConnection,
+        retention_days: i64,
+        now: DateTime<Utc>,
+    ) -> Result<usize, DbErr> {
+        if retention_days <= 0 {
+            return Ok(0);
+        }
+
+        let expired = Customer::find()
+            .filter(customer::Column::IsDeleted.eq(true))
+            // 升级时回填的删除时间由 CURRENT_TIMESTAMP 生成，与应用写入的格式不同
+            .filter(Expr::cust_with_values(
+                "datetime(customers.deleted_at) < datetime(?)",
+                [now - Duration::days(retention_days)],
+            ))
+            .order_by_asc(customer::Column::Id)
+            .all(db)
+            .await?;
+
+        for customer in &expired {
+            Self::purge(db, &Actor::system(customer.organization_id), customer).await?;
+        }
+
+        Ok(expired.len())
+    }
+
+    /// 后台定期清除超过保留期限的客户
+    pub fn spawn_auto_purge(db: DatabaseConnection, settings: TrashSettings) {
+        if settings.retention_days <= 0 {
+            tracing::info!("回收站自动清除未启用");
+            return;
+        }
+
+        tokio::spawn(async move {
+            let minutes = settings.purge_interval_minutes.max(1);
+            let mut interval = tokio::time::interval(StdDuration::from_secs(minutes * 60));
+            loop {
+                interval.tick().await;
+                match Self::purge_expired(&db, settings.retention_days, Utc::now()).await {
+                    Ok(0) => {}
+                    Ok(purged) => tracing::info!("已从回收站清除 {} 位客户", purged),
+                    Err(e) => tracing::warn!("回收站自动清除失败: {}", e),
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sea_orm::ConnectionTrait;
+
+    use super::*;
+    use crate::{entities::user_role::UserRole, test_support};
+
+    async fn trash(db: &DatabaseConnection, customer: &customer::Model, deleted_at: DateTime<Utc>) {
+        let mut active: customer::ActiveModel = customer.clone().into();
+        active.is_deleted = Set(true);
+        active.deleted_at = Set(Some(deleted_at));
+        active.update(db).await.unwrap();
+    }
+
+    async fn exists(db: &DatabaseConnection, customer_id: i32) -> bool {
+        Customer::find_by_id(customer_id).one(db).await.unwrap().is_some()
+    }
+
+    #[test]
+    fn purge_at_requires_a_retention_period() {
+        let settings = TrashSettings { retention_days: 0, purge_interval_minutes: 60 };
+        assert_eq!(settings.purge_at(Utc::now()), None);
+
+        let deleted_at = Utc::now();
+        let settings = TrashSettings { retention_days: 30, purge_interval_minutes: 60 };
+        assert_eq!(settings.purge_at(deleted_at), Some(deleted_at + Duration::days(30)));
+    }
+
+    #[tokio::test]
+    async fn purges_only_customers_deleted_before_the_retention_period() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let now = Utc::now();
+        let expired = test_support::create_customer(&db, "张三", None, Some(alice.id)).await;
+        test_support::create_track(&db, expired.id, Some(alice.id), now - Duration::days(60)).await;
+        trash(&db, &expired, now - Duration::days(31)).await;
+        let recent = test_support::create_customer(&db, "李四", None, Some(alice.id)).await;
+        trash(&db, &recent, now - Duration::days(29)).await;
+        let active = test_support::create_customer(&db, "王五", None, Some(alice.id)).await;
+
+        assert_eq!(TrashService::purge_expired(&db, 0, now).await.unwrap(), 0);
+        assert_eq!(TrashService::purge_expired(&db, 30, now).await.unwrap(), 1);
+
+        assert!(!exists(&db, expired.id).await);
+        assert_eq!(CustomerTrack::find().count(&db).await.unwrap(), 0);
+        assert!(exists(&db, recent.id).await);
+        assert!(exists(&db, active.id).await);
+    }
+
+    #[tokio::test]
+    async fn customers_trashed_before_the_upgrade_start_their_retention_now() {
+        let db = test_support::test_db().await;
+        let customer = test_support::create_customer(&db, "张三", None, None).await;
+        // 与 019 的回填相同：删除时间取升级时的 CURRENT_TIMESTAMP，而不是很久以前的更新时间
+        db.execute_unprepared(&format!(
+            "UPDATE customers SET is_deleted = 1, updated_at = '2020-01-01 00:00:00',
+             deleted_at = CURRENT_TIMESTAMP WHERE id = {}",
+            customer.id
+        ))
+        .await
+        .unwrap();
+
+        assert_eq!(TrashService::purge_expired(&db, 1, Utc::now()).await.unwrap(), 0);
+        assert!(exists(&db, customer.id).await);
+        let later = Utc::now() + Duration::days(2);
+        assert_eq!(TrashService::purge_expired(&db, 1, later).await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn restores_deleted_customers_but_not_merged_ones() {
+        let db = test_support::test_db().await;
+        let admin = test_support::create_user(&db, "admin", UserRole::Admin).await;
+        let (scope, actor) = test_support::scope_of(&db, &admin).await;
+        let deleted = test_support::create_customer(&db, "张三", None, None).await;
+        trash(&db, &deleted, Utc::now()).await;
+        let survivor = test_support::create_customer(&db, "李四", None, None).await;
+        let merged = test_support::create_customer(&db, "李 四", None, None).await;
+        let mut active: customer::ActiveModel = merged.into();
+        active.is_deleted = Set(true);
+        active.merged_into_id = Set(Some(survivor.id));
+        let merged = active.update(&db).await.unwrap();
+
+        let (trashed, total) = TrashService::list(&db, &scope, None, 1, 20).await.unwrap();
+        assert_eq!(total, 2);
+        assert!(trashed.iter().all(|customer| customer.is_deleted));
+
+        let customer = TrashService::find(&db, &scope, deleted.id).await.unwrap().unwrap();
+        let restored = TrashService::restore(&db, &actor, customer).await.unwrap();
+        assert!(!restored.is_deleted);
+        assert_eq!(restored.deleted_at, None);
+
+        let result = TrashService::restore(&db, &actor, merged).await;
+        assert!(matches!(result, Err(TrashError::Merged(id)) if id == survivor.id));
+    }
+}
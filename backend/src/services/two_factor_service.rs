@@ -0,0 +1,427 @@
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+use crate::{
+    entities::{
+        recovery_code::{self, Entity as RecoveryCode},
+        two_factor_challenge::{self, Entity as TwoFactorChallenge},
+        user::{self, Entity as User},
+    },
+    utils::token::{generate_secret, hash_secret},
+};
+
+/// 生成的恢复码数量
+const RECOVERY_CODE_COUNT: usize = 10;
+/// TOTP 时间步长（秒）
+const TOTP_STEP_SECONDS: u64 = 30;
+/// 允许的时钟偏差，前后各一个时间步
+const TOTP_SKEW_STEPS: u64 = 1;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TwoFactorError {
+    #[error("双因素认证已启用")]
+    AlreadyEnabled,
+    #[error("双因素认证未启用")]
+    NotEnabled,
+    #[error("尚未开始绑定双因素认证")]
+    NotEnrolled,
+    #[error("验证码错误")]
+    InvalidCode,
+    #[error("TOTP 密钥无效: {0}")]
+    Secret(String),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 绑定信息，用户将 `otpauth_uri` 扫码添加到身份验证器
+pub struct Enrollment {
+    pub secret: String,
+    pub otpauth_uri: String,
+}
+
+pub struct TwoFactorService;
+
+impl TwoFactorService {
+    /// 生成新的 TOTP 密钥并暂存，需要调用 [`TwoFactorService::enable`] 确认后才生效
+    pub async fn begin_enrollment(
+        db: &DatabaseConnection,
+        user: user::Model,
+        issuer: &str,
+    ) -> Result<Enrollment, TwoFactorError> {
+        if user.totp_enabled {
+            return Err(TwoFactorError::AlreadyEnabled);
+        }
+
+        let secret = match Secret::generate_secret().to_encoded() {
+            Secret::Encoded(secret) => secret,
+            Secret::Raw(_) => unreachable!("to_encoded always returns Secret::Encoded"),
+        };
+        let totp = build_totp(&secret, issuer, &user.username)?;
+
+        let mut user_active: user::ActiveModel = user.into();
+        user_active.totp_secret = Set(Some(secret.clone()));
+        user_active.updated_at = Set(Utc::now());
+        user_active.update(db).await?;
+
+        Ok(Enrollment {
+            secret,
+            otpauth_uri: totp.get_url(),
+        })
+    }
+
+    /// 用身份验证器上的验证码确认绑定，成功后返回一次性恢复码（明文只返回这一次）
+    pub async fn enable(
+        db: &DatabaseConnection,
+        user: user::Model,
+        issuer: &str,
+        code: &str,
+    ) -> Result<Vec<String>, TwoFactorError> {
+        if user.totp_enabled {
+            return Err(TwoFactorError::AlreadyEnabled);
+        }
+        let secret = user.totp_secret.as_deref().ok_or(TwoFactorError::NotEnrolled)?;
+        let step = matching_step(secret, issuer, &user.username, code, Utc::now())?
+            .ok_or(TwoFactorError::InvalidCode)?;
+
+        // 启用和生成恢复码要么都完成，要么都不生效
+        let txn = db.begin().await?;
+        if !accept_step(&txn, user.id, step).await? {
+            return Err(TwoFactorError::InvalidCode);
+        }
+        let user_id = user.id;
+        let mut user_active: user::ActiveModel = user.into();
+        user_active.totp_enabled = Set(true);
+        user_active.updated_at = Set(Utc::now());
+        user_active.update(&txn).await?;
+
+        let codes = Self::regenerate_recovery_codes(&txn, user_id).await?;
+        txn.commit().await?;
+
+        Ok(codes)
+    }
+
+    /// 校验登录时提交的验证码，可以是 TOTP 验证码，也可以是未使用过的恢复码。
+    /// 每个 TOTP 时间步只能通过一次，截获的验证码不能再次使用
+    pub async fn verify(
+        db: &DatabaseConnection,
+        user: &user::Model,
+        issuer: &str,
+        code: &str,
+    ) -> Result<bool, TwoFactorError> {
+        let secret = match (&user.totp_secret, user.totp_enabled) {
+            (Some(secret), true) => secret,
+            _ => return Err(TwoFactorError::NotEnabled),
+        };
+
+        if let Some(step) = matching_step(secret, issuer, &user.username, code, Utc::now())? {
+            return Ok(accept_step(db, user.id, step).await?);
+        }
+
+        Self::consume_recovery_code(db, user.id, code).await
+    }
+
+    /// 关闭双因素认证并删除恢复码，供本人关闭或管理员通过CLI重置
+    pub async fn reset(db: &DatabaseConnection, user: user::Model) -> Result<(), TwoFactorError> {
+        let user_id = user.id;
+        let mut user_active: user::ActiveModel = user.into();
+        user_active.totp_secret = Set(None);
+        user_active.totp_enabled = Set(false);
+        user_active.totp_last_step = Set(None);
+        user_active.updated_at = Set(Utc::now());
+        user_active.update(db).await?;
+
+        RecoveryCode::delete_many()
+            .filter(recovery_code::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        Ok(())
+    }
+
+    /// 作废旧恢复码并生成新的一组
+    pub async fn regenerate_recovery_codes<C: ConnectionTrait>(
+        db: &C,
+        user_id: i32,
+    ) -> Result<Vec<String>, TwoFactorError> {
+        RecoveryCode::delete_many()
+            .filter(recovery_code::Column::UserId.eq(user_id))
+            .exec(db)
+            .await?;
+
+        let now = Utc::now();
+        let mut codes = Vec::with_capacity(RECOVERY_CODE_COUNT);
+        for _ in 0..RECOVERY_CODE_COUNT {
+            let raw = Uuid::new_v4().simple().to_string();
+            let code = format!("{}-{}", &raw[..5], &raw[5..10]);
+            recovery_code::ActiveModel {
+                user_id: Set(user_id),
+                code_hash: Set(hash_secret(&code)),
+                used_at: Set(None),
+                created_at: Set(now),
+                ..Default::default()
+            }
+            .insert(db)
+            .await?;
+            codes.push(code);
+        }
+
+        Ok(codes)
+    }
+
+    async fn consume_recovery_code(
+        db: &DatabaseConnection,
+        user_id: i32,
+        code: &str,
+    ) -> Result<bool, TwoFactorError> {
+        let code = code.trim().to_lowercase();
+        let result = RecoveryCode::update_many()
+            .col_expr(recovery_code::Column::UsedAt, Utc::now().into())
+            .filter(recovery_code::Column::UserId.eq(user_id))
+            .filter(recovery_code::Column::CodeHash.eq(hash_secret(&code)))
+            .filter(recovery_code::Column::UsedAt.is_null())
+            .exec(db)
+            .await?;
+
+        Ok(result.rows_affected > 0)
+    }
+
+    /// 密码校验通过后登记一次待完成的登录，返回写入中间令牌的随机串
+    pub async fn create_challenge(
+        db: &DatabaseConnection,
+        user_id: i32,
+        expires_at: DateTime<Utc>,
+    ) -> Result<String, TwoFactorError> {
+        let now = Utc::now();
+        TwoFactorChallenge::delete_many()
+            .filter(two_factor_challenge::Column::ExpiresAt.lt(now))
+            .exec(db)
+            .await?;
+
+        let challenge = generate_secret();
+        two_factor_challenge::ActiveModel {
+            user_id: Set(user_id),
+            token_hash: Set(hash_secret(&challenge)),
+            created_at: Set(now),
+            expires_at: Set(expires_at),
+            ..Default::default()
+        }
+        .insert(db)
+        .await?;
+
+        Ok(challenge)
+    }
+
+    /// 验证码通过后取出待完成的登录，同一个中间令牌只有第一次能成功
+    pub async fn consume_challenge(
+        db: &DatabaseConnection,
+        user_id: i32,
+        challenge: &str,
+    ) -> Result<bool, TwoFactorError> {
+        let result = TwoFactorChallenge::delete_many()
+            .filter(two_factor_challenge::Column::TokenHash.eq(hash_secret(challenge)))
+            .filter(two_factor_challenge::Column::UserId.eq(user_id))
+            .filter(two_factor_challenge::Column::ExpiresAt.gt(Utc::now()))
+            .exec(db)
+            .await?;
+        Ok(result.rows_affected > 0)
+    }
+}
+
+fn build_totp(secret: &str, issuer: &str, account: &str) -> Result<TOTP, TwoFactorError> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| TwoFactorError::Secret(format!("{:?}", e)))?;
+    // 时钟偏差由 matching_step 逐个时间步处理，以便知道匹配的是哪一步
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        0,
+        TOTP_STEP_SECONDS,
+        bytes,
+        Some(issuer.to_string()),
+        account.to_string(),
+    )
+    .map_err(|e| TwoFactorError::Secret(e.to_string()))
+}
+
+/// 验证码匹配的时间步，允许前后各偏差一步；都不匹配时返回 `None`
+fn matching_step(
+    secret: &str,
+    issuer: &str,
+    account: &str,
+    code: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<i64>, TwoFactorError> {
+    let totp = build_totp(secret, issuer, account)?;
+    let current = now.timestamp().max(0) as u64 / TOTP_STEP_SECONDS;
+    let code = code.trim();
+    let step = (current.saturating_sub(TOTP_SKEW_STEPS)..=current + TOTP_SKEW_STEPS)
+        .find(|step| totp.check(code, step * TOTP_STEP_SECONDS));
+    Ok(step.map(|step| step as i64))
+}
+
+/// 记录通过校验的时间步。只有比上次更晚的时间步才会写入，
+/// 没有写入说明这个验证码（或更早的验证码）已经用过
+async fn accept_step<C: ConnectionTrait>(db: &C, user_id: i32, step: i64) -> Result<bool, DbErr> {
+    let result = User::update_many()
+        .col_expr(user::Column::TotpLastStep, step.into())
+        .filter(user::Column::Id.eq(user_id))
+        .filter(
+            Condition::any()
+                .add(user::Column::TotpLastStep.is_null())
+                .add(user::Column::TotpLastStep.lt(step)),
+        )
+        .exec(db)
+        .await?;
+    Ok(result.rows_affected > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+    use crate::{entities::user_role::UserRole, test_support};
+
+    const ISSUER: &str = "Test";
+
+    fn code_at(user: &user::Model, time: DateTime<Utc>) -> String {
+        let secret = user.totp_secret.as_deref().unwrap();
+        build_totp(secret, ISSUER, &user.username)
+            .unwrap()
+            .generate(time.timestamp() as u64)
+    }
+
+    async fn enrolled_user(db: &DatabaseConnection) -> (user::Model, Vec<String>) {
+        let user = test_support::create_user(db, "alice", UserRole::Sales).await;
+        TwoFactorService::begin_enrollment(db, user.clone(), ISSUER).await.unwrap();
+        let user = User::find_by_id(user.id).one(db).await.unwrap().unwrap();
+        // 绑定时使用上一个时间步的验证码，登录时的当前验证码仍然可用
+        let code = code_at(&user, Utc::now() - Duration::seconds(30));
+        let codes = TwoFactorService::enable(db, user.clone(), ISSUER, &code).await.unwrap();
+        let user = User::find_by_id(user.id).one(db).await.unwrap().unwrap();
+        (user, codes)
+    }
+
+    #[tokio::test]
+    async fn enable_turns_on_two_factor_with_recovery_codes() {
+        let db = test_support::test_db().await;
+        let (user, codes) = enrolled_user(&db).await;
+
+        assert!(user.totp_enabled);
+        assert_eq!(codes.len(), RECOVERY_CODE_COUNT);
+        let stored = RecoveryCode::find()
+            .filter(recovery_code::Column::UserId.eq(user.id))
+            .all(&db)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), RECOVERY_CODE_COUNT);
+    }
+
+    #[tokio::test]
+    async fn enable_rejects_a_wrong_code_without_side_effects() {
+        let db = test_support::test_db().await;
+        let user = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        TwoFactorService::begin_enrollment(&db, user.clone(), ISSUER).await.unwrap();
+        let user = User::find_by_id(user.id).one(&db).await.unwrap().unwrap();
+
+        let result = TwoFactorService::enable(&db, user.clone(), ISSUER, "000000x").await;
+        assert!(matches!(result, Err(TwoFactorError::InvalidCode)));
+
+        let user = User::find_by_id(user.id).one(&db).await.unwrap().unwrap();
+        assert!(!user.totp_enabled);
+        let codes = RecoveryCode::find().all(&db).await.unwrap();
+        assert!(codes.is_empty());
+    }
+
+    #[tokio::test]
+    async fn a_totp_code_is_accepted_only_once() {
+        let db = test_support::test_db().await;
+        let (user, _) = enrolled_user(&db).await;
+        let code = code_at(&user, Utc::now());
+
+        assert!(TwoFactorService::verify(&db, &user, ISSUER, &code).await.unwrap());
+        assert!(!TwoFactorService::verify(&db, &user, ISSUER, &code).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn codes_older_than_the_last_accepted_step_are_rejected() {
+        let db = test_support::test_db().await;
+        let (user, _) = enrolled_user(&db).await;
+        let now = Utc::now();
+
+        let next = code_at(&user, now + Duration::seconds(30));
+        assert!(TwoFactorService::verify(&db, &user, ISSUER, &next).await.unwrap());
+
+        let current = code_at(&user, now);
+        if current != next {
+            assert!(!TwoFactorService::verify(&db, &user, ISSUER, &current).await.unwrap());
+        }
+    }
+
+    #[test]
+    fn steps_outside_the_skew_do_not_match() {
+        let secret = match Secret::generate_secret().to_encoded() {
+            Secret::Encoded(secret) => secret,
+            Secret::Raw(_) => unreachable!(),
+        };
+        let totp = build_totp(&secret, ISSUER, "alice").unwrap();
+        let now = Utc::now();
+        let step = now.timestamp() / 30;
+
+        let code = totp.generate(now.timestamp() as u64);
+        let matched = matching_step(&secret, ISSUER, "alice", &code, now).unwrap();
+        assert_eq!(matched, Some(step));
+
+        let stale = totp.generate((now - Duration::seconds(90)).timestamp() as u64);
+        if stale != code {
+            assert_eq!(matching_step(&secret, ISSUER, "alice", &stale, now).unwrap(), None);
+        }
+    }
+
+    #[tokio::test]
+    async fn recovery_codes_are_single_use() {
+        let db = test_support::test_db().await;
+        let (user, codes) = enrolled_user(&db).await;
+
+        assert!(TwoFactorService::verify(&db, &user, ISSUER, &codes[0]).await.unwrap());
+        assert!(!TwoFactorService::verify(&db, &user, ISSUER, &codes[0]).await.unwrap());
+        assert!(TwoFactorService::verify(&db, &user, ISSUER, &codes[1]).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn challenges_are_single_use_and_bound_to_the_user() {
+        let db = test_support::test_db().await;
+        let alice = test_support::create_user(&db, "alice", UserRole::Sales).await;
+        let bob = test_support::create_user(&db, "bob", UserRole::Sales).await;
+        let expires_at = Utc::now() + Duration::minutes(5);
+
+        let challenge = TwoFactorService::create_challenge(&db, alice.id, expires_at).await.unwrap();
+        assert!(!TwoFactorService::consume_challenge(&db, bob.id, &challenge).await.unwrap());
+        assert!(TwoFactorService::consume_challenge(&db, alice.id, &challenge).await.unwrap());
+        assert!(!TwoFactorService::consume_challenge(&db, alice.id, &challenge).await.unwrap());
+
+        let expired = Utc::now() - Duration::seconds(1);
+        let challenge = TwoFactorService::create_challenge(&db, alice.id, expired).await.unwrap();
+        assert!(!TwoFactorService::consume_challenge(&db, alice.id, &challenge).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn reset_clears_the_secret_and_codes() {
+        let db = test_support::test_db().await;
+        let (user, _) = enrolled_user(&db).await;
+
+        TwoFactorService::reset(&db, user.clone()).await.unwrap();
+
+        let user = User::find_by_id(user.id).one(&db).await.unwrap().unwrap();
+        assert!(!user.totp_enabled);
+        assert!(user.totp_secret.is_none());
+        assert!(user.totp_last_step.is_none());
+        assert!(RecoveryCode::find().all(&db).await.unwrap().is_empty());
+    }
+}
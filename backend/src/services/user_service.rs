@@ -0,0 +1,384 @@
+use bcrypt::BcryptError;
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+
+use crate::{
+    entities::{
+        audit_log::{AuditAction, AuditEntity},
+        organization::Entity as Organization,
+        user::{self, Entity as User},
+        user_role::UserRole,
+    },
+    services::{
+        api_token_service::ApiTokenService,
+        audit_service::{Actor, AuditService},
+        session_service::{SessionError, SessionService},
+    },
+    utils::{
+        password::hash_password,
+        token::generate_secret,
+        validation::{
+            validate_email, validate_name, validate_password, validate_username, PasswordPolicy,
+        },
+    },
+};
+
+#[derive(Debug, thiserror::Error)]
+pub enum UserError {
+    #[error("用户名 '{0}' 已存在")]
+    UsernameTaken(String),
+    #[error("用户名需为3到50位的字母、数字或下划线")]
+    InvalidUsername,
+    #[error("姓名不能为空且不超过100个字符")]
+    InvalidName,
+    #[error("邮箱格式不正确")]
+    InvalidEmail,
+    #[error("邮箱 '{0}' 已被其他用户使用")]
+    EmailTaken(String),
+    #[error("{0}")]
+    InvalidPassword(String),
+    #[error("组织 {0} 不存在")]
+    OrganizationNotFound(i32),
+    #[error("用户不存在")]
+    NotFound,
+    #[error("主管在该组织中不存在")]
+    ManagerNotFound,
+    #[error("用户 '{0}' 不是主管")]
+    NotAManager(String),
+    #[error("不能将用户设置为自己的主管")]
+    SelfManager,
+    #[error("密码加密失败: {0}")]
+    Hash(#[from] BcryptError),
+    #[error(transparent)]
+    Session(#[from] SessionError),
+    #[error(transparent)]
+    Database(#[from] DbErr),
+}
+
+/// 新建用户的参数
+pub struct NewUser {
+    pub username: String,
+    /// 为空时设置随机密码，用户只能通过单点登录进入
+    pub password: Option<String>,
+    pub name: String,
+    pub email: Option<String>,
+    pub role: UserRole,
+    pub manager_id: Option<i32>,
+    pub organization_id: i32,
+}
+
+/// 修改用户资料，字段为空表示不修改；`manager_id` 为 `Some(None)` 表示清除主管
+#[derive(Default)]
+pub struct UserUpdate {
+    pub name: Option<String>,
+    pub email: Option<Option<String>>,
+    pub role: Option<UserRole>,
+    pub manager_id: Option<Option<i32>>,
+}
+
+/// 用户列表的查询条件
+pub struct UserListFilter {
+    /// 为空时列出所有组织的用户（仅命令行使用）
+    pub organization_id: Option<i32>,
+    /// 按用户名或姓名模糊搜索
+    pub search: Option<String>,
+    pub page: u64,
+    pub limit: u64,
+}
+
+/// 用户管理，命令行和管理员接口共用，保证两边行为一致
+pub struct UserService;
+
+impl UserService {
+    pub async fn find_by_username(
+        db: &DatabaseConnection,
+        username: &str,
+    ) -> Result<user::Model, UserError> {
+        User::find()
+            .filter(user::Column::Username.eq(username))
+            .one(db)
+            .await?
+            .ok_or(UserError::NotFound)
+    }
+
+    pub async fn find_in_organization(
+        db: &DatabaseConnection,
+        organization_id: i32,
+        user_id: i32,
+    ) -> Result<user::Model, UserError> {
+        User::find_by_id(user_id)
+            .filter(user::Column::OrganizationId.eq(organization_id))
+            .one(db)
+            .await?
+            .ok_or(UserError::NotFound)
+    }
+
+    pub async fn create(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        policy: &PasswordPolicy,
+        new_user: NewUser,
+    ) -> Result<user::Model, UserError> {
+        if !validate_username(&new_user.username) {
+            return Err(UserError::InvalidUsername);
+        }
+        let name = new_user.name.trim();
+        if !validate_name(name) {
+            return Err(UserError::InvalidName);
+        }
+        let password = match new_user.password {
+            Some(password) => {
+                validate_password(&password, policy).map_err(UserError::InvalidPassword)?;
+                password
+            }
+            None => generate_secret(),
+        };
+        let email = match new_user.email {
+            Some(email) => Some(check_email(db, &email, None).await?),
+            None => None,
+        };
+
+        let existing_user = User::find()
+            .filter(user::Column::Username.eq(&new_user.username))
+            .one(db)
+            .await?;
+        if existing_user.is_some() {
+            return Err(UserError::UsernameTaken(new_user.username));
+        }
+
+        if Organization::find_by_id(new_user.organization_id).one(db).await?.is_none() {
+            return Err(UserError::OrganizationNotFound(new_user.organization_id));
+        }
+
+        if let Some(manager_id) = new_user.manager_id {
+            check_manager(db, new_user.organization_id, manager_id).await?;
+        }
+
+        let password_hash = hash_password(&password)?;
+        let now = Utc::now();
+
+        let txn = db.begin().await?;
+        let user = user::ActiveModel {
+            username: Set(new_user.username),
+            password_hash: Set(password_hash),
+            name: Set(name.to_string()),
+            email: Set(email),
+            created_at: Set(now),
+            updated_at: Set(now),
+            is_active: Set(true),
+            last_login_at: Set(None),
+            role: Set(new_user.role),
+            manager_id: Set(new_user.manager_id),
+            organization_id: Set(new_user.organization_id),
+            ..Default::default()
+        }
+        .insert(&txn)
+        .await?;
+        AuditService::record(
+            &txn,
+            actor,
+            AuditAction::Create,
+            AuditEntity::User,
+            user.id,
+            None,
+            Some(&user),
+        )
+        .await?;
+        txn.commit().await?;
+
+        Ok(user)
+    }
+
+    /// 分页查询用户，返回当前页和总数
+    pub async fn list(
+        db: &DatabaseConnection,
+        filter: &UserListFilter,
+    ) -> Result<(Vec<user::Model>, u64), UserError> {
+        let mut query = User::find();
+
+        if let Some(organization_id) = filter.organization_id {
+            query = query.filter(user::Column::OrganizationId.eq(organization_id));
+        }
+        if let Some(search) = filter.search.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+            query = query.filter(
+                Condition::any()
+                    .add(user::Column::Username.contains(search))
+                    .add(user::Column::Name.contains(search)),
+            );
+        }
+
+        let paginator = query
+            .order_by_asc(user::Column::Id)
+            .paginate(db, filter.limit.max(1));
+        let users = paginator.fetch_page(filter.page.saturating_sub(1)).await?;
+        let total = paginator.num_items().await?;
+
+        Ok((users, total))
+    }
+
+    pub async fn update(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        user: user::Model,
+        update: UserUpdate,
+    ) -> Result<user::Model, UserError> {
+        let mut user_active: user::ActiveModel = user.clone().into();
+
+        if let Some(name) = update.name {
+            let name = name.trim();
+            if !validate_name(name) {
+                return Err(UserError::InvalidName);
+            }
+            user_active.name = Set(name.to_string());
+        }
+        if let Some(email) = update.email {
+            let email = match email {
+                Some(email) => Some(check_email(db, &email, Some(user.id)).await?),
+                None => None,
+            };
+            user_active.email = Set(email);
+        }
+        if let Some(role) = update.role {
+            user_active.role = Set(role);
+        }
+        if let Some(manager_id) = update.manager_id {
+            if let Some(manager_id) = manager_id {
+                if manager_id == user.id {
+                    return Err(UserError::SelfManager);
+                }
+                check_manager(db, user.organization_id, manager_id).await?;
+            }
+            user_active.manager_id = Set(manager_id);
+        }
+        user_active.updated_at = Set(Utc::now());
+
+        let txn = db.begin().await?;
+        let updated_user = save_with_audit(&txn, actor, &user, user_active).await?;
+        txn.commit().await?;
+
+        Ok(updated_user)
+    }
+
+    /// 启用或停用用户；停用时吊销其所有会话，已签发的令牌立即失效
+    pub async fn set_active(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        user: user::Model,
+        active: bool,
+    ) -> Result<user::Model, UserError> {
+        let mut user_active: user::ActiveModel = user.clone().into();
+        user_active.is_active = Set(active);
+        user_active.updated_at = Set(Utc::now());
+
+        // 停用和吊销会话在同一事务中，不会出现已停用但会话仍有效的中间状态
+        let txn = db.begin().await?;
+        let updated_user = save_with_audit(&txn, actor, &user, user_active).await?;
+        if !active {
+            SessionService::revoke_all_for_user(&txn, updated_user.id).await?;
+        }
+        txn.commit().await?;
+
+        Ok(updated_user)
+    }
+
+    /// 重置密码并吊销该用户的所有会话和 API 令牌
+    pub async fn reset_password(
+        db: &DatabaseConnection,
+        actor: &Actor,
+        policy: &PasswordPolicy,
+        user: user::Model,
+        new_password: &str,
+    ) -> Result<user::Model, UserError> {
+        validate_password(new_password, policy).map_err(UserError::InvalidPassword)?;
+
+        let password_hash = hash_password(new_password)?;
+        let mut user_active: user::ActiveModel = user.into();
+        user_active.password_hash = Set(password_hash);
+        user_active.updated_at = Set(Utc::now());
+
+        let txn = db.begin().await?;
+        let user = user_active.update(&txn).await?;
+        // 密码哈希不序列化，只记录发生了重置
+        AuditService::record(
+            &txn,
+            actor,
+            AuditAction::Update,
+            AuditEntity::User,
+            user.id,
+            Some(&serde_json::json!({ "password": "******" })),
+            Some(&serde_json::json!({ "password": "(已重置)" })),
+        )
+        .await?;
+        // 旧密码签发的令牌全部失效
+        SessionService::revoke_all_for_user(&txn, user.id).await?;
+        ApiTokenService::revoke_all_for_user(&txn, user.id).await?;
+        txn.commit().await?;
+
+        Ok(user)
+    }
+}
+
+/// 规范化邮箱（去空格、转小写）并检查是否已被其他用户使用
+async fn check_email(
+    db: &DatabaseConnection,
+    email: &str,
+    user_id: Option<i32>,
+) -> Result<String, UserError> {
+    let email = email.trim().to_lowercase();
+    if !validate_email(&email) {
+        return Err(UserError::InvalidEmail);
+    }
+
+    let mut query = User::find().filter(user::Column::Email.eq(&email));
+    if let Some(user_id) = user_id {
+        query = query.filter(user::Column::Id.ne(user_id));
+    }
+    if query.one(db).await?.is_some() {
+        return Err(UserError::EmailTaken(email));
+    }
+
+    Ok(email)
+}
+
+/// 主管必须存在于同一组织且角色为主管
+async fn check_manager(
+    db: &DatabaseConnection,
+    organization_id: i32,
+    manager_id: i32,
+) -> Result<(), UserError> {
+    let manager = User::find_by_id(manager_id)
+        .filter(user::Column::OrganizationId.eq(organization_id))
+        .one(db)
+        .await?
+        .ok_or(UserError::ManagerNotFound)?;
+
+    if manager.role != UserRole::Manager {
+        return Err(UserError::NotAManager(manager.username));
+    }
+
+    Ok(())
+}
+
+async fn save_with_audit<C: ConnectionTrait>(
+    db: &C,
+    actor: &Actor,
+    before: &user::Model,
+    user_active: user::ActiveModel,
+) -> Result<user::Model, UserError> {
+    let updated_user = user_active.update(db).await?;
+    AuditService::record(
+        db,
+        actor,
+        AuditAction::Update,
+        AuditEntity::User,
+        updated_user.id,
+        Some(before),
+        Some(&updated_user),
+    )
+    .await?;
+
+    Ok(updated_user)
+}
@@ -0,0 +1,271 @@
+//! 单元测试共用的数据库、测试数据和接口调用
+
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::connect_info::MockConnectInfo,
+    http::{header, Method, Request, StatusCode},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{ActiveModelTrait, ConnectOptions, Database, DatabaseConnection, Set};
+use serde_json::Value;
+use tower::ServiceExt;
+
+use crate::{
+    entities::{
+        customer, customer_group::CustomerGroup, customer_track, next_action::NextAction,
+        organization, user, user_role::UserRole,
+    },
+    handlers::auth::AppState,
+    middleware::auth::CurrentUser,
+    migration::DatabaseMigrator,
+    routes::create_routes,
+    services::{
+        audit_service::Actor,
+        customer_group_service::CustomerGroupService,
+        customer_pool_service::PoolSettings,
+        duplicate_service::{normalize_name, normalize_phone},
+        jwt_key_service::JwtKeyStore,
+        login_throttle_service::LoginThrottlePolicy,
+        permission_service::{AccessScope, PermissionService},
+        pipeline_service::PipelineService,
+        trash_service::TrashSettings,
+    },
+    utils::{pinyin, validation::PasswordPolicy},
+};
+
+/// 测试用户的密码
+pub const PASSWORD: &str = "secret123";
+
+/// 已执行全部迁移的内存数据库。内存数据库属于单个连接，所以连接池只有一个连接
+pub async fn test_db() -> DatabaseConnection {
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    options.max_connections(1).min_connections(1).sqlx_logging(false);
+    let db = Database::connect(options).await.expect("连接测试数据库失败");
+    DatabaseMigrator::new(String::new())
+        .migrate(&db)
+        .await
+        .expect("测试数据库迁移失败");
+    db
+}
+
+/// 创建另一个组织，带默认的客户分组和销售阶段
+pub async fn create_organization(db: &DatabaseConnection, name: &str) -> organization::Model {
+    let now = Utc::now();
+    let org = organization::ActiveModel {
+        name: Set(name.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .expect("创建测试组织失败");
+    CustomerGroupService::create_defaults(db, org.id)
+        .await
+        .expect("创建默认客户分组失败");
+    PipelineService::create_defaults(db, org.id)
+        .await
+        .expect("创建默认销售阶段失败");
+    org
+}
+
+/// 在默认组织中创建用户，密码为 [`PASSWORD`]
+pub async fn create_user(db: &DatabaseConnection, username: &str, role: UserRole) -> user::Model {
+    create_user_in(db, username, role, 1, None).await
+}
+
+pub async fn create_user_in(
+    db: &DatabaseConnection,
+    username: &str,
+    role: UserRole,
+    organization_id: i32,
+    manager_id: Option<i32>,
+) -> user::Model {
+    let now = Utc::now();
+    user::ActiveModel {
+        username: Set(username.to_string()),
+        // 最低强度的哈希，让测试不被 bcrypt 拖慢
+        password_hash: Set(bcrypt::hash(PASSWORD, 4).expect("计算密码哈希失败")),
+        name: Set(username.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        is_active: Set(true),
+        last_login_at: Set(None),
+        role: Set(role),
+        manager_id: Set(manager_id),
+        organization_id: Set(organization_id),
+        totp_enabled: Set(false),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .expect("创建测试用户失败")
+}
+
+/// 在默认组织中创建“团课”分组的客户，`owner_id` 为空表示在公海中
+pub async fn create_customer(
+    db: &DatabaseConnection,
+    name: &str,
+    phone: Option<&str>,
+    owner_id: Option<i32>,
+) -> customer::Model {
+    create_customer_in(db, name, phone, owner_id, 1).await
+}
+
+pub async fn create_customer_in(
+    db: &DatabaseConnection,
+    name: &str,
+    phone: Option<&str>,
+    owner_id: Option<i32>,
+    organization_id: i32,
+) -> customer::Model {
+    let now = Utc::now();
+    customer::ActiveModel {
+        name: Set(name.to_string()),
+        name_pinyin: Set(pinyin::full_pinyin(name)),
+        name_initials: Set(pinyin::name_initials(name)),
+        name_normalized: Set(normalize_name(name)),
+        phone: Set(phone.map(str::to_string)),
+        phone_normalized: Set(phone.and_then(normalize_phone)),
+        rate: Set(0.0),
+        customer_group: Set(CustomerGroup("团课".to_string())),
+        user_id: Set(owner_id),
+        organization_id: Set(organization_id),
+        created_at: Set(now),
+        updated_at: Set(now),
+        is_deleted: Set(false),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .expect("创建测试客户失败")
+}
+
+/// 给客户添加一条跟进记录
+pub async fn create_track(
+    db: &DatabaseConnection,
+    customer_id: i32,
+    user_id: Option<i32>,
+    track_time: DateTime<Utc>,
+) -> customer_track::Model {
+    customer_track::ActiveModel {
+        customer_id: Set(customer_id),
+        content: Set("电话沟通".to_string()),
+        next_action: Set(NextAction::default()),
+        track_time: Set(track_time),
+        next_track_time: Set(None),
+        created_at: Set(track_time),
+        updated_at: Set(track_time),
+        user_id: Set(user_id),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .expect("创建测试跟进记录失败")
+}
+
+/// 以登录会话身份访问的当前用户
+pub fn current_user(user: &user::Model) -> CurrentUser {
+    CurrentUser {
+        id: user.id,
+        username: user.username.clone(),
+        name: user.name.clone(),
+        role: user.role,
+        organization_id: user.organization_id,
+        session_id: Some(0),
+        scopes: None,
+    }
+}
+
+/// 用户按角色计算的访问范围和对应的操作人
+pub async fn scope_of(db: &DatabaseConnection, user: &user::Model) -> (AccessScope, Actor) {
+    let current_user = current_user(user);
+    let scope = PermissionService::scope_for(db, &current_user)
+        .await
+        .expect("计算访问范围失败");
+    (scope, Actor::from(&current_user))
+}
+
+/// 在内存中调用接口的应用，不监听端口
+pub struct TestApp {
+    pub state: AppState,
+    router: Router,
+}
+
+impl TestApp {
+    pub async fn new() -> Self {
+        let db = test_db().await;
+        let jwt_keys = Arc::new(JwtKeyStore::load(&db).await.expect("加载签名密钥失败"));
+        let state = AppState {
+            db,
+            jwt_keys,
+            access_token_expire_minutes: 15,
+            refresh_token_expire_days: 30,
+            login_throttle: LoginThrottlePolicy {
+                max_failed_per_username: 3,
+                max_failed_per_ip: 20,
+                lockout_base_seconds: 60,
+                lockout_max_seconds: 3600,
+            },
+            trust_proxy_headers: false,
+            totp_issuer: "Test".to_string(),
+            password_policy: PasswordPolicy::default(),
+            oidc: None,
+            pool: PoolSettings {
+                release_days: 0,
+                release_days_by_group: Vec::new(),
+                claim_limit: 0,
+                release_interval_minutes: 60,
+            },
+            trash: TrashSettings { retention_days: 0, purge_interval_minutes: 60 },
+        };
+        let router = create_routes(state.clone())
+            .layer(MockConnectInfo(SocketAddr::from(([127, 0, 0, 1], 40000))));
+        Self { state, router }
+    }
+
+    pub fn db(&self) -> &DatabaseConnection {
+        &self.state.db
+    }
+
+    /// 发送请求，返回状态码和 JSON 响应（响应不是 JSON 时为 `Value::Null`）
+    pub async fn request(
+        &self,
+        method: Method,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<Value>,
+    ) -> (StatusCode, Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {}", token));
+        }
+        let body = match body {
+            Some(body) => {
+                request = request.header(header::CONTENT_TYPE, "application/json");
+                Body::from(body.to_string())
+            }
+            None => Body::empty(),
+        };
+        let response = self
+            .router
+            .clone()
+            .oneshot(request.body(body).expect("构造请求失败"))
+            .await
+            .expect("请求失败");
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.expect("读取响应失败");
+        (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+    }
+
+    /// 用 [`PASSWORD`] 登录，返回访问令牌
+    pub async fn login(&self, username: &str) -> String {
+        let body = serde_json::json!({ "username": username, "password": PASSWORD });
+        let (status, json) = self.request(Method::POST, "/api/auth/login", None, Some(body)).await;
+        assert_eq!(status, StatusCode::OK, "登录失败: {}", json);
+        json["token"].as_str().expect("登录响应缺少 token").to_string()
+    }
+}
@@ -0,0 +1,45 @@
+use axum::http::{header::USER_AGENT, HeaderMap};
+use std::net::SocketAddr;
+
+/// 获取客户端IP。
+///
+/// 部署在 Nginx 之后时，连接地址是代理的地址，真实IP由代理写入 `X-Real-IP`。
+/// 只有在信任代理头时才读取该头，否则客户端可以随意伪造。
+pub fn client_ip(headers: &HeaderMap, peer: SocketAddr, trust_proxy_headers: bool) -> String {
+    if trust_proxy_headers
+        && let Some(ip) = headers
+            .get("x-real-ip")
+            .and_then(|value| value.to_str().ok())
+            .map(str::trim)
+            .filter(|ip| !ip.is_empty())
+    {
+        return ip.to_string();
+    }
+
+    peer.ip().to_string()
+}
+
+/// 会话和登录历史中保存的 User-Agent 最大长度
+const MAX_USER_AGENT_LEN: usize = 255;
+
+/// 发起请求的客户端信息
+#[derive(Debug, Clone)]
+pub struct ClientInfo {
+    pub ip: String,
+    pub user_agent: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn from_request(headers: &HeaderMap, peer: SocketAddr, trust_proxy_headers: bool) -> Self {
+        let user_agent = headers
+            .get(USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|ua| ua.chars().take(MAX_USER_AGENT_LEN).collect::<String>())
+            .filter(|ua| !ua.is_empty());
+
+        Self {
+            ip: client_ip(headers, peer, trust_proxy_headers),
+            user_agent,
+        }
+    }
+}
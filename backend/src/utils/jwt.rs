@@ -1,7 +1,11 @@
 use chrono::{Duration, Utc};
-use jsonwebtoken::{encode, EncodingKey, Header};
 use serde::{Deserialize, Serialize};
+use crate::entities::user;
 use crate::middleware::auth::Claims;
+use crate::services::jwt_key_service::{JwtKeyError, JwtKeyStore, ISSUER};
+
+/// 访问令牌的受众；其他用途的令牌使用不同的受众，不能用来访问接口
+pub const ACCESS_AUDIENCE: &str = "customer-tracker-api";
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenPair {
@@ -10,43 +14,85 @@ pub struct TokenPair {
 }
 
 pub fn generate_jwt_token(
-    user_id: i32,
-    username: &str,
-    name: &str,
-    secret: &str,
-    expires_hours: i64,
-) -> Result<TokenPair, jsonwebtoken::errors::Error> {
+    user: &user::Model,
+    session_id: i32,
+    keys: &JwtKeyStore,
+    expires_minutes: i64,
+) -> Result<TokenPair, JwtKeyError> {
     let now = Utc::now();
-    let expires_at = now + Duration::hours(expires_hours);
+    let expires_at = now + Duration::minutes(expires_minutes);
     
     let claims = Claims {
+        user_id: user.id,
+        username: user.username.clone(),
+        name: user.name.clone(),
+        role: user.role,
+        org_id: user.organization_id,
+        sid: session_id,
+        iss: ISSUER.to_string(),
+        aud: ACCESS_AUDIENCE.to_string(),
+        exp: expires_at.timestamp() as usize,
+        iat: now.timestamp() as usize,
+    };
+
+    let token = keys.sign(&claims)?;
+
+    Ok(TokenPair {
+        access_token: token,
+        expires_in: expires_minutes * 60, // Convert to seconds
+    })
+}
+
+pub fn verify_token(token: &str, keys: &JwtKeyStore) -> Result<Claims, JwtKeyError> {
+    keys.verify(token, ACCESS_AUDIENCE)
+}
+
+/// 双因素认证登录的中间令牌，只能用于提交验证码，不能访问其他接口
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TwoFactorClaims {
+    pub user_id: i32,
+    pub purpose: String,
+    /// 对应一条待完成的登录，令牌使用一次后失效
+    pub jti: String,
+    pub iss: String,
+    pub aud: String,
+    pub exp: usize,
+    pub iat: usize,
+}
+
+const TWO_FACTOR_PURPOSE: &str = "two_factor";
+const TWO_FACTOR_AUDIENCE: &str = "customer-tracker-two-factor";
+
+pub fn generate_two_factor_token(
+    user_id: i32,
+    challenge: &str,
+    keys: &JwtKeyStore,
+    expires_minutes: i64,
+) -> Result<TokenPair, JwtKeyError> {
+    let now = Utc::now();
+    let expires_at = now + Duration::minutes(expires_minutes);
+
+    let claims = TwoFactorClaims {
         user_id,
-        username: username.to_string(),
-        name: name.to_string(),
+        purpose: TWO_FACTOR_PURPOSE.to_string(),
+        jti: challenge.to_string(),
+        iss: ISSUER.to_string(),
+        aud: TWO_FACTOR_AUDIENCE.to_string(),
         exp: expires_at.timestamp() as usize,
         iat: now.timestamp() as usize,
     };
 
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_ref()),
-    )?;
+    let token = keys.sign(&claims)?;
 
     Ok(TokenPair {
         access_token: token,
-        expires_in: expires_hours * 3600, // Convert to seconds
+        expires_in: expires_minutes * 60,
     })
 }
 
-pub fn verify_token(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    use jsonwebtoken::{decode, DecodingKey, Validation};
-    
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_ref()),
-        &Validation::default(),
-    )?;
-    
-    Ok(token_data.claims)
-}
\ No newline at end of file
+/// 校验中间令牌的签名和用途，令牌是否已经使用过由调用方检查
+pub fn verify_two_factor_token(token: &str, keys: &JwtKeyStore) -> Option<TwoFactorClaims> {
+    let claims = keys.verify::<TwoFactorClaims>(token, TWO_FACTOR_AUDIENCE).ok()?;
+
+    (claims.purpose == TWO_FACTOR_PURPOSE).then_some(claims)
+}
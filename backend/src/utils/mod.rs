@@ -1,3 +1,6 @@
+pub mod client_ip;
 pub mod password;
+pub mod pinyin;
 pub mod jwt;
+pub mod token;
 pub mod validation;
\ No newline at end of file
@@ -0,0 +1,104 @@
+use pinyin::ToPinyin;
+
+/// 作为姓氏时读音和常用读音不同的字，只用于名称的第一个字
+const SURNAME_READINGS: [(char, &str); 16] = [
+    ('曾', "zeng"),
+    ('单', "shan"),
+    ('解', "xie"),
+    ('仇', "qiu"),
+    ('区', "ou"),
+    ('朴', "piao"),
+    ('乐', "yue"),
+    ('查', "zha"),
+    ('缪', "miao"),
+    ('盖', "ge"),
+    ('翟', "zhai"),
+    ('覃', "qin"),
+    ('召', "shao"),
+    ('尉', "yu"),
+    ('种', "chong"),
+    ('繁', "po"),
+];
+
+/// 全拼：汉字转为不带声调的拼音（ü 写作 v），字母和数字转小写保留，其他字符去掉。
+/// 例如「张三」为 zhangsan
+pub fn full_pinyin(text: &str) -> String {
+    convert(text, |pinyin| pinyin.replace('ü', "v"))
+}
+
+/// 首字母：每个汉字取拼音的第一个字母，例如「张三」为 zs
+pub fn initials(text: &str) -> String {
+    convert(text, |pinyin| pinyin[..1].to_string())
+}
+
+/// 声母简拼：和首字母相同，但 zh、ch、sh 保留两个字母，例如「张三」为 zhs
+pub fn initials_with_zh(text: &str) -> String {
+    convert(text, |pinyin| {
+        let length = if ["zh", "ch", "sh"].iter().any(|prefix| pinyin.starts_with(prefix)) {
+            2
+        } else {
+            1
+        };
+        pinyin[..length].to_string()
+    })
+}
+
+/// 保存在客户上的简拼：首字母和声母简拼两种写法，不同时用空格分隔，例如「张三」为 `zs zhs`
+pub fn name_initials(name: &str) -> String {
+    let short = initials(name);
+    let with_zh = initials_with_zh(name);
+    if short == with_zh {
+        short
+    } else {
+        format!("{short} {with_zh}")
+    }
+}
+
+fn convert(text: &str, syllable: impl Fn(&'static str) -> String) -> String {
+    let mut converted = String::with_capacity(text.len());
+    for (i, c) in text.trim_start().chars().enumerate() {
+        let surname = SURNAME_READINGS
+            .iter()
+            .find(|(surname, _)| i == 0 && *surname == c)
+            .map(|(_, reading)| *reading);
+        if let Some(pinyin) = surname.or_else(|| c.to_pinyin().map(|pinyin| pinyin.plain())) {
+            converted.push_str(&syllable(pinyin));
+        } else if c.is_ascii_alphanumeric() {
+            converted.push(c.to_ascii_lowercase());
+        }
+    }
+    converted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn full_pinyin_drops_tones_and_symbols() {
+        assert_eq!(full_pinyin("张三"), "zhangsan");
+        assert_eq!(full_pinyin("吕绿"), "lvlv");
+        assert_eq!(full_pinyin("Amy 王-2号"), "amywang2hao");
+        assert_eq!(full_pinyin(""), "");
+    }
+
+    #[test]
+    fn surnames_use_their_surname_reading_only_first() {
+        assert_eq!(full_pinyin("曾经"), "zengjing");
+        assert_eq!(full_pinyin(" 单田芳"), "shantianfang");
+        assert_eq!(full_pinyin("王曾"), "wangceng");
+        assert_eq!(initials("解放"), "xf");
+    }
+
+    #[test]
+    fn initials_with_and_without_zh_ch_sh() {
+        assert_eq!(initials("张三"), "zs");
+        assert_eq!(initials_with_zh("张三"), "zhs");
+        assert_eq!(initials_with_zh("陈诗"), "chsh");
+        assert_eq!(initials("张s"), "zs");
+        assert_eq!(name_initials("张三"), "zs zhs");
+        // 没有 zh、ch、sh 时只保存一种写法
+        assert_eq!(name_initials("李四"), "ls");
+        assert_eq!(name_initials("Amy"), "amy");
+    }
+}
@@ -0,0 +1,12 @@
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// 生成随机的不透明令牌（64位十六进制）
+pub fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// 令牌只以 SHA-256 哈希形式入库
+pub fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
@@ -10,14 +10,68 @@ pub fn validate_username(username: &str) -> bool {
     re.is_match(username)
 }
 
-pub fn validate_password(password: &str) -> bool {
-    password.len() >= 6
+/// 密码策略，由配置项 `PASSWORD_*` 控制
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 6,
+            require_uppercase: false,
+            require_lowercase: false,
+            require_digit: false,
+            require_symbol: false,
+        }
+    }
+}
+
+/// 按密码策略校验密码，不满足时返回说明原因的错误信息
+pub fn validate_password(password: &str, policy: &PasswordPolicy) -> Result<(), String> {
+    let mut problems = Vec::new();
+
+    if password.chars().count() < policy.min_length {
+        problems.push(format!("长度不少于 {} 个字符", policy.min_length));
+    }
+    if policy.require_uppercase && !password.chars().any(|c| c.is_ascii_uppercase()) {
+        problems.push("包含大写字母".to_string());
+    }
+    if policy.require_lowercase && !password.chars().any(|c| c.is_ascii_lowercase()) {
+        problems.push("包含小写字母".to_string());
+    }
+    if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+        problems.push("包含数字".to_string());
+    }
+    if policy.require_symbol && !password.chars().any(|c| !c.is_alphanumeric()) {
+        problems.push("包含特殊字符".to_string());
+    }
+
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("密码需要{}", problems.join("、")))
+    }
 }
 
 pub fn validate_name(name: &str) -> bool {
     !name.trim().is_empty() && name.len() <= 100
 }
 
+pub fn validate_email(email: &str) -> bool {
+    if email.len() > 255 {
+        return false;
+    }
+
+    let re = Regex::new(r"^[^@\s]+@[^@\s]+\.[^@\s]+$").unwrap();
+    re.is_match(email)
+}
+
 pub fn validate_phone(phone: &str) -> bool {
     if phone.is_empty() {
         return true; // Phone is optional
@@ -29,5 +83,5 @@ pub fn validate_phone(phone: &str) -> bool {
 }
 
 pub fn validate_rate(rate: f32) -> bool {
-    rate >= 0.0 && rate <= 5.0
+    (0.0..=5.0).contains(&rate)
 }
\ No newline at end of file
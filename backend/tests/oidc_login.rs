@@ -0,0 +1,186 @@
+//! 单点登录的授权码流程，对接本地模拟的身份提供方
+
+#[path = "support/mock_oidc.rs"]
+mod mock_oidc;
+
+use customer_tracker::{
+    entities::{user, user_role::UserRole},
+    migration::DatabaseMigrator,
+    services::oidc_service::{OidcError, OidcProvider, OidcSettings},
+};
+use chrono::Utc;
+use jsonwebtoken::Algorithm;
+use mock_oidc::{Identity, MockProvider};
+use sea_orm::{ActiveModelTrait, ConnectOptions, Database, DatabaseConnection, EntityTrait, Set};
+
+const CLIENT_ID: &str = "customer-tracker";
+const CLIENT_SECRET: &str = "mock-secret";
+const REDIRECT_URI: &str = "http://127.0.0.1/api/auth/oidc/callback";
+
+async fn test_db() -> DatabaseConnection {
+    let mut options = ConnectOptions::new("sqlite::memory:");
+    options.max_connections(1).min_connections(1).sqlx_logging(false);
+    let db = Database::connect(options).await.unwrap();
+    DatabaseMigrator::new(String::new()).migrate(&db).await.unwrap();
+    db
+}
+
+async fn create_user(db: &DatabaseConnection, username: &str, email: &str) -> user::Model {
+    let now = Utc::now();
+    user::ActiveModel {
+        username: Set(username.to_string()),
+        password_hash: Set(String::new()),
+        name: Set(username.to_string()),
+        email: Set(Some(email.to_string())),
+        created_at: Set(now),
+        updated_at: Set(now),
+        is_active: Set(true),
+        role: Set(UserRole::Sales),
+        organization_id: Set(1),
+        totp_enabled: Set(false),
+        ..Default::default()
+    }
+    .insert(db)
+    .await
+    .unwrap()
+}
+
+/// 在随机端口上启动模拟身份提供方，返回对接它的 `OidcProvider`。
+/// 模拟身份提供方用客户端密钥签名，需要明确接受 HS256
+async fn start(configure: impl FnOnce(&mut MockProvider)) -> OidcProvider {
+    start_accepting(configure, vec![Algorithm::HS256]).await
+}
+
+async fn start_accepting(
+    configure: impl FnOnce(&mut MockProvider),
+    id_token_algorithms: Vec<Algorithm>,
+) -> OidcProvider {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let issuer = format!("http://{}", listener.local_addr().unwrap());
+
+    let mut mock = MockProvider::new(
+        issuer.clone(),
+        CLIENT_ID.to_string(),
+        CLIENT_SECRET.to_string(),
+        Identity {
+            sub: "mock|alice".to_string(),
+            email: "alice@example.com".to_string(),
+            email_verified: true,
+            name: "Alice".to_string(),
+            preferred_username: "alice".to_string(),
+        },
+    );
+    configure(&mut mock);
+    tokio::spawn(async move { axum::serve(listener, mock.router()).await });
+
+    OidcProvider::new(OidcSettings {
+        issuer_url: issuer,
+        client_id: CLIENT_ID.to_string(),
+        client_secret: Some(CLIENT_SECRET.to_string()),
+        redirect_uri: REDIRECT_URI.to_string(),
+        scopes: "openid email profile".to_string(),
+        jit_provisioning: false,
+        default_role: UserRole::Sales,
+        default_organization_id: 1,
+        require_verified_email: true,
+        id_token_algorithms,
+    })
+}
+
+/// 跟随 `begin` 返回的地址完成授权，返回回调中的 code 和 state
+async fn authorize(url: &str) -> (String, String) {
+    let client = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap();
+    let response = client.get(url).send().await.unwrap();
+    assert!(response.status().is_redirection(), "授权失败: {}", response.status());
+
+    let location = response.headers()["location"].to_str().unwrap();
+    let callback = reqwest::Url::parse(location).unwrap();
+    assert!(location.starts_with(REDIRECT_URI));
+    let param = |name: &str| {
+        callback
+            .query_pairs()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.into_owned())
+            .unwrap()
+    };
+    (param("code"), param("state"))
+}
+
+#[tokio::test]
+async fn logs_in_and_binds_the_subject_by_verified_email() {
+    let db = test_db().await;
+    let alice = create_user(&db, "alice", "alice@example.com").await;
+    let provider = start(|_| {}).await;
+
+    let (code, state) = authorize(&provider.begin(&db).await.unwrap()).await;
+    let user = provider.complete(&db, &code, &state).await.unwrap();
+
+    assert_eq!(user.id, alice.id);
+    assert_eq!(user.oidc_subject.as_deref(), Some("mock|alice"));
+}
+
+#[tokio::test]
+async fn state_can_only_be_used_once() {
+    let db = test_db().await;
+    create_user(&db, "alice", "alice@example.com").await;
+    let provider = start(|_| {}).await;
+
+    let (code, state) = authorize(&provider.begin(&db).await.unwrap()).await;
+    provider.complete(&db, &code, &state).await.unwrap();
+
+    // 换一个新的授权码也不能重用 state
+    let (second_code, _) = authorize(&provider.begin(&db).await.unwrap()).await;
+    let replay = provider.complete(&db, &second_code, &state).await;
+    assert!(matches!(replay, Err(OidcError::InvalidState)), "{:?}", replay);
+}
+
+#[tokio::test]
+async fn rejects_an_id_token_with_a_different_nonce() {
+    let db = test_db().await;
+    create_user(&db, "alice", "alice@example.com").await;
+    let provider = start(|mock| mock.nonce_override = Some("forged".to_string())).await;
+
+    let (code, state) = authorize(&provider.begin(&db).await.unwrap()).await;
+    let result = provider.complete(&db, &code, &state).await;
+    assert!(matches!(result, Err(OidcError::InvalidIdToken(_))), "{:?}", result);
+}
+
+#[tokio::test]
+async fn refuses_unverified_email() {
+    let db = test_db().await;
+    let alice = create_user(&db, "alice", "alice@example.com").await;
+    let provider = start(|mock| mock.identity.email_verified = false).await;
+
+    let (code, state) = authorize(&provider.begin(&db).await.unwrap()).await;
+    let result = provider.complete(&db, &code, &state).await;
+    assert!(matches!(result, Err(OidcError::EmailNotVerified)), "{:?}", result);
+
+    // 未验证的邮箱不能把身份绑定到已有用户上
+    let alice = user::Entity::find_by_id(alice.id).one(&db).await.unwrap().unwrap();
+    assert_eq!(alice.oidc_subject, None);
+}
+
+#[tokio::test]
+async fn rejects_discovery_metadata_for_another_issuer() {
+    let db = test_db().await;
+    let provider =
+        start(|mock| mock.advertised_issuer = Some("https://attacker.example".to_string())).await;
+
+    let result = provider.begin(&db).await;
+    assert!(matches!(result, Err(OidcError::Provider(_))), "{:?}", result);
+}
+
+#[tokio::test]
+async fn rejects_hs256_id_tokens_unless_configured() {
+    let db = test_db().await;
+    create_user(&db, "alice", "alice@example.com").await;
+    // 发现文档声明了 HS256，但没有在配置中明确接受
+    let provider = start_accepting(|_| {}, Vec::new()).await;
+
+    let (code, state) = authorize(&provider.begin(&db).await.unwrap()).await;
+    let result = provider.complete(&db, &code, &state).await;
+    assert!(matches!(result, Err(OidcError::InvalidIdToken(_))), "{:?}", result);
+}
@@ -0,0 +1,212 @@
+//! 本地模拟的 OpenID Connect 身份提供方，供 `mock_oidc_provider` 示例和单点登录的集成测试使用。
+//!
+//! 授权请求会直接通过，以配置的身份签发 HS256 身份令牌（用客户端密钥签名）。
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Redirect, Response},
+    routing::{get, post},
+    Form, Router,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{Duration, Utc};
+use jsonwebtoken::{encode, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct MockProvider {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub identity: Identity,
+    /// 发现文档中声明的签发方，为空时与 `issuer` 相同
+    pub advertised_issuer: Option<String>,
+    /// 写入身份令牌的 nonce，为空时使用授权请求中的 nonce
+    pub nonce_override: Option<String>,
+    codes: Arc<Mutex<HashMap<String, PendingCode>>>,
+}
+
+#[derive(Clone)]
+pub struct Identity {
+    pub sub: String,
+    pub email: String,
+    pub email_verified: bool,
+    pub name: String,
+    pub preferred_username: String,
+}
+
+struct PendingCode {
+    client_id: String,
+    redirect_uri: String,
+    nonce: Option<String>,
+    code_challenge: String,
+}
+
+#[derive(Deserialize)]
+struct AuthorizeQuery {
+    client_id: String,
+    redirect_uri: String,
+    state: String,
+    nonce: Option<String>,
+    code_challenge: String,
+    code_challenge_method: String,
+}
+
+#[derive(Deserialize)]
+struct TokenForm {
+    grant_type: String,
+    code: String,
+    client_id: String,
+    client_secret: Option<String>,
+    redirect_uri: String,
+    code_verifier: String,
+}
+
+#[derive(Serialize)]
+struct IdTokenClaims {
+    iss: String,
+    sub: String,
+    aud: String,
+    exp: i64,
+    iat: i64,
+    nonce: Option<String>,
+    email: String,
+    email_verified: bool,
+    name: String,
+    preferred_username: String,
+}
+
+impl MockProvider {
+    pub fn new(issuer: String, client_id: String, client_secret: String, identity: Identity) -> Self {
+        Self {
+            issuer,
+            client_id,
+            client_secret,
+            identity,
+            advertised_issuer: None,
+            nonce_override: None,
+            codes: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/.well-known/openid-configuration", get(discovery))
+            .route("/authorize", get(authorize))
+            .route("/token", post(token))
+            .route("/jwks", get(jwks))
+            .with_state(self)
+    }
+}
+
+fn token_error(error: &str) -> Response {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": error }))).into_response()
+}
+
+async fn discovery(State(provider): State<MockProvider>) -> Json<serde_json::Value> {
+    Json(json!({
+        "issuer": provider.advertised_issuer.as_ref().unwrap_or(&provider.issuer),
+        "authorization_endpoint": format!("{}/authorize", provider.issuer),
+        "token_endpoint": format!("{}/token", provider.issuer),
+        "jwks_uri": format!("{}/jwks", provider.issuer),
+        "response_types_supported": ["code"],
+        "subject_types_supported": ["public"],
+        "id_token_signing_alg_values_supported": ["HS256"],
+        "code_challenge_methods_supported": ["S256"],
+    }))
+}
+
+// 不展示登录页，直接带着授权码跳回
+async fn authorize(
+    State(provider): State<MockProvider>,
+    Query(query): Query<AuthorizeQuery>,
+) -> Response {
+    if query.client_id != provider.client_id {
+        return (StatusCode::BAD_REQUEST, "unknown client_id").into_response();
+    }
+    if query.code_challenge_method != "S256" {
+        return (StatusCode::BAD_REQUEST, "only S256 is supported").into_response();
+    }
+
+    let code = Uuid::new_v4().simple().to_string();
+    provider.codes.lock().unwrap().insert(
+        code.clone(),
+        PendingCode {
+            client_id: query.client_id,
+            redirect_uri: query.redirect_uri.clone(),
+            nonce: query.nonce,
+            code_challenge: query.code_challenge,
+        },
+    );
+
+    let separator = if query.redirect_uri.contains('?') { '&' } else { '?' };
+    Redirect::to(&format!(
+        "{}{}code={}&state={}",
+        query.redirect_uri, separator, code, query.state
+    ))
+    .into_response()
+}
+
+async fn token(State(provider): State<MockProvider>, Form(form): Form<TokenForm>) -> Response {
+    if form.grant_type != "authorization_code" {
+        return token_error("unsupported_grant_type");
+    }
+    if form.client_secret.as_deref() != Some(provider.client_secret.as_str()) {
+        return token_error("invalid_client");
+    }
+
+    let Some(pending) = provider.codes.lock().unwrap().remove(&form.code) else {
+        return token_error("invalid_grant");
+    };
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(form.code_verifier.as_bytes()));
+    if pending.client_id != form.client_id
+        || pending.redirect_uri != form.redirect_uri
+        || pending.code_challenge != challenge
+    {
+        return token_error("invalid_grant");
+    }
+
+    let now = Utc::now();
+    let claims = IdTokenClaims {
+        iss: provider.issuer.clone(),
+        sub: provider.identity.sub.clone(),
+        aud: provider.client_id.clone(),
+        exp: (now + Duration::minutes(5)).timestamp(),
+        iat: now.timestamp(),
+        nonce: provider.nonce_override.clone().or(pending.nonce),
+        email: provider.identity.email.clone(),
+        email_verified: provider.identity.email_verified,
+        name: provider.identity.name.clone(),
+        preferred_username: provider.identity.preferred_username.clone(),
+    };
+    let id_token = match encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(provider.client_secret.as_bytes()),
+    ) {
+        Ok(id_token) => id_token,
+        Err(_) => return StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+    };
+
+    Json(json!({
+        "access_token": Uuid::new_v4().simple().to_string(),
+        "token_type": "Bearer",
+        "expires_in": 300,
+        "id_token": id_token,
+    }))
+    .into_response()
+}
+
+// 身份令牌用 HS256 签名，没有公钥
+async fn jwks() -> Json<serde_json::Value> {
+    Json(json!({ "keys": [] }))
+}
@@ -245,18 +245,14 @@ generate_env_config() {
         cp .env .env.backup
     fi
     
-    # 生成随机 JWT 密钥
-    print_info "生成安全的JWT密钥..."
-    JWT_SECRET=$(openssl rand -base64 64 | tr -d '\n')
-    
     # 创建简化的生产环境配置文件
     cat > .env << EOF
 # 客户追踪系统 - 极简配置
 # 生成时间: $(date)
 # 部署地址: ${PROTOCOL}://${DOMAIN}
 
-# 唯一必需的安全配置
-JWT_SECRET=${JWT_SECRET}
+# JWT 签名密钥由后端首次启动时自动生成并保存在数据库中
+# 轮换密钥: docker compose exec backend ./customer-tracker server rotate-jwt-key
 EOF
     
     print_success "环境变量配置已生成 (极简模式)"
@@ -305,7 +301,7 @@ init_database() {
         if docker-compose -f docker-compose.prod.yaml exec -T backend /app/customer-tracker user list 2>/dev/null | grep -q "^$ADMIN_USERNAME$"; then
             print_warning "用户 '$ADMIN_USERNAME' 已存在，跳过创建"
         else
-            if docker-compose -f docker-compose.prod.yaml exec -T backend /app/customer-tracker user create -u "$ADMIN_USERNAME" -p "$ADMIN_PASSWORD" -n "$ADMIN_NAME"; then
+            if docker-compose -f docker-compose.prod.yaml exec -T backend /app/customer-tracker user create -u "$ADMIN_USERNAME" -p "$ADMIN_PASSWORD" -n "$ADMIN_NAME" -r admin; then
                 print_success "管理员用户 '$ADMIN_USERNAME' 创建成功"
             else
                 print_error "创建用户失败"
@@ -11,6 +11,15 @@ const routes = [
       title: '登录'
     }
   },
+  {
+    path: '/sso/callback',
+    name: 'SsoCallback',
+    component: () => import('@/views/SsoCallback.vue'),
+    meta: {
+      requiresAuth: false,
+      title: '单点登录'
+    }
+  },
   {
     path: '/',
     redirect: '/customers'
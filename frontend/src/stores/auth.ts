@@ -5,17 +5,31 @@ import type {
   User, 
   LoginRequest, 
   LoginResponse, 
+  TwoFactorChallenge,
+  OidcConfigResponse,
+  OidcAuthorizeResponse,
+  OidcCallbackRequest,
   RefreshTokenResponse,
   LogoutResponse,
   ApiError 
 } from '@/types'
 
 const TOKEN_KEY = 'customer_tracker_token'
+const REFRESH_TOKEN_KEY = 'customer_tracker_refresh_token'
+
+export interface LoginResult {
+  success: boolean
+  user?: User
+  message?: string
+  twoFactorRequired?: boolean
+}
 
 export const useAuthStore = defineStore('auth', () => {
   const token = ref<string | null>(localStorage.getItem(TOKEN_KEY))
   const user = ref<User | null>(null)
   const loading = ref(false)
+  // Intermediate token while waiting for the two-factor code
+  const twoFactorToken = ref<string | null>(null)
 
   const isAuthenticated = computed(() => !!token.value && !!user.value)
 
@@ -38,18 +52,17 @@ export const useAuthStore = defineStore('auth', () => {
   }
 
   // Login
-  const login = async (credentials: LoginRequest) => {
+  const login = async (credentials: LoginRequest): Promise<LoginResult> => {
     try {
       loading.value = true
-      const response = await request.post<LoginResponse>('/api/auth/login', credentials)
-      
-      token.value = response.data.token
-      user.value = response.data.user
-      
-      // Save to localStorage
-      localStorage.setItem(TOKEN_KEY, response.data.token)
-      
-      return { success: true, user: response.data.user }
+      const response = await request.post<LoginResponse | TwoFactorChallenge>('/api/auth/login', credentials)
+
+      if ('two_factor_required' in response.data) {
+        twoFactorToken.value = response.data.two_factor_token
+        return { success: false, twoFactorRequired: true }
+      }
+
+      return { success: true, user: saveLogin(response.data) }
     } catch (error: any) {
       const apiError = error as ApiError
       return { 
@@ -61,6 +74,76 @@ export const useAuthStore = defineStore('auth', () => {
     }
   }
 
+  // Second login step when two-factor authentication is enabled
+  const loginTwoFactor = async (code: string): Promise<LoginResult> => {
+    try {
+      loading.value = true
+      const response = await request.post<LoginResponse>('/api/auth/login/2fa', {
+        two_factor_token: twoFactorToken.value,
+        code,
+      })
+      twoFactorToken.value = null
+      return { success: true, user: saveLogin(response.data) }
+    } catch (error: any) {
+      const apiError = error as ApiError
+      return {
+        success: false,
+        message: apiError.status === 401 ? '验证码错误' : apiError.message || '验证失败'
+      }
+    } finally {
+      loading.value = false
+    }
+  }
+
+  // Whether the backend has single sign-on configured
+  const fetchSsoEnabled = async (): Promise<boolean> => {
+    try {
+      const response = await request.get<OidcConfigResponse>('/api/auth/oidc/config')
+      return response.data.enabled
+    } catch (error) {
+      return false
+    }
+  }
+
+  // Leave the app for the identity provider; it redirects back to /sso/callback
+  const startSso = async (): Promise<LoginResult> => {
+    try {
+      loading.value = true
+      const response = await request.post<OidcAuthorizeResponse>('/api/auth/oidc/authorize')
+      window.location.href = response.data.authorization_url
+      return { success: true }
+    } catch (error: any) {
+      const apiError = error as ApiError
+      loading.value = false
+      return { success: false, message: apiError.message || '单点登录不可用' }
+    }
+  }
+
+  // Exchange the code returned by the identity provider for our own tokens
+  const loginSso = async (payload: OidcCallbackRequest): Promise<LoginResult> => {
+    try {
+      loading.value = true
+      const response = await request.post<LoginResponse>('/api/auth/oidc/callback', payload)
+      return { success: true, user: saveLogin(response.data) }
+    } catch (error: any) {
+      const apiError = error as ApiError
+      return { success: false, message: apiError.message || '单点登录失败' }
+    } finally {
+      loading.value = false
+    }
+  }
+
+  const saveLogin = (data: LoginResponse): User => {
+    token.value = data.token
+    user.value = data.user
+
+    // Save to localStorage
+    localStorage.setItem(TOKEN_KEY, data.token)
+    localStorage.setItem(REFRESH_TOKEN_KEY, data.refresh_token)
+
+    return data.user
+  }
+
   // Logout
   const logout = async () => {
     try {
@@ -73,15 +156,19 @@ export const useAuthStore = defineStore('auth', () => {
       token.value = null
       user.value = null
       localStorage.removeItem(TOKEN_KEY)
+      localStorage.removeItem(REFRESH_TOKEN_KEY)
     }
   }
 
   // Refresh token
   const refreshToken = async (): Promise<boolean> => {
     try {
-      const response = await request.post<RefreshTokenResponse>('/api/auth/refresh')
+      const response = await request.post<RefreshTokenResponse>('/api/auth/refresh', {
+        refresh_token: localStorage.getItem(REFRESH_TOKEN_KEY),
+      })
       token.value = response.data.token
       localStorage.setItem(TOKEN_KEY, response.data.token)
+      localStorage.setItem(REFRESH_TOKEN_KEY, response.data.refresh_token)
       return true
     } catch (error) {
       logout()
@@ -105,9 +192,14 @@ export const useAuthStore = defineStore('auth', () => {
     token,
     user,
     loading,
+    twoFactorToken,
     isAuthenticated,
     initAuth,
     login,
+    loginTwoFactor,
+    fetchSsoEnabled,
+    startSso,
+    loginSso,
     logout,
     refreshToken,
     getCurrentUser
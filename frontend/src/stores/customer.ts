@@ -5,6 +5,7 @@ import type {
   Customer, 
   CustomerWithLatestTrack,
   CustomerCreateRequest, 
+  CreateCustomerResponse,
   CustomerUpdateRequest,
   CustomerListResponse,
   NextAction,
@@ -73,7 +74,7 @@ export const useCustomerStore = defineStore('customer', () => {
   const createCustomer = async (customerData: CustomerCreateRequest) => {
     try {
       loading.value = true
-      const response = await request.post<Customer>('/api/customers', customerData)
+      const response = await request.post<CreateCustomerResponse>('/api/customers', customerData)
       
       // 转换为 CustomerWithLatestTrack 格式并添加到本地列表
       const customerWithTrack: CustomerWithLatestTrack = {
@@ -81,7 +82,10 @@ export const useCustomerStore = defineStore('customer', () => {
         track_count: 0,
         latest_track_time: undefined,
         latest_next_action: undefined,
-        latest_content: undefined
+        latest_content: undefined,
+        tags: response.data.tags ?? [],
+        stage_id: response.data.stage_id ?? null,
+        stage: response.data.stage ?? null
       }
       customers.value.unshift(customerWithTrack)
       totalCount.value += 1
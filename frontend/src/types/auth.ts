@@ -1,7 +1,11 @@
+export type UserRole = 'admin' | 'manager' | 'sales'
+
 export interface User {
   id: number
   username: string
   name: string
+  role: UserRole
+  two_factor_enabled: boolean
   last_login_at?: string
 }
 
@@ -13,12 +17,40 @@ export interface LoginRequest {
 export interface LoginResponse {
   token: string
   expires_in: number
+  refresh_token: string
+  refresh_expires_in: number
   user: User
 }
 
+export interface TwoFactorChallenge {
+  two_factor_required: true
+  two_factor_token: string
+  expires_in: number
+}
+
+export interface TwoFactorLoginRequest {
+  two_factor_token: string
+  code: string
+}
+
+export interface OidcConfigResponse {
+  enabled: boolean
+}
+
+export interface OidcAuthorizeResponse {
+  authorization_url: string
+}
+
+export interface OidcCallbackRequest {
+  code: string
+  state: string
+}
+
 export interface RefreshTokenResponse {
   token: string
   expires_in: number
+  refresh_token: string
+  refresh_expires_in: number
 }
 
 export interface LogoutResponse {
@@ -1,5 +1,83 @@
 export type NextAction = '继续跟进' | '结束跟进'
-export type CustomerGroup = '团课' | '小班' | '私教' | '教培'
+// 分组名称，可选的分组由管理员维护，见 CustomerGroupConfig
+export type CustomerGroup = string
+
+export interface CustomerGroupConfig {
+  id: number
+  name: CustomerGroup
+  display_name: string
+  color?: string | null
+  sort_order: number
+  // 归档的分组不能再分配给客户
+  is_archived: boolean
+  customer_count?: number
+  created_at: string
+  updated_at: string
+}
+
+// 当前用户对客户的权限：负责人（含主管和管理员）、可编辑或只读的协作者
+export type CustomerAccess = 'owner' | 'write' | 'read'
+
+// 自定义字段
+export type CustomFieldType = 'text' | 'number' | 'date' | 'select' | 'multi_select'
+// 数字、日期（YYYY-MM-DD）、文本或单选为单个值，多选为数组
+export type CustomFieldValueData = string | number | string[] | null
+
+export interface CustomField {
+  id: number
+  key: string
+  label: string
+  field_type: CustomFieldType
+  options: string[]
+  // 为空表示适用于所有分组
+  customer_groups: CustomerGroup[]
+  required: boolean
+  sort_order: number
+  is_active: boolean
+  created_at: string
+  updated_at: string
+}
+
+export interface CustomFieldValue {
+  field_id: number
+  key: string
+  label: string
+  field_type: CustomFieldType
+  options: string[]
+  required: boolean
+  value: CustomFieldValueData
+}
+
+export interface Tag {
+  id: number
+  name: string
+  color?: string | null
+  created_by?: number | null
+  created_at: string
+  updated_at: string
+}
+
+// 标签及当前用户可见的使用该标签的客户数，用于筛选
+export interface TagWithCount extends Tag {
+  customer_count: number
+}
+
+// 销售阶段：进行中、赢单、输单
+export type StageOutcome = 'open' | 'won' | 'lost'
+
+export interface PipelineStage {
+  id: number
+  key: string
+  name: string
+  outcome: StageOutcome
+  color?: string | null
+  sort_order: number
+  // 停用的阶段不能再选择
+  is_active: boolean
+  customer_count?: number
+  created_at: string
+  updated_at: string
+}
 
 export interface Customer {
   id: number
@@ -10,12 +88,79 @@ export interface Customer {
   rate: number
   customer_group: CustomerGroup
   next_action: NextAction
-  user_id: number
+  // 为空表示客户在公海中
+  user_id: number | null
   track_count?: number
   last_track_at?: string
   created_at: string
   updated_at: string
   is_deleted: boolean
+  // 被合并到的客户
+  merged_into_id?: number | null
+  access?: CustomerAccess
+  custom_fields?: CustomFieldValue[]
+  tags?: Tag[]
+  stage_id?: number | null
+  stage?: PipelineStage | null
+}
+
+// 查重依据：电话相同或名称相近
+export type DuplicateReason = 'phone' | 'name'
+
+export interface DuplicateCandidate {
+  id: number
+  // 名称、电话和负责人在无权查看该客户时为空
+  name: string | null
+  phone?: string | null
+  user_id: number | null
+  owner_name?: string | null
+  reasons: DuplicateReason[]
+  access: CustomerAccess | null
+}
+
+export interface CreateCustomerResponse extends Customer {
+  duplicates: DuplicateCandidate[]
+}
+
+export interface DuplicateGroup {
+  reason: DuplicateReason
+  key: string
+  customers: Customer[]
+}
+
+export interface DuplicateReportResponse {
+  groups: DuplicateGroup[]
+}
+
+// 回收站中的客户
+export interface TrashedCustomer {
+  id: number
+  name: string
+  phone?: string | null
+  address?: string | null
+  rate: number
+  customer_group: CustomerGroup
+  user_id: number | null
+  track_count: number
+  deleted_at?: string | null
+  deleted_by?: number | null
+  deleted_by_name?: string | null
+  // 合并产生的删除不能恢复
+  merged_into_id?: number | null
+  // 未设置保留期限时为空
+  purge_at?: string | null
+}
+
+export interface TrashListResponse {
+  customers: TrashedCustomer[]
+  total: number
+  page: number
+  limit: number
+}
+
+export interface MergeCustomerResponse {
+  customer: Customer
+  moved_tracks: number
 }
 
 export interface CustomerWithLatestTrack {
@@ -31,10 +176,13 @@ export interface CustomerWithLatestTrack {
   latest_next_action?: NextAction
   latest_content?: string
   track_count: number
-  user_id: number
+  user_id: number | null
   created_at: string
   updated_at: string
   is_deleted: boolean
+  tags: Tag[]
+  stage_id: number | null
+  stage: PipelineStage | null
 }
 
 export interface CustomerCreateRequest {
@@ -44,6 +192,8 @@ export interface CustomerCreateRequest {
   notes?: string | null
   rate?: number
   customer_group?: CustomerGroup
+  custom_fields?: Record<string, CustomFieldValueData>
+  tag_ids?: number[]
 }
 
 export interface CustomerUpdateRequest {
@@ -53,6 +203,10 @@ export interface CustomerUpdateRequest {
   notes?: string | null
   rate?: number
   customer_group?: CustomerGroup
+  // 只修改出现的字段，null 表示清除
+  custom_fields?: Record<string, CustomFieldValueData>
+  // 给出时替换全部标签
+  tag_ids?: number[]
 }
 
 export interface CustomerResponse {
@@ -69,6 +223,55 @@ export interface CustomerListResponse {
 export interface CustomerListQuery {
   page?: number
   limit?: number
+  // 名称、名称的全拼或简拼（如 zs、zhs、张s）、电话
   search?: string
+  stage_id?: number
+  // 逗号分隔的标签ID
+  any_tags?: string
+  all_tags?: string
+  // 默认按更新时间倒序；名称和下次跟进时间默认从小到大
+  sort?: CustomerSortField
+  order?: 'asc' | 'desc'
+  rate_min?: number
+  rate_max?: number
+  // YYYY-MM-DD，包含两端
+  created_from?: string
+  created_to?: string
+  // 只看下次跟进时间已过的客户
+  overdue?: boolean
+  has_tracks?: boolean
+  address?: string
+}
+
+export type CustomerSortField =
+  | 'name'
+  | 'rate'
+  | 'created_at'
+  | 'updated_at'
+  | 'latest_track_time'
+  | 'next_track_time'
+
+
+export type SearchHitKind = 'customer' | 'track'
+
+// 全文搜索结果，snippet 已转义 HTML，匹配内容用 <mark> 标出
+export interface SearchHit {
+  kind: SearchHitKind
+  customer_id: number
+  customer_name: string
+  track_id: number | null
+  track_time: string | null
+  snippet: string
+  // 越小越相关
+  rank: number
 }
 
+export interface SearchQuery {
+  q: string
+  limit?: number
+}
+
+export interface SearchResponse {
+  query: string
+  hits: SearchHit[]
+}
@@ -1,4 +1,4 @@
-import type { NextAction } from './customer'
+import type { NextAction, PipelineStage } from './customer'
 
 export interface CustomerTrack {
   id: number
@@ -9,17 +9,25 @@ export interface CustomerTrack {
   next_track_time?: string
   created_at: string
   updated_at: string
+  // 填写人，协作者添加的记录显示协作者姓名
+  user_id?: number | null
+  user_name?: string | null
+  // 这次跟进把客户推进到的阶段
+  stage_id?: number | null
 }
 
 export interface TrackCreateRequest {
   customer_id: number
   content: string
-  next_action: NextAction
+  // 未给出时按阶段推断
+  next_action?: NextAction
+  stage_id?: number
 }
 
 export interface TrackUpdateRequest {
   content?: string
   next_action?: NextAction
+  stage_id?: number
 }
 
 export interface TrackResponse {
@@ -47,4 +55,5 @@ export interface CustomerTrackListResponse {
 
 export interface NextActionsResponse {
   actions: string[]
+  stages: PipelineStage[]
 }
\ No newline at end of file
@@ -1,5 +1,8 @@
 import axios, { type AxiosResponse, type AxiosError, type InternalAxiosRequestConfig } from 'axios'
-import type { ApiError } from '@/types'
+import type { ApiError, RefreshTokenResponse } from '@/types'
+
+const TOKEN_KEY = 'customer_tracker_token'
+const REFRESH_TOKEN_KEY = 'customer_tracker_refresh_token'
 
 const request = axios.create({
   baseURL: '',
@@ -12,7 +15,7 @@ const request = axios.create({
 // Request interceptor - add auth token
 request.interceptors.request.use(
   (config: InternalAxiosRequestConfig) => {
-    const token = localStorage.getItem('customer_tracker_token')
+    const token = localStorage.getItem(TOKEN_KEY)
     
     if (token && config.headers) {
       config.headers.Authorization = `Bearer ${token}`
@@ -25,12 +28,51 @@ request.interceptors.request.use(
   }
 )
 
+// Refresh the access token with the stored refresh token.
+// Concurrent 401s share one in-flight refresh, since each refresh token is single-use.
+let refreshing: Promise<string | null> | null = null
+
+const refreshAccessToken = (): Promise<string | null> => {
+  const refreshToken = localStorage.getItem(REFRESH_TOKEN_KEY)
+  if (!refreshToken) {
+    return Promise.resolve(null)
+  }
+
+  if (!refreshing) {
+    refreshing = axios
+      .post<RefreshTokenResponse>('/api/auth/refresh', { refresh_token: refreshToken })
+      .then((response) => {
+        localStorage.setItem(TOKEN_KEY, response.data.token)
+        localStorage.setItem(REFRESH_TOKEN_KEY, response.data.refresh_token)
+        return response.data.token
+      })
+      .catch(() => null)
+      .finally(() => {
+        refreshing = null
+      })
+  }
+
+  return refreshing
+}
+
 // Response interceptor - handle errors globally
 request.interceptors.response.use(
   (response: AxiosResponse) => {
     return response
   },
   async (error: AxiosError) => {
+    const original = error.config as (InternalAxiosRequestConfig & { _retried?: boolean }) | undefined
+
+    // Access token expired: try a single refresh before giving up
+    if (error.response?.status === 401 && original && !original._retried && !original.url?.startsWith('/api/auth/')) {
+      original._retried = true
+      const newToken = await refreshAccessToken()
+      if (newToken) {
+        original.headers.Authorization = `Bearer ${newToken}`
+        return request(original)
+      }
+    }
+
     const apiError: ApiError = {
       message: '请求失败',
       status: error.response?.status,
@@ -45,7 +87,8 @@ request.interceptors.response.use(
           // Token expired or invalid
           apiError.message = '登录已过期，请重新登录'
           // Clear token and redirect to login
-          localStorage.removeItem('customer_tracker_token')
+          localStorage.removeItem(TOKEN_KEY)
+          localStorage.removeItem(REFRESH_TOKEN_KEY)
           // Don't redirect here as it might cause infinite loops
           // Let the route guard handle redirection
           break
@@ -61,6 +104,10 @@ request.interceptors.response.use(
         case 422:
           apiError.message = data?.message || '请求参数错误'
           break
+
+        case 429:
+          apiError.message = '尝试次数过多，请稍后再试'
+          break
           
         case 500:
           apiError.message = '服务器内部错误'